@@ -127,6 +127,56 @@ func (c *Cedarling) GetLogsByRequestIdAndTag(request_id string, tag string) []st
 	return internal.CallGetLogsByRequestIdAndTag(c.instance_id, request_id, tag)
 }
 
+// Queries the stored logs with filters, time range and cursor pagination
+func (c *Cedarling) QueryLogs(query LogQuery) (LogQueryResult, error) {
+	query_json, err := json.Marshal(query)
+	if err != nil {
+		return LogQueryResult{}, err
+	}
+	result := internal.CallQueryLogs(c.instance_id, string(query_json))
+	err = result.Error()
+	if err != nil {
+		return LogQueryResult{}, err
+	}
+	var query_result LogQueryResult
+	err = json.Unmarshal([]byte(result.JsonValue()), &query_result)
+	if err != nil {
+		return LogQueryResult{}, err
+	}
+	return query_result, nil
+}
+
+// Subscribes to the logs logged from now on, whatever the log type.
+// The callback is called from a separate goroutine with each log entry as a JSON string,
+// until the subscription is closed or the cedarling instance is shut down.
+func (c *Cedarling) SubscribeLogs(config LogSubscriptionConfig, callback func(entry string)) (*LogSubscription, error) {
+	config_json, err := json.Marshal(config)
+	if err != nil {
+		return nil, err
+	}
+	result := internal.CallSubscribeLogs(c.instance_id, string(config_json))
+	err = result.Error()
+	if err != nil {
+		return nil, err
+	}
+	var subscription_id uint
+	err = json.Unmarshal([]byte(result.JsonValue()), &subscription_id)
+	if err != nil {
+		return nil, err
+	}
+
+	poll_interval := config.PollInterval
+	if poll_interval <= 0 {
+		poll_interval = DefaultLogPollInterval
+	}
+	subscription := &LogSubscription{
+		subscription_id: subscription_id,
+		done:            make(chan struct{}),
+	}
+	go subscription.run(poll_interval, callback)
+	return subscription, nil
+}
+
 // Closes the cedarling instance
 func (c *Cedarling) ShutDown() {
 	internal.CallShutDown(c.instance_id)
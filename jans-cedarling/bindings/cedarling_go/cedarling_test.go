@@ -5,6 +5,7 @@ import (
 	"fmt"
 	"reflect"
 	"testing"
+	"time"
 )
 
 var bootstrapConfig string = `
@@ -397,6 +398,76 @@ func TestAuthorizeUnsignedSuccess(t *testing.T) {
 
 }
 
+func TestSubscribeLogs(t *testing.T) {
+	config, err := loadTestConfig(func(conf map[string]any) {
+		conf["CEDARLING_PRINCIPAL_BOOLEAN_OPERATION"] = `{
+            "and": [
+                {"===": [{"var": "Jans::TestPrincipal1"}, "ALLOW"]}
+            ]
+        }`
+		conf["CEDARLING_POLICY_STORE_LOCAL_FN"] = "../../test_files/policy-store_ok_2.yaml"
+	})
+	if err != nil {
+		t.Fatalf("Failed to load test config: %v", err)
+	}
+
+	instance, err := NewCedarling(config)
+	if err != nil {
+		t.Fatalf("Failed to create Cedarling instance: %v", err)
+	}
+
+	entries := make(chan string, 10)
+	subscription, err := instance.SubscribeLogs(LogSubscriptionConfig{
+		LogKind:      "Decision",
+		PollInterval: 10 * time.Millisecond,
+	}, func(entry string) {
+		entries <- entry
+	})
+	if err != nil {
+		t.Fatalf("Failed to subscribe to logs: %v", err)
+	}
+
+	request := RequestUnsigned{
+		Principals: []EntityData{
+			{
+				CedarMapping: CedarEntityMapping{EntityType: "Jans::TestPrincipal1", ID: "1"},
+				Payload:      map[string]any{"is_ok": true},
+			},
+		},
+		Action:   "Jans::Action::\"UpdateForTestPrincipals\"",
+		Resource: EntityData{
+			CedarMapping: CedarEntityMapping{EntityType: "Jans::Issue", ID: "random_id"},
+			Payload:      map[string]any{"org_id": "some_long_id", "country": "US"},
+		},
+	}
+	if _, err := instance.AuthorizeUnsigned(request); err != nil {
+		t.Fatalf("Authorization failed: %v", err)
+	}
+
+	select {
+	case entry := <-entries:
+		var log map[string]any
+		if err := json.Unmarshal([]byte(entry), &log); err != nil {
+			t.Fatalf("Log entry should be JSON: %v", err)
+		}
+		if log["log_kind"] != "Decision" {
+			t.Errorf("Expected a decision log, got %v", log["log_kind"])
+		}
+	case <-time.After(5 * time.Second):
+		t.Fatal("Decision log was not sent to the callback")
+	}
+
+	subscription.Close()
+	select {
+	case <-subscription.Done():
+	case <-time.After(5 * time.Second):
+		t.Fatal("Subscription was not done after being closed")
+	}
+	if subscription.DroppedCount() != 0 {
+		t.Errorf("Expected no dropped entries, got %d", subscription.DroppedCount())
+	}
+}
+
 func TestAuthorizeValidationError(t *testing.T) {
 	config, err := loadTestConfig(nil)
 	if err != nil {
@@ -577,3 +648,22 @@ func ExampleCedarling_GetLogsByTag() {
 	logs := instance.GetLogsByTag("debug")
 	fmt.Println(logs[0])
 }
+
+func ExampleCedarling_QueryLogs() {
+	config, err := loadTestConfig(nil)
+	if err != nil {
+		fmt.Printf("Failed to load bootstrap config: %v\n", err)
+	}
+	config["CEDARLING_LOG_TYPE"] = "memory" // logs are only stored when running in memory logging mode
+
+	instance, err := NewCedarling(config)
+	if err != nil {
+		fmt.Printf("Failed to create Cedarling instance: %v\n", err)
+	}
+	limit := uint(10)
+	result, err := instance.QueryLogs(LogQuery{LogKind: "System", Level: "INFO", Limit: &limit})
+	if err != nil {
+		fmt.Printf("Failed to query logs: %v\n", err)
+	}
+	fmt.Println(result.Count)
+}
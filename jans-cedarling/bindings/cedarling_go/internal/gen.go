@@ -40,6 +40,10 @@ const void c_G2RCall_get_log_ids(const void*, const void*);
 const void c_G2RCall_get_logs_by_tag(const void*, const void*);
 const void c_G2RCall_get_logs_by_request_id(const void*, const void*);
 const void c_G2RCall_get_logs_by_request_id_and_tag(const void*, const void*);
+const void c_G2RCall_query_logs(const void*, const void*);
+const void c_G2RCall_subscribe_logs(const void*, const void*);
+const void c_G2RCall_poll_log_subscription(const void*, const void*);
+const void c_G2RCall_close_log_subscription(const void*);
 const void c_G2RCall_shut_down(const void*);
 */
 import "C"
@@ -419,6 +423,59 @@ func (G2RCallImpl) get_logs_by_request_id_and_tag(instance_id *uint, id *string,
 	asmcall.CallFuncG0P1(unsafe.Pointer(C.c_rust2go_internal_drop), unsafe.Pointer(_internal_slot[1]))
 	return val
 }
+func (G2RCallImpl) query_logs(instance_id *uint, query_json *string) Result {
+	_internal_slot := [2]unsafe.Pointer{}
+	_internal_params := [2]unsafe.Pointer{}
+	instance_id_ref, instance_id_buffer := cvt_ref(cntC_uintptr_t, refC_uintptr_t)(instance_id)
+	_internal_params[0] = unsafe.Pointer(&instance_id_ref)
+	query_json_ref, query_json_buffer := cvt_ref(cntString, refString)(query_json)
+	_internal_params[1] = unsafe.Pointer(&query_json_ref)
+	asmcall.CallFuncG0P2(unsafe.Pointer(C.c_G2RCall_query_logs), unsafe.Pointer(&_internal_slot), unsafe.Pointer(&_internal_params))
+	runtime.KeepAlive(_internal_slot)
+	runtime.KeepAlive(_internal_params)
+	runtime.KeepAlive(instance_id_buffer)
+	runtime.KeepAlive(query_json_buffer)
+	val := ownResult(*(*C.ResultRef)(_internal_slot[0]))
+	asmcall.CallFuncG0P1(unsafe.Pointer(C.c_rust2go_internal_drop), unsafe.Pointer(_internal_slot[1]))
+	return val
+}
+func (G2RCallImpl) subscribe_logs(instance_id *uint, config_json *string) Result {
+	_internal_slot := [2]unsafe.Pointer{}
+	_internal_params := [2]unsafe.Pointer{}
+	instance_id_ref, instance_id_buffer := cvt_ref(cntC_uintptr_t, refC_uintptr_t)(instance_id)
+	_internal_params[0] = unsafe.Pointer(&instance_id_ref)
+	config_json_ref, config_json_buffer := cvt_ref(cntString, refString)(config_json)
+	_internal_params[1] = unsafe.Pointer(&config_json_ref)
+	asmcall.CallFuncG0P2(unsafe.Pointer(C.c_G2RCall_subscribe_logs), unsafe.Pointer(&_internal_slot), unsafe.Pointer(&_internal_params))
+	runtime.KeepAlive(_internal_slot)
+	runtime.KeepAlive(_internal_params)
+	runtime.KeepAlive(instance_id_buffer)
+	runtime.KeepAlive(config_json_buffer)
+	val := ownResult(*(*C.ResultRef)(_internal_slot[0]))
+	asmcall.CallFuncG0P1(unsafe.Pointer(C.c_rust2go_internal_drop), unsafe.Pointer(_internal_slot[1]))
+	return val
+}
+func (G2RCallImpl) poll_log_subscription(subscription_id *uint) Result {
+	_internal_slot := [2]unsafe.Pointer{}
+	_internal_params := [1]unsafe.Pointer{}
+	subscription_id_ref, subscription_id_buffer := cvt_ref(cntC_uintptr_t, refC_uintptr_t)(subscription_id)
+	_internal_params[0] = unsafe.Pointer(&subscription_id_ref)
+	asmcall.CallFuncG0P2(unsafe.Pointer(C.c_G2RCall_poll_log_subscription), unsafe.Pointer(&_internal_slot), unsafe.Pointer(&_internal_params))
+	runtime.KeepAlive(_internal_slot)
+	runtime.KeepAlive(_internal_params)
+	runtime.KeepAlive(subscription_id_buffer)
+	val := ownResult(*(*C.ResultRef)(_internal_slot[0]))
+	asmcall.CallFuncG0P1(unsafe.Pointer(C.c_rust2go_internal_drop), unsafe.Pointer(_internal_slot[1]))
+	return val
+}
+func (G2RCallImpl) close_log_subscription(subscription_id *uint) {
+	_internal_params := [1]unsafe.Pointer{}
+	subscription_id_ref, subscription_id_buffer := cvt_ref(cntC_uintptr_t, refC_uintptr_t)(subscription_id)
+	_internal_params[0] = unsafe.Pointer(&subscription_id_ref)
+	asmcall.CallFuncG0P1(unsafe.Pointer(C.c_G2RCall_close_log_subscription), unsafe.Pointer(&_internal_params))
+	runtime.KeepAlive(_internal_params)
+	runtime.KeepAlive(subscription_id_buffer)
+}
 func (G2RCallImpl) shut_down(instance_id *uint) {
 	_internal_params := [1]unsafe.Pointer{}
 	instance_id_ref, instance_id_buffer := cvt_ref(cntC_uintptr_t, refC_uintptr_t)(instance_id)
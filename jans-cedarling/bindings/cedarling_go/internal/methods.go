@@ -91,6 +91,25 @@ func CallGetLogsByRequestIdAndTag(instance_id uint, request_id string, tag strin
 	return logs
 }
 
+func CallQueryLogs(instance_id uint, query_json string) Result {
+	result := G2R.query_logs(&instance_id, &query_json)
+	return result
+}
+
+func CallSubscribeLogs(instance_id uint, config_json string) Result {
+	result := G2R.subscribe_logs(&instance_id, &config_json)
+	return result
+}
+
+func CallPollLogSubscription(subscription_id uint) Result {
+	result := G2R.poll_log_subscription(&subscription_id)
+	return result
+}
+
+func CallCloseLogSubscription(subscription_id uint) {
+	G2R.close_log_subscription(&subscription_id)
+}
+
 func CallShutDown(instance_id uint) {
 	G2R.shut_down(&instance_id)
 }
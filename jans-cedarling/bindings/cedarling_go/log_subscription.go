@@ -0,0 +1,69 @@
+package cedarling_go
+
+import (
+	"encoding/json"
+	"sync"
+	"sync/atomic"
+	"time"
+
+	"github.com/JanssenProject/jans/jans-cedarling/bindings/cedarling_go/internal"
+)
+
+// Represents a log subscription created by Cedarling.SubscribeLogs
+type LogSubscription struct {
+	subscription_id uint
+	dropped_count   atomic.Uint64
+	done            chan struct{}
+	close_once      sync.Once
+}
+
+// Entries received by the subscription since the previous poll
+type logSubscriptionPoll struct {
+	Entries      []json.RawMessage `json:"entries"`
+	DroppedCount uint64            `json:"dropped_count"`
+	Closed       bool              `json:"closed"`
+}
+
+// Sends the entries to the callback until the subscription is closed
+func (s *LogSubscription) run(poll_interval time.Duration, callback func(entry string)) {
+	defer close(s.done)
+	ticker := time.NewTicker(poll_interval)
+	defer ticker.Stop()
+
+	for {
+		result := internal.CallPollLogSubscription(s.subscription_id)
+		if result.Error() != nil {
+			return
+		}
+		var poll logSubscriptionPoll
+		if err := json.Unmarshal([]byte(result.JsonValue()), &poll); err != nil {
+			return
+		}
+		s.dropped_count.Store(poll.DroppedCount)
+		for _, entry := range poll.Entries {
+			callback(string(entry))
+		}
+		if poll.Closed {
+			return
+		}
+		<-ticker.C
+	}
+}
+
+// Stops receiving new log entries. The buffered entries are still sent to the callback.
+func (s *LogSubscription) Close() {
+	s.close_once.Do(func() {
+		internal.CallCloseLogSubscription(s.subscription_id)
+	})
+}
+
+// Returns a channel that is closed once every entry was sent to the callback
+// after the subscription was closed
+func (s *LogSubscription) Done() <-chan struct{} {
+	return s.done
+}
+
+// Returns the number of log entries discarded because the buffer was full
+func (s *LogSubscription) DroppedCount() uint64 {
+	return s.dropped_count.Load()
+}
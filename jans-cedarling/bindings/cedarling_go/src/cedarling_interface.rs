@@ -64,5 +64,9 @@ pub trait G2RCall {
     fn get_logs_by_tag(instance_id: usize, tag: String) -> Vec<String>;
     fn get_logs_by_request_id(instance_id: usize, request_id: String) -> Vec<String>;
     fn get_logs_by_request_id_and_tag(instance_id: usize, id: String, tag: String) -> Vec<String>;
+    fn query_logs(instance_id: usize, query_json: String) -> Result;
+    fn subscribe_logs(instance_id: usize, config_json: String) -> Result;
+    fn poll_log_subscription(subscription_id: usize) -> Result;
+    fn close_log_subscription(subscription_id: usize);
     fn shut_down(instance_id: usize);
 }
@@ -19,6 +19,7 @@ static BINDINGS_RUNTIME: LazyLock<BindingsRuntime> = LazyLock::new(|| {
     BindingsRuntime {
         runtime: Arc::new(rt),
         instances: Mutex::new(HashMap::new()),
+        subscriptions: Mutex::new(HashMap::new()),
     }
 });
 
@@ -73,6 +74,16 @@ macro_rules! execute_in_runtime {
 struct BindingsRuntime {
     runtime: Arc<Runtime>,
     instances: Mutex<HashMap<usize, Arc<base::Cedarling>>>,
+    subscriptions: Mutex<HashMap<usize, Arc<base::LogSubscription>>>,
+}
+
+/// Entries received by a log subscription since the previous poll
+#[derive(serde::Serialize)]
+struct LogSubscriptionPoll {
+    entries: Vec<serde_json::Value>,
+    dropped_count: u64,
+    /// The subscription is closed and every entry was received
+    closed: bool,
 }
 
 impl BindingsRuntime {
@@ -92,6 +103,27 @@ impl BindingsRuntime {
     fn get_instance(&self, instance_id: usize) -> Option<Arc<base::Cedarling>> {
         self.instances.lock().unwrap().get(&instance_id).cloned()
     }
+
+    fn add_subscription(&self, subscription: base::LogSubscription) -> usize {
+        let subscription_id = get_instance_id();
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .insert(subscription_id, Arc::new(subscription));
+        subscription_id
+    }
+
+    fn remove_subscription(&self, subscription_id: usize) {
+        self.subscriptions.lock().unwrap().remove(&subscription_id);
+    }
+
+    fn get_subscription(&self, subscription_id: usize) -> Option<Arc<base::LogSubscription>> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .get(&subscription_id)
+            .cloned()
+    }
 }
 
 // From rust2go doc example:
@@ -206,6 +238,43 @@ impl cedarling_interface::G2RCall for cedarling_interface::G2RCallImpl {
             .collect()
     }
 
+    fn query_logs(instance_id: usize, query_json: String) -> Result {
+        let query: base::LogQuery = from_json_str!(query_json);
+        let instance = get_instance!(instance_id);
+        Result::success(instance.query_logs(&query))
+    }
+
+    fn subscribe_logs(instance_id: usize, config_json: String) -> Result {
+        let config: base::LogSubscriptionConfig = from_json_str!(config_json);
+        let instance = get_instance!(instance_id);
+        let subscription_id = BINDINGS_RUNTIME.add_subscription(instance.subscribe_logs(config));
+        Result::success(subscription_id)
+    }
+
+    fn poll_log_subscription(subscription_id: usize) -> Result {
+        let Some(subscription) = BINDINGS_RUNTIME.get_subscription(subscription_id) else {
+            return Result::error("Subscription not found");
+        };
+        // no entry can be added once closed, so check it before draining the buffer
+        let closed = subscription.is_closed();
+        let entries = std::iter::from_fn(|| subscription.try_recv()).collect();
+        if closed {
+            BINDINGS_RUNTIME.remove_subscription(subscription_id);
+        }
+        Result::success(LogSubscriptionPoll {
+            entries,
+            dropped_count: subscription.dropped_count(),
+            closed,
+        })
+    }
+
+    fn close_log_subscription(subscription_id: usize) {
+        // the subscription stays registered until the buffered entries are polled
+        if let Some(subscription) = BINDINGS_RUNTIME.get_subscription(subscription_id) {
+            subscription.close();
+        }
+    }
+
     fn shut_down(instance_id: usize) {
         let instance = get_instance_or_return!(instance_id);
         BINDINGS_RUNTIME.runtime.block_on(instance.shut_down());
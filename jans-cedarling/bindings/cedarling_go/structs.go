@@ -3,6 +3,7 @@ package cedarling_go
 import (
 	"encoding/json"
 	"fmt"
+	"time"
 )
 
 // Represents a cedarling request
@@ -209,3 +210,57 @@ func (r RequestUnsigned) MarshalJSON() ([]byte, error) {
 	return json.Marshal(aux)
 
 }
+
+// Represents a query over the stored logs.
+// Unset fields are not used as filters.
+type LogQuery struct {
+	// Unix timestamp in milliseconds, inclusive
+	Since *uint64 `json:"since,omitempty"`
+	// Unix timestamp in milliseconds, exclusive
+	Until *uint64 `json:"until,omitempty"`
+	// Log kind i.e. "Decision" or "System"
+	LogKind string `json:"log_kind,omitempty"`
+	// Minimum log level i.e. "INFO"
+	Level string `json:"level,omitempty"`
+	// "ALLOW" or "DENY"
+	Decision  string `json:"decision,omitempty"`
+	Principal string `json:"principal,omitempty"`
+	Action    string `json:"action,omitempty"`
+	Resource  string `json:"resource,omitempty"`
+	// ID of the last entry of the previous page
+	Cursor    string `json:"cursor,omitempty"`
+	Limit     *uint  `json:"limit,omitempty"`
+	CountOnly bool   `json:"count_only,omitempty"`
+	// "oldest_first" (default) or "newest_first"
+	Order string `json:"order,omitempty"`
+}
+
+// Represents the result of a log query
+type LogQueryResult struct {
+	Entries    []json.RawMessage `json:"entries"`
+	Count      uint              `json:"count"`
+	NextCursor *string           `json:"next_cursor"`
+}
+
+// Default interval at which a log subscription checks for new entries
+const DefaultLogPollInterval = 100 * time.Millisecond
+
+// Represents the configuration of a log subscription.
+// Unset filters are not used.
+type LogSubscriptionConfig struct {
+	// Log kind i.e. "Decision" or "System"
+	LogKind string `json:"log_kind,omitempty"`
+	// Minimum log level i.e. "INFO"
+	Level string `json:"level,omitempty"`
+	// "ALLOW" or "DENY"
+	Decision  string `json:"decision,omitempty"`
+	Principal string `json:"principal,omitempty"`
+	Action    string `json:"action,omitempty"`
+	Resource  string `json:"resource,omitempty"`
+	// Maximum number of entries waiting to be sent to the callback
+	BufferSize *uint `json:"buffer_size,omitempty"`
+	// "drop_oldest" (default) or "drop_newest"
+	OverflowPolicy string `json:"overflow_policy,omitempty"`
+	// Interval at which new entries are checked, DefaultLogPollInterval if not set
+	PollInterval time.Duration `json:"-"`
+}
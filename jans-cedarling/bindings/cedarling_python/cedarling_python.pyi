@@ -115,8 +115,27 @@ class Cedarling:
     def get_logs_by_request_id_and_tag(
         self, request_id: str, tag: str) -> List[Dict]: ...
 
+    def query_logs(self, query: Optional[Dict[str, Any]] = None) -> Dict: ...
+
+    def subscribe_logs(
+        self, config: Optional[Dict[str, Any]] = None) -> LogSubscription: ...
+
     def shut_down(self): ...
 
+@final
+class LogSubscription:
+    def __aiter__(self) -> LogSubscription: ...
+
+    async def __anext__(self) -> Dict: ...
+
+    def recv(self) -> Optional[Dict]: ...
+
+    def try_recv(self) -> Optional[Dict]: ...
+
+    def dropped_count(self) -> int: ...
+
+    def close(self): ...
+
 @final
 class Request:
     tokens: Dict[str, str]
@@ -176,6 +195,8 @@ class AuthorizeResult:
 
     def request_id(self) -> str: ...
 
+    def annotations(self) -> Dict[str, List[str]]: ...
+
 
 @final
 class AuthorizeResultResponse:
@@ -6,6 +6,7 @@
  */
 use super::authorize_result_response::AuthorizeResultResponse;
 use pyo3::prelude::*;
+use std::collections::HashMap;
 
 /// AuthorizeResult
 /// ===============
@@ -21,6 +22,10 @@ use pyo3::prelude::*;
 /// .. method:: workload(self) -> AuthorizeResultResponse
 ///     Returns the detailed response as an `AuthorizeResultResponse` object.
 ///
+/// .. method:: annotations(self) -> dict[str, list[str]]
+///     Returns the annotations of the policies that determined the decision, such as
+///     `{"obligation": ["mfa"]}`.
+///
 #[pyclass]
 pub struct AuthorizeResult {
     inner: cedarling::AuthorizeResult,
@@ -56,6 +61,15 @@ impl AuthorizeResult {
     fn request_id(&self) -> String {
         self.inner.request_id.clone()
     }
+
+    /// Get the annotations of the policies that determined the decision
+    fn annotations(&self) -> HashMap<String, Vec<String>> {
+        self.inner
+            .annotations
+            .iter()
+            .map(|(key, values)| (key.clone(), values.iter().cloned().collect()))
+            .collect()
+    }
 }
 
 impl From<cedarling::AuthorizeResult> for AuthorizeResult {
@@ -5,16 +5,18 @@
  * Copyright (c) 2024, Gluu, Inc.
  */
 
-use cedarling::LogStorage;
+use cedarling::{LogQuery, LogStorage, LogSubscriptionConfig};
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 
 use crate::authorize::authorize_result::AuthorizeResult;
 use crate::authorize::errors::authorize_error_to_py;
 use crate::authorize::request::Request;
 use crate::authorize::request_unsigned::RequestUnsigned;
 use crate::config::bootstrap_config::BootstrapConfig;
-use serde_pyobject::to_pyobject;
+use crate::log_subscription::LogSubscription;
+use serde_pyobject::{from_pyobject, to_pyobject};
 
 /// Cedarling
 /// =========
@@ -82,6 +84,26 @@ use serde_pyobject::to_pyobject;
 ///
 ///     :returns: A list of log entries matching both the request ID and tag, each converted to a Python dictionary.
 ///
+/// .. method:: query_logs(self, query: dict|None = None) -> dict
+///
+///     Retrieves log entries matching the filters of the query without removing them from storage.
+///     Supported keys are 'since', 'until' (unix timestamps in milliseconds), 'log_kind', 'level', 'decision',
+///     'principal', 'action', 'resource', 'cursor', 'limit', 'count_only' and 'order' ('oldest_first' or 'newest_first').
+///
+///     :param query: A dictionary with the filters and pagination of the query.
+///
+///     :returns: A dictionary with the 'entries' of the page, the 'count' of all matching entries and the 'next_cursor' to get the next page.
+///
+/// .. method:: subscribe_logs(self, config: dict|None = None) -> LogSubscription
+///
+///     Subscribes to the log entries logged from now on, whatever the log type.
+///     Supported keys are the filters of `query_logs` ('log_kind', 'level', 'decision', 'principal', 'action', 'resource'),
+///     'buffer_size' and 'overflow_policy' ('drop_oldest' or 'drop_newest').
+///
+///     :param config: A dictionary with the filters and buffering of the subscription.
+///
+///     :returns: A `LogSubscription` async iterator over the log entries.
+///
 /// .. method:: shut_down(self)
 ///
 ///     Closes the connections to the Lock Server and pushes all available logs.
@@ -183,6 +205,35 @@ impl Cedarling {
         })
     }
 
+    /// Returns log entries matching the query, without removing them from the storage.
+    #[pyo3(signature = (query=None))]
+    fn query_logs(&self, query: Option<Bound<'_, PyDict>>) -> PyResult<PyObject> {
+        let query: LogQuery = match query {
+            Some(query) => from_pyobject(query)
+                .map_err(|err| PyValueError::new_err(format!("invalid log query: {}", err)))?,
+            None => LogQuery::default(),
+        };
+        let result = self.inner.query_logs(&query);
+
+        Python::with_gil(|py| -> PyResult<PyObject> {
+            to_pyobject(py, &result)
+                .map(|v| v.unbind())
+                .map_err(|err| err.0)
+        })
+    }
+
+    /// Subscribes to the log entries logged from now on.
+    #[pyo3(signature = (config=None))]
+    fn subscribe_logs(&self, config: Option<Bound<'_, PyDict>>) -> PyResult<LogSubscription> {
+        let config: LogSubscriptionConfig = match config {
+            Some(config) => from_pyobject(config).map_err(|err| {
+                PyValueError::new_err(format!("invalid log subscription config: {}", err))
+            })?,
+            None => LogSubscriptionConfig::default(),
+        };
+        Ok(self.inner.subscribe_logs(config).into())
+    }
+
     /// Closes the connections to the Lock Server and pushes all available logs.
     fn shut_down(&self) {
         self.inner.shut_down();
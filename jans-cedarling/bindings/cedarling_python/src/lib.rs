@@ -14,6 +14,7 @@ use pyo3::prelude::*;
 mod authorize;
 mod cedarling;
 mod config;
+mod log_subscription;
 
 #[pymodule]
 fn cedarling_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
@@ -21,6 +22,7 @@ fn cedarling_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
     authorize::register_entities(m)?;
 
     m.add_class::<cedarling::Cedarling>()?;
+    m.add_class::<log_subscription::LogSubscription>()?;
 
     Ok(())
 }
@@ -0,0 +1,109 @@
+/*
+ * This software is available under the Apache-2.0 license.
+ * See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+ *
+ * Copyright (c) 2024, Gluu, Inc.
+ */
+
+use std::sync::Arc;
+
+use pyo3::exceptions::PyStopAsyncIteration;
+use pyo3::prelude::*;
+use serde_pyobject::to_pyobject;
+
+/// LogSubscription
+/// ===============
+///
+/// Receives the log entries logged after `Cedarling.subscribe_logs` was called.
+/// It is an async iterator that ends when the subscription is closed or Cedarling is shut down:
+///
+/// .. code-block:: python
+///
+///     async for entry in cedarling.subscribe_logs({"log_kind": "Decision"}):
+///         print(entry)
+///
+/// Methods
+/// -------
+/// .. method:: recv(self) -> dict|None
+///
+///     Waits for the next log entry, without holding the GIL.
+///     Returns None when the subscription is closed and every buffered entry was received.
+///
+/// .. method:: try_recv(self) -> dict|None
+///
+///     Returns the next log entry if one is buffered, without waiting.
+///
+/// .. method:: dropped_count(self) -> int
+///
+///     Number of log entries discarded because the buffer was full.
+///
+/// .. method:: close(self)
+///
+///     Stops receiving new log entries. The buffered entries can still be received.
+#[pyclass]
+pub struct LogSubscription {
+    inner: Arc<cedarling::LogSubscription>,
+}
+
+impl From<cedarling::LogSubscription> for LogSubscription {
+    fn from(inner: cedarling::LogSubscription) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+#[pymethods]
+impl LogSubscription {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    // the entry is received in the default executor of the event loop, so the
+    // loop is never blocked and the logging threads never wait for the GIL
+    fn __anext__<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyAny>> {
+        let event_loop = slf
+            .py()
+            .import_bound("asyncio")?
+            .call_method0("get_running_loop")?;
+        event_loop.call_method1(
+            "run_in_executor",
+            (slf.py().None(), slf.getattr("_recv_or_stop")?),
+        )
+    }
+
+    fn _recv_or_stop(&self, py: Python) -> PyResult<PyObject> {
+        match self.recv(py)? {
+            Some(entry) => Ok(entry),
+            None => Err(PyStopAsyncIteration::new_err(())),
+        }
+    }
+
+    fn recv(&self, py: Python) -> PyResult<Option<PyObject>> {
+        let inner = self.inner.clone();
+        py.allow_threads(move || inner.blocking_recv())
+            .map(|entry| entry_to_py(py, &entry))
+            .transpose()
+    }
+
+    fn try_recv(&self, py: Python) -> PyResult<Option<PyObject>> {
+        self.inner
+            .try_recv()
+            .map(|entry| entry_to_py(py, &entry))
+            .transpose()
+    }
+
+    fn dropped_count(&self) -> u64 {
+        self.inner.dropped_count()
+    }
+
+    fn close(&self) {
+        self.inner.close();
+    }
+}
+
+fn entry_to_py(py: Python, entry: &serde_json::Value) -> PyResult<PyObject> {
+    to_pyobject(py, entry)
+        .map(|v| v.unbind())
+        .map_err(|err| err.0)
+}
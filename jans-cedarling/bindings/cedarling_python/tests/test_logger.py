@@ -3,6 +3,8 @@
 #
 # Copyright (c) 2024, Gluu, Inc.
 
+import asyncio
+
 from cedarling_python import Cedarling
 from config import load_bootstrap_config
 
@@ -91,3 +93,65 @@ def test_stdout_logger():
     # We should not have logs in memory (confirm logs are printed only to stdout)
     assert len(cedarling.get_log_ids()) == 0
     assert len(cedarling.pop_logs()) == 0
+
+
+def test_query_logs():
+    config = load_bootstrap_config(log_type="memory", log_ttl=60)
+    cedarling = Cedarling(config)
+
+    # init logs are not decision logs
+    cedarling.pop_logs()
+    cedarling.authorize(REQUEST)
+
+    result = cedarling.query_logs({"log_kind": "Decision", "limit": 1})
+    assert result["count"] == 1, "one decision log should be found"
+    assert len(result["entries"]) == 1
+    assert result["entries"][0]["log_kind"] == "Decision"
+    assert result["next_cursor"] is None
+
+    result = cedarling.query_logs({"count_only": True})
+    assert result["count"] > 1
+    assert len(result["entries"]) == 0
+
+    # querying doesn't remove the logs
+    assert cedarling.query_logs()["count"] == len(cedarling.get_log_ids())
+
+    try:
+        cedarling.query_logs({"order": "random"})
+    except ValueError:
+        pass
+    else:
+        assert False, "ValueError was not raised for an invalid query"
+
+
+def test_subscribe_logs():
+    config = load_bootstrap_config(log_type="off")
+    cedarling = Cedarling(config)
+
+    # subscriptions receive the logs whatever the log type
+    subscription = cedarling.subscribe_logs({"log_kind": "Decision"})
+    cedarling.authorize(REQUEST)
+
+    async def next_entry():
+        async for entry in subscription:
+            return entry
+
+    entry = asyncio.run(next_entry())
+    assert entry["log_kind"] == "Decision"
+    assert subscription.try_recv() is None
+    assert subscription.dropped_count() == 0
+
+    # the iterator ends once the subscription is closed
+    subscription.close()
+
+    async def collect():
+        return [entry async for entry in subscription]
+
+    assert asyncio.run(collect()) == []
+
+    try:
+        cedarling.subscribe_logs({"overflow_policy": "block"})
+    except ValueError:
+        pass
+    else:
+        assert False, "ValueError was not raised for an invalid subscription config"
@@ -6,7 +6,9 @@
 
 use cedarling::{self as core, BootstrapConfig, BootstrapConfigRaw, LogStorage};
 use std::sync::Arc;
+mod log_subscription;
 mod result;
+use log_subscription::*;
 use result::*;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -306,6 +308,48 @@ impl Cedarling {
         Ok(result)
     }
 
+    /// Get logs matching the filters of the query, without removing them from the storage.
+    /// The query is a JSON object with the optional keys `since`, `until` (unix timestamps in
+    /// milliseconds), `log_kind`, `level`, `decision`, `principal`, `action`, `resource`,
+    /// `cursor`, `limit`, `count_only` and `order`.
+    #[uniffi::method]
+    pub fn query_logs(&self, query_json: &str) -> Result<LogQueryResult, LogError> {
+        let query: core::LogQuery =
+            serde_json::from_str(query_json).map_err(|e| LogError::LoggingFailed {
+                error_msg: format!("invalid log query: {e}"),
+            })?;
+
+        self.inner
+            .query_logs(&query)
+            .try_into()
+            .map_err(|e: serde_json::Error| LogError::LoggingFailed {
+                error_msg: e.to_string(),
+            })
+    }
+
+    /// Subscribes to the log entries logged from now on, whatever the log type.
+    /// The entries are sent to the listener from a dedicated thread until the returned
+    /// subscription is closed or Cedarling is shut down.
+    /// The config accepts the filters of `query_logs` (`log_kind`, `level`, `decision`,
+    /// `principal`, `action`, `resource`), `buffer_size` and `overflow_policy`.
+    #[uniffi::method]
+    pub fn subscribe_logs(
+        &self,
+        config_json: &str,
+        listener: Arc<dyn LogListener>,
+    ) -> Result<Arc<LogSubscription>, LogError> {
+        let config: core::LogSubscriptionConfig =
+            serde_json::from_str(config_json).map_err(|e| LogError::LoggingFailed {
+                error_msg: format!("invalid log subscription config: {e}"),
+            })?;
+
+        LogSubscription::start(self.inner.subscribe_logs(config), listener)
+            .map(Arc::new)
+            .map_err(|e| LogError::LoggingFailed {
+                error_msg: format!("failed to start log subscription: {e}"),
+            })
+    }
+
     /// Closes the connections to the Lock Server and pushes all available logs.
     #[uniffi::method]
     pub fn shut_down(&self) {
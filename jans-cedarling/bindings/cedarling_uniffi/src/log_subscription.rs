@@ -0,0 +1,63 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use cedarling as core;
+use std::sync::Arc;
+use std::thread;
+
+// Callback implemented by the foreign code to receive the log entries
+#[uniffi::export(with_foreign)]
+pub trait LogListener: Send + Sync {
+    // Called with each log entry as a JSON string, in the order they were logged
+    fn on_log(&self, entry_json: String);
+}
+
+// Handle of a log subscription, the entries are sent to the listener until it is closed
+#[derive(uniffi::Object)]
+pub struct LogSubscription {
+    inner: Arc<core::LogSubscription>,
+}
+
+impl LogSubscription {
+    // Starts a thread that sends the entries of the subscription to the listener
+    pub(crate) fn start(
+        subscription: core::LogSubscription,
+        listener: Arc<dyn LogListener>,
+    ) -> std::io::Result<Self> {
+        let inner = Arc::new(subscription);
+        let receiver = inner.clone();
+        thread::Builder::new()
+            .name("cedarling-log-subscription".to_string())
+            .spawn(move || {
+                while let Some(entry) = receiver.blocking_recv() {
+                    listener.on_log(entry.to_string());
+                }
+            })?;
+        Ok(Self { inner })
+    }
+}
+
+#[uniffi::export]
+impl LogSubscription {
+    // Number of log entries discarded because the buffer was full
+    #[uniffi::method]
+    pub fn dropped_count(&self) -> u64 {
+        self.inner.dropped_count()
+    }
+
+    // Stops sending new log entries, the buffered entries are still sent
+    #[uniffi::method]
+    pub fn close(&self) {
+        self.inner.close();
+    }
+}
+
+impl Drop for LogSubscription {
+    fn drop(&mut self) {
+        // the listener thread holds the subscription too, so it has to be closed
+        // explicitly for the thread to stop
+        self.inner.close();
+    }
+}
@@ -14,6 +14,9 @@ pub struct AuthorizeResult {
     pub principals: HashMap<String, Response>,
     pub decision: bool,
     pub request_id: String,
+    // Annotations of the policies that determined the decision, keyed by the annotation
+    pub annotations: HashMap<String, Vec<String>>,
+    pub principal_annotations: HashMap<String, HashMap<String, Vec<String>>>,
 }
 
 #[derive(Debug, uniffi::Record)]
@@ -62,6 +65,13 @@ impl From<cedar_policy::Response> for Response {
     }
 }
 
+fn annotations_to_map(annotations: core::PolicyAnnotations) -> HashMap<String, Vec<String>> {
+    annotations
+        .into_iter()
+        .map(|(key, values)| (key, values.into_iter().collect()))
+        .collect()
+}
+
 impl From<core::AuthorizeResult> for AuthorizeResult {
     fn from(result: core::AuthorizeResult) -> Self {
         AuthorizeResult {
@@ -74,6 +84,39 @@ impl From<core::AuthorizeResult> for AuthorizeResult {
                 .collect(),
             decision: result.decision,
             request_id: result.request_id,
+            annotations: annotations_to_map(result.annotations),
+            principal_annotations: result
+                .principal_annotations
+                .into_iter()
+                .map(|(k, v)| (k.into(), annotations_to_map(v)))
+                .collect(),
         }
     }
 }
+
+/// A page of log entries returned by `Cedarling::query_logs`.
+#[derive(Debug, uniffi::Record)]
+pub struct LogQueryResult {
+    /// Log entries of the page as JSON strings.
+    pub entries: Vec<String>,
+    /// Number of entries matching the filters, regardless of the cursor and limit.
+    pub count: u64,
+    /// Cursor to get the next page, if there are more matching entries.
+    pub next_cursor: Option<String>,
+}
+
+impl TryFrom<core::LogQueryResult> for LogQueryResult {
+    type Error = serde_json::Error;
+
+    fn try_from(result: core::LogQueryResult) -> Result<Self, Self::Error> {
+        Ok(LogQueryResult {
+            entries: result
+                .entries
+                .iter()
+                .map(serde_json::to_string)
+                .collect::<Result<_, _>>()?,
+            count: result.count as u64,
+            next_cursor: result.next_cursor,
+        })
+    }
+}
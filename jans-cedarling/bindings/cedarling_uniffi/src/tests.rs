@@ -4,8 +4,10 @@
 // Copyright (c) 2024, Gluu, Inc.
 
 use crate::Cedarling;
-use crate::{EntityData, JsonValue};
+use crate::{EntityData, JsonValue, LogListener};
 use serde_json::json;
+use std::sync::{Mutex, mpsc};
+use std::time::Duration;
 use std::{collections::HashMap, sync::Arc};
 use test_utils::token_claims::generate_token_using_claims;
 
@@ -206,3 +208,96 @@ fn test_authorize_unsigned_success() {
         result
     );
 }
+
+#[test]
+fn test_query_logs() {
+    let cedarling = Cedarling::load_from_file(String::from(
+        "../../bindings/cedarling_uniffi/test_files/bootstrap.json",
+    ))
+    .expect("Error in initializing Cedarling");
+
+    // logs are written to stdout, so there is nothing to query
+    let result = cedarling
+        .query_logs(r#"{"log_kind": "Decision", "limit": 10}"#)
+        .expect("query should be executed successfully");
+    assert_eq!(result.count, 0);
+    assert!(result.entries.is_empty());
+    assert_eq!(result.next_cursor, None);
+
+    assert!(
+        cedarling.query_logs(r#"{"order": "random"}"#).is_err(),
+        "invalid query should return an error"
+    );
+}
+
+#[test]
+fn test_subscribe_logs() {
+    struct ChannelListener(Mutex<mpsc::Sender<String>>);
+
+    impl LogListener for ChannelListener {
+        fn on_log(&self, entry_json: String) {
+            let _ = self.0.lock().unwrap().send(entry_json);
+        }
+    }
+
+    let cedarling = Cedarling::load_from_file(String::from(
+        "../../bindings/cedarling_uniffi/test_files/bootstrap.json",
+    ))
+    .expect("Error in initializing Cedarling");
+
+    let (sender, receiver) = mpsc::channel();
+    let subscription = cedarling
+        .subscribe_logs(
+            r#"{"log_kind": "Decision"}"#,
+            Arc::new(ChannelListener(Mutex::new(sender))),
+        )
+        .expect("subscription should be started");
+
+    let resource = json!({
+        "cedar_entity_mapping": {"entity_type": "Jans::Issue", "id": "some_id"},
+        "app_id": "admin_ui_id",
+        "name": "My App",
+        "permission": "view_clients",
+        "sub": "qzxn1Scrb9lWtGxVedMCky-Ql_ILspZaQA6fyuYktw0"
+    });
+    let principals = ["Jans::TestPrincipal1", "Jans::TestPrincipal2"].map(|entity_type| {
+        let principal = json!({
+            "cedar_entity_mapping": {"entity_type": entity_type, "id": "some_principal_id"},
+            "is_ok": true
+        });
+        Arc::new(EntityData::from_json(principal.to_string()).unwrap())
+    });
+    cedarling
+        .authorize_unsigned(
+            principals.into(),
+            r#"Jans::Action::"UpdateTestPrincipal""#.to_string(),
+            Arc::new(EntityData::from_json(resource.to_string()).unwrap()),
+            JsonValue("{}".to_string()),
+        )
+        .expect("Should be executed successfully.");
+
+    // the listener is called from another thread, so the entry is awaited
+    let entry = receiver
+        .recv_timeout(Duration::from_secs(5))
+        .expect("decision log should be sent to the listener");
+    let entry: serde_json::Value = serde_json::from_str(&entry).expect("entry should be JSON");
+    assert_eq!(entry["log_kind"], "Decision");
+    assert_eq!(subscription.dropped_count(), 0);
+
+    // the listener thread stops once the subscription is closed
+    subscription.close();
+    assert!(matches!(
+        receiver.recv_timeout(Duration::from_secs(5)),
+        Err(mpsc::RecvTimeoutError::Disconnected)
+    ));
+
+    assert!(
+        cedarling
+            .subscribe_logs(
+                r#"{"overflow_policy": "block"}"#,
+                Arc::new(ChannelListener(Mutex::new(mpsc::channel().0))),
+            )
+            .is_err(),
+        "invalid config should return an error"
+    );
+}
@@ -4,14 +4,18 @@
 // Copyright (c) 2024, Gluu, Inc.
 
 use cedarling::bindings::cedar_policy;
-use cedarling::{BootstrapConfig, BootstrapConfigRaw, LogStorage, Request, RequestUnsigned};
+use cedarling::{
+    BootstrapConfig, BootstrapConfigRaw, LogQuery, LogStorage, LogSubscriptionConfig, Request,
+    RequestUnsigned,
+};
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 use serde_json::json;
 use serde_wasm_bindgen::Error;
 use std::collections::HashMap;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
-use wasm_bindgen_futures::js_sys::{Array, Map, Object, Reflect};
+use wasm_bindgen_futures::future_to_promise;
+use wasm_bindgen_futures::js_sys::{Array, Map, Object, Promise, Reflect, Symbol};
 
 #[cfg(test)]
 mod tests;
@@ -168,12 +172,133 @@ impl Cedarling {
             .collect()
     }
 
+    /// Get logs matching the filters of the query, without removing them from the storage.
+    /// The query can be `Map`, `Object` or `undefined`.
+    /// Returns `Object` with the `entries` of the page, the `count` of all matching entries
+    /// and the `next_cursor` to get the next page.
+    pub fn query_logs(&self, query: JsValue) -> Result<JsValue, Error> {
+        let query: LogQuery = if query.is_undefined() || query.is_null() {
+            LogQuery::default()
+        } else if query.is_instance_of::<Map>() {
+            serde_wasm_bindgen::from_value(Object::from_entries(&query)?.into())?
+        } else {
+            serde_wasm_bindgen::from_value(query)?
+        };
+
+        let result = self.instance.query_logs(&query);
+        let result = serde_json::to_value(result).map_err(|err| Error::new(err.to_string()))?;
+        convert_json_to_object(&result)
+    }
+
+    /// Subscribe to the logs logged from now on, whatever the log type.
+    /// The config can be `Map`, `Object` or `undefined`, with the filters of `query_logs`,
+    /// the `buffer_size` and the `overflow_policy`.
+    /// Returns a `LogSubscription` that can be used with `for await`.
+    #[wasm_bindgen(unchecked_return_type = "LogSubscription")]
+    pub fn subscribe_logs(&self, config: JsValue) -> Result<JsValue, Error> {
+        let config: LogSubscriptionConfig = if config.is_undefined() || config.is_null() {
+            LogSubscriptionConfig::default()
+        } else if config.is_instance_of::<Map>() {
+            serde_wasm_bindgen::from_value(Object::from_entries(&config)?.into())?
+        } else {
+            serde_wasm_bindgen::from_value(config)?
+        };
+
+        let subscription = LogSubscription {
+            inner: Rc::new(self.instance.subscribe_logs(config)),
+        };
+        subscription.into_async_iterable()
+    }
+
     /// Closes the connections to the Lock Server and pushes all available logs.
     pub async fn shut_down(&self) {
         self.instance.shut_down().await;
     }
 }
 
+/// Receives the logs logged after `Cedarling.subscribe_logs` was called.
+/// Implements the async iterator protocol, so it can be used with `for await`.
+#[wasm_bindgen]
+pub struct LogSubscription {
+    inner: Rc<cedarling::LogSubscription>,
+}
+
+#[wasm_bindgen]
+impl LogSubscription {
+    /// Wait for the next log entry.
+    /// Resolves to `{ done: true }` when the subscription is closed and every buffered
+    /// entry was received, otherwise to `{ done: false, value: entry }`.
+    #[wasm_bindgen(unchecked_return_type = "Promise<IteratorResult<any>>")]
+    pub fn next(&self) -> Promise {
+        let inner = self.inner.clone();
+        future_to_promise(async move {
+            match inner.recv().await {
+                Some(entry) => iterator_result(false, convert_json_to_object(&entry)?),
+                None => iterator_result(true, JsValue::UNDEFINED),
+            }
+        })
+    }
+
+    /// Close the subscription, called when a `for await` loop exits early.
+    #[wasm_bindgen(
+        js_name = "return",
+        unchecked_return_type = "Promise<IteratorResult<any>>"
+    )]
+    pub fn return_(&self) -> Promise {
+        self.close();
+        future_to_promise(async { iterator_result(true, JsValue::UNDEFINED) })
+    }
+
+    /// Get the next log entry if one is buffered, without waiting.
+    pub fn try_recv(&self) -> Result<JsValue, Error> {
+        match self.inner.try_recv() {
+            Some(entry) => convert_json_to_object(&entry),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    /// Number of log entries discarded because the buffer was full.
+    pub fn dropped_count(&self) -> u64 {
+        self.inner.dropped_count()
+    }
+
+    /// Stop receiving new log entries. The buffered entries can still be received.
+    pub fn close(&self) {
+        self.inner.close();
+    }
+}
+
+impl LogSubscription {
+    /// Convert to a JS object with a `Symbol.asyncIterator` method.
+    ///
+    /// The method returns a new wrapper of the same subscription instead of `this`,
+    /// so the JS object doesn't reference itself from Rust.
+    fn into_async_iterable(self) -> Result<JsValue, Error> {
+        let inner = self.inner.clone();
+        let async_iterator = Closure::<dyn Fn() -> JsValue>::new(move || {
+            LogSubscription {
+                inner: inner.clone(),
+            }
+            .into()
+        });
+
+        let value = JsValue::from(self);
+        Reflect::set(
+            &value,
+            &Symbol::async_iterator(),
+            &async_iterator.into_js_value(),
+        )?;
+        Ok(value)
+    }
+}
+
+fn iterator_result(done: bool, value: JsValue) -> Result<JsValue, JsValue> {
+    let result = Object::new();
+    Reflect::set(&result, &"done".into(), &done.into())?;
+    Reflect::set(&result, &"value".into(), &value)?;
+    Ok(result.into())
+}
+
 /// convert json to js object
 fn convert_json_to_object(json_value: &serde_json::Value) -> Result<JsValue, Error> {
     let js_map_value = serde_wasm_bindgen::to_value(json_value)?;
@@ -242,6 +367,12 @@ pub struct AuthorizeResult {
     /// Request ID of the authorization request
     #[wasm_bindgen(getter_with_clone)]
     pub request_id: String,
+
+    /// Annotations of the policies that determined the decision,
+    /// such as `@obligation("mfa")`
+    #[wasm_bindgen(skip)]
+    #[serde(skip_serializing_if = "cedarling::PolicyAnnotations::is_empty")]
+    pub annotations: cedarling::PolicyAnnotations,
 }
 
 #[wasm_bindgen]
@@ -272,6 +403,7 @@ impl From<cedarling::AuthorizeResult> for AuthorizeResult {
                 .collect(),
             decision: value.decision,
             request_id: value.request_id,
+            annotations: value.annotations,
         }
     }
 }
@@ -14,6 +14,7 @@ use serde::Deserialize;
 use serde_json::json;
 use std::{collections::HashMap, sync::LazyLock};
 use test_utils::token_claims::generate_token_using_claims;
+use wasm_bindgen_futures::js_sys::Function;
 use wasm_bindgen_test::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
@@ -436,11 +437,37 @@ async fn test_memory_log_interface() {
     let js_request =
         serde_wasm_bindgen::to_value(&request).expect("Request should be converted to JsObject");
 
+    let subscription_config = serde_wasm_bindgen::to_value(&json!({"log_kind": "Decision"}))
+        .expect("subscription config should be converted to JsValue");
+    let subscription = instance
+        .subscribe_logs(subscription_config)
+        .expect("subscribe_logs should not throw error");
+
     let _result = instance
         .authorize(js_request)
         .await
         .expect("authorize request should be executed");
 
+    let next = Reflect::get(&subscription, &"next".into())
+        .expect("subscription should have next method")
+        .unchecked_into::<Function>()
+        .call0(&subscription)
+        .expect("next should return a promise");
+    let next_result = wasm_bindgen_futures::JsFuture::from(Promise::unchecked_from_js(next))
+        .await
+        .expect("next promise should be resolved");
+    assert_eq!(
+        Reflect::get(&next_result, &"done".into()).expect("next result should have done"),
+        JsValue::FALSE,
+        "subscription should receive the decision log"
+    );
+    let next_entry =
+        Reflect::get(&next_result, &"value".into()).expect("next result should have value");
+    assert_eq!(
+        Reflect::get(&next_entry, &"log_kind".into()).expect("log entry should have log_kind"),
+        JsValue::from_str("Decision")
+    );
+
     let js_log_ids = instance.get_log_ids();
     let logs_count = js_log_ids.length();
 
@@ -454,6 +481,23 @@ async fn test_memory_log_interface() {
         assert_ne!(log_val, JsValue::NULL, "log result should be not null")
     }
 
+    let query = serde_wasm_bindgen::to_value(&json!({"log_kind": "Decision", "limit": 1}))
+        .expect("query should be converted to JsValue");
+    let query_result = instance
+        .query_logs(query)
+        .expect("query_logs should not throw error");
+    let query_count =
+        Reflect::get(&query_result, &"count".into()).expect("query result should have count");
+    assert_eq!(
+        query_count.as_f64(),
+        Some(1.0),
+        "one decision log should be found"
+    );
+    let query_entries = Array::from(
+        &Reflect::get(&query_result, &"entries".into()).expect("query result should have entries"),
+    );
+    assert_eq!(query_entries.length(), 1, "page should contain one entry");
+
     let pop_logs_result = instance.pop_logs().expect("pop_logs not throw error");
     assert_eq!(
         logs_count,
@@ -533,7 +577,7 @@ async fn test_authorize_unsigned() {
             "country": "US"
         }))
         .expect("ResourceData should be deserialized correctly"),
-        context: json!({})
+        context: json!({}),
     };
 
     let result = instance
@@ -17,6 +17,7 @@ use test_utils::token_claims::{
 use tokio::runtime::Runtime;
 
 const POLICY_STORE: &str = include_str!("../../test_files/policy-store_ok.yaml");
+const POLICY_STORE_ID: &str = "a1bf93115de86de760ee0bea1d529b521489e5a11747";
 
 fn without_jwt_validation_benchmark(c: &mut Criterion) {
     let runtime = Runtime::new().expect("init tokio runtime");
@@ -85,6 +86,29 @@ fn with_jwt_validation_hs256_benchmark(c: &mut Criterion) {
     oidc_endpoint.assert();
 }
 
+fn policy_count_benchmark(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("init tokio runtime");
+    let request =
+        prepare_cedarling_request_for_without_jwt_validation().expect("should prepare request");
+
+    let mut group = c.benchmark_group("authz_authorize_policy_count");
+    for policy_count in [100, 1_000, 10_000] {
+        let cedarling = runtime
+            .block_on(prepare_cedarling_with_generated_policies(policy_count))
+            .expect("should initialize Cedarling");
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(policy_count),
+            &runtime,
+            |b, rt| {
+                b.to_async(rt)
+                    .iter(|| cedarling.authorize(black_box(request.clone())));
+            },
+        );
+    }
+    group.finish();
+}
+
 fn measurement_config() -> Criterion {
     Criterion::default()
         .measurement_time(Duration::from_secs(5))
@@ -94,7 +118,7 @@ fn measurement_config() -> Criterion {
 criterion_group! {
     name = authz_benchmark;
     config = measurement_config();
-    targets = without_jwt_validation_benchmark, with_jwt_validation_hs256_benchmark
+    targets = without_jwt_validation_benchmark, with_jwt_validation_hs256_benchmark, policy_count_benchmark
 }
 
 criterion_main!(authz_benchmark);
@@ -102,6 +126,7 @@ criterion_main!(authz_benchmark);
 async fn prepare_cedarling_without_jwt_validation() -> Result<Cedarling, InitCedarlingError> {
     let bootstrap_config = BootstrapConfig {
         application_name: "test_app".to_string(),
+        pdp_id: None,
         log_config: LogConfig {
             log_type: LogTypeConfig::Off,
             log_level: LogLevel::DEBUG,
@@ -109,6 +134,10 @@ async fn prepare_cedarling_without_jwt_validation() -> Result<Cedarling, InitCed
         policy_store_config: PolicyStoreConfig {
             source: cedarling::PolicyStoreSource::Yaml(POLICY_STORE.to_string()),
         },
+        policy_validation_config: PolicyValidationConfig::default(),
+        policy_store_poll_interval: None,
+        policy_store_signature_config: None,
+        shadow_policy_store_config: None,
         jwt_config: JwtConfig::new_without_validation(),
         authorization_config: AuthorizationConfig {
             use_user_principal: true,
@@ -119,6 +148,7 @@ async fn prepare_cedarling_without_jwt_validation() -> Result<Cedarling, InitCed
         },
         entity_builder_config: EntityBuilderConfig::default().with_workload().with_user(),
         lock_config: None,
+        http_config: HttpConfig::default(),
         max_base64_size: None,
         max_default_entities: None,
         token_cache_max_ttl_secs: 60,
@@ -134,17 +164,16 @@ async fn prepare_cedarling_with_jwt_validation(
         serde_yml::from_str::<serde_yml::Value>(POLICY_STORE).expect("a valid YAML policy store");
 
     // We overwrite the idp endpoint here with out mock server
-    policy_store["policy_stores"]["a1bf93115de86de760ee0bea1d529b521489e5a11747"]["trusted_issuers"]
-        ["Jans123123"]["openid_configuration_endpoint"] =
+    policy_store["policy_stores"][POLICY_STORE_ID]["trusted_issuers"]["Jans123123"]["openid_configuration_endpoint"] =
         format!("{}/.well-known/openid-configuration", base_idp_url).into();
 
     // Also update the AnotherIssuer to use the mock server
-    policy_store["policy_stores"]["a1bf93115de86de760ee0bea1d529b521489e5a11747"]["trusted_issuers"]
-        ["AnotherIssuer"]["openid_configuration_endpoint"] =
+    policy_store["policy_stores"][POLICY_STORE_ID]["trusted_issuers"]["AnotherIssuer"]["openid_configuration_endpoint"] =
         format!("{}/.well-known/openid-configuration", base_idp_url).into();
 
     let bootstrap_config = BootstrapConfig {
         application_name: "test_app".to_string(),
+        pdp_id: None,
         log_config: LogConfig {
             log_type: LogTypeConfig::Off,
             log_level: LogLevel::DEBUG,
@@ -154,12 +183,82 @@ async fn prepare_cedarling_with_jwt_validation(
                 serde_yml::to_string(&policy_store).expect("serialize policy store to YAML"),
             ),
         },
+        policy_validation_config: PolicyValidationConfig::default(),
+        policy_store_poll_interval: None,
+        policy_store_signature_config: None,
+        shadow_policy_store_config: None,
         jwt_config: JwtConfig {
             jwks: None,
             jwt_sig_validation: true,
             jwt_status_validation: false,
             signature_algorithms_supported: HashSet::from([Algorithm::HS256]),
+            ..Default::default()
+        },
+        authorization_config: AuthorizationConfig {
+            use_user_principal: true,
+            use_workload_principal: true,
+            principal_bool_operator: JsonRule::default(),
+            id_token_trust_mode: IdTokenTrustMode::Never,
+            ..Default::default()
         },
+        entity_builder_config: EntityBuilderConfig::default().with_workload().with_user(),
+        lock_config: None,
+        http_config: HttpConfig::default(),
+        max_base64_size: None,
+        max_default_entities: None,
+        token_cache_max_ttl_secs: 60,
+    };
+
+    Cedarling::new(&bootstrap_config).await
+}
+
+/// Prepares Cedarling with the policies of the policy store and `policy_count` generated
+/// policies for the `Update` action of the schema, each one allowing the users or the
+/// workloads of another organization. The generated policies apply to the requests of
+/// the benchmark without changing their decisions.
+async fn prepare_cedarling_with_generated_policies(
+    policy_count: usize,
+) -> Result<Cedarling, InitCedarlingError> {
+    let mut policy_store =
+        serde_yml::from_str::<serde_yml::Value>(POLICY_STORE).expect("a valid YAML policy store");
+
+    let policies = &mut policy_store["policy_stores"][POLICY_STORE_ID]["policies"];
+    for i in 0..policy_count {
+        let principal_type = if i % 2 == 0 { "Workload" } else { "User" };
+        let policy = json!({
+            "description": format!("generated policy {i}"),
+            "creation_date": "2024-09-20T17:22:39.996050",
+            "policy_content": {
+                "encoding": "none",
+                "content_type": "cedar",
+                "body": format!(
+                    "permit(principal is Jans::{principal_type}, \
+                    action == Jans::Action::\"Update\", resource is Jans::Issue) \
+                    when {{ resource.org_id == \"generated_org_{i}\" }};"
+                ),
+            },
+        });
+        policies[format!("generated_{i}")] =
+            serde_yml::to_value(policy).expect("serialize generated policy to YAML");
+    }
+
+    let bootstrap_config = BootstrapConfig {
+        application_name: "test_app".to_string(),
+        pdp_id: None,
+        log_config: LogConfig {
+            log_type: LogTypeConfig::Off,
+            log_level: LogLevel::DEBUG,
+        },
+        policy_store_config: PolicyStoreConfig {
+            source: cedarling::PolicyStoreSource::Yaml(
+                serde_yml::to_string(&policy_store).expect("serialize policy store to YAML"),
+            ),
+        },
+        policy_validation_config: PolicyValidationConfig::default(),
+        policy_store_poll_interval: None,
+        policy_store_signature_config: None,
+        shadow_policy_store_config: None,
+        jwt_config: JwtConfig::new_without_validation(),
         authorization_config: AuthorizationConfig {
             use_user_principal: true,
             use_workload_principal: true,
@@ -169,6 +268,7 @@ async fn prepare_cedarling_with_jwt_validation(
         },
         entity_builder_config: EntityBuilderConfig::default().with_workload().with_user(),
         lock_config: None,
+        http_config: HttpConfig::default(),
         max_base64_size: None,
         max_default_entities: None,
         token_cache_max_ttl_secs: 60,
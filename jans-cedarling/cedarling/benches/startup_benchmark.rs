@@ -5,10 +5,20 @@
 
 use cedarling::*;
 use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use jsonwebtoken::Algorithm;
+use mockito::Matcher;
+use serde_json::json;
+use std::collections::HashSet;
+use std::time::Duration;
 use std::{hint::black_box, sync::LazyLock};
+use test_utils::token_claims::{generate_jwks, generate_keypair_hs256};
 use tokio::runtime::Runtime;
 
 const POLICY_STORE: &str = include_str!("../../test_files/policy-store_ok.yaml");
+const POLICY_STORE_ID: &str = "a1bf93115de86de760ee0bea1d529b521489e5a11747";
+const TRUSTED_ISSUER_COUNT: usize = 20;
+/// Simulated latency of the responses of the identity providers
+const IDP_LATENCY: Duration = Duration::from_millis(10);
 
 fn local_policy_store_benchmark(c: &mut Criterion) {
     let runtime = Runtime::new().expect("init tokio runtime");
@@ -23,11 +33,65 @@ fn local_policy_store_benchmark(c: &mut Criterion) {
     );
 }
 
-criterion_group!(cedarling_startup_benchmark, local_policy_store_benchmark,);
+/// Startup with many trusted issuers, initialized one at a time and concurrently
+fn trusted_issuers_benchmark(c: &mut Criterion) {
+    let mut mock_server = mockito::Server::new();
+    let runtime = Runtime::new().expect("init tokio runtime");
+
+    let oidc = json!({
+        "issuer": mock_server.url(),
+        "jwks_uri": &format!("{}/jwks", mock_server.url()),
+    })
+    .to_string();
+    let _oidc_endpoint = mock_server
+        .mock(
+            "GET",
+            Matcher::Regex(r"^/issuer\d+/\.well-known/openid-configuration$".to_string()),
+        )
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_chunked_body(move |w| {
+            std::thread::sleep(IDP_LATENCY);
+            w.write_all(oidc.as_bytes())
+        })
+        .create();
+
+    let keys = generate_keypair_hs256(Some("some_hs256_key"));
+    let jwks = json!({"keys": generate_jwks(&vec![keys]).keys}).to_string();
+    let _jwks_endpoint = mock_server
+        .mock("GET", "/jwks")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_chunked_body(move |w| {
+            std::thread::sleep(IDP_LATENCY);
+            w.write_all(jwks.as_bytes())
+        })
+        .create();
+
+    let mut group = c.benchmark_group("cedarling_startup_trusted_issuers");
+    for max_concurrency in [1, 8] {
+        let config = bootstrap_config_with_trusted_issuers(&mock_server.url(), max_concurrency);
+        group.bench_with_input(
+            BenchmarkId::new("max_concurrency", max_concurrency),
+            &runtime,
+            |b, rt| {
+                b.to_async(rt).iter(|| Cedarling::new(black_box(&config)));
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    cedarling_startup_benchmark,
+    local_policy_store_benchmark,
+    trusted_issuers_benchmark,
+);
 criterion_main!(cedarling_startup_benchmark);
 
 static BSCONFIG_LOCAL: LazyLock<BootstrapConfig> = LazyLock::new(|| BootstrapConfig {
     application_name: "test_app".to_string(),
+    pdp_id: None,
     log_config: LogConfig {
         log_type: LogTypeConfig::Off,
         log_level: LogLevel::DEBUG,
@@ -35,6 +99,10 @@ static BSCONFIG_LOCAL: LazyLock<BootstrapConfig> = LazyLock::new(|| BootstrapCon
     policy_store_config: PolicyStoreConfig {
         source: cedarling::PolicyStoreSource::Yaml(POLICY_STORE.to_string()),
     },
+    policy_validation_config: PolicyValidationConfig::default(),
+    policy_store_poll_interval: None,
+    policy_store_signature_config: None,
+    shadow_policy_store_config: None,
     jwt_config: JwtConfig::new_without_validation(),
     authorization_config: AuthorizationConfig {
         use_user_principal: true,
@@ -47,7 +115,49 @@ static BSCONFIG_LOCAL: LazyLock<BootstrapConfig> = LazyLock::new(|| BootstrapCon
         .with_user()
         .with_workload(),
     lock_config: None,
+    http_config: HttpConfig::default(),
     max_base64_size: None,
     max_default_entities: None,
     token_cache_max_ttl_secs: 60,
 });
+
+/// Bootstrap config with [`TRUSTED_ISSUER_COUNT`] trusted issuers served by the mock server
+fn bootstrap_config_with_trusted_issuers(
+    base_idp_url: &str,
+    max_concurrency: usize,
+) -> BootstrapConfig {
+    let mut policy_store =
+        serde_yml::from_str::<serde_yml::Value>(POLICY_STORE).expect("a valid YAML policy store");
+
+    let trusted_issuers = (0..TRUSTED_ISSUER_COUNT)
+        .map(|i| {
+            (
+                format!("Issuer{i}"),
+                json!({
+                    "name": format!("Issuer {i}"),
+                    "description": "A Test IDP",
+                    "openid_configuration_endpoint":
+                        format!("{base_idp_url}/issuer{i}/.well-known/openid-configuration"),
+                }),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>();
+    policy_store["policy_stores"][POLICY_STORE_ID]["trusted_issuers"] =
+        serde_yml::to_value(trusted_issuers).expect("serialize trusted issuers to YAML");
+
+    BootstrapConfig {
+        policy_store_config: PolicyStoreConfig {
+            source: cedarling::PolicyStoreSource::Yaml(
+                serde_yml::to_string(&policy_store).expect("serialize policy store to YAML"),
+            ),
+        },
+        jwt_config: JwtConfig {
+            jwks: None,
+            jwt_sig_validation: true,
+            jwt_status_validation: false,
+            signature_algorithms_supported: HashSet::from([Algorithm::HS256]),
+            trusted_issuer_init: TrustedIssuerInitConfig::new(max_concurrency, 0, false),
+        },
+        ..BSCONFIG_LOCAL.clone()
+    }
+}
@@ -20,6 +20,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         jwt_sig_validation: true,
         jwt_status_validation: false,
         signature_algorithms_supported: HashSet::from_iter([Algorithm::HS256, Algorithm::RS256]),
+        ..Default::default()
     };
 
     // You must change this with your own tokens
@@ -32,6 +33,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // policy store configuration.
     let cedarling = Cedarling::new(&BootstrapConfig {
         application_name: "test_app".to_string(),
+        pdp_id: None,
         log_config: LogConfig {
             log_type: LogTypeConfig::StdOut,
             log_level: LogLevel::INFO,
@@ -39,6 +41,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         policy_store_config: PolicyStoreConfig {
             source: PolicyStoreSource::Yaml(POLICY_STORE_RAW_YAML.to_string()),
         },
+        policy_validation_config: PolicyValidationConfig::default(),
+        policy_store_poll_interval: None,
+        policy_store_signature_config: None,
+        shadow_policy_store_config: None,
         jwt_config,
         authorization_config: AuthorizationConfig {
             use_user_principal: true,
@@ -54,6 +60,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         },
         entity_builder_config: EntityBuilderConfig::default().with_user().with_workload(),
         lock_config: None,
+        http_config: HttpConfig::default(),
         max_default_entities: None,
         max_base64_size: None,
         token_cache_max_ttl_secs: 60,
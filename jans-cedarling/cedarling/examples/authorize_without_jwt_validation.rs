@@ -12,6 +12,7 @@ static POLICY_STORE_RAW: &str = include_str!("../../test_files/policy-store_ok.y
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cedarling = Cedarling::new(&BootstrapConfig {
         application_name: "test_app".to_string(),
+        pdp_id: None,
         log_config: LogConfig {
             log_type: LogTypeConfig::StdOut,
             log_level: LogLevel::INFO,
@@ -19,11 +20,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         policy_store_config: PolicyStoreConfig {
             source: PolicyStoreSource::Yaml(POLICY_STORE_RAW.to_string()),
         },
+        policy_validation_config: PolicyValidationConfig::default(),
+        policy_store_poll_interval: None,
+        policy_store_signature_config: None,
+        shadow_policy_store_config: None,
         jwt_config: JwtConfig {
             jwks: None,
             jwt_sig_validation: false,
             jwt_status_validation: false,
             signature_algorithms_supported: HashSet::new(),
+            ..Default::default()
         }
         .allow_all_algorithms(),
         authorization_config: AuthorizationConfig {
@@ -41,9 +47,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 ]
             }))
             .unwrap(),
+            ..Default::default()
         },
         entity_builder_config: EntityBuilderConfig::default().with_user().with_workload(),
         lock_config: None,
+        http_config: HttpConfig::default(),
         max_default_entities: None,
         max_base64_size: None,
         token_cache_max_ttl_secs: 60,
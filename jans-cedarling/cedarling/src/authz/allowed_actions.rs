@@ -0,0 +1,263 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! # Allowed actions
+//! Evaluates every action of the schema that applies to the principals and the resource,
+//! building the entities only once.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use cedar_policy::{Entities, EntityUid};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::request::EntityData;
+use super::{
+    AuthorizeError, AuthorizeResult, Authz, ExecuteAuthorizeParameters, InvalidPrincipalError,
+    build_context, validate_id_tkn_trust_mode,
+};
+use crate::authorization_config::IdTokenTrustMode;
+use crate::entity_builder::{BuiltEntities, BuiltEntitiesUnsigned};
+use crate::jwt::Token;
+use crate::log::interface::LogWriter;
+use crate::log::{
+    BaseLogEntry, DecisionLogEntry, Diagnostics, DiagnosticsRefs, LogTokensInfo, LogType, gen_uuid7,
+};
+
+/// Principals of an [`allowed_actions`](crate::Cedarling::allowed_actions) request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPrincipals {
+    /// JWTs to build the principals from, like [`Request::tokens`](crate::Request::tokens)
+    Tokens(HashMap<String, String>),
+    /// Principal entities, like [`RequestUnsigned::principals`](crate::RequestUnsigned::principals)
+    Principals(Vec<EntityData>),
+}
+
+/// Result of an [`allowed_actions`](crate::Cedarling::allowed_actions) request
+#[derive(Debug, Clone, Serialize)]
+pub struct AllowedActionsResult {
+    /// UIDs of the allowed actions, such as `Jans::Action::"Read"`
+    pub allowed: BTreeSet<String>,
+    /// Authorization result of each evaluated action, keyed by the action UID.
+    /// The diagnostics of each principal are in [`AuthorizeResult::principals`].
+    pub actions: BTreeMap<String, AuthorizeResult>,
+    /// Request ID, shared by the decision logs of all the actions
+    pub request_id: String,
+}
+
+/// Entities built once for all the actions
+struct ActionsEntities {
+    entities: Entities,
+    built_entities: BuiltEntities,
+    principals: Vec<EntityUid>,
+    workload: Option<EntityUid>,
+    person: Option<EntityUid>,
+    resource: EntityUid,
+    tokens: HashMap<String, Arc<Token>>,
+}
+
+impl Authz {
+    /// Evaluates all the actions of the schema that apply to the principals and the resource.
+    ///
+    /// An action applies if its `appliesTo` contains the type of the resource and the type of
+    /// at least one principal. Each action is evaluated only for the principals it applies to.
+    pub(crate) async fn allowed_actions(
+        &self,
+        principals: RequestPrincipals,
+        resource: EntityData,
+        context: Value,
+    ) -> Result<AllowedActionsResult, AuthorizeError> {
+        let request_id = gen_uuid7();
+        let policy_store = self.policy_store();
+        let schema = &policy_store.schema;
+
+        let ActionsEntities {
+            entities,
+            built_entities,
+            principals,
+            workload,
+            person,
+            resource,
+            tokens,
+        } = self
+            .build_actions_entities(principals, &resource, &schema.schema)
+            .await?;
+
+        let mut actions = BTreeMap::<EntityUid, Vec<&EntityUid>>::new();
+        for principal in principals.iter() {
+            for action in schema
+                .json
+                .actions_applying_to(principal.type_name(), resource.type_name())
+            {
+                actions.entry(action).or_default().push(principal);
+            }
+        }
+
+        let tokens_logging_info = LogTokensInfo::new(
+            &tokens,
+            self.config
+                .authorization
+                .decision_log_default_jwt_id
+                .as_str(),
+        );
+
+        let mut result = AllowedActionsResult {
+            allowed: BTreeSet::new(),
+            actions: BTreeMap::new(),
+            request_id: request_id.to_string(),
+        };
+        for (action, action_principals) in actions {
+            let start_time = Utc::now();
+
+            let context = build_context(
+                &schema.json,
+                context.clone(),
+                &built_entities,
+                &schema.schema,
+                &action,
+            )?;
+
+            let mut responses = HashMap::new();
+            for principal in action_principals.iter() {
+                let response = self
+                    .execute_authorize(ExecuteAuthorizeParameters {
+                        policy_store: &policy_store,
+                        entities: &entities,
+                        principal: (*principal).clone(),
+                        action: action.clone(),
+                        resource: resource.clone(),
+                        context: context.clone(),
+                    })
+                    .map_err(|err| InvalidPrincipalError::new(principal, err))?;
+                responses.insert((*principal).clone(), response);
+            }
+
+            let principal_rule = self.principal_rule(&policy_store, &action);
+            let mut action_result = AuthorizeResult::new_for_many_principals(
+                principal_rule.rule,
+                responses,
+                workload.clone(),
+                person.clone(),
+                request_id,
+            )?;
+            action_result.collect_annotations(
+                principal_rule.rule,
+                policy_store.policies.get_set(),
+                &self.config.authorization.annotation_keys,
+            );
+
+            let decision_time_micro_sec = Utc::now()
+                .signed_duration_since(start_time)
+                .num_microseconds()
+                .unwrap_or(i64::MAX);
+            let diagnostics = action_principals
+                .iter()
+                .filter_map(|principal| {
+                    action_result.principals.get(principal.to_string().as_str())
+                })
+                .map(|response| Diagnostics::new(response.diagnostics(), &policy_store.policies))
+                .collect::<Vec<_>>();
+            let diagnostics = diagnostics.iter().map(Some).collect::<Vec<_>>();
+
+            self.config.log_service.as_ref().log_any(&DecisionLogEntry {
+                base: BaseLogEntry::new(LogType::Decision, request_id),
+                policystore_id: policy_store.id.as_str(),
+                policystore_version: policy_store.get_store_version(),
+                principal: DecisionLogEntry::all_principals(
+                    &action_principals.into_iter().cloned().collect::<Vec<_>>(),
+                ),
+                user: None,
+                workload: None,
+                lock_client_id: None,
+                action: action.to_string(),
+                resource: resource.to_string(),
+                decision: action_result.decision.into(),
+                tokens: tokens_logging_info.clone(),
+                decision_time_micro_sec,
+                diagnostics: DiagnosticsRefs::new(diagnostics.as_slice()),
+                explanations: None,
+                principal_rule,
+                cache_hit: false,
+            });
+
+            if action_result.decision {
+                result.allowed.insert(action.to_string());
+            }
+            result.actions.insert(action.to_string(), action_result);
+        }
+
+        Ok(result)
+    }
+
+    /// Builds the entities of the principals and the resource.
+    async fn build_actions_entities(
+        &self,
+        principals: RequestPrincipals,
+        resource: &EntityData,
+        schema: &cedar_policy::Schema,
+    ) -> Result<ActionsEntities, AuthorizeError> {
+        match principals {
+            RequestPrincipals::Tokens(tokens) => {
+                let tokens = self.jwt_service().validate_tokens(&tokens).await?;
+
+                if let IdTokenTrustMode::Strict = self.config.authorization.id_token_trust_mode {
+                    validate_id_tkn_trust_mode(&tokens)?;
+                }
+
+                let entities_data = self.entity_builder().build_entities(&tokens, resource)?;
+                let built_entities = entities_data.built_entities();
+                let workload = entities_data.workload.as_ref().map(|e| e.uid());
+                let person = entities_data.user.as_ref().map(|e| e.uid());
+                let resource = entities_data.resource.uid();
+
+                Ok(ActionsEntities {
+                    entities: entities_data.entities(Some(schema))?,
+                    built_entities,
+                    principals: workload.iter().chain(person.iter()).cloned().collect(),
+                    workload,
+                    person,
+                    resource,
+                    tokens,
+                })
+            },
+            RequestPrincipals::Principals(principals) => {
+                let BuiltEntitiesUnsigned {
+                    principals,
+                    roles,
+                    memberships,
+                    resource,
+                    built_entities,
+                } = self
+                    .entity_builder()
+                    .build_entities_unsigned(&principals, resource)?;
+                let principal_uids = principals.iter().map(|p| p.uid()).collect();
+                let resource_uid = resource.uid();
+
+                let entities = Entities::from_entities(
+                    principals
+                        .into_iter()
+                        .chain(roles)
+                        .chain(memberships)
+                        .chain([resource]),
+                    Some(schema),
+                )
+                .map_err(Box::new)?;
+
+                Ok(ActionsEntities {
+                    entities,
+                    built_entities,
+                    principals: principal_uids,
+                    workload: None,
+                    person: None,
+                    resource: resource_uid,
+                    tokens: HashMap::new(),
+                })
+            },
+        }
+    }
+}
@@ -5,13 +5,15 @@
  * Copyright (c) 2024, Gluu, Inc.
  */
 
-use cedar_policy::{Decision, EntityUid, Response};
+use cedar_policy::{Decision, EntityUid, Policy, PolicySet, Response};
 use serde::ser::{SerializeMap, SerializeStruct};
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Serialize, Serializer};
 use smol_str::{SmolStr, ToSmolStr};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::str::FromStr;
 use uuid7::Uuid;
 
+use super::PolicyTrace;
 use crate::common::json_rules::{ApplyRuleError, JsonRule, RuleApplier};
 
 /// Result of authorization and evaluation cedar policy
@@ -40,6 +42,79 @@ pub struct AuthorizeResult {
 
     /// Request ID, generated per each request call, is used to get logs from memory logger
     pub request_id: String,
+
+    /// Evaluation trace of the policies for each principal, keyed by the principal UID.
+    /// Only set when explain mode is enabled for the request or in the bootstrap config.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanations: Option<HashMap<String, Vec<PolicyTrace>>>,
+
+    /// Annotations of the policies that determined the decisions of the principals
+    /// which decided the result
+    #[serde(skip_serializing_if = "PolicyAnnotations::is_empty")]
+    pub annotations: PolicyAnnotations,
+
+    /// Annotations of the policies that determined the decision of each principal
+    /// which decided the result, keyed like [`AuthorizeResult::principals`].
+    /// Principals without annotations are not included.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub principal_annotations: HashMap<SmolStr, PolicyAnnotations>,
+}
+
+/// Annotations of the policies that determined a decision, keyed by the annotation.
+///
+/// Policies declare them as `@obligation("mfa")`, `@advice("show_banner")`
+/// or `@reason_code("E42")`, only the annotations of
+/// [`AuthorizationConfig::annotation_keys`](crate::AuthorizationConfig::annotation_keys)
+/// are collected.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PolicyAnnotations(BTreeMap<String, BTreeSet<String>>);
+
+impl PolicyAnnotations {
+    /// Returns `true` if there are no annotations
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the values of an annotation, such as `obligation`
+    pub fn get(&self, key: &str) -> Option<&BTreeSet<String>> {
+        self.0.get(key)
+    }
+
+    /// Returns an iterator over the annotations and their values
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &BTreeSet<String>)> {
+        self.0.iter()
+    }
+
+    fn add_policy(&mut self, policy: &Policy, keys: &[String]) {
+        for key in keys {
+            // `@obligation` without a value has an empty value
+            if let Some(value) = policy.annotation(key).filter(|value| !value.is_empty()) {
+                self.0
+                    .entry(key.clone())
+                    .or_default()
+                    .insert(value.to_string());
+            }
+        }
+    }
+
+    fn extend(&mut self, other: &PolicyAnnotations) {
+        for (key, values) in other.iter() {
+            self.0
+                .entry(key.clone())
+                .or_default()
+                .extend(values.iter().cloned());
+        }
+    }
+}
+
+impl IntoIterator for PolicyAnnotations {
+    type Item = (String, BTreeSet<String>);
+    type IntoIter = std::collections::btree_map::IntoIter<String, BTreeSet<String>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
 }
 
 /// Custom serializer for an Option<cedar_policy::Response> which converts `None` to an empty string and vice versa.
@@ -186,9 +261,67 @@ impl AuthorizeResult {
             person: person_result,
             principals: principals_response,
             request_id: request_id.to_string(),
+            explanations: None,
+            annotations: PolicyAnnotations::default(),
+            principal_annotations: HashMap::new(),
         })
     }
 
+    /// Collects the annotations of the policies that determined the decision of each
+    /// principal that decided the result.
+    ///
+    /// A principal decided the result if the principal rule uses it and its decision
+    /// is the same as the result, e.g. only the denied principals of a denied request.
+    pub(crate) fn collect_annotations(
+        &mut self,
+        principal_rule: &JsonRule,
+        policies: &PolicySet,
+        keys: &[String],
+    ) {
+        if keys.is_empty() {
+            return;
+        }
+
+        // the rule can use principals by their type name or their UID
+        let rule_vars = principal_rule.vars();
+        let rule_types = rule_vars
+            .iter()
+            .filter_map(|var| EntityUid::from_str(var).ok())
+            .map(|uid| uid.type_name().to_smolstr())
+            .collect::<HashSet<_>>();
+        let decision = self.cedar_decision();
+
+        for (principal, response) in &self.principals {
+            if response.decision() != decision {
+                continue;
+            }
+            let principal_type = EntityUid::from_str(principal)
+                .ok()
+                .map(|uid| uid.type_name().to_smolstr());
+            let used_by_rule = rule_vars.contains(principal.as_str())
+                || rule_types.contains(principal)
+                || principal_type.is_some_and(|type_name| rule_vars.contains(type_name.as_str()));
+            if !used_by_rule {
+                continue;
+            }
+
+            let mut annotations = PolicyAnnotations::default();
+            for policy in response
+                .diagnostics()
+                .reason()
+                .filter_map(|id| policies.policy(id))
+            {
+                annotations.add_policy(policy, keys);
+            }
+
+            if !annotations.is_empty() {
+                self.annotations.extend(&annotations);
+                self.principal_annotations
+                    .insert(principal.clone(), annotations);
+            }
+        }
+    }
+
     /// Decision of result
     /// works based on [`AuthorizeResult::is_allowed`]
     pub fn cedar_decision(&self) -> Decision {
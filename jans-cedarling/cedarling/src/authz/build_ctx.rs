@@ -3,7 +3,6 @@
 //
 // Copyright (c) 2024, Gluu, Inc.
 
-use super::AuthzConfig;
 use crate::common::cedar_schema::cedar_json::CedarSchemaJson;
 use crate::common::cedar_schema::cedar_json::attribute::Attribute;
 use crate::entity_builder::BuiltEntities;
@@ -13,7 +12,7 @@ use smol_str::ToSmolStr;
 
 /// Constructs the authorization context by adding the built entities from the tokens
 pub fn build_context(
-    config: &AuthzConfig,
+    json_schema: &CedarSchemaJson,
     request_context: Value,
     build_entities: &BuiltEntities,
     schema: &cedar_policy::Schema,
@@ -21,7 +20,6 @@ pub fn build_context(
 ) -> Result<cedar_policy::Context, BuildContextError> {
     let namespace = action.type_name().namespace();
     let action_name = &action.id().escaped();
-    let json_schema = &config.policy_store.schema.json;
 
     // TODO: we would to implement a way for the user to decide which entities
     // should be added to the context that doesn't use the Cedar schema.
@@ -0,0 +1,359 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! # Decision cache
+//! Caches the results of the `authorize` requests, keyed by a hash of the validated tokens,
+//! the action, the resource and the context.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+
+use cedar_policy::EntityUid;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+use smol_str::SmolStr;
+use sparkv::SparKV;
+use uuid7::Uuid;
+
+use super::request::EntityData;
+use super::{AuthorizeResult, Authz};
+use crate::bootstrap_config::DecisionCacheConfig;
+use crate::common::policy_store::PolicyStoreWithID;
+use crate::jwt::Token;
+use crate::log::interface::LogWriter;
+use crate::log::{
+    BaseLogEntry, DecisionLogEntry, Diagnostics, DiagnosticsRefs, LogTokensInfo, LogType,
+};
+
+const CACHE_LOCK_EXPECT_MESSAGE: &str = "decision cache lock should not be poisoned";
+
+/// Result of a request with the information of its decision log
+pub(crate) struct CachedDecision {
+    pub result: AuthorizeResult,
+    pub principal: Vec<SmolStr>,
+    pub user: Option<HashMap<String, Value>>,
+    pub workload: Option<HashMap<String, Value>>,
+    pub resource: String,
+    pub diagnostics: Vec<Diagnostics>,
+}
+
+/// Cache of the results of the `authorize` requests
+pub(crate) struct DecisionCache {
+    cache: RwLock<SparKV<Arc<CachedDecision>>>,
+    ttl: Duration,
+    /// Incremented when the policy store changes. It is part of the keys, so the results
+    /// of the requests that started before the change are never reused.
+    generation: AtomicU64,
+}
+
+impl DecisionCache {
+    pub fn new(config: &DecisionCacheConfig) -> Self {
+        let ttl = Duration::seconds(i64::try_from(config.ttl_secs).unwrap_or(i64::MAX));
+        let sparkv_config = sparkv::Config {
+            max_items: config.max_items,
+            // the size of the results is not limited
+            max_item_size: 0,
+            max_ttl: ttl,
+            default_ttl: ttl,
+            auto_clear_expired: true,
+        };
+
+        Self {
+            cache: RwLock::new(SparKV::with_config_and_sizer(sparkv_config, None)),
+            ttl,
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the current generation of the cache.
+    ///
+    /// It must be read before getting the policy store the request is evaluated with.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Removes all the cached results, called when the policy store or the default
+    /// entities change.
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.cache.write().expect(CACHE_LOCK_EXPECT_MESSAGE).clear();
+    }
+
+    /// Returns the key of a request, or `None` if the resource cannot be serialized.
+    ///
+    /// Tokens are identified by their `jti` and `iss` claims, or by all their claims
+    /// if they have no `jti`.
+    pub fn key(
+        generation: u64,
+        tokens: &HashMap<String, Arc<Token>>,
+        action: &str,
+        resource: &EntityData,
+        context: &Value,
+    ) -> Option<String> {
+        let resource = serde_json::to_value(resource).ok()?;
+
+        let tokens = tokens
+            .iter()
+            .map(|(name, token)| {
+                let id = match token.get_claim_val("jti") {
+                    Some(jti) => serde_json::json!({
+                        "jti": jti,
+                        "iss": token.get_claim_val("iss"),
+                    }),
+                    None => Value::Object(
+                        token
+                            .claims_value()
+                            .iter()
+                            .map(|(k, v)| (k.clone(), v.clone()))
+                            .collect(),
+                    ),
+                };
+                (name.as_str(), id)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let mut buf = format!("{generation}\n{action}\n");
+        for (name, id) in tokens {
+            buf.push_str(name);
+            buf.push('=');
+            write_canonical_json(&id, &mut buf);
+            buf.push('\n');
+        }
+        write_canonical_json(&resource, &mut buf);
+        buf.push('\n');
+        write_canonical_json(context, &mut buf);
+
+        Some(hash_key(&buf))
+    }
+
+    pub fn get(&self, key: &str) -> Option<Arc<CachedDecision>> {
+        self.cache
+            .read()
+            .expect(CACHE_LOCK_EXPECT_MESSAGE)
+            .get(key)
+            .cloned()
+    }
+
+    /// Caches the result until the configured TTL or the expiration of the first
+    /// token that expires, whichever comes first.
+    pub fn insert(
+        &self,
+        key: &str,
+        decision: CachedDecision,
+        tokens: &HashMap<String, Arc<Token>>,
+        now: DateTime<Utc>,
+    ) {
+        let ttl = tokens
+            .values()
+            .filter_map(|token| token.get_claim_val("exp").and_then(Value::as_i64))
+            .map(|exp| Duration::seconds(exp - now.timestamp()))
+            .fold(self.ttl, Duration::min);
+        if ttl <= Duration::zero() {
+            return;
+        }
+
+        // the result is not cached if the cache is full
+        let _ = self
+            .cache
+            .write()
+            .expect(CACHE_LOCK_EXPECT_MESSAGE)
+            .set_with_ttl(key, Arc::new(decision), ttl, &[]);
+    }
+}
+
+impl Authz {
+    /// Returns the cached result of a request with the ID of the request
+    /// and logs its decision log, flagged as a cache hit.
+    pub(super) fn cached_result(
+        &self,
+        cached: &CachedDecision,
+        request_id: Uuid,
+        start_time: DateTime<Utc>,
+        policy_store: &PolicyStoreWithID,
+        action: &EntityUid,
+        tokens: &HashMap<String, Arc<Token>>,
+    ) -> AuthorizeResult {
+        let mut result = cached.result.clone();
+        result.request_id = request_id.to_string();
+
+        let decision_time_micro_sec = Utc::now()
+            .signed_duration_since(start_time)
+            .num_microseconds()
+            .unwrap_or(i64::MAX);
+        let diagnostics = cached.diagnostics.iter().map(Some).collect::<Vec<_>>();
+
+        self.config.log_service.as_ref().log_any(&DecisionLogEntry {
+            base: BaseLogEntry::new(LogType::Decision, request_id),
+            policystore_id: policy_store.id.as_str(),
+            policystore_version: policy_store.get_store_version(),
+            principal: cached.principal.clone(),
+            user: cached.user.clone(),
+            workload: cached.workload.clone(),
+            lock_client_id: None,
+            action: action.to_string(),
+            resource: cached.resource.clone(),
+            decision: result.decision.into(),
+            tokens: LogTokensInfo::new(
+                tokens,
+                self.config
+                    .authorization
+                    .decision_log_default_jwt_id
+                    .as_str(),
+            ),
+            decision_time_micro_sec,
+            diagnostics: DiagnosticsRefs::new(diagnostics.as_slice()),
+            explanations: None,
+            principal_rule: self.principal_rule(policy_store, action),
+            cache_hit: true,
+        });
+
+        result
+    }
+}
+
+/// Writes the JSON value with the keys of the objects sorted,
+/// so equal values always give the same string.
+fn write_canonical_json(value: &Value, buf: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(k, _)| *k);
+
+            buf.push('{');
+            for (i, (k, v)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    buf.push(',');
+                }
+                buf.push_str(&Value::String(k.clone()).to_string());
+                buf.push(':');
+                write_canonical_json(v, buf);
+            }
+            buf.push('}');
+        },
+        Value::Array(values) => {
+            buf.push('[');
+            for (i, v) in values.iter().enumerate() {
+                if i > 0 {
+                    buf.push(',');
+                }
+                write_canonical_json(v, buf);
+            }
+            buf.push(']');
+        },
+        value => buf.push_str(&value.to_string()),
+    }
+}
+
+/// Hashes the key material to 128 bits with `ahash`, so the keys of different requests
+/// do not collide in practice and are short for SparKV which utilizes BTree.
+fn hash_key(s: &str) -> String {
+    static HASHERS: LazyLock<[ahash::RandomState; 2]> = LazyLock::new(|| {
+        [(); 2].map(|_| {
+            ahash::RandomState::with_seeds(
+                rand::random(),
+                rand::random(),
+                rand::random(),
+                rand::random(),
+            )
+        })
+    });
+
+    format!(
+        "{:016x}{:016x}",
+        HASHERS[0].hash_one(s),
+        HASHERS[1].hash_one(s)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn resource(attributes: Value) -> EntityData {
+        let mut resource = json!({
+            "cedar_entity_mapping": {
+                "entity_type": "Jans::Issue",
+                "id": "issue_1"
+            },
+        });
+        resource
+            .as_object_mut()
+            .unwrap()
+            .extend(attributes.as_object().unwrap().clone());
+        serde_json::from_value(resource).unwrap()
+    }
+
+    #[test]
+    fn key_does_not_depend_on_the_order_of_the_keys() {
+        let tokens = HashMap::new();
+        let first = DecisionCache::key(
+            0,
+            &tokens,
+            "Jans::Action::\"Read\"",
+            &resource(json!({"org": "1", "attrs": {"a": 1, "b": [1, {"c": 2, "d": 3}]}})),
+            &json!({"x": 1, "y": 2}),
+        );
+        let second = DecisionCache::key(
+            0,
+            &tokens,
+            "Jans::Action::\"Read\"",
+            &resource(json!({"attrs": {"b": [1, {"d": 3, "c": 2}], "a": 1}, "org": "1"})),
+            &json!({"y": 2, "x": 1}),
+        );
+        assert!(first.is_some());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn key_depends_on_the_request_and_the_generation() {
+        let tokens = HashMap::new();
+        let key = |generation, action, context| {
+            DecisionCache::key(generation, &tokens, action, &resource(json!({})), &context)
+        };
+
+        let base = key(0, "Jans::Action::\"Read\"", json!({}));
+        assert_ne!(base, key(1, "Jans::Action::\"Read\"", json!({})));
+        assert_ne!(base, key(0, "Jans::Action::\"Update\"", json!({})));
+        assert_ne!(base, key(0, "Jans::Action::\"Read\"", json!({"x": 1})));
+    }
+
+    #[test]
+    fn invalidate_clears_the_cache() {
+        let cache = DecisionCache::new(&DecisionCacheConfig::default());
+        let generation = cache.generation();
+        let _ = cache
+            .cache
+            .write()
+            .unwrap()
+            .set("key", Arc::new(cached_decision()), &[]);
+
+        cache.invalidate();
+
+        assert!(cache.get("key").is_none());
+        assert_ne!(cache.generation(), generation);
+    }
+
+    fn cached_decision() -> CachedDecision {
+        CachedDecision {
+            result: AuthorizeResult {
+                workload: None,
+                person: None,
+                principals: HashMap::new(),
+                decision: true,
+                request_id: String::new(),
+                explanations: None,
+                annotations: Default::default(),
+                principal_annotations: HashMap::new(),
+            },
+            principal: Vec::new(),
+            user: None,
+            workload: None,
+            resource: String::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+}
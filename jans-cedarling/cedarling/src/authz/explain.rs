@@ -0,0 +1,411 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! # Explain mode
+//! Cedar only reports the policies that determined the decision. To explain a decision,
+//! each policy is evaluated again on its own: first its scope, then its conditions one by one,
+//! in the order they are written, until a condition is not satisfied.
+
+use std::collections::BTreeSet;
+
+use cedar_policy::{
+    AuthorizationError, Authorizer, Decision, Effect, Entities, Policy, PolicyId, PolicySet,
+    Request,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// Evaluation trace of a policy whose scope matched the request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyTrace {
+    /// ID of the policy
+    pub id: String,
+    /// Effect of the policy
+    pub effect: PolicyEffect,
+    /// `true` if all the conditions of the policy are satisfied
+    pub satisfied: bool,
+    /// First condition of the policy that is not satisfied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_condition: Option<FailedCondition>,
+    /// Attributes read by the evaluated conditions, such as `principal.country`
+    /// or `context.network`
+    pub attributes_read: BTreeSet<String>,
+}
+
+/// Effect of a policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyEffect {
+    /// `permit` policy
+    Permit,
+    /// `forbid` policy
+    Forbid,
+}
+
+/// Condition of a policy that is not satisfied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedCondition {
+    /// Position of the condition in the policy, starting at 0
+    pub index: usize,
+    /// Kind of the condition
+    pub kind: ConditionKind,
+    /// Body of the condition in the Cedar syntax
+    pub condition: String,
+    /// Error of the evaluation, for example when the condition reads a missing attribute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Kind of a policy condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConditionKind {
+    /// `when { ... }` condition
+    When,
+    /// `unless { ... }` condition
+    Unless,
+}
+
+/// Explains the evaluation of the policies for the request.
+///
+/// Only the policies whose scope matched the request are returned, ordered by ID.
+pub(crate) fn explain_policies(
+    authorizer: &Authorizer,
+    request: &Request,
+    policies: &PolicySet,
+    entities: &Entities,
+) -> Vec<PolicyTrace> {
+    let mut traces = policies
+        .policies()
+        .filter_map(|policy| explain_policy(authorizer, request, policy, entities))
+        .collect::<Vec<_>>();
+    traces.sort_by(|a, b| a.id.cmp(&b.id));
+    traces
+}
+
+fn explain_policy(
+    authorizer: &Authorizer,
+    request: &Request,
+    policy: &Policy,
+    entities: &Entities,
+) -> Option<PolicyTrace> {
+    let Ok(Value::Object(mut est)) = policy.to_json() else {
+        return None;
+    };
+    let conditions = match est.remove("conditions") {
+        Some(Value::Array(conditions)) => conditions,
+        _ => Vec::new(),
+    };
+    // each part of the policy is evaluated as a `permit` policy,
+    // so the request is allowed when the part is satisfied
+    est.insert("effect".to_string(), json!("permit"));
+
+    let part = PolicyPart {
+        authorizer,
+        request,
+        entities,
+        id: policy.id(),
+        est: &est,
+    };
+    if part.evaluate(None) != Ok(true) {
+        // the scope did not match
+        return None;
+    }
+
+    let mut attributes_read = BTreeSet::new();
+    let mut failed_condition = None;
+    for (index, condition) in conditions.iter().enumerate() {
+        if let Some(body) = condition.get("body") {
+            collect_attributes(body, &mut attributes_read);
+        }
+
+        let error = match part.evaluate(Some(condition)) {
+            Ok(true) => continue,
+            Ok(false) => None,
+            Err(err) => Some(err),
+        };
+        failed_condition = Some(FailedCondition {
+            index,
+            kind: match condition.get("kind").and_then(Value::as_str) {
+                Some("unless") => ConditionKind::Unless,
+                _ => ConditionKind::When,
+            },
+            condition: condition_to_cedar(condition),
+            error,
+        });
+        break;
+    }
+
+    Some(PolicyTrace {
+        id: policy.id().to_string(),
+        effect: match policy.effect() {
+            Effect::Permit => PolicyEffect::Permit,
+            Effect::Forbid => PolicyEffect::Forbid,
+        },
+        satisfied: failed_condition.is_none(),
+        failed_condition,
+        attributes_read,
+    })
+}
+
+/// Scope of a policy in the JSON format, evaluated alone or with one of its conditions.
+struct PolicyPart<'a> {
+    authorizer: &'a Authorizer,
+    request: &'a Request,
+    entities: &'a Entities,
+    id: &'a PolicyId,
+    est: &'a Map<String, Value>,
+}
+
+impl PolicyPart<'_> {
+    /// Returns `true` if the scope and the condition are satisfied,
+    /// or the evaluation error.
+    fn evaluate(&self, condition: Option<&Value>) -> Result<bool, String> {
+        let mut est = self.est.clone();
+        est.insert(
+            "conditions".to_string(),
+            Value::Array(condition.into_iter().cloned().collect()),
+        );
+        let policy = Policy::from_json(Some(self.id.clone()), Value::Object(est))
+            .map_err(|e| e.to_string())?;
+        let policies = PolicySet::from_policies([policy]).map_err(|e| e.to_string())?;
+
+        let response = self
+            .authorizer
+            .is_authorized(self.request, &policies, self.entities);
+        if let Some(AuthorizationError::PolicyEvaluationError(err)) =
+            response.diagnostics().errors().next()
+        {
+            return Err(err.inner().to_string());
+        }
+        Ok(response.decision() == Decision::Allow)
+    }
+}
+
+/// Converts the JSON representation of a condition to the Cedar syntax.
+///
+/// Falls back to the JSON representation if the condition can not be converted.
+fn condition_to_cedar(condition: &Value) -> String {
+    let body = condition.get("body").cloned().unwrap_or_default();
+    let policy = json!({
+        "effect": "permit",
+        "principal": {"op": "All"},
+        "action": {"op": "All"},
+        "resource": {"op": "All"},
+        "conditions": [{"kind": "when", "body": body}],
+    });
+
+    Policy::from_json(None, policy)
+        .ok()
+        .and_then(|policy| policy.to_cedar())
+        .and_then(|src| {
+            let start = src.find("when {")? + "when {".len();
+            let end = src.rfind('}')?;
+            src.get(start..end).map(|body| {
+                body.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+        })
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// Collects the attributes read by an expression in the JSON format.
+///
+/// Only the attributes of variables are collected, e.g. `principal.manager.country`,
+/// attributes of literals and of other expressions are skipped.
+fn collect_attributes(expr: &Value, attributes: &mut BTreeSet<String>) {
+    match expr {
+        Value::Object(obj) => {
+            for (key, value) in obj {
+                if key == "." || key == "has" {
+                    if let Some(path) = attribute_path(value) {
+                        attributes.insert(path);
+                    }
+                    if let Some(left) = value.get("left") {
+                        collect_attributes(left, attributes);
+                    }
+                } else {
+                    collect_attributes(value, attributes);
+                }
+            }
+        },
+        Value::Array(values) => values
+            .iter()
+            .for_each(|value| collect_attributes(value, attributes)),
+        _ => {},
+    }
+}
+
+/// Returns the path of an attribute access such as `{"left": {"Var": "principal"}, "attr": "country"}`.
+fn attribute_path(access: &Value) -> Option<String> {
+    let attr = access.get("attr")?.as_str()?;
+    let left = access.get("left")?;
+
+    let left_path = match left.get("Var") {
+        Some(var) => var.as_str()?.to_string(),
+        None => attribute_path(left.get(".")?)?,
+    };
+    Some(format!("{left_path}.{attr}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy::{Context, Entity, EntityUid, RestrictedExpression};
+    use std::collections::{HashMap, HashSet};
+    use std::str::FromStr;
+
+    const POLICIES: &str = r#"
+        @id("same_country")
+        permit(principal, action == Action::"read", resource)
+        when { principal.country == resource.country }
+        unless { context.blocked };
+
+        @id("other_action")
+        permit(principal, action == Action::"write", resource);
+
+        @id("missing_attribute")
+        forbid(principal, action, resource)
+        when { principal.level > 3 };
+    "#;
+
+    fn uid(uid: &str) -> EntityUid {
+        EntityUid::from_str(uid).expect("entity UID should be valid")
+    }
+
+    fn entity(uid_str: &str, country: &str) -> Entity {
+        Entity::new(
+            uid(uid_str),
+            HashMap::from([(
+                "country".to_string(),
+                RestrictedExpression::new_string(country.to_string()),
+            )]),
+            HashSet::new(),
+        )
+        .expect("entity should be valid")
+    }
+
+    fn explain(user_country: &str, blocked: bool) -> Vec<PolicyTrace> {
+        let policies = PolicySet::from_str(POLICIES).expect("policies should be valid");
+        let policies = PolicySet::from_policies(policies.policies().map(|policy| {
+            let id = policy.annotation("id").expect("policy should have an ID");
+            policy.new_id(PolicyId::new(id))
+        }))
+        .expect("policy IDs should be unique");
+
+        let entities = Entities::from_entities(
+            [
+                entity("User::\"alice\"", user_country),
+                entity("Document::\"1\"", "US"),
+            ],
+            None,
+        )
+        .expect("entities should be valid");
+        let context = Context::from_json_value(json!({"blocked": blocked}), None)
+            .expect("context should be valid");
+        let request = Request::new(
+            uid("User::\"alice\""),
+            uid("Action::\"read\""),
+            uid("Document::\"1\""),
+            context,
+            None,
+        )
+        .expect("request should be valid");
+
+        explain_policies(&Authorizer::new(), &request, &policies, &entities)
+    }
+
+    #[test]
+    fn explains_satisfied_policy() {
+        let traces = explain("US", false);
+
+        let ids = traces.iter().map(|t| t.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            ["missing_attribute", "same_country"],
+            "only the policies with a matching scope should be explained"
+        );
+
+        let trace = &traces[1];
+        assert!(trace.satisfied);
+        assert_eq!(trace.effect, PolicyEffect::Permit);
+        assert_eq!(trace.failed_condition, None);
+        assert_eq!(
+            trace.attributes_read,
+            BTreeSet::from([
+                "context.blocked".to_string(),
+                "principal.country".to_string(),
+                "resource.country".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn explains_first_failing_condition() {
+        let traces = explain("DE", true);
+        let trace = traces
+            .iter()
+            .find(|t| t.id == "same_country")
+            .expect("policy should be explained");
+
+        assert!(!trace.satisfied);
+        let failed = trace
+            .failed_condition
+            .as_ref()
+            .expect("a condition should fail");
+        assert_eq!(failed.index, 0);
+        assert_eq!(failed.kind, ConditionKind::When);
+        assert!(
+            failed.condition.contains("country"),
+            "condition should be in the cedar syntax: {}",
+            failed.condition
+        );
+        assert_eq!(failed.error, None);
+        assert!(
+            !trace.attributes_read.contains("context.blocked"),
+            "the second condition is not evaluated"
+        );
+
+        let traces = explain("US", true);
+        let failed = traces
+            .iter()
+            .find(|t| t.id == "same_country")
+            .and_then(|t| t.failed_condition.as_ref())
+            .expect("the unless condition should fail");
+        assert_eq!(failed.index, 1);
+        assert_eq!(failed.kind, ConditionKind::Unless);
+    }
+
+    #[test]
+    fn explains_evaluation_error() {
+        let traces = explain("US", false);
+        let trace = traces
+            .iter()
+            .find(|t| t.id == "missing_attribute")
+            .expect("policy should be explained");
+
+        assert!(!trace.satisfied);
+        assert_eq!(trace.effect, PolicyEffect::Forbid);
+        let failed = trace
+            .failed_condition
+            .as_ref()
+            .expect("the condition should fail");
+        assert!(
+            failed
+                .error
+                .as_ref()
+                .is_some_and(|err| err.contains("level")),
+            "error should mention the missing attribute: {:?}",
+            failed.error
+        );
+        assert_eq!(
+            trace.attributes_read,
+            BTreeSet::from(["principal.level".to_string()])
+        );
+    }
+}
@@ -10,9 +10,11 @@
 
 use crate::authorization_config::IdTokenTrustMode;
 use crate::bootstrap_config::AuthorizationConfig;
+use crate::bootstrap_config::{PolicyStoreSignatureConfig, PolicyStoreSource};
 use crate::common::json_rules::ApplyRuleError;
-use crate::common::policy_store::PolicyStoreWithID;
+use crate::common::policy_store::{PolicyStoreWithID, TemplateLink, TemplateLinkError};
 use crate::entity_builder::*;
+use crate::init::policy_store::save_template_links;
 use crate::jwt::{self, Token};
 use crate::log::interface::LogWriter;
 use crate::log::{
@@ -22,29 +24,49 @@ use crate::log::{
 use build_ctx::*;
 use cedar_policy::{Entities, Entity, EntityUid};
 use chrono::Utc;
+use decision_cache::{CachedDecision, DecisionCache};
 use request::{Request, RequestUnsigned};
+use shadow::{ShadowEvaluator, ShadowRequest};
 use std::collections::{HashMap, HashSet};
 use std::io::Cursor;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock};
 use trust_mode::*;
 use uuid7::Uuid;
 
+mod allowed_actions;
 mod authorize_result;
 mod build_ctx;
+mod decision_cache;
+mod explain;
+mod principal_rule;
+mod shadow;
 mod trust_mode;
 
 pub(crate) mod request;
 
-pub use authorize_result::AuthorizeResult;
+pub use allowed_actions::{AllowedActionsResult, RequestPrincipals};
+pub use authorize_result::{AuthorizeResult, PolicyAnnotations};
+pub use explain::{ConditionKind, FailedCondition, PolicyEffect, PolicyTrace};
+pub use principal_rule::PrincipalRule;
+pub use shadow::ShadowStats;
 
 /// Configuration to Authz to initialize service without errors
 pub(crate) struct AuthzConfig {
     pub log_service: Logger,
-    pub policy_store: PolicyStoreWithID,
+    /// Source the policy store was loaded from, the template links are saved to it
+    pub policy_store_source: PolicyStoreSource,
+    /// Keys the policy store is signed with, the template links of a signed policy store
+    /// are not saved
+    pub policy_store_signature_config: Option<PolicyStoreSignatureConfig>,
+    /// JWT service of the initial policy store, it is replaced along with the policy store
+    /// when the trusted issuers change
     pub jwt_service: Arc<jwt::JwtService>,
+    /// Entity builder of the initial policy store, it is replaced along with the policy store
     pub entity_builder: Arc<EntityBuilder>,
     pub authorization: AuthorizationConfig,
+    /// Policy store the requests are also evaluated against, without affecting the decisions
+    pub shadow_policy_store: Option<PolicyStoreWithID>,
 }
 
 /// Authorization Service
@@ -52,12 +74,26 @@ pub(crate) struct AuthzConfig {
 /// It leverages other services as needed to complete its evaluations.
 pub struct Authz {
     config: AuthzConfig,
+    // the policy store is replaced when the template links change or when a newer
+    // policy store is polled, each request uses the policy store that was current when
+    // it started
+    policy_store: RwLock<Arc<PolicyStoreWithID>>,
+    entity_builder: RwLock<Arc<EntityBuilder>>,
+    jwt_service: RwLock<Arc<jwt::JwtService>>,
     authorizer: cedar_policy::Authorizer,
+    shadow: Option<ShadowEvaluator>,
+    decision_cache: Option<DecisionCache>,
+    template_links_save: Mutex<()>,
 }
 
+const POLICY_STORE_LOCK_EXPECT_MESSAGE: &str = "policy store lock should not be poisoned";
+
 impl Authz {
     /// Create a new Authorization Service
-    pub(crate) fn new(config: AuthzConfig) -> Result<Self, AuthzServiceInitError> {
+    pub(crate) fn new(
+        mut config: AuthzConfig,
+        policy_store: PolicyStoreWithID,
+    ) -> Result<Self, AuthzServiceInitError> {
         config.log_service.log_any(
             LogEntry::new_with_data(LogType::System, None)
                 .set_cedar_version()
@@ -65,20 +101,207 @@ impl Authz {
                 .set_message("Cedarling Authz initialized successfully".to_string()),
         );
 
+        for uid in principal_rule::unknown_rule_actions(&config.authorization, &policy_store) {
+            config.log_service.log_any(
+                LogEntry::new_with_data(LogType::System, None)
+                    .set_level(LogLevel::WARN)
+                    .set_message(format!(
+                        "principal boolean operation is defined for `{uid}`, which is not an \
+                         action of the schema"
+                    )),
+            );
+        }
+
+        let shadow = config
+            .shadow_policy_store
+            .take()
+            .map(|shadow_store| ShadowEvaluator::new(shadow_store, config.log_service.clone()));
+
+        let decision_cache = config
+            .authorization
+            .decision_cache
+            .as_ref()
+            .map(DecisionCache::new);
+
         Ok(Self {
+            entity_builder: RwLock::new(config.entity_builder.clone()),
+            jwt_service: RwLock::new(config.jwt_service.clone()),
             config,
+            policy_store: RwLock::new(Arc::new(policy_store)),
             authorizer: cedar_policy::Authorizer::new(),
+            shadow,
+            decision_cache,
+            template_links_save: Mutex::new(()),
         })
     }
 
+    /// Returns the current policy store
+    pub(crate) fn policy_store(&self) -> Arc<PolicyStoreWithID> {
+        self.policy_store
+            .read()
+            .expect(POLICY_STORE_LOCK_EXPECT_MESSAGE)
+            .clone()
+    }
+
+    /// Returns the entity builder of the current policy store
+    pub(crate) fn entity_builder(&self) -> Arc<EntityBuilder> {
+        self.entity_builder
+            .read()
+            .expect(POLICY_STORE_LOCK_EXPECT_MESSAGE)
+            .clone()
+    }
+
+    /// Returns the JWT service of the trusted issuers of the current policy store
+    pub(crate) fn jwt_service(&self) -> Arc<jwt::JwtService> {
+        self.jwt_service
+            .read()
+            .expect(POLICY_STORE_LOCK_EXPECT_MESSAGE)
+            .clone()
+    }
+
+    /// Replaces the policy store and the entity builder created from it, e.g. when a
+    /// newer policy store is polled. The JWT service is only given if the trusted
+    /// issuers changed, otherwise the current one is kept.
+    ///
+    /// The template links of the current policy store that the new one does not have
+    /// are linked again, the ones that no longer link are logged and dropped.
+    pub(crate) fn replace_policy_store(
+        &self,
+        mut policy_store: PolicyStoreWithID,
+        entity_builder: Arc<EntityBuilder>,
+        jwt_service: Option<Arc<jwt::JwtService>>,
+    ) {
+        let mut current = self
+            .policy_store
+            .write()
+            .expect(POLICY_STORE_LOCK_EXPECT_MESSAGE);
+
+        for (link_id, link) in current.policies.template_links() {
+            if policy_store.policies.template_links().contains_key(link_id) {
+                continue;
+            }
+            if let Err(err) = policy_store
+                .store
+                .policies
+                .link_template(link_id, link.clone())
+            {
+                self.config.log_service.log_any(
+                    LogEntry::new_with_data(LogType::System, None)
+                        .set_level(LogLevel::WARN)
+                        .set_message(format!(
+                            "template link `{link_id}` was dropped, it does not link in the \
+                             new policy store"
+                        ))
+                        .set_error(err.to_string()),
+                );
+            }
+        }
+
+        *self
+            .entity_builder
+            .write()
+            .expect(POLICY_STORE_LOCK_EXPECT_MESSAGE) = entity_builder;
+        if let Some(jwt_service) = jwt_service {
+            *self
+                .jwt_service
+                .write()
+                .expect(POLICY_STORE_LOCK_EXPECT_MESSAGE) = jwt_service;
+        }
+        *current = Arc::new(policy_store);
+        if let Some(decision_cache) = &self.decision_cache {
+            decision_cache.invalidate();
+        }
+    }
+
+    /// Returns the counters of the evaluations against the shadow policy store,
+    /// if one is configured
+    pub(crate) fn shadow_stats(&self) -> Option<ShadowStats> {
+        let shadow = self.shadow.as_ref()?;
+        Some(shadow.stats(&self.policy_store().id))
+    }
+
+    /// Links a policy template, the linked policy gets the ID of the link.
+    pub(crate) fn add_template_link(
+        &self,
+        link_id: &str,
+        link: TemplateLink,
+    ) -> Result<(), TemplateLinkError> {
+        self.update_policy_store(|store| store.store.policies.link_template(link_id, link))
+    }
+
+    /// Removes a policy linked from a template.
+    pub(crate) fn remove_template_link(
+        &self,
+        link_id: &str,
+    ) -> Result<TemplateLink, TemplateLinkError> {
+        self.update_policy_store(|store| store.store.policies.unlink_template(link_id))
+    }
+
+    /// Applies a change of the template links to the current policy store, then saves
+    /// the links next to the policy store file.
+    ///
+    /// The policy store is only copied if a request still uses it, and is left as is if
+    /// the change fails. The links are saved after the policy store lock is released, a
+    /// failed save is logged and the change is kept in memory.
+    fn update_policy_store<T>(
+        &self,
+        update: impl FnOnce(&mut PolicyStoreWithID) -> Result<T, TemplateLinkError>,
+    ) -> Result<T, TemplateLinkError> {
+        // keeps the saves in the order of the changes
+        let _save_guard = self
+            .template_links_save
+            .lock()
+            .expect(POLICY_STORE_LOCK_EXPECT_MESSAGE);
+
+        let (value, policy_store_id, links) = {
+            let mut current = self
+                .policy_store
+                .write()
+                .expect(POLICY_STORE_LOCK_EXPECT_MESSAGE);
+            let policy_store = Arc::make_mut(&mut current);
+            let value = update(policy_store)?;
+            if let Some(decision_cache) = &self.decision_cache {
+                decision_cache.invalidate();
+            }
+            (
+                value,
+                policy_store.id.clone(),
+                policy_store.policies.template_links().clone(),
+            )
+        };
+
+        match save_template_links(
+            &self.config.policy_store_source,
+            self.config.policy_store_signature_config.as_ref(),
+            &policy_store_id,
+            &links,
+        ) {
+            Ok(true) => {},
+            Ok(false) => self.config.log_service.log_any(
+                LogEntry::new_with_data(LogType::System, None)
+                    .set_level(LogLevel::DEBUG)
+                    .set_message("template links are only kept in memory".to_string()),
+            ),
+            Err(err) => self.config.log_service.log_any(
+                LogEntry::new_with_data(LogType::System, None)
+                    .set_level(LogLevel::WARN)
+                    .set_message(
+                        "failed to save the template links, they are only kept in memory"
+                            .to_string(),
+                    )
+                    .set_error(err.to_string()),
+            ),
+        }
+        Ok(value)
+    }
+
     // decode JWT tokens to structs AccessTokenData, IdTokenData, UserInfoTokenData using jwt service
     pub(crate) async fn decode_tokens<'a>(
         &'a self,
         request: &'a Request,
     ) -> Result<HashMap<String, Arc<Token>>, AuthorizeError> {
         let tokens = self
-            .config
-            .jwt_service
+            .jwt_service()
             .validate_tokens(&request.tokens)
             .await?;
         Ok(tokens)
@@ -88,6 +311,22 @@ impl Authz {
     /// - evaluate if authorization is granted for *person*
     /// - evaluate if authorization is granted for *workload*
     pub async fn authorize(&self, request: Request) -> Result<AuthorizeResult, AuthorizeError> {
+        self.authorize_request(request, false).await
+    }
+
+    /// Evaluate Authorization Request and return the evaluation trace of the policies.
+    pub async fn authorize_explain(
+        &self,
+        request: Request,
+    ) -> Result<AuthorizeResult, AuthorizeError> {
+        self.authorize_request(request, true).await
+    }
+
+    async fn authorize_request(
+        &self,
+        request: Request,
+        explain: bool,
+    ) -> Result<AuthorizeResult, AuthorizeError> {
         let start_time = Utc::now();
         // We use uuid v7 because it is generated based on the time and sortable.
         // and we need sortable ids to use it in the sparkv database.
@@ -96,7 +335,14 @@ impl Authz {
         // Request ID should be passed to each log entry for tracing in logs and to get log entities from memory logger
         let request_id = gen_uuid7();
 
-        let schema = &self.config.policy_store.schema;
+        // the generation of the decision cache is read before the policy store,
+        // so a result of a replaced policy store is never reused
+        let decision_cache = self
+            .decision_cache
+            .as_ref()
+            .map(|cache| (cache, cache.generation()));
+        let policy_store = self.policy_store();
+        let schema = &policy_store.schema;
 
         let tokens = self.decode_tokens(&request).await?;
 
@@ -108,17 +354,44 @@ impl Authz {
         let action = cedar_policy::EntityUid::from_str(request.action.as_str())
             .map_err(|e| AuthorizeError::Action(Box::new(e)))?;
 
+        let explain = explain || self.config.authorization.explain;
+
+        // the evaluation traces are not cached
+        let cache_key = match decision_cache {
+            Some((_, generation)) if !explain => DecisionCache::key(
+                generation,
+                &tokens,
+                &request.action,
+                &request.resource,
+                &request.context,
+            ),
+            _ => None,
+        };
+        // the entities and the context are not built for a cached result, so it is not
+        // evaluated against the shadow policy store
+        if let (Some((cache, _)), Some(key)) = (decision_cache, &cache_key)
+            && let Some(cached) = cache.get(key)
+        {
+            return Ok(self.cached_result(
+                &cached,
+                request_id,
+                start_time,
+                &policy_store,
+                &action,
+                &tokens,
+            ));
+        }
+
         // Parse [`cedar_policy::Entity`]-s to [`AuthorizeEntitiesData`] that hold all entities (for usability).
         let entities_data = self
-            .config
-            .entity_builder
+            .entity_builder()
             .build_entities(&tokens, &request.resource)?;
 
         // Get entity UIDs what we will be used on authorize check
         let resource_uid = entities_data.resource.uid();
 
         let context = build_context(
-            &self.config,
+            &schema.json,
             request.context.clone(),
             &entities_data.built_entities(),
             &schema.schema,
@@ -132,25 +405,34 @@ impl Authz {
         // hold all entities that will be used on authorize check.
         let entities: Entities = entities_data.entities(Some(&schema.schema))?;
 
+        let mut explanations = HashMap::new();
+
         let (workload_authz_result, workload_authz_info, workload_entity_claims) =
             if let Some(workload) = workload_principal.clone() {
                 let principal = workload;
 
-                let authz_result = self
-                    .execute_authorize(ExecuteAuthorizeParameters {
-                        entities: &entities,
-                        principal: principal.clone(),
-                        action: action.clone(),
-                        resource: resource_uid.clone(),
-                        context: context.clone(),
-                    })
+                let (authz_result, trace) = self
+                    .execute_authorize_explained(
+                        ExecuteAuthorizeParameters {
+                            policy_store: &policy_store,
+                            entities: &entities,
+                            principal: principal.clone(),
+                            action: action.clone(),
+                            resource: resource_uid.clone(),
+                            context: context.clone(),
+                        },
+                        explain,
+                    )
                     .map_err(|err| InvalidPrincipalError::new(&principal, err))?;
+                if let Some(trace) = trace {
+                    explanations.insert(principal.to_string(), trace);
+                }
 
                 let authz_info = AuthorizeInfo {
                     principal: principal.to_string(),
                     diagnostics: Diagnostics::new(
                         authz_result.diagnostics(),
-                        &self.config.policy_store.policies,
+                        &policy_store.policies,
                     ),
                     decision: authz_result.decision().into(),
                 };
@@ -174,55 +456,69 @@ impl Authz {
             };
 
         // Check authorize where principal is `"Jans::User"` from cedar-policy schema.
-        let (user_authz_result, user_authz_info, user_entity_claims) =
-            if let Some(user) = person_principal.clone() {
-                let principal = user;
-
-                let authz_result = self
-                    .execute_authorize(ExecuteAuthorizeParameters {
+        let (user_authz_result, user_authz_info, user_entity_claims) = if let Some(user) =
+            person_principal.clone()
+        {
+            let principal = user;
+
+            let (authz_result, trace) = self
+                .execute_authorize_explained(
+                    ExecuteAuthorizeParameters {
+                        policy_store: &policy_store,
                         entities: &entities,
                         principal: principal.clone(),
                         action: action.clone(),
                         resource: resource_uid.clone(),
                         context: context.clone(),
-                    })
-                    .map_err(|err| InvalidPrincipalError::new(&principal, err))?;
-
-                let authz_info = AuthorizeInfo {
-                    principal: principal.to_string(),
-                    diagnostics: Diagnostics::new(
-                        authz_result.diagnostics(),
-                        &self.config.policy_store.policies,
-                    ),
-                    decision: authz_result.decision().into(),
-                };
-
-                let user_entity_claims = get_entity_claims(
-                    self.config
-                        .authorization
-                        .decision_log_user_claims
-                        .as_slice(),
-                    &entities,
-                    principal,
-                );
-
-                (
-                    Some(authz_result),
-                    Some(authz_info),
-                    Some(user_entity_claims),
+                    },
+                    explain,
                 )
-            } else {
-                (None, None, None)
+                .map_err(|err| InvalidPrincipalError::new(&principal, err))?;
+            if let Some(trace) = trace {
+                explanations.insert(principal.to_string(), trace);
+            }
+
+            let authz_info = AuthorizeInfo {
+                principal: principal.to_string(),
+                diagnostics: Diagnostics::new(authz_result.diagnostics(), &policy_store.policies),
+                decision: authz_result.decision().into(),
             };
 
-        let result = AuthorizeResult::new(
-            &self.config.authorization.principal_bool_operator,
-            workload_principal,
-            person_principal,
+            let user_entity_claims = get_entity_claims(
+                self.config
+                    .authorization
+                    .decision_log_user_claims
+                    .as_slice(),
+                &entities,
+                principal,
+            );
+
+            (
+                Some(authz_result),
+                Some(authz_info),
+                Some(user_entity_claims),
+            )
+        } else {
+            (None, None, None)
+        };
+
+        let principal_rule = self.principal_rule(&policy_store, &action);
+        let mut result = AuthorizeResult::new(
+            principal_rule.rule,
+            workload_principal.clone(),
+            person_principal.clone(),
             workload_authz_result,
             user_authz_result,
             request_id,
         )?;
+        result.collect_annotations(
+            principal_rule.rule,
+            policy_store.policies.get_set(),
+            &self.config.authorization.annotation_keys,
+        );
+        if explain {
+            result.explanations = Some(explanations);
+        }
 
         // measure time how long request executes
         let since_start = Utc::now().signed_duration_since(start_time);
@@ -231,6 +527,27 @@ impl Authz {
             i64::MAX
         });
 
+        if let Some(shadow) = &self.shadow {
+            shadow.evaluate(
+                &self.config.authorization,
+                ShadowRequest {
+                    request_id,
+                    policy_store: policy_store.clone(),
+                    entities: entities.clone(),
+                    responses: ShadowRequest::responses(
+                        &result,
+                        workload_principal.iter().chain(person_principal.iter()),
+                    ),
+                    workload: workload_principal,
+                    person: person_principal,
+                    action,
+                    resource: resource_uid.clone(),
+                    context,
+                    decision: result.decision,
+                },
+            );
+        }
+
         // FROM THIS POINT WE ONLY MAKE LOGS
 
         // getting entities as json
@@ -266,12 +583,31 @@ impl Authz {
                 .as_str(),
         );
 
+        if let (Some((cache, _)), Some(key)) = (decision_cache, &cache_key) {
+            let cached = CachedDecision {
+                result: result.clone(),
+                principal: DecisionLogEntry::principal(
+                    result.person.is_some(),
+                    result.workload.is_some(),
+                ),
+                user: user_entity_claims.clone(),
+                workload: workload_entity_claims.clone(),
+                resource: resource_uid.to_string(),
+                diagnostics: [user_authz_diagnostic, workload_authz_diagnostic]
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .collect(),
+            };
+            cache.insert(key, cached, &tokens, Utc::now());
+        }
+
         // Decision log
         // we log decision log before debug log, to avoid cloning diagnostic info
         self.config.log_service.as_ref().log_any(&DecisionLogEntry {
             base: BaseLogEntry::new(LogType::Decision, request_id),
-            policystore_id: self.config.policy_store.id.as_str(),
-            policystore_version: self.config.policy_store.get_store_version(),
+            policystore_id: policy_store.id.as_str(),
+            policystore_version: policy_store.get_store_version(),
             principal: DecisionLogEntry::principal(
                 result.person.is_some(),
                 result.workload.is_some(),
@@ -285,6 +621,9 @@ impl Authz {
             tokens: tokens_logging_info,
             decision_time_micro_sec,
             diagnostics: DiagnosticsRefs::new(&[user_authz_diagnostic, workload_authz_diagnostic]),
+            explanations: result.explanations.as_ref(),
+            principal_rule,
+            cache_hit: false,
         });
 
         // DEBUG LOG
@@ -314,6 +653,23 @@ impl Authz {
     pub async fn authorize_unsigned(
         &self,
         request: RequestUnsigned,
+    ) -> Result<AuthorizeResult, AuthorizeError> {
+        self.authorize_unsigned_request(request, false).await
+    }
+
+    /// Evaluate Authorization Request with unsigned data and return the evaluation
+    /// trace of the policies.
+    pub async fn authorize_unsigned_explain(
+        &self,
+        request: RequestUnsigned,
+    ) -> Result<AuthorizeResult, AuthorizeError> {
+        self.authorize_unsigned_request(request, true).await
+    }
+
+    async fn authorize_unsigned_request(
+        &self,
+        request: RequestUnsigned,
+        explain: bool,
     ) -> Result<AuthorizeResult, AuthorizeError> {
         let start_time = Utc::now();
         // We use uuid v7 because it is generated based on the time and sortable.
@@ -323,7 +679,8 @@ impl Authz {
         // Request ID should be passed to each log entry for tracing in logs and to get log entities from memory logger
         let request_id = gen_uuid7();
 
-        let schema = &self.config.policy_store.schema;
+        let policy_store = self.policy_store();
+        let schema = &policy_store.schema;
         // Parse action UID.
         let action = cedar_policy::EntityUid::from_str(request.action.as_str())
             .map_err(|e| AuthorizeError::Action(Box::new(e)))?;
@@ -331,12 +688,12 @@ impl Authz {
         let BuiltEntitiesUnsigned {
             principals,
             roles,
+            memberships,
             resource,
             built_entities,
         } = self
-            .config
-            .entity_builder
-            .build_entities_unsigned(&request)?;
+            .entity_builder()
+            .build_entities_unsigned(&request.principals, &request.resource)?;
         let principal_uids = principals
             .iter()
             .map(|p| p.uid())
@@ -344,7 +701,7 @@ impl Authz {
         let resource_uid = resource.uid();
 
         let context = build_context(
-            &self.config,
+            &schema.json,
             request.context.clone(),
             &built_entities,
             &schema.schema,
@@ -352,34 +709,56 @@ impl Authz {
         )?;
 
         let entities = Entities::from_entities(
-            principals.into_iter().chain(roles).chain([resource]),
+            principals
+                .into_iter()
+                .chain(roles)
+                .chain(memberships)
+                .chain([resource]),
             Some(&schema.schema),
         )
         .map_err(Box::new)?;
 
+        let explain = explain || self.config.authorization.explain;
+        let mut explanations = HashMap::new();
         let mut principal_responses = HashMap::new();
 
         for principal_uid in principal_uids.iter() {
-            let auth_result = self
-                .execute_authorize(ExecuteAuthorizeParameters {
-                    entities: &entities,
-                    principal: principal_uid.clone(),
-                    action: action.clone(),
-                    resource: resource_uid.clone(),
-                    context: context.clone(),
-                })
+            let (auth_result, trace) = self
+                .execute_authorize_explained(
+                    ExecuteAuthorizeParameters {
+                        policy_store: &policy_store,
+                        entities: &entities,
+                        principal: principal_uid.clone(),
+                        action: action.clone(),
+                        resource: resource_uid.clone(),
+                        context: context.clone(),
+                    },
+                    explain,
+                )
                 .map_err(|err| InvalidPrincipalError::new(principal_uid, err))?;
+            if let Some(trace) = trace {
+                explanations.insert(principal_uid.to_string(), trace);
+            }
 
             principal_responses.insert(principal_uid.clone(), auth_result);
         }
 
-        let result = AuthorizeResult::new_for_many_principals(
-            &self.config.authorization.principal_bool_operator,
+        let principal_rule = self.principal_rule(&policy_store, &action);
+        let mut result = AuthorizeResult::new_for_many_principals(
+            principal_rule.rule,
             principal_responses,
             None,
             None,
             request_id,
         )?;
+        result.collect_annotations(
+            principal_rule.rule,
+            policy_store.policies.get_set(),
+            &self.config.authorization.annotation_keys,
+        );
+        if explain {
+            result.explanations = Some(explanations);
+        }
 
         // measure time how long request executes
         let since_start = Utc::now().signed_duration_since(start_time);
@@ -388,6 +767,24 @@ impl Authz {
             i64::MAX
         });
 
+        if let Some(shadow) = &self.shadow {
+            shadow.evaluate(
+                &self.config.authorization,
+                ShadowRequest {
+                    request_id,
+                    policy_store: policy_store.clone(),
+                    entities: entities.clone(),
+                    responses: ShadowRequest::responses(&result, principal_uids.iter()),
+                    workload: None,
+                    person: None,
+                    action,
+                    resource: resource_uid.clone(),
+                    context,
+                    decision: result.decision,
+                },
+            );
+        }
+
         // FROM THIS POINT WE ONLY MAKE LOGS
 
         // getting entities as json
@@ -403,10 +800,7 @@ impl Authz {
             .iter()
             .map(|(principal, response)| AuthorizeInfo {
                 principal: principal.to_string(),
-                diagnostics: Diagnostics::new(
-                    response.diagnostics(),
-                    &self.config.policy_store.policies,
-                ),
+                diagnostics: Diagnostics::new(response.diagnostics(), &policy_store.policies),
                 decision: response.decision().into(),
             })
             .collect::<Vec<_>>();
@@ -425,8 +819,8 @@ impl Authz {
         // we log decision log before debug log, to avoid cloning diagnostic info
         self.config.log_service.as_ref().log_any(&DecisionLogEntry {
             base: BaseLogEntry::new(LogType::Decision, request_id),
-            policystore_id: self.config.policy_store.id.as_str(),
-            policystore_version: self.config.policy_store.get_store_version(),
+            policystore_id: policy_store.id.as_str(),
+            policystore_version: policy_store.get_store_version(),
             principal: DecisionLogEntry::all_principals(principal_uids.as_slice()),
             user: None,
             workload: None,
@@ -437,6 +831,9 @@ impl Authz {
             tokens: LogTokensInfo::empty(),
             decision_time_micro_sec,
             diagnostics: DiagnosticsRefs::new(diagnostics.as_slice()),
+            explanations: result.explanations.as_ref(),
+            principal_rule,
+            cache_hit: false,
         });
 
         // DEBUG LOG
@@ -460,26 +857,62 @@ impl Authz {
     }
 
     /// Execute cedar policy is_authorized method to check
-    /// if allowed make request with given parameters
+    /// if allowed make request with given parameters.
     fn execute_authorize(
         &self,
         parameters: ExecuteAuthorizeParameters,
     ) -> Result<cedar_policy::Response, Box<cedar_policy::RequestValidationError>> {
+        self.execute_authorize_explained(parameters, false)
+            .map(|(response, _)| response)
+    }
+
+    /// Same as [`Authz::execute_authorize`], but also returns the evaluation trace
+    /// of the policies if `explain` is set.
+    fn execute_authorize_explained(
+        &self,
+        parameters: ExecuteAuthorizeParameters,
+        explain: bool,
+    ) -> Result<
+        (cedar_policy::Response, Option<Vec<PolicyTrace>>),
+        Box<cedar_policy::RequestValidationError>,
+    > {
+        let principal_type = parameters.principal.type_name().clone();
+        let resource_type = parameters.resource.type_name().clone();
+        let action = parameters.action.clone();
+
         let request_principal_workload = cedar_policy::Request::new(
             parameters.principal,
             parameters.action,
             parameters.resource,
             parameters.context,
-            Some(&self.config.policy_store.schema.schema),
+            Some(&parameters.policy_store.schema.schema),
         )?;
 
+        // only the policies whose scope can match the request are evaluated,
+        // the request is validated first so only the types of the schema are indexed
+        let policies = parameters.policy_store.policies.policies_for(
+            &principal_type,
+            &action,
+            &resource_type,
+            || parameters.policy_store.schema.json.action_groups(&action),
+        );
+
         let response = self.authorizer.is_authorized(
             &request_principal_workload,
-            self.config.policy_store.policies.get_set(),
+            &policies,
             parameters.entities,
         );
 
-        Ok(response)
+        let trace = explain.then(|| {
+            explain::explain_policies(
+                &self.authorizer,
+                &request_principal_workload,
+                &policies,
+                parameters.entities,
+            )
+        });
+
+        Ok((response, trace))
     }
 
     #[cfg(test)]
@@ -489,8 +922,7 @@ impl Authz {
         tokens: &HashMap<String, Arc<Token>>,
     ) -> Result<AuthorizeEntitiesData, AuthorizeError> {
         Ok(self
-            .config
-            .entity_builder
+            .entity_builder()
             .build_entities(tokens, &request.resource)?)
     }
 
@@ -520,6 +952,7 @@ pub enum AuthzServiceInitError {
 
 /// Helper struct to hold named parameters for [`Authz::execute_authorize`] method.
 struct ExecuteAuthorizeParameters<'a> {
+    policy_store: &'a PolicyStoreWithID,
     entities: &'a Entities,
     principal: EntityUid,
     action: EntityUid,
@@ -534,6 +967,8 @@ pub struct AuthorizeEntitiesData {
     pub workload: Option<Entity>,
     pub user: Option<Entity>,
     pub roles: Vec<Entity>,
+    /// Entities built from the `membership_mapping` of the tokens
+    pub memberships: Vec<Entity>,
     pub resource: Entity,
     pub default_entities: HashMap<EntityUid, Entity>,
 }
@@ -552,6 +987,7 @@ impl AuthorizeEntitiesData {
         // Add request entities (these will override default entities if conflicts exist)
         merged_entities.extend(vec![self.resource].into_iter().map(|e| (e.uid(), e)));
         merged_entities.extend(self.issuers.into_iter().map(|e| (e.uid(), e)));
+        merged_entities.extend(self.memberships.into_iter().map(|e| (e.uid(), e)));
         merged_entities.extend(self.roles.into_iter().map(|e| (e.uid(), e)));
         merged_entities.extend(self.tokens.into_values().map(|e| (e.uid(), e)));
         merged_entities.extend(
@@ -578,6 +1014,7 @@ impl AuthorizeEntitiesData {
     /// - **Token Entities**: e.g., `access_token`, `id_token`, etc.
     /// - **Principal Entities**: e.g., `Workload`, `User`, etc.
     /// - **Role Entities**
+    /// - **Membership Entities**: e.g., `Group` entities from the `membership_mapping`
     /// - **Default Entities**: Entities loaded from the policy store configuration
     ///
     /// Only entities that have been built will be included
@@ -598,6 +1035,7 @@ impl AuthorizeEntitiesData {
                 .map(|e| (e.uid(), e.clone())),
         );
         merged_entities.extend(self.issuers.iter().map(|e| (e.uid(), e.clone())));
+        merged_entities.extend(self.memberships.iter().map(|e| (e.uid(), e.clone())));
         merged_entities.extend(self.roles.iter().map(|e| (e.uid(), e.clone())));
         merged_entities.extend(self.tokens.values().map(|e| (e.uid(), e.clone())));
         merged_entities.extend(
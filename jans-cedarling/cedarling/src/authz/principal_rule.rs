@@ -0,0 +1,132 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! # Principal rules
+//! Selects the JsonLogic rule that combines the decisions of the principals for an action.
+
+use std::iter;
+use std::str::FromStr;
+
+use cedar_policy::EntityUid;
+use serde::Serialize;
+
+use super::Authz;
+use crate::bootstrap_config::AuthorizationConfig;
+use crate::common::json_rules::JsonRule;
+use crate::common::policy_store::PolicyStoreWithID;
+
+/// What the principal rule of an action is defined for
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrincipalRuleSource {
+    /// The rule is defined for the action
+    Action,
+    /// The rule is defined for an action group the action is a member of
+    ActionGroup,
+    /// The global `CEDARLING_PRINCIPAL_BOOLEAN_OPERATION` rule
+    Default,
+}
+
+/// JsonLogic rule used to combine the decisions of the principals for an action
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PrincipalRule<'a> {
+    /// What the rule is defined for
+    pub source: PrincipalRuleSource,
+    /// UID of the action or action group the rule is defined for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    /// The rule
+    pub rule: &'a JsonRule,
+}
+
+impl Authz {
+    /// Returns the principal rule of the action.
+    pub(crate) fn principal_rule<'a>(
+        &'a self,
+        policy_store: &'a PolicyStoreWithID,
+        action: &EntityUid,
+    ) -> PrincipalRule<'a> {
+        select_principal_rule(&self.config.authorization, policy_store, action)
+    }
+}
+
+/// Returns the principal rule of the action.
+///
+/// The rule of the action is used first, then the rule of the nearest action group,
+/// then the global rule. For the same action or group, the rules of the bootstrap
+/// config take precedence over the rules of the policy store.
+pub(crate) fn select_principal_rule<'a>(
+    authorization: &'a AuthorizationConfig,
+    policy_store: &'a PolicyStoreWithID,
+    action: &EntityUid,
+) -> PrincipalRule<'a> {
+    let config_rules = &authorization.principal_bool_operator_by_action;
+    let store_rules = policy_store
+        .principal_bool_operations
+        .as_ref()
+        .filter(|rules| !rules.is_empty());
+
+    if !config_rules.is_empty() || store_rules.is_some() {
+        let groups = policy_store.schema.json.action_groups(action);
+        let candidates = iter::once((PrincipalRuleSource::Action, action)).chain(
+            groups
+                .iter()
+                .map(|group| (PrincipalRuleSource::ActionGroup, group)),
+        );
+
+        for (source, uid) in candidates {
+            let uid = uid.to_string();
+            let rule = config_rules
+                .get(&uid)
+                .or_else(|| store_rules.and_then(|rules| rules.get(&uid)));
+            if let Some(rule) = rule {
+                return PrincipalRule {
+                    source,
+                    uid: Some(uid),
+                    rule,
+                };
+            }
+        }
+    }
+
+    PrincipalRule {
+        source: PrincipalRuleSource::Default,
+        uid: None,
+        rule: &authorization.principal_bool_operator,
+    }
+}
+
+/// Returns the keys of the principal rules that are not actions or action groups of the schema.
+pub(crate) fn unknown_rule_actions<'a>(
+    authorization: &'a AuthorizationConfig,
+    policy_store: &'a PolicyStoreWithID,
+) -> Vec<&'a str> {
+    let mut unknown = authorization
+        .principal_bool_operator_by_action
+        .keys()
+        .chain(
+            policy_store
+                .principal_bool_operations
+                .iter()
+                .flatten()
+                .map(|(uid, _)| uid),
+        )
+        .filter(|uid| {
+            let Ok(uid) = EntityUid::from_str(uid) else {
+                return true;
+            };
+            uid.type_name().basename() != "Action"
+                || policy_store
+                    .schema
+                    .json
+                    .get_action(&uid.type_name().namespace(), uid.id().unescaped())
+                    .is_none()
+        })
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    unknown.sort();
+    unknown.dedup();
+    unknown
+}
@@ -0,0 +1,264 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! # Shadow policy store
+//! Evaluates the requests against a second policy store, without affecting the decisions,
+//! and logs the requests where its decision differs from the decision of the policy store.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use cedar_policy::{Context, Entities, EntityUid, Response};
+use serde::Serialize;
+use uuid7::Uuid;
+
+use super::AuthorizeResult;
+use super::principal_rule::select_principal_rule;
+use crate::bootstrap_config::AuthorizationConfig;
+use crate::common::json_rules::JsonRule;
+use crate::common::policy_store::PolicyStoreWithID;
+use crate::log::interface::LogWriter;
+use crate::log::{
+    BaseLogEntry, Decision, DecisionDiffLogEntry, LogType, Logger, PrincipalDecisionDiff,
+};
+
+/// Counters of the evaluations against the shadow policy store
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ShadowStats {
+    /// ID of the policy store
+    pub policy_store_id: String,
+    /// ID of the shadow policy store
+    pub shadow_policy_store_id: String,
+    /// Number of requests evaluated against the shadow policy store
+    pub evaluations: u64,
+    /// Number of requests where the shadow policy store decided differently
+    pub decision_diffs: u64,
+    /// Number of requests where the policies that determined the decision of
+    /// a principal differ
+    pub policy_diffs: u64,
+    /// Number of requests the shadow policy store could not evaluate
+    pub errors: u64,
+}
+
+#[derive(Default)]
+struct ShadowCounters {
+    evaluations: AtomicU64,
+    decision_diffs: AtomicU64,
+    policy_diffs: AtomicU64,
+    errors: AtomicU64,
+}
+
+/// Request evaluated against the shadow policy store, with the responses of the policy store
+pub(crate) struct ShadowRequest {
+    pub request_id: Uuid,
+    /// Policy store the request was evaluated against
+    pub policy_store: Arc<PolicyStoreWithID>,
+    pub entities: Entities,
+    /// Responses of the policy store for each principal
+    pub responses: HashMap<EntityUid, Response>,
+    pub workload: Option<EntityUid>,
+    pub person: Option<EntityUid>,
+    pub action: EntityUid,
+    pub resource: EntityUid,
+    pub context: Context,
+    /// Decision of the policy store
+    pub decision: bool,
+}
+
+impl ShadowRequest {
+    /// Returns the responses of the principals from the result of the policy store
+    pub fn responses<'a>(
+        result: &AuthorizeResult,
+        principals: impl IntoIterator<Item = &'a EntityUid>,
+    ) -> HashMap<EntityUid, Response> {
+        principals
+            .into_iter()
+            .filter_map(|principal| {
+                let response = result.principals.get(principal.to_string().as_str())?;
+                Some((principal.clone(), response.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Evaluates the requests against the shadow policy store
+pub(crate) struct ShadowEvaluator {
+    policy_store: Arc<PolicyStoreWithID>,
+    counters: Arc<ShadowCounters>,
+    log: Logger,
+}
+
+impl ShadowEvaluator {
+    pub fn new(policy_store: PolicyStoreWithID, log: Logger) -> Self {
+        Self {
+            policy_store: Arc::new(policy_store),
+            counters: Default::default(),
+            log,
+        }
+    }
+
+    /// Returns the counters of the shadow evaluations
+    pub fn stats(&self, policy_store_id: &str) -> ShadowStats {
+        self.counters
+            .snapshot(policy_store_id, &self.policy_store.id)
+    }
+
+    /// Evaluates the request in a separate task, so the caller gets its decision
+    /// without waiting for the shadow evaluation.
+    pub fn evaluate(&self, authorization: &AuthorizationConfig, request: ShadowRequest) {
+        let rule = select_principal_rule(authorization, &self.policy_store, &request.action)
+            .rule
+            .clone();
+        let policy_store = self.policy_store.clone();
+        let counters = self.counters.clone();
+        let log = self.log.clone();
+
+        crate::http::spawn_task(async move {
+            compare_decisions(&policy_store, &counters, &log, &rule, request);
+        });
+    }
+}
+
+impl ShadowCounters {
+    fn snapshot(&self, policy_store_id: &str, shadow_policy_store_id: &str) -> ShadowStats {
+        ShadowStats {
+            policy_store_id: policy_store_id.to_string(),
+            shadow_policy_store_id: shadow_policy_store_id.to_string(),
+            evaluations: self.evaluations.load(Ordering::Relaxed),
+            decision_diffs: self.decision_diffs.load(Ordering::Relaxed),
+            policy_diffs: self.policy_diffs.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Evaluates the request against the shadow policy store and logs a [`DecisionDiffLogEntry`]
+/// if the decision or the policies that determined it differ.
+fn compare_decisions(
+    shadow_store: &PolicyStoreWithID,
+    counters: &ShadowCounters,
+    log: &Logger,
+    rule: &JsonRule,
+    request: ShadowRequest,
+) {
+    let authorizer = cedar_policy::Authorizer::new();
+
+    let mut shadow_responses = HashMap::new();
+    let mut principals = BTreeMap::new();
+    let mut failed = false;
+    for (principal, response) in request.responses.iter() {
+        let shadow_request = cedar_policy::Request::new(
+            principal.clone(),
+            request.action.clone(),
+            request.resource.clone(),
+            request.context.clone(),
+            Some(&shadow_store.schema.schema),
+        );
+
+        let (shadow_decision, shadow_reason, shadow_errors) = match shadow_request {
+            Ok(shadow_request) => {
+                let policies = shadow_store.policies.policies_for(
+                    principal.type_name(),
+                    &request.action,
+                    request.resource.type_name(),
+                    || shadow_store.schema.json.action_groups(&request.action),
+                );
+                let shadow_response =
+                    authorizer.is_authorized(&shadow_request, &policies, &request.entities);
+                let diff = (
+                    shadow_response.decision().into(),
+                    policy_ids(&shadow_response),
+                    shadow_response
+                        .diagnostics()
+                        .errors()
+                        .map(|err| err.to_string())
+                        .collect(),
+                );
+                shadow_responses.insert(principal.clone(), shadow_response);
+                diff
+            },
+            Err(err) => {
+                failed = true;
+                (Decision::Deny, BTreeSet::new(), vec![err.to_string()])
+            },
+        };
+
+        let diff = PrincipalDecisionDiff {
+            decision: response.decision().into(),
+            shadow_decision,
+            reason: policy_ids(response),
+            shadow_reason,
+            shadow_errors,
+        };
+        if diff.decision != diff.shadow_decision
+            || diff.reason != diff.shadow_reason
+            || !diff.shadow_errors.is_empty()
+        {
+            principals.insert(principal.to_string(), diff);
+        }
+    }
+
+    // the shadow decision is a deny if a principal could not be evaluated
+    let shadow_decision = if failed {
+        false
+    } else {
+        match AuthorizeResult::new_for_many_principals(
+            rule,
+            shadow_responses,
+            request.workload,
+            request.person,
+            request.request_id,
+        ) {
+            Ok(result) => result.decision,
+            Err(_) => {
+                failed = true;
+                false
+            },
+        }
+    };
+
+    counters.evaluations.fetch_add(1, Ordering::Relaxed);
+    if failed {
+        counters.errors.fetch_add(1, Ordering::Relaxed);
+    }
+    let decision_diff = request.decision != shadow_decision;
+    if decision_diff {
+        counters.decision_diffs.fetch_add(1, Ordering::Relaxed);
+    }
+    let policy_diff = principals
+        .values()
+        .any(|diff| diff.reason != diff.shadow_reason);
+    if policy_diff {
+        counters.policy_diffs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    if !decision_diff && principals.is_empty() && !failed {
+        return;
+    }
+
+    log.log_any(&DecisionDiffLogEntry {
+        base: BaseLogEntry::new(LogType::DecisionDiff, request.request_id),
+        policystore_id: request.policy_store.id.clone(),
+        policystore_version: request.policy_store.get_store_version().to_string(),
+        shadow_policystore_id: shadow_store.id.clone(),
+        shadow_policystore_version: shadow_store.get_store_version().to_string(),
+        action: request.action.to_string(),
+        resource: request.resource.to_string(),
+        decision: request.decision.into(),
+        shadow_decision: shadow_decision.into(),
+        principals,
+        counters: counters.snapshot(&request.policy_store.id, &shadow_store.id),
+    });
+}
+
+/// IDs of the policies that determined the decision
+fn policy_ids(response: &Response) -> BTreeSet<String> {
+    response
+        .diagnostics()
+        .reason()
+        .map(|id| id.to_string())
+        .collect()
+}
@@ -8,10 +8,13 @@
 //! Blocking client of Cedarling
 
 use crate::{
-    AuthorizeError, AuthorizeResult, BootstrapConfig, InitCedarlingError, LogStorage, Request,
-    RequestUnsigned,
+    AllowedActionsResult, AuthorizeError, AuthorizeResult, BootstrapConfig, DeregisterClientError,
+    EntityData, InitCedarlingError, LogQuery, LogQueryResult, LogStorage, LogSubscription,
+    LogSubscriptionConfig, Request, RequestPrincipals, RequestUnsigned, ShadowStats, TemplateLink,
+    TemplateLinkError,
 };
 use crate::{BootstrapConfigRaw, Cedarling as AsyncCedarling};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 
@@ -61,10 +64,67 @@ impl Cedarling {
             .block_on(self.instance.authorize_unsigned(request))
     }
 
+    /// Evaluates every action of the schema that applies to the principals and the resource.
+    ///
+    /// See [`AsyncCedarling::allowed_actions`].
+    pub fn allowed_actions(
+        &self,
+        principals: RequestPrincipals,
+        resource: EntityData,
+        context: serde_json::Value,
+    ) -> Result<AllowedActionsResult, AuthorizeError> {
+        self.runtime
+            .block_on(self.instance.allowed_actions(principals, resource, context))
+    }
+
+    /// Links a policy template of the policy store to the values of its slots.
+    ///
+    /// See [`AsyncCedarling::add_template_link`].
+    pub fn add_template_link(
+        &self,
+        link_id: &str,
+        link: TemplateLink,
+    ) -> Result<(), TemplateLinkError> {
+        self.instance.add_template_link(link_id, link)
+    }
+
+    /// Removes a policy linked from a template and returns its link.
+    pub fn remove_template_link(&self, link_id: &str) -> Result<TemplateLink, TemplateLinkError> {
+        self.instance.remove_template_link(link_id)
+    }
+
+    /// Returns the template links of the policy store, by link ID.
+    pub fn template_links(&self) -> HashMap<String, TemplateLink> {
+        self.instance.template_links()
+    }
+
+    /// Returns the counters of the evaluations against the shadow policy store.
+    ///
+    /// See [`AsyncCedarling::shadow_stats`].
+    pub fn shadow_stats(&self) -> Option<ShadowStats> {
+        self.instance.shadow_stats()
+    }
+
+    /// Subscribes to the log entries logged from now on, whatever the log type.
+    ///
+    /// Use [`LogSubscription::blocking_recv`] to wait for entries.
+    pub fn subscribe_logs(&self, config: LogSubscriptionConfig) -> LogSubscription {
+        self.instance.subscribe_logs(config)
+    }
+
     /// Closes the connections to the Lock Server and pushes all available logs.
     pub fn shut_down(&self) {
         self.runtime.block_on(self.instance.shut_down());
     }
+
+    /// Stops sending logs to the Lock Server then deletes the client Cedarling registered
+    /// with the IDP.
+    ///
+    /// See [`AsyncCedarling::deregister_lock_client`].
+    pub fn deregister_lock_client(&self) -> Result<bool, DeregisterClientError> {
+        self.runtime
+            .block_on(self.instance.deregister_lock_client())
+    }
 }
 
 impl LogStorage for Cedarling {
@@ -91,4 +151,8 @@ impl LogStorage for Cedarling {
     fn get_logs_by_request_id_and_tag(&self, id: &str, tag: &str) -> Vec<serde_json::Value> {
         self.instance.get_logs_by_request_id_and_tag(id, tag)
     }
+
+    fn query_logs(&self, query: &LogQuery) -> LogQueryResult {
+        self.instance.query_logs(query)
+    }
 }
@@ -26,6 +26,13 @@ pub struct AuthorizationConfig {
     /// making authz (authorization) decisions.
     pub principal_bool_operator: JsonRule,
 
+    /// Boolean operations used instead of `principal_bool_operator` for some actions,
+    /// keyed by the UID of the action or of an action group, such as `Jans::Action::"Read"`.
+    ///
+    /// They take precedence over the operations of the policy store.
+    /// bootstrap property: `CEDARLING_PRINCIPAL_BOOLEAN_OPERATION_BY_ACTION`
+    pub principal_bool_operator_by_action: HashMap<String, JsonRule>,
+
     /// List of claims to map from user entity, such as ["sub", "email", "username", ...]
     /// `CEDARLING_DECISION_LOG_USER_CLAIMS` in [bootstrap properties](https://github.com/JanssenProject/jans/wiki/Cedarling-Nativity-Plan#bootstrap-properties) documentation.
     pub decision_log_user_claims: Vec<String>,
@@ -58,6 +65,66 @@ pub struct AuthorizationConfig {
     /// [`IfPresent`]: IdTokenTrustMode::IfPresent
     /// [`Strict`]: IdTokenTrustMode::Strict
     pub id_token_trust_mode: IdTokenTrustMode,
+
+    /// When `true`, every authorization result contains the evaluation trace of the
+    /// policies, see [`Cedarling::authorize_explain`](crate::Cedarling::authorize_explain).
+    /// bootstrap property: `CEDARLING_AUTHZ_EXPLAIN`
+    pub explain: bool,
+
+    /// Cache of the results of the `authorize` requests, disabled when `None`.
+    /// bootstrap properties: `CEDARLING_DECISION_CACHE`, `CEDARLING_DECISION_CACHE_TTL`
+    /// and `CEDARLING_DECISION_CACHE_MAX_ITEMS`
+    pub decision_cache: Option<DecisionCacheConfig>,
+
+    /// Annotations of the determining policies returned in the authorization result,
+    /// such as `obligation` for `@obligation("mfa")`.
+    /// bootstrap property: `CEDARLING_AUTHZ_ANNOTATION_KEYS`
+    pub annotation_keys: Vec<String>,
+}
+
+/// Returns the annotations returned in the authorization result by default
+pub(crate) fn default_annotation_keys() -> Vec<String> {
+    ["obligation", "advice", "reason_code"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+/// Configuration of the decision cache
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DecisionCacheConfig {
+    /// Maximum time in seconds a decision is cached.
+    /// The decision is not cached longer than the tokens of the request are valid.
+    pub ttl_secs: u64,
+    /// Maximum number of cached decisions
+    pub max_items: usize,
+}
+
+impl DecisionCacheConfig {
+    const DEFAULT_TTL_SECS: u64 = 60;
+    const DEFAULT_MAX_ITEMS: usize = 10_000;
+
+    /// Creates the config, zero values are replaced by the defaults
+    pub fn new(ttl_secs: u64, max_items: usize) -> Self {
+        Self {
+            ttl_secs: if ttl_secs == 0 {
+                Self::DEFAULT_TTL_SECS
+            } else {
+                ttl_secs
+            },
+            max_items: if max_items == 0 {
+                Self::DEFAULT_MAX_ITEMS
+            } else {
+                max_items
+            },
+        }
+    }
+}
+
+impl Default for DecisionCacheConfig {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
 }
 
 /// Raw authorization config
@@ -68,12 +135,18 @@ pub struct AuthorizationConfigRaw {
     pub use_workload_principal: bool,
     /// Principal bool operator
     pub principal_bool_operator: JsonRule,
+    /// Principal bool operators by action
+    pub principal_bool_operator_by_action: HashMap<String, JsonRule>,
     /// Decision log default JWT ID
     pub decision_log_default_jwt_id: String,
     /// Decision log user claims
     pub decision_log_user_claims: Vec<String>,
     /// Decision log workload claims
     pub decision_log_workload_claims: Vec<String>,
+    /// Decision cache, disabled if `None`
+    pub decision_cache: Option<DecisionCacheConfig>,
+    /// Annotations returned in the authorization result
+    pub annotation_keys: Vec<String>,
 }
 
 /// ID token trust mode
@@ -102,10 +175,14 @@ impl Default for AuthorizationConfig {
             use_user_principal: true,
             use_workload_principal: true,
             principal_bool_operator: JsonRule::default(),
+            principal_bool_operator_by_action: HashMap::new(),
             decision_log_default_jwt_id: "jti".to_string(),
             decision_log_user_claims: Vec::new(),
             decision_log_workload_claims: Vec::new(),
             id_token_trust_mode: IdTokenTrustMode::Strict,
+            explain: false,
+            decision_cache: None,
+            annotation_keys: default_annotation_keys(),
         }
     }
 }
@@ -116,10 +193,14 @@ impl From<AuthorizationConfigRaw> for AuthorizationConfig {
             use_user_principal: raw.use_user_principal,
             use_workload_principal: raw.use_workload_principal,
             principal_bool_operator: raw.principal_bool_operator,
+            principal_bool_operator_by_action: raw.principal_bool_operator_by_action,
             decision_log_default_jwt_id: raw.decision_log_default_jwt_id,
             decision_log_user_claims: raw.decision_log_user_claims,
             decision_log_workload_claims: raw.decision_log_workload_claims,
             id_token_trust_mode: IdTokenTrustMode::Strict,
+            explain: false,
+            decision_cache: raw.decision_cache,
+            annotation_keys: raw.annotation_keys,
         }
     }
 }
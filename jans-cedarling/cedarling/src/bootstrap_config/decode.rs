@@ -12,16 +12,24 @@ use std::fmt::Display;
 use std::fs;
 use std::path::Path;
 use std::str::FromStr;
+use std::time::Duration;
 
-use super::authorization_config::{AuthorizationConfig, IdTokenTrustMode};
+use super::authorization_config::{
+    AuthorizationConfig, DecisionCacheConfig, IdTokenTrustMode, default_annotation_keys,
+};
 use super::raw_config::LoggerType;
 use super::{
-    BootstrapConfig, BootstrapConfigLoadingError, JwtConfig, LogConfig, LogTypeConfig,
-    MemoryLogConfig, PolicyStoreConfig, PolicyStoreSource,
+    BootstrapConfig, BootstrapConfigLoadingError, HttpConfig, JwtConfig, LogConfig, LogTypeConfig,
+    MemoryLogConfig, PolicyStoreConfig, PolicyStoreSignatureConfig, PolicyStoreSource,
+    PolicyValidationConfig, TrustedIssuerInitConfig,
 };
 use super::{BootstrapConfigRaw, LockServiceConfig};
+use crate::app_types::PdpID;
+use crate::jwt::cast_to_algorithm;
 use crate::log::LogLevel;
-use jsonwebtoken::Algorithm;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey};
+use semver::Version;
 use serde::{Deserialize, Deserializer, Serialize};
 
 impl BootstrapConfig {
@@ -44,7 +52,14 @@ impl BootstrapConfig {
             return Err(BootstrapConfigLoadingError::BothPrincipalsDisabled);
         }
 
+        let pdp_id = raw
+            .pdp_id
+            .as_deref()
+            .map(|pdp_id| PdpID::parse(pdp_id).map(|_| pdp_id.to_string()))
+            .transpose()?;
+
         let lock_config = raw.lock.is_enabled().then(|| raw.try_into()).transpose()?;
+        let http_config = HttpConfig::try_from(&raw.http_client)?;
 
         // Decode LogCofig
         let log_type = match raw.log_type {
@@ -64,42 +79,24 @@ impl BootstrapConfig {
         };
 
         // Decode policy store
-        let policy_store_config = match (
-            raw.local_policy_store.clone(),
-            raw.policy_store_uri.clone(),
-            raw.policy_store_local_fn.clone(),
-        ) {
-            // Case: no policy store provided
-            (None, None, None) => Err(BootstrapConfigLoadingError::MissingPolicyStore)?,
-            // Case: get the policy store from a JSON string
-            (Some(policy_store), None, None) => PolicyStoreConfig {
-                source: PolicyStoreSource::Json(policy_store),
-            },
-            // Case: get the policy store from the lock server
-            (None, Some(policy_store_uri), None) => PolicyStoreConfig {
-                source: PolicyStoreSource::LockServer(policy_store_uri),
-            },
-            // Case: get the policy store from a local JSON file
-            (None, None, Some(raw_path)) => {
-                let path = Path::new(&raw_path);
-                let file_ext = Path::new(&path)
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|x| x.to_lowercase());
-
-                let source = match file_ext.as_deref() {
-                    Some("json") => PolicyStoreSource::FileJson(path.into()),
-                    Some("yaml") | Some("yml") => PolicyStoreSource::FileYaml(path.into()),
-                    _ => Err(
-                        BootstrapConfigLoadingError::UnsupportedPolicyStoreFileFormat(raw_path),
-                    )?,
-                };
-                PolicyStoreConfig { source }
-            },
-            // Case: multiple polict stores were set
-            _ => Err(BootstrapConfigLoadingError::ConflictingPolicyStores)?,
+        let policy_store_config = PolicyStoreConfig {
+            source: decode_policy_store_source(
+                raw.local_policy_store.clone(),
+                raw.policy_store_uri.clone(),
+                raw.policy_store_local_fn.clone(),
+                BootstrapConfigLoadingError::ConflictingPolicyStores,
+            )?
+            .ok_or(BootstrapConfigLoadingError::MissingPolicyStore)?,
         };
 
+        let shadow_policy_store_config = decode_policy_store_source(
+            raw.local_shadow_policy_store.clone(),
+            raw.shadow_policy_store_uri.clone(),
+            raw.shadow_policy_store_local_fn.clone(),
+            BootstrapConfigLoadingError::ConflictingShadowPolicyStores,
+        )?
+        .map(|source| PolicyStoreConfig { source });
+
         // Load the jwks from a local file
         let jwks = raw
             .local_jwks
@@ -117,29 +114,142 @@ impl BootstrapConfig {
             jwt_sig_validation: raw.jwt_sig_validation.into(),
             jwt_status_validation: raw.jwt_status_validation.into(),
             signature_algorithms_supported: raw.jwt_signature_algorithms_supported.clone(),
+            trusted_issuer_init: TrustedIssuerInitConfig::new(
+                raw.trusted_issuer_init_concurrency,
+                raw.trusted_issuer_init_timeout,
+                raw.trusted_issuer_allow_partial_init.is_enabled(),
+            ),
         };
 
         let authorization_config = AuthorizationConfig {
             use_user_principal: raw.user_authz.is_enabled(),
             use_workload_principal: raw.workload_authz.is_enabled(),
             principal_bool_operator: raw.principal_bool_operation.clone(),
+            principal_bool_operator_by_action: raw.principal_bool_operation_by_action.clone(),
             decision_log_user_claims: raw.decision_log_user_claims.clone(),
             decision_log_workload_claims: raw.decision_log_workload_claims.clone(),
             decision_log_default_jwt_id: raw.decision_log_default_jwt_id.clone(),
             id_token_trust_mode: raw.id_token_trust_mode.clone(),
+            explain: raw.authz_explain.is_enabled(),
+            decision_cache: raw.decision_cache.is_enabled().then(|| {
+                DecisionCacheConfig::new(raw.decision_cache_ttl, raw.decision_cache_max_items)
+            }),
+            annotation_keys: raw
+                .authz_annotation_keys
+                .clone()
+                .unwrap_or_else(default_annotation_keys),
         };
 
         Ok(Self {
             application_name: raw.application_name.clone(),
+            pdp_id,
             log_config,
             policy_store_config,
+            policy_validation_config: PolicyValidationConfig {
+                level: raw.policy_validation,
+                mode: raw.policy_validation_mode,
+                reject_unknown_keys: raw.policy_store_strict.is_enabled(),
+            },
+            policy_store_poll_interval: (raw.policy_store_poll_interval > 0)
+                .then(|| Duration::from_secs(raw.policy_store_poll_interval)),
+            policy_store_signature_config: decode_policy_store_signature_config(
+                raw.policy_store_trusted_keys.clone(),
+                raw.policy_store_min_version.as_deref(),
+            )?,
+            shadow_policy_store_config,
             jwt_config,
             authorization_config,
             entity_builder_config: raw.into(),
             lock_config,
+            http_config,
             max_default_entities: raw.max_default_entities,
             max_base64_size: raw.max_base64_size,
             token_cache_max_ttl_secs: raw.token_cache_max_ttl,
         })
     }
 }
+
+/// Decodes the source of a policy store from its bootstrap properties.
+///
+/// Returns `conflict_err` if more than one source is set.
+fn decode_policy_store_source(
+    local: Option<String>,
+    uri: Option<String>,
+    local_fn: Option<String>,
+    conflict_err: BootstrapConfigLoadingError,
+) -> Result<Option<PolicyStoreSource>, BootstrapConfigLoadingError> {
+    let source = match (local, uri, local_fn) {
+        // Case: no policy store provided
+        (None, None, None) => return Ok(None),
+        // Case: get the policy store from a JSON string
+        (Some(policy_store), None, None) => PolicyStoreSource::Json(policy_store),
+        // Case: get the policy store from the lock server
+        (None, Some(policy_store_uri), None) => PolicyStoreSource::LockServer(policy_store_uri),
+        // Case: get the policy store from a local directory
+        (None, None, Some(raw_path)) if Path::new(&raw_path).is_dir() => {
+            PolicyStoreSource::Directory(raw_path.into())
+        },
+        // Case: get the policy store from a local JSON, YAML or zip file
+        (None, None, Some(raw_path)) => {
+            let path = Path::new(&raw_path);
+            let file_ext = Path::new(&path)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|x| x.to_lowercase());
+
+            match file_ext.as_deref() {
+                Some("json") => PolicyStoreSource::FileJson(path.into()),
+                Some("yaml") | Some("yml") => PolicyStoreSource::FileYaml(path.into()),
+                Some("zip") => PolicyStoreSource::Archive(path.into()),
+                _ => Err(BootstrapConfigLoadingError::UnsupportedPolicyStoreFileFormat(raw_path))?,
+            }
+        },
+        // Case: multiple polict stores were set
+        _ => return Err(conflict_err),
+    };
+    Ok(Some(source))
+}
+
+/// Decodes the trusted keys of the signed policy stores.
+///
+/// Each key must be usable to verify a signature, so a mistake in the keys is reported
+/// on startup instead of rejecting every policy store.
+fn decode_policy_store_signature_config(
+    trusted_keys: Option<JwkSet>,
+    min_version: Option<&str>,
+) -> Result<Option<PolicyStoreSignatureConfig>, BootstrapConfigLoadingError> {
+    let min_version = min_version
+        .map(|version| Version::parse(version.strip_prefix('v').unwrap_or(version)))
+        .transpose()
+        .map_err(BootstrapConfigLoadingError::InvalidPolicyStoreMinVersion)?;
+
+    let Some(trusted_keys) = trusted_keys else {
+        return match min_version {
+            Some(_) => Err(BootstrapConfigLoadingError::MissingPolicyStoreTrustedKeys),
+            None => Ok(None),
+        };
+    };
+
+    if trusted_keys.keys.is_empty() {
+        return Err(BootstrapConfigLoadingError::InvalidPolicyStoreTrustedKey(
+            "the key set is empty".to_string(),
+        ));
+    }
+    for (i, jwk) in trusted_keys.keys.iter().enumerate() {
+        let invalid_key = |err: String| {
+            BootstrapConfigLoadingError::InvalidPolicyStoreTrustedKey(format!("key {i}: {err}"))
+        };
+        let alg = jwk
+            .common
+            .key_algorithm
+            .ok_or_else(|| invalid_key("the `alg` is not set".to_string()))?;
+        cast_to_algorithm(alg)
+            .map_err(|alg| invalid_key(format!("unsupported algorithm `{alg}`")))?;
+        DecodingKey::from_jwk(jwk).map_err(|err| invalid_key(err.to_string()))?;
+    }
+
+    Ok(Some(PolicyStoreSignatureConfig {
+        trusted_keys,
+        min_version,
+    }))
+}
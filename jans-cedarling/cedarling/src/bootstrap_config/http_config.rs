@@ -0,0 +1,255 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use super::BootstrapConfigLoadingError;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Options of the HTTP clients Cedarling uses for its outbound requests, by destination.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HttpConfig {
+    /// Client used to fetch the OpenID configuration, the JWKS and the status lists
+    /// of the trusted issuers.
+    pub idp: HttpClientConfig,
+    /// Client used to communicate with the Lock Server, including the Dynamic Client
+    /// Registration with its IDP.
+    pub lock: HttpClientConfig,
+    /// Client used to fetch the policy store.
+    pub policy_store: HttpClientConfig,
+}
+
+/// Options of an HTTP client.
+///
+/// The options other than `accept_invalid_certs` are not supported in WASM builds,
+/// where the browser handles the connections.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HttpClientConfig {
+    /// PEM file with the certificates of the CAs trusted in addition to the system roots.
+    pub ca_bundle: Option<PathBuf>,
+    /// Certificate presented to the servers that request one.
+    pub client_cert: Option<HttpClientCertConfig>,
+    /// URL of the proxy used for HTTP and HTTPS requests.
+    pub proxy: Option<String>,
+    /// Comma separated hosts that are reached without the proxy.
+    pub no_proxy: Option<String>,
+    /// Maximum duration of a request, from connecting until the response body is read.
+    pub timeout: Option<Duration>,
+    /// Maximum duration to establish a connection.
+    pub connect_timeout: Option<Duration>,
+    /// Accept server certificates that fail validation. Used for testing.
+    pub accept_invalid_certs: bool,
+}
+
+/// Client certificate used for mutual TLS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpClientCertConfig {
+    /// PEM file with the certificate chain.
+    pub cert_file: PathBuf,
+    /// PEM file with the PKCS#8 private key of the certificate.
+    pub key_file: PathBuf,
+}
+
+/// Raw value of the `CEDARLING_HTTP_CLIENT` bootstrap property.
+///
+/// The top level options apply to every destination, the `idp`, `lock` and
+/// `policy_store` objects override them for a destination.
+///
+/// Example:
+/// ```json
+/// {
+///     "ca_bundle": "/etc/cedarling/private-ca.pem",
+///     "proxy": "http://proxy.internal:3128",
+///     "no_proxy": "localhost,.internal",
+///     "timeout_ms": 10000,
+///     "lock": {
+///         "client_cert_file": "/etc/cedarling/client.crt",
+///         "client_key_file": "/etc/cedarling/client.key"
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct HttpConfigRaw {
+    /// Options of every destination
+    #[serde(flatten)]
+    pub default: HttpClientConfigRaw,
+    /// Options of the requests to the trusted issuers
+    #[serde(default)]
+    pub idp: Option<HttpClientConfigRaw>,
+    /// Options of the requests to the Lock Server
+    #[serde(default)]
+    pub lock: Option<HttpClientConfigRaw>,
+    /// Options of the requests for the policy store
+    #[serde(default)]
+    pub policy_store: Option<HttpClientConfigRaw>,
+}
+
+/// Raw options of an HTTP client, see [`HttpClientConfig`].
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct HttpClientConfigRaw {
+    /// PEM file with the CA certificates trusted besides the system ones
+    #[serde(default)]
+    pub ca_bundle: Option<String>,
+    /// PEM file with the client certificate chain, requires `client_key_file`
+    #[serde(default)]
+    pub client_cert_file: Option<String>,
+    /// PEM file with the PKCS#8 private key of the client certificate
+    #[serde(default)]
+    pub client_key_file: Option<String>,
+    /// URL of the proxy the requests are sent through
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Comma separated hosts that are not reached through the proxy
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+    /// Maximum duration of a request, in milliseconds
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Maximum duration to establish a connection, in milliseconds
+    #[serde(default)]
+    pub connect_timeout_ms: Option<u64>,
+    /// Accept server certificates that fail validation
+    #[serde(default)]
+    pub accept_invalid_certs: Option<bool>,
+}
+
+impl HttpClientConfigRaw {
+    /// Returns the options set in `self`, falling back to the ones of `default`.
+    fn or(&self, default: &Self) -> Self {
+        Self {
+            ca_bundle: self.ca_bundle.clone().or(default.ca_bundle.clone()),
+            client_cert_file: self
+                .client_cert_file
+                .clone()
+                .or(default.client_cert_file.clone()),
+            client_key_file: self
+                .client_key_file
+                .clone()
+                .or(default.client_key_file.clone()),
+            proxy: self.proxy.clone().or(default.proxy.clone()),
+            no_proxy: self.no_proxy.clone().or(default.no_proxy.clone()),
+            timeout_ms: self.timeout_ms.or(default.timeout_ms),
+            connect_timeout_ms: self.connect_timeout_ms.or(default.connect_timeout_ms),
+            accept_invalid_certs: self.accept_invalid_certs.or(default.accept_invalid_certs),
+        }
+    }
+
+    fn decode(
+        &self,
+        destination: &'static str,
+    ) -> Result<HttpClientConfig, BootstrapConfigLoadingError> {
+        let client_cert = match (&self.client_cert_file, &self.client_key_file) {
+            (None, None) => None,
+            (Some(cert_file), Some(key_file)) => Some(HttpClientCertConfig {
+                cert_file: cert_file.into(),
+                key_file: key_file.into(),
+            }),
+            (Some(_), None) => Err(BootstrapConfigLoadingError::MissingHttpClientProperty(
+                destination,
+                "client_key_file",
+            ))?,
+            (None, Some(_)) => Err(BootstrapConfigLoadingError::MissingHttpClientProperty(
+                destination,
+                "client_cert_file",
+            ))?,
+        };
+
+        Ok(HttpClientConfig {
+            ca_bundle: self.ca_bundle.as_ref().map(PathBuf::from),
+            client_cert,
+            proxy: self.proxy.clone(),
+            no_proxy: self.no_proxy.clone(),
+            timeout: self.timeout_ms.map(Duration::from_millis),
+            connect_timeout: self.connect_timeout_ms.map(Duration::from_millis),
+            accept_invalid_certs: self.accept_invalid_certs.unwrap_or_default(),
+        })
+    }
+}
+
+impl TryFrom<&HttpConfigRaw> for HttpConfig {
+    type Error = BootstrapConfigLoadingError;
+
+    fn try_from(raw: &HttpConfigRaw) -> Result<Self, Self::Error> {
+        let decode = |overrides: &Option<HttpClientConfigRaw>, destination| {
+            overrides
+                .as_ref()
+                .map(|overrides| overrides.or(&raw.default))
+                .unwrap_or_else(|| raw.default.clone())
+                .decode(destination)
+        };
+
+        Ok(Self {
+            idp: decode(&raw.idp, "idp")?,
+            lock: decode(&raw.lock, "lock")?,
+            policy_store: decode(&raw.policy_store, "policy_store")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn overrides_the_options_by_destination() {
+        let raw = serde_json::from_str::<HttpConfigRaw>(
+            r#"{
+                "ca_bundle": "/etc/cedarling/private-ca.pem",
+                "proxy": "http://proxy.internal:3128",
+                "timeout_ms": 10000,
+                "lock": {
+                    "proxy": "http://lock-proxy.internal:3128",
+                    "client_cert_file": "/etc/cedarling/client.crt",
+                    "client_key_file": "/etc/cedarling/client.key"
+                }
+            }"#,
+        )
+        .expect("should deserialize the raw config");
+        let config = HttpConfig::try_from(&raw).expect("should decode the config");
+
+        let default = HttpClientConfig {
+            ca_bundle: Some("/etc/cedarling/private-ca.pem".into()),
+            proxy: Some("http://proxy.internal:3128".to_string()),
+            timeout: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        assert_eq!(config.idp, default);
+        assert_eq!(config.policy_store, default);
+        assert_eq!(
+            config.lock,
+            HttpClientConfig {
+                proxy: Some("http://lock-proxy.internal:3128".to_string()),
+                client_cert: Some(HttpClientCertConfig {
+                    cert_file: "/etc/cedarling/client.crt".into(),
+                    key_file: "/etc/cedarling/client.key".into(),
+                }),
+                ..default
+            }
+        );
+    }
+
+    #[test]
+    fn errors_on_a_certificate_without_key() {
+        let raw = HttpConfigRaw {
+            policy_store: Some(HttpClientConfigRaw {
+                client_cert_file: Some("/etc/cedarling/client.crt".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let err = HttpConfig::try_from(&raw).expect_err("the key should be required");
+        assert!(
+            matches!(
+                err,
+                BootstrapConfigLoadingError::MissingHttpClientProperty(
+                    "policy_store",
+                    "client_key_file"
+                )
+            ),
+            "{err}"
+        );
+    }
+}
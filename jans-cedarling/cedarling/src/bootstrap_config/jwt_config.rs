@@ -39,6 +39,54 @@ pub struct JwtConfig {
     pub jwt_status_validation: bool,
     /// Only tokens signed with algorithms in this list can be valid.
     pub signature_algorithms_supported: HashSet<Algorithm>,
+    /// Options for the initialization of the trusted issuers on startup.
+    #[serde(default)]
+    pub trusted_issuer_init: TrustedIssuerInitConfig,
+}
+
+/// Options for the initialization of the trusted issuers on startup.
+///
+/// Each trusted issuer is initialized by fetching its OpenID configuration, its JWKS and
+/// its status list. The trusted issuers are initialized concurrently.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrustedIssuerInitConfig {
+    /// Maximum number of trusted issuers initialized at the same time.
+    pub max_concurrency: usize,
+    /// Maximum time in seconds to initialize a trusted issuer.
+    pub timeout_secs: u64,
+    /// Continue the startup when some trusted issuers fail to initialize.
+    ///
+    /// The trusted issuers that failed are marked as unavailable and the tokens they issued
+    /// are rejected. When `false`, the startup fails if a trusted issuer fails to initialize.
+    pub allow_partial: bool,
+}
+
+impl TrustedIssuerInitConfig {
+    const DEFAULT_MAX_CONCURRENCY: usize = 8;
+    const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+    /// Creates the config, zero values are replaced by the defaults
+    pub fn new(max_concurrency: usize, timeout_secs: u64, allow_partial: bool) -> Self {
+        Self {
+            max_concurrency: if max_concurrency == 0 {
+                Self::DEFAULT_MAX_CONCURRENCY
+            } else {
+                max_concurrency
+            },
+            timeout_secs: if timeout_secs == 0 {
+                Self::DEFAULT_TIMEOUT_SECS
+            } else {
+                timeout_secs
+            },
+            allow_partial,
+        }
+    }
+}
+
+impl Default for TrustedIssuerInitConfig {
+    fn default() -> Self {
+        Self::new(0, 0, false)
+    }
 }
 
 /// Validation options related to JSON Web Tokens (JWT).
@@ -161,6 +209,7 @@ impl Default for JwtConfig {
             jwt_sig_validation: true,
             jwt_status_validation: true,
             signature_algorithms_supported: HashSet::new(),
+            trusted_issuer_init: TrustedIssuerInitConfig::default(),
         }
     }
 }
@@ -173,6 +222,7 @@ impl JwtConfig {
             jwt_sig_validation: false,
             jwt_status_validation: false,
             signature_algorithms_supported: HashSet::new(),
+            trusted_issuer_init: TrustedIssuerInitConfig::default(),
         }
         .allow_all_algorithms()
     }
@@ -250,6 +300,7 @@ impl From<JwtConfigRaw> for JwtConfig {
             jwt_sig_validation: raw.jwt_sig_validation,
             jwt_status_validation: raw.jwt_status_validation,
             signature_algorithms_supported: supported_algorithms,
+            trusted_issuer_init: TrustedIssuerInitConfig::default(),
         }
     }
 }
@@ -1,7 +1,9 @@
-use crate::{BootstrapConfigRaw, BootstrapConfigLoadingError};
 use crate::log::LogLevel;
-use url::Url;
+use crate::{BootstrapConfigLoadingError, BootstrapConfigRaw};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::time::Duration;
+use url::Url;
 
 /// Lock service config
 #[derive(Debug, Clone, PartialEq)]
@@ -29,6 +31,84 @@ pub struct LockServiceConfig {
     pub listen_sse: bool,
     /// Allow interaction with a Lock server with invalid certificates. Used for testing.
     pub accept_invalid_certs: bool,
+    /// Where the client credentials from the Dynamic Client Registration are saved.
+    /// If `None` then a new client is registered on each startup.
+    pub credentials_store: Option<LockCredentialsStoreConfig>,
+    /// How Cedarling authenticates its client with the token endpoint of the IDP.
+    pub client_auth: LockClientAuthConfig,
+}
+
+/// Encrypted file where the Lock client credentials are saved across restarts.
+#[derive(Clone, PartialEq)]
+pub struct LockCredentialsStoreConfig {
+    /// Path of the file, set with `CEDARLING_LOCK_CREDENTIALS_FILE`.
+    pub path: PathBuf,
+    /// Passphrase the file is encrypted with, set with `CEDARLING_LOCK_CREDENTIALS_KEY`.
+    pub key: String,
+}
+
+impl std::fmt::Debug for LockCredentialsStoreConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LockCredentialsStoreConfig")
+            .field("path", &self.path)
+            .field("key", &"<redacted>")
+            .finish()
+    }
+}
+
+/// The `token_endpoint_auth_method` Cedarling registers its client with, set with
+/// `CEDARLING_LOCK_TOKEN_ENDPOINT_AUTH_METHOD`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, strum::AsRefStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum LockTokenEndpointAuthMethod {
+    /// Client secret issued by the IDP
+    #[default]
+    ClientSecretBasic,
+    /// JWT signed with the private key of the client
+    PrivateKeyJwt,
+    /// Mutual TLS with a certificate issued by a CA trusted by the IDP
+    TlsClientAuth,
+    /// Mutual TLS with a self-signed certificate
+    SelfSignedTlsClientAuth,
+}
+
+/// How Cedarling authenticates its client with the token endpoint of the IDP.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum LockClientAuthConfig {
+    /// Authenticate with the client secret issued by the IDP.
+    #[default]
+    ClientSecretBasic,
+    /// Authenticate with a JWT signed with a private key
+    /// ([RFC 7523](https://datatracker.ietf.org/doc/html/rfc7523)). The public key is
+    /// registered in the `jwks` of the client.
+    PrivateKeyJwt {
+        /// PEM file with the PKCS#8 (or PKCS#1 RSA) private key.
+        key_file: PathBuf,
+        /// JWK file with the public key.
+        jwk_file: PathBuf,
+    },
+    /// Authenticate with mutual TLS using a certificate issued by a CA trusted by the IDP
+    /// ([RFC 8705](https://datatracker.ietf.org/doc/html/rfc8705#section-2.1)).
+    TlsClientAuth {
+        /// PEM file with the client certificate chain.
+        cert_file: PathBuf,
+        /// PEM file with the PKCS#8 private key of the certificate.
+        key_file: PathBuf,
+        /// The subject distinguished name of the certificate.
+        subject_dn: String,
+    },
+    /// Authenticate with mutual TLS using a self-signed certificate that is registered
+    /// in the `jwks` of the client
+    /// ([RFC 8705](https://datatracker.ietf.org/doc/html/rfc8705#section-2.2)).
+    SelfSignedTlsClientAuth {
+        /// PEM file with the client certificate.
+        cert_file: PathBuf,
+        /// PEM file with the PKCS#8 private key of the certificate.
+        key_file: PathBuf,
+        /// JWK file with the public key of the certificate.
+        jwk_file: PathBuf,
+    },
 }
 
 /// Raw lock service config
@@ -52,6 +132,10 @@ pub struct LockServiceConfigRaw {
     pub listen_sse: bool,
     /// Accept invalid certs
     pub accept_invalid_certs: bool,
+    /// Credentials store
+    pub credentials_store: Option<LockCredentialsStoreConfig>,
+    /// Client authentication
+    pub client_auth: LockClientAuthConfig,
 }
 
 impl Default for LockServiceConfig {
@@ -68,6 +152,8 @@ impl Default for LockServiceConfig {
             telemetry_interval: None,
             listen_sse: false,
             accept_invalid_certs: false,
+            credentials_store: None,
+            client_auth: LockClientAuthConfig::default(),
         }
     }
 }
@@ -76,7 +162,8 @@ impl From<LockServiceConfigRaw> for LockServiceConfig {
     fn from(raw: LockServiceConfigRaw) -> Self {
         Self {
             log_level: raw.log_level,
-            config_uri: raw.config_uri
+            config_uri: raw
+                .config_uri
                 .parse()
                 .expect("Failed to parse lock server configuration URI from raw config"),
             dynamic_config: raw.dynamic_config,
@@ -86,6 +173,8 @@ impl From<LockServiceConfigRaw> for LockServiceConfig {
             telemetry_interval: raw.telemetry_interval,
             listen_sse: raw.listen_sse,
             accept_invalid_certs: raw.accept_invalid_certs,
+            credentials_store: raw.credentials_store,
+            client_auth: raw.client_auth,
         }
     }
 }
@@ -111,6 +200,28 @@ impl TryFrom<&BootstrapConfigRaw> for LockServiceConfig {
 
         let listen_sse = raw.listen_sse.into();
 
+        let credentials_store = raw
+            .lock_credentials_file
+            .as_ref()
+            .map(|path| {
+                let key = raw
+                    .lock_credentials_key
+                    .clone()
+                    .ok_or(BootstrapConfigLoadingError::MissingLockCredentialsKey)?;
+                // the stored client is only reused by the same PDP ID, a random one would
+                // register a new client on each startup
+                if raw.pdp_id.is_none() {
+                    return Err(BootstrapConfigLoadingError::MissingPdpIdForLockCredentials);
+                }
+                Ok::<_, BootstrapConfigLoadingError>(LockCredentialsStoreConfig {
+                    path: path.into(),
+                    key,
+                })
+            })
+            .transpose()?;
+
+        let client_auth = client_auth_config(raw)?;
+
         Ok(LockServiceConfig {
             config_uri,
             dynamic_config: raw.dynamic_configuration.into(),
@@ -121,6 +232,57 @@ impl TryFrom<&BootstrapConfigRaw> for LockServiceConfig {
             listen_sse,
             log_level: raw.log_level,
             accept_invalid_certs: raw.accept_invalid_certs.into(),
+            credentials_store,
+            client_auth,
         })
     }
-} 
\ No newline at end of file
+}
+
+fn client_auth_config(
+    raw: &BootstrapConfigRaw,
+) -> Result<LockClientAuthConfig, BootstrapConfigLoadingError> {
+    let method = raw.lock_token_endpoint_auth_method;
+    let required = |value: &Option<String>, property: &'static str| {
+        value
+            .clone()
+            .ok_or(BootstrapConfigLoadingError::MissingLockClientAuthProperty(
+                method, property,
+            ))
+    };
+    let key_file =
+        || required(&raw.lock_client_key_file, "CEDARLING_LOCK_CLIENT_KEY_FILE").map(PathBuf::from);
+    let jwk_file =
+        || required(&raw.lock_client_jwk_file, "CEDARLING_LOCK_CLIENT_JWK_FILE").map(PathBuf::from);
+    let cert_file = || {
+        required(
+            &raw.lock_client_cert_file,
+            "CEDARLING_LOCK_CLIENT_CERT_FILE",
+        )
+        .map(PathBuf::from)
+    };
+
+    let config = match method {
+        LockTokenEndpointAuthMethod::ClientSecretBasic => LockClientAuthConfig::ClientSecretBasic,
+        LockTokenEndpointAuthMethod::PrivateKeyJwt => LockClientAuthConfig::PrivateKeyJwt {
+            key_file: key_file()?,
+            jwk_file: jwk_file()?,
+        },
+        LockTokenEndpointAuthMethod::TlsClientAuth => LockClientAuthConfig::TlsClientAuth {
+            cert_file: cert_file()?,
+            key_file: key_file()?,
+            subject_dn: required(
+                &raw.lock_tls_client_auth_subject_dn,
+                "CEDARLING_LOCK_TLS_CLIENT_AUTH_SUBJECT_DN",
+            )?,
+        },
+        LockTokenEndpointAuthMethod::SelfSignedTlsClientAuth => {
+            LockClientAuthConfig::SelfSignedTlsClientAuth {
+                cert_file: cert_file()?,
+                key_file: key_file()?,
+                jwk_file: jwk_file()?,
+            }
+        },
+    };
+
+    Ok(config)
+}
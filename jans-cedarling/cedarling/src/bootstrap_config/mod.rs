@@ -12,6 +12,8 @@ mod decode;
 pub mod authorization_config;
 /// Entity builder config module
 pub mod entity_builder_config;
+/// HTTP client config module
+pub mod http_config;
 /// JWT config module
 pub mod jwt_config;
 /// Lock config module
@@ -24,19 +26,31 @@ pub mod policy_store_config;
 pub mod raw_config;
 
 #[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
 use std::{io, path::Path};
 
 use config::{Config, File};
 
 // Re-export types that need to be public
-pub use authorization_config::{AuthorizationConfig, AuthorizationConfigRaw, IdTokenTrustMode};
+pub use authorization_config::{
+    AuthorizationConfig, AuthorizationConfigRaw, DecisionCacheConfig, IdTokenTrustMode,
+};
 pub use entity_builder_config::{
     EntityBuilderConfig, EntityBuilderConfigRaw, EntityNames, UnsignedRoleIdSrc,
 };
-pub use jwt_config::{JwtConfig, JwtConfigRaw};
-pub use lock_config::{LockServiceConfig, LockServiceConfigRaw};
+pub use http_config::{
+    HttpClientCertConfig, HttpClientConfig, HttpClientConfigRaw, HttpConfig, HttpConfigRaw,
+};
+pub use jwt_config::{JwtConfig, JwtConfigRaw, TrustedIssuerInitConfig};
+pub use lock_config::{
+    LockClientAuthConfig, LockCredentialsStoreConfig, LockServiceConfig, LockServiceConfigRaw,
+    LockTokenEndpointAuthMethod,
+};
 pub use log_config::{LogConfig, LogConfigRaw, LogTypeConfig, MemoryLogConfig};
-pub use policy_store_config::{PolicyStoreConfig, PolicyStoreConfigRaw, PolicyStoreSource};
+pub use policy_store_config::{
+    PolicyStoreConfig, PolicyStoreConfigRaw, PolicyStoreSignatureConfig, PolicyStoreSource,
+    PolicyValidationConfig, PolicyValidationLevel, PolicyValidationMode,
+};
 pub use raw_config::{BootstrapConfigRaw, FeatureToggle};
 
 /// Bootstrap configuration
@@ -46,10 +60,24 @@ pub use raw_config::{BootstrapConfigRaw, FeatureToggle};
 pub struct BootstrapConfig {
     /// `CEDARLING_APPLICATION_NAME` in [bootstrap properties](https://github.com/JanssenProject/jans/wiki/Cedarling-Nativity-Plan#bootstrap-properties) documentation.
     pub application_name: String,
+    /// UUID of this Cedarling instance, set with `CEDARLING_PDP_ID`.
+    /// If `None` then a random one is generated on each startup.
+    pub pdp_id: Option<String>,
     /// A set of properties used to configure logging in the `Cedarling` application.
     pub log_config: LogConfig,
     /// A set of properties used to load `PolicyStore` in the `Cedarling` application.
     pub policy_store_config: PolicyStoreConfig,
+    /// A set of properties used to validate the policies against the schema when the `PolicyStore` is loaded.
+    pub policy_validation_config: PolicyValidationConfig,
+    /// How often the policy store of `CEDARLING_POLICY_STORE_URI` is fetched again.
+    /// If `None` then the policy store is only fetched on startup.
+    pub policy_store_poll_interval: Option<Duration>,
+    /// Trusted keys the policy stores must be signed with.
+    /// If `None` then the policy stores are not required to be signed.
+    pub policy_store_signature_config: Option<PolicyStoreSignatureConfig>,
+    /// Source of the shadow policy store the requests are also evaluated against.
+    /// If `None` then shadow evaluation is disabled.
+    pub shadow_policy_store_config: Option<PolicyStoreConfig>,
     /// A set of properties used to configure JWT in the `Cedarling` application.
     pub jwt_config: JwtConfig,
     /// A set of properties used to configure authorization workflow in the `Cedarling` application.
@@ -59,6 +87,9 @@ pub struct BootstrapConfig {
     /// Lock service configuration.
     /// If `None` then lock service is disabled.
     pub lock_config: Option<LockServiceConfig>,
+    /// Options of the HTTP clients used for the requests to the IDPs, the Lock Server
+    /// and the policy store.
+    pub http_config: HttpConfig,
     /// Maximum number of default entities allowed in a policy store.
     /// This prevents DoS attacks by limiting the number of entities that can be loaded.
     pub max_default_entities: Option<usize>,
@@ -201,14 +232,40 @@ pub enum BootstrapConfigLoadingError {
     )]
     ConflictingPolicyStores,
 
+    /// Error returned when multiple shadow policy store sources were provided.
+    #[error(
+        "Multiple shadow store options were provided. Make sure you only one of these properties \
+         is set: `CEDARLING_SHADOW_POLICY_STORE_URI`, `CEDARLING_SHADOW_POLICY_STORE_LOCAL` or \
+         `CEDARLING_SHADOW_POLICY_STORE_LOCAL_FN`"
+    )]
+    ConflictingShadowPolicyStores,
+
     /// Error returned when no policy store source was provided.
     #[error("No Policy store was provided.")]
     MissingPolicyStore,
 
     /// Error returned when the policy store file is in an unsupported format.
-    #[error("Unsupported policy store file format for: {0}. Supported formats include: JSON, YAML")]
+    #[error(
+        "Unsupported policy store file format for: {0}. Supported formats include: JSON, YAML, \
+         a zip archive or a directory"
+    )]
     UnsupportedPolicyStoreFileFormat(String),
 
+    /// Error returned when `CEDARLING_POLICY_STORE_TRUSTED_KEYS` contains an unusable key.
+    #[error("Invalid key in `CEDARLING_POLICY_STORE_TRUSTED_KEYS`: {0}")]
+    InvalidPolicyStoreTrustedKey(String),
+
+    /// Error returned when `CEDARLING_POLICY_STORE_MIN_VERSION` is not a semantic version.
+    #[error("Invalid `CEDARLING_POLICY_STORE_MIN_VERSION`, expected a semantic version: {0}")]
+    InvalidPolicyStoreMinVersion(semver::Error),
+
+    /// Error returned when `CEDARLING_POLICY_STORE_MIN_VERSION` is set without trusted keys.
+    #[error(
+        "the `CEDARLING_POLICY_STORE_MIN_VERSION` is set but `CEDARLING_POLICY_STORE_TRUSTED_KEYS` \
+         is not set."
+    )]
+    MissingPolicyStoreTrustedKeys,
+
     /// Error returned when failing to load a local JWKS
     #[error("Failed to load local JWKS from {0}: {1}")]
     LoadLocalJwks(String, String),
@@ -230,6 +287,34 @@ pub enum BootstrapConfigLoadingError {
     /// Error returned when the lock server configuration URI is invalid.
     #[error("Invalid lock server configuration URI: {0}")]
     InvalidLockServerConfigUri(url::ParseError),
+
+    /// Error returned when `CEDARLING_LOCK_CREDENTIALS_FILE` is set but `CEDARLING_LOCK_CREDENTIALS_KEY` is not set.
+    #[error(
+        "the `CEDARLING_LOCK_CREDENTIALS_FILE` is set but `CEDARLING_LOCK_CREDENTIALS_KEY` is not set."
+    )]
+    MissingLockCredentialsKey,
+
+    /// Error returned when `CEDARLING_LOCK_CREDENTIALS_FILE` is set but `CEDARLING_PDP_ID` is not set.
+    #[error(
+        "the `CEDARLING_LOCK_CREDENTIALS_FILE` is set but `CEDARLING_PDP_ID` is not set, the stored client is only reused with the same `CEDARLING_PDP_ID`."
+    )]
+    MissingPdpIdForLockCredentials,
+
+    /// Error returned when a property required by the `CEDARLING_LOCK_TOKEN_ENDPOINT_AUTH_METHOD` is not set.
+    #[error(
+        "the `CEDARLING_LOCK_TOKEN_ENDPOINT_AUTH_METHOD` is set to `{}` but `{1}` is not set.",
+        .0.as_ref()
+    )]
+    MissingLockClientAuthProperty(LockTokenEndpointAuthMethod, &'static str),
+
+    /// Error returned when only one of the client certificate and its key is set in
+    /// `CEDARLING_HTTP_CLIENT`.
+    #[error("the `{1}` of the `{0}` HTTP client is not set in `CEDARLING_HTTP_CLIENT`.")]
+    MissingHttpClientProperty(&'static str, &'static str),
+
+    /// Error returned when `CEDARLING_PDP_ID` is not a valid UUID.
+    #[error("Invalid `CEDARLING_PDP_ID`, expected a UUID: {0}")]
+    InvalidPdpId(String),
 }
 
 impl From<url::ParseError> for BootstrapConfigLoadingError {
@@ -306,4 +391,118 @@ mod tests {
         assert!(config.entity_builder_config.build_user);
         assert!(config.entity_builder_config.build_workload);
     }
+
+    #[test]
+    fn test_decode_lock_credentials_store() {
+        let raw = BootstrapConfigRaw {
+            local_policy_store: Some("{}".to_string()),
+            user_authz: FeatureToggle::Enabled,
+            lock: FeatureToggle::Enabled,
+            lock_server_configuration_uri: Some(
+                "https://test.jans.io/.well-known/lock-server-configuration".to_string(),
+            ),
+            lock_credentials_file: Some("/var/lib/cedarling/lock.json".to_string()),
+            pdp_id: Some("0197a6c2-6c3b-4bfe-9a3f-6f1b2a1f0c2d".to_string()),
+            ..Default::default()
+        };
+
+        let err = BootstrapConfig::from_raw_config(&raw)
+            .expect_err("the credentials key should be required");
+        assert!(
+            matches!(err, BootstrapConfigLoadingError::MissingLockCredentialsKey),
+            "{err}"
+        );
+
+        let raw = BootstrapConfigRaw {
+            lock_credentials_key: Some("some passphrase".to_string()),
+            pdp_id: None,
+            ..raw
+        };
+        let err =
+            BootstrapConfig::from_raw_config(&raw).expect_err("the PDP ID should be required");
+        assert!(
+            matches!(
+                err,
+                BootstrapConfigLoadingError::MissingPdpIdForLockCredentials
+            ),
+            "{err}"
+        );
+
+        let raw = BootstrapConfigRaw {
+            pdp_id: Some("0197a6c2-6c3b-4bfe-9a3f-6f1b2a1f0c2d".to_string()),
+            ..raw
+        };
+
+        let config = BootstrapConfig::from_raw_config(&raw).expect("should decode the config");
+        assert_eq!(
+            config.pdp_id.as_deref(),
+            Some("0197a6c2-6c3b-4bfe-9a3f-6f1b2a1f0c2d")
+        );
+        let store = config
+            .lock_config
+            .and_then(|lock_config| lock_config.credentials_store)
+            .expect("the credentials store should be configured");
+        assert_eq!(store.path, Path::new("/var/lib/cedarling/lock.json"));
+        assert_eq!(store.key, "some passphrase");
+
+        let raw = BootstrapConfigRaw {
+            pdp_id: Some("not-a-uuid".to_string()),
+            ..raw
+        };
+        let err = BootstrapConfig::from_raw_config(&raw).expect_err("the PDP ID should be a UUID");
+        assert!(
+            matches!(err, BootstrapConfigLoadingError::InvalidPdpId(_)),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_decode_lock_client_auth() {
+        let method =
+            serde_json::from_str::<LockTokenEndpointAuthMethod>(r#""self_signed_tls_client_auth""#)
+                .expect("should decode the auth method");
+        assert_eq!(method, LockTokenEndpointAuthMethod::SelfSignedTlsClientAuth);
+
+        let raw = BootstrapConfigRaw {
+            local_policy_store: Some("{}".to_string()),
+            user_authz: FeatureToggle::Enabled,
+            lock: FeatureToggle::Enabled,
+            lock_server_configuration_uri: Some(
+                "https://test.jans.io/.well-known/lock-server-configuration".to_string(),
+            ),
+            lock_token_endpoint_auth_method: LockTokenEndpointAuthMethod::TlsClientAuth,
+            lock_client_cert_file: Some("/etc/cedarling/client.crt".to_string()),
+            lock_client_key_file: Some("/etc/cedarling/client.key".to_string()),
+            ..Default::default()
+        };
+
+        let err =
+            BootstrapConfig::from_raw_config(&raw).expect_err("the subject DN should be required");
+        assert!(
+            matches!(
+                err,
+                BootstrapConfigLoadingError::MissingLockClientAuthProperty(
+                    LockTokenEndpointAuthMethod::TlsClientAuth,
+                    "CEDARLING_LOCK_TLS_CLIENT_AUTH_SUBJECT_DN"
+                )
+            ),
+            "{err}"
+        );
+
+        let raw = BootstrapConfigRaw {
+            lock_tls_client_auth_subject_dn: Some("CN=cedarling,O=Gluu".to_string()),
+            ..raw
+        };
+        let config = BootstrapConfig::from_raw_config(&raw).expect("should decode the config");
+        assert_eq!(
+            config
+                .lock_config
+                .map(|lock_config| lock_config.client_auth),
+            Some(LockClientAuthConfig::TlsClientAuth {
+                cert_file: "/etc/cedarling/client.crt".into(),
+                key_file: "/etc/cedarling/client.key".into(),
+                subject_dn: "CN=cedarling,O=Gluu".to_string(),
+            })
+        );
+    }
 }
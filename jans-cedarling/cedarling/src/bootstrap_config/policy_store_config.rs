@@ -3,6 +3,9 @@
 //
 // Copyright (c) 2024, Gluu, Inc.
 
+use crate::common::policy_store::PolicyStoreWithID;
+use jsonwebtoken::jwk::JwkSet;
+use semver::Version;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -15,6 +18,55 @@ pub struct PolicyStoreConfig {
     pub source: PolicyStoreSource,
 }
 
+/// Controls how the policy store is validated when it is loaded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PolicyValidationConfig {
+    /// What to do when a policy does not validate against the schema.
+    pub level: PolicyValidationLevel,
+    /// Which mode the Cedar validator runs in.
+    pub mode: PolicyValidationMode,
+    /// Rejects the JSON and YAML policy store documents with keys that are not in the
+    /// policy store JSON schema, these keys are ignored otherwise.
+    #[serde(default)]
+    pub reject_unknown_keys: bool,
+}
+
+/// What to do when a policy does not validate against the schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyValidationLevel {
+    /// The policies are not validated.
+    #[default]
+    Off,
+    /// Each validation issue is logged with the `System` log type and loading continues.
+    Warn,
+    /// Loading the policy store fails, the error lists every validation issue.
+    Error,
+}
+
+/// Mode of the Cedar validator, see the [Cedar documentation](https://docs.cedarpolicy.com/policies/validation.html).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyValidationMode {
+    /// Strict validation.
+    #[default]
+    Strict,
+    /// Permissive validation, accepts some expressions that mix types which strict mode rejects.
+    Permissive,
+}
+
+/// Trusted keys of the signed policy stores.
+///
+/// When set, a policy store is only loaded if it is signed with one of the trusted keys,
+/// either as a JWS or with a detached signature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyStoreSignatureConfig {
+    /// JSON Web Keys the policy store signatures are verified with, each key must set its `alg`.
+    pub trusted_keys: JwkSet,
+    /// Signed policy stores with a lower `version` are rejected.
+    pub min_version: Option<Version>,
+}
+
 /// Raw policy store config
 pub struct PolicyStoreConfigRaw {
     /// Source
@@ -25,6 +77,7 @@ pub struct PolicyStoreConfigRaw {
 
 /// `PolicyStoreSource` represents the source from which policies will be retrieved.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum PolicyStoreSource {
     /// Read the policy directly from a raw JSON string.
     ///
@@ -47,6 +100,20 @@ pub enum PolicyStoreSource {
 
     /// Read policy from a YAML File.
     FileYaml(PathBuf),
+
+    /// Read the policy store from a directory with a manifest, a Cedar schema and
+    /// `.cedar` policy files.
+    Directory(PathBuf),
+
+    /// Read the policy store from a zip archive with the same layout as [`Self::Directory`].
+    Archive(PathBuf),
+
+    /// Use a policy store built in code, for example with
+    /// [`PolicyStoreBuilder`](crate::PolicyStoreBuilder).
+    ///
+    /// This source cannot be serialized and cannot be signed.
+    #[serde(skip)]
+    InMemory(PolicyStoreWithID),
 }
 
 /// Raw policy store source
@@ -61,6 +128,10 @@ pub enum PolicyStoreSourceRaw {
     FileJson(String),
     /// File YAML
     FileYaml(String),
+    /// Directory
+    Directory(String),
+    /// Zip archive
+    Archive(String),
 }
 
 impl From<PolicyStoreConfigRaw> for PolicyStoreConfig {
@@ -72,6 +143,8 @@ impl From<PolicyStoreConfigRaw> for PolicyStoreConfig {
                 "lock_server" => PolicyStoreSource::LockServer(raw.path.unwrap_or_default()),
                 "file_json" => PolicyStoreSource::FileJson(raw.path.unwrap_or_default().into()),
                 "file_yaml" => PolicyStoreSource::FileYaml(raw.path.unwrap_or_default().into()),
+                "directory" => PolicyStoreSource::Directory(raw.path.unwrap_or_default().into()),
+                "archive" => PolicyStoreSource::Archive(raw.path.unwrap_or_default().into()),
                 _ => PolicyStoreSource::FileYaml("policy-store.yaml".into()),
             },
         }
@@ -6,6 +6,9 @@
 #[cfg(not(target_arch = "wasm32"))]
 use super::super::BootstrapConfigLoadingError;
 use super::super::authorization_config::IdTokenTrustMode;
+use super::super::http_config::HttpConfigRaw;
+use super::super::lock_config::LockTokenEndpointAuthMethod;
+use super::super::policy_store_config::{PolicyValidationLevel, PolicyValidationMode};
 use super::default_values::*;
 use super::feature_types::*;
 use super::json_util::*;
@@ -13,6 +16,7 @@ use crate::UnsignedRoleIdSrc;
 use crate::common::json_rules::JsonRule;
 use crate::log::LogLevel;
 use jsonwebtoken::Algorithm;
+use jsonwebtoken::jwk::JwkSet;
 use serde::{Deserialize, Serialize};
 #[cfg(not(target_arch = "wasm32"))]
 use std::collections::HashMap;
@@ -32,6 +36,15 @@ pub struct BootstrapConfigRaw {
     #[serde(rename = "CEDARLING_APPLICATION_NAME")]
     pub application_name: String,
 
+    /// UUID of this Cedarling instance, a random one is generated on each startup if
+    /// it is not set.
+    #[serde(
+        rename = "CEDARLING_PDP_ID",
+        default,
+        deserialize_with = "parse_option_string"
+    )]
+    pub pdp_id: Option<String>,
+
     /// Location of policy store JSON, used if policy store is not local, or retreived from Lock Master.
     #[serde(
         rename = "CEDARLING_POLICY_STORE_URI",
@@ -44,6 +57,25 @@ pub struct BootstrapConfigRaw {
     #[serde(rename = "CEDARLING_POLICY_STORE_ID", default)]
     pub policy_store_id: String,
 
+    /// How often, in seconds, the policy store of `CEDARLING_POLICY_STORE_URI` is fetched
+    /// again (0 to only fetch it on startup).
+    #[serde(rename = "CEDARLING_POLICY_STORE_POLL_INTERVAL", default)]
+    #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
+    pub policy_store_poll_interval: u64,
+
+    /// JSON Web Key Set with the keys the policy stores must be signed with.
+    #[serde(rename = "CEDARLING_POLICY_STORE_TRUSTED_KEYS", default)]
+    #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
+    pub policy_store_trusted_keys: Option<JwkSet>,
+
+    /// Lowest `version` of the signed policy stores that is accepted.
+    #[serde(
+        rename = "CEDARLING_POLICY_STORE_MIN_VERSION",
+        default,
+        deserialize_with = "parse_option_string"
+    )]
+    pub policy_store_min_version: Option<String>,
+
     /// How the Logs will be presented.
     #[serde(rename = "CEDARLING_LOG_TYPE", default)]
     pub log_type: LoggerType,
@@ -96,6 +128,33 @@ pub struct BootstrapConfigRaw {
     #[serde(rename = "CEDARLING_WORKLOAD_AUTHZ", default)]
     pub workload_authz: FeatureToggle,
 
+    /// When `enabled`, each authorization result contains a trace of the policies whose
+    /// scope matched the request, even if the request does not ask for it.
+    #[serde(rename = "CEDARLING_AUTHZ_EXPLAIN", default)]
+    pub authz_explain: FeatureToggle,
+
+    /// When `enabled`, the results of `authorize` requests are cached and reused for
+    /// requests with the same tokens, action, resource and context.
+    #[serde(rename = "CEDARLING_DECISION_CACHE", default)]
+    pub decision_cache: FeatureToggle,
+
+    /// Maximum time in seconds a decision is cached, it is also limited by the expiration
+    /// of the tokens. Zero means the default of 60 seconds.
+    #[serde(rename = "CEDARLING_DECISION_CACHE_TTL", default)]
+    #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
+    pub decision_cache_ttl: u64,
+
+    /// Maximum number of cached decisions. Zero means the default of 10000.
+    #[serde(rename = "CEDARLING_DECISION_CACHE_MAX_ITEMS", default)]
+    #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
+    pub decision_cache_max_items: usize,
+
+    /// Annotations of the determining policies returned in the authorization result,
+    /// such as `["obligation", "advice", "reason_code"]`, which is the default.
+    #[serde(rename = "CEDARLING_AUTHZ_ANNOTATION_KEYS", default)]
+    #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
+    pub authz_annotation_keys: Option<Vec<String>>,
+
     /// Specifies what boolean operation to use for the `USER` and `WORKLOAD` when
     /// making authz (authorization) decisions.
     ///
@@ -114,6 +173,19 @@ pub struct BootstrapConfigRaw {
     #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
     pub principal_bool_operation: JsonRule,
 
+    /// Principal boolean operations used instead of `CEDARLING_PRINCIPAL_BOOLEAN_OPERATION`
+    /// for some actions, keyed by the UID of the action or of an action group of the schema.
+    ///
+    /// Example:
+    /// ```json
+    /// {
+    ///     "Jans::Action::\"Read\"": {"===": [{"var": "Jans::Workload"}, "ALLOW"]}
+    /// }
+    /// ```
+    #[serde(rename = "CEDARLING_PRINCIPAL_BOOLEAN_OPERATION_BY_ACTION", default)]
+    #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
+    pub principal_bool_operation_by_action: HashMap<String, JsonRule>,
+
     /// Mapping name of cedar schema TrustedIssuer entity
     #[serde(rename = "CEDARLING_MAPPING_TRUSTED_ISSUER", default)]
     #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
@@ -158,6 +230,44 @@ pub struct BootstrapConfigRaw {
     )]
     pub policy_store_local_fn: Option<String>,
 
+    /// JSON object with a shadow policy store.
+    ///
+    /// The requests are also evaluated against the shadow policy store, and the differences
+    /// with the decisions of the policy store are logged.
+    #[serde(rename = "CEDARLING_SHADOW_POLICY_STORE_LOCAL", default)]
+    #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
+    pub local_shadow_policy_store: Option<String>,
+
+    /// Location of a shadow policy store JSON, used to retrieve it from the Lock Server.
+    #[serde(
+        rename = "CEDARLING_SHADOW_POLICY_STORE_URI",
+        default,
+        deserialize_with = "parse_option_string"
+    )]
+    pub shadow_policy_store_uri: Option<String>,
+
+    /// Path to a shadow policy store JSON or YAML file
+    #[serde(
+        rename = "CEDARLING_SHADOW_POLICY_STORE_LOCAL_FN",
+        default,
+        deserialize_with = "parse_option_string"
+    )]
+    pub shadow_policy_store_local_fn: Option<String>,
+
+    /// What to do when a policy does not validate against the schema when the
+    /// policy store is loaded: `off`, `warn` or `error`.
+    #[serde(rename = "CEDARLING_POLICY_VALIDATION", default)]
+    pub policy_validation: PolicyValidationLevel,
+
+    /// Mode of the Cedar validator used to validate the policies: `strict` or `permissive`.
+    #[serde(rename = "CEDARLING_POLICY_VALIDATION_MODE", default)]
+    pub policy_validation_mode: PolicyValidationMode,
+
+    /// When `enabled`, the policy store documents with keys that are not in the policy
+    /// store JSON schema are rejected instead of ignoring these keys.
+    #[serde(rename = "CEDARLING_POLICY_STORE_STRICT", default)]
+    pub policy_store_strict: FeatureToggle,
+
     /// Maximum number of default entities allowed in a policy store.
     /// This prevents DoS attacks by limiting the number of entities that can be loaded.
     /// If value is 0, there is no limit. But if None, default value is applied.
@@ -195,6 +305,23 @@ pub struct BootstrapConfigRaw {
     #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
     pub jwt_signature_algorithms_supported: HashSet<Algorithm>,
 
+    /// Maximum number of trusted issuers initialized at the same time on startup.
+    /// Zero means the default of 8.
+    #[serde(rename = "CEDARLING_TRUSTED_ISSUER_INIT_CONCURRENCY", default)]
+    #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
+    pub trusted_issuer_init_concurrency: usize,
+
+    /// Maximum time in seconds to initialize a trusted issuer on startup.
+    /// Zero means the default of 30 seconds.
+    #[serde(rename = "CEDARLING_TRUSTED_ISSUER_INIT_TIMEOUT", default)]
+    #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
+    pub trusted_issuer_init_timeout: u64,
+
+    /// When `enabled`, the startup continues when some trusted issuers fail to initialize
+    /// and these trusted issuers are marked as unavailable.
+    #[serde(rename = "CEDARLING_TRUSTED_ISSUER_ALLOW_PARTIAL_INIT", default)]
+    pub trusted_issuer_allow_partial_init: FeatureToggle,
+
     /// Varying levels of validations based on the preference of the developer.
     ///
     /// # Strict Mode
@@ -255,6 +382,71 @@ pub struct BootstrapConfigRaw {
     #[serde(rename = "CEDARLING_LOCK_ACCEPT_INVALID_CERTS", default)]
     pub accept_invalid_certs: FeatureToggle,
 
+    /// Path of the encrypted file where the client credentials from the DCR are saved,
+    /// so they are reused on the next startups. Requires `CEDARLING_PDP_ID`.
+    #[serde(
+        rename = "CEDARLING_LOCK_CREDENTIALS_FILE",
+        default,
+        deserialize_with = "parse_option_string"
+    )]
+    pub lock_credentials_file: Option<String>,
+
+    /// Passphrase the `CEDARLING_LOCK_CREDENTIALS_FILE` is encrypted with.
+    #[serde(
+        rename = "CEDARLING_LOCK_CREDENTIALS_KEY",
+        default,
+        deserialize_with = "parse_option_string"
+    )]
+    pub lock_credentials_key: Option<String>,
+
+    /// How Cedarling authenticates its client with the token endpoint of the IDP:
+    /// `client_secret_basic` (default), `private_key_jwt`, `tls_client_auth` or
+    /// `self_signed_tls_client_auth`.
+    #[serde(rename = "CEDARLING_LOCK_TOKEN_ENDPOINT_AUTH_METHOD", default)]
+    pub lock_token_endpoint_auth_method: LockTokenEndpointAuthMethod,
+
+    /// PEM file with the PKCS#8 (or PKCS#1 RSA) private key of the client. Required for
+    /// `private_key_jwt` and the mutual TLS authentication methods.
+    #[serde(
+        rename = "CEDARLING_LOCK_CLIENT_KEY_FILE",
+        default,
+        deserialize_with = "parse_option_string"
+    )]
+    pub lock_client_key_file: Option<String>,
+
+    /// JWK file with the public key of the client key, registered in the `jwks` of the
+    /// client. Required for `private_key_jwt` and `self_signed_tls_client_auth`.
+    #[serde(
+        rename = "CEDARLING_LOCK_CLIENT_JWK_FILE",
+        default,
+        deserialize_with = "parse_option_string"
+    )]
+    pub lock_client_jwk_file: Option<String>,
+
+    /// PEM file with the client certificate used for mutual TLS.
+    #[serde(
+        rename = "CEDARLING_LOCK_CLIENT_CERT_FILE",
+        default,
+        deserialize_with = "parse_option_string"
+    )]
+    pub lock_client_cert_file: Option<String>,
+
+    /// The subject distinguished name of the client certificate, registered with
+    /// the `tls_client_auth` method.
+    #[serde(
+        rename = "CEDARLING_LOCK_TLS_CLIENT_AUTH_SUBJECT_DN",
+        default,
+        deserialize_with = "parse_option_string"
+    )]
+    pub lock_tls_client_auth_subject_dn: Option<String>,
+
+    /// Options of the HTTP clients: a custom CA bundle, a client certificate, a proxy
+    /// and timeouts, optionally overridden for the `idp`, `lock` and `policy_store`
+    /// destinations. See [`HttpConfigRaw`].
+    #[serde(rename = "CEDARLING_HTTP_CLIENT", default)]
+    #[serde(deserialize_with = "deserialize_or_parse_string_as_json")]
+    pub http_client: HttpConfigRaw,
+
     /// Allows to limit maximum token cache TTL in seconds.
     /// Zero means no token cache TTL limit.
     #[serde(rename = "CEDARLING_TOKEN_CACHE_MAX_TTL", default)]
@@ -498,4 +690,31 @@ mod tests {
             },
         );
     }
+
+    /// Tests that the policy validation settings are parsed from environment variables.
+    #[test]
+    fn test_from_raw_config_and_env_policy_validation() {
+        with_env_vars(
+            vec![
+                ("CEDARLING_POLICY_VALIDATION", "error"),
+                ("CEDARLING_POLICY_VALIDATION_MODE", "permissive"),
+            ],
+            || {
+                let config = BootstrapConfigRaw::from_raw_config_and_env(None).unwrap();
+
+                assert_eq!(config.policy_validation, PolicyValidationLevel::Error);
+                assert_eq!(
+                    config.policy_validation_mode,
+                    PolicyValidationMode::Permissive
+                );
+            },
+        );
+
+        with_env_vars(vec![], || {
+            let config = BootstrapConfigRaw::from_raw_config_and_env(None).unwrap();
+
+            assert_eq!(config.policy_validation, PolicyValidationLevel::Off);
+            assert_eq!(config.policy_validation_mode, PolicyValidationMode::Strict);
+        });
+    }
 }
@@ -5,6 +5,9 @@
 
 //! Module that contains structures used as configuration internally in the application
 //! It is usefull to use it with DI container
+use std::str::FromStr;
+
+use crate::BootstrapConfigLoadingError;
 use derive_more::derive::Display;
 use serde::Serialize;
 use uuid7::{Uuid, uuid4};
@@ -20,6 +23,13 @@ impl PdpID {
         // we use uuid v4 because it is generated based on random numbers.
         PdpID(uuid4())
     }
+
+    /// Parses the ID set with the `CEDARLING_PDP_ID` bootstrap property.
+    pub fn parse(pdp_id: &str) -> Result<Self, BootstrapConfigLoadingError> {
+        Uuid::from_str(pdp_id)
+            .map(PdpID)
+            .map_err(|err| BootstrapConfigLoadingError::InvalidPdpId(err.to_string()))
+    }
 }
 
 /// Name of application from configuration
@@ -11,7 +11,8 @@ use attribute::*;
 use cedar_policy::ParseErrors;
 use entity_type::*;
 use serde::Deserialize;
-use std::{collections::HashMap, str::FromStr};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
 
 pub(crate) mod action;
 pub(crate) mod attribute;
@@ -52,6 +53,78 @@ impl CedarSchemaJson {
             .and_then(|nmspce| nmspce.actions.get(name))
     }
 
+    /// Returns the UIDs of the actions that apply to the principal type and the resource type,
+    /// ordered by UID.
+    ///
+    /// Entity types without a namespace in `appliesTo` are in the namespace of the action.
+    pub fn actions_applying_to(
+        &self,
+        principal_type: &cedar_policy::EntityTypeName,
+        resource_type: &cedar_policy::EntityTypeName,
+    ) -> Vec<cedar_policy::EntityUid> {
+        let principal_type = principal_type.to_string();
+        let resource_type = resource_type.to_string();
+
+        let mut actions = Vec::new();
+        for (namespace_name, namespace) in self.namespaces.iter() {
+            let contains = |types: &HashSet<EntityName>, type_name: &str| {
+                types.iter().any(|name| {
+                    if name.contains(CEDAR_NAMESPACE_SEPARATOR) {
+                        name == type_name
+                    } else {
+                        join_namespace(namespace_name, name) == type_name
+                    }
+                })
+            };
+            let Ok(action_type) =
+                cedar_policy::EntityTypeName::from_str(&join_namespace(namespace_name, "Action"))
+            else {
+                continue;
+            };
+
+            for (name, action) in namespace.actions.iter() {
+                if contains(&action.applies_to.principal_types, &principal_type)
+                    && contains(&action.applies_to.resource_types, &resource_type)
+                {
+                    actions.push(cedar_policy::EntityUid::from_type_name_and_id(
+                        action_type.clone(),
+                        cedar_policy::EntityId::new(name),
+                    ));
+                }
+            }
+        }
+        actions.sort_by_cached_key(|uid| uid.to_string());
+        actions
+    }
+
+    /// Returns the UIDs of the action groups the action is a member of, directly or
+    /// through other groups, the nearest groups first.
+    pub fn action_groups(&self, action: &cedar_policy::EntityUid) -> Vec<cedar_policy::EntityUid> {
+        let mut groups = Vec::<cedar_policy::EntityUid>::new();
+        let mut queue = VecDeque::from([action.clone()]);
+        while let Some(uid) = queue.pop_front() {
+            let namespace = uid.type_name().namespace();
+            if uid.type_name().basename() != "Action" {
+                continue;
+            }
+            let Some(schema_action) = self.get_action(&namespace, uid.id().unescaped()) else {
+                continue;
+            };
+
+            // groups that were already found are skipped, so cycles end the search
+            let mut parents = schema_action
+                .member_of()
+                .filter_map(|group| group.uid(&namespace).ok())
+                .filter(|group| group != action && !groups.contains(group))
+                .collect::<Vec<_>>();
+            parents.sort_by_cached_key(|uid| uid.to_string());
+            parents.dedup();
+            groups.extend(parents.iter().cloned());
+            queue.extend(parents);
+        }
+        groups
+    }
+
     pub fn get_common_type(
         &self,
         type_name: &str,
@@ -368,4 +441,98 @@ mod test_deserialize_json_cedar_schema {
             "should get entity from `\"\"` namespace"
         );
     }
+
+    #[test]
+    fn can_get_actions_applying_to_types() {
+        let schema = serde_json::from_value::<CedarSchemaJson>(json!({
+            "Jans": {
+                "entityTypes": {
+                    "User": {},
+                    "Workload": {},
+                    "Issue": {},
+                },
+                "actions": {
+                    "Read": {
+                        "appliesTo": {
+                            "principalTypes": ["User", "Workload"],
+                            "resourceTypes": ["Issue"],
+                        }
+                    },
+                    "Update": {
+                        "appliesTo": {
+                            "principalTypes": ["Jans::User"],
+                            "resourceTypes": ["Jans::Issue"],
+                        }
+                    },
+                    "Deploy": {
+                        "appliesTo": {
+                            "principalTypes": ["Workload"],
+                            "resourceTypes": ["Issue"],
+                        }
+                    },
+                },
+            },
+        }))
+        .expect("should successfully build schema");
+
+        let type_name =
+            |name: &str| cedar_policy::EntityTypeName::from_str(name).expect("valid type name");
+        let actions = |principal: &str| {
+            schema
+                .actions_applying_to(&type_name(principal), &type_name("Jans::Issue"))
+                .iter()
+                .map(|uid| uid.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            actions("Jans::User"),
+            ["Jans::Action::\"Read\"", "Jans::Action::\"Update\""]
+        );
+        assert_eq!(
+            actions("Jans::Workload"),
+            ["Jans::Action::\"Deploy\"", "Jans::Action::\"Read\""]
+        );
+        assert!(actions("Jans::Issue").is_empty());
+    }
+
+    #[test]
+    fn can_get_action_groups() {
+        let schema = serde_json::from_value::<CedarSchemaJson>(json!({
+            "Jans": {
+                "entityTypes": {},
+                "actions": {
+                    "all": {},
+                    "writes": {"memberOf": [{"id": "all"}]},
+                    "admin": {"memberOf": [{"id": "all", "type": "Jans::Action"}]},
+                    "Update": {
+                        "memberOf": [{"id": "writes"}, {"id": "admin"}],
+                        "appliesTo": {
+                            "principalTypes": ["User"],
+                            "resourceTypes": ["Issue"],
+                        }
+                    },
+                },
+            },
+        }))
+        .expect("should successfully build schema");
+
+        let groups = schema
+            .action_groups(
+                &"Jans::Action::\"Update\""
+                    .parse()
+                    .expect("valid action UID"),
+            )
+            .iter()
+            .map(|uid| uid.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            [
+                "Jans::Action::\"admin\"",
+                "Jans::Action::\"writes\"",
+                "Jans::Action::\"all\"",
+            ]
+        );
+    }
 }
@@ -5,17 +5,27 @@
 
 use super::attribute::Attribute;
 use super::*;
+use cedar_policy::ParseErrors;
 use serde::Deserialize;
 use std::collections::HashSet;
+use std::str::FromStr;
 
 #[derive(Debug, Deserialize, PartialEq, Clone)]
 pub struct Action {
     #[serde(rename = "memberOf", default)]
     member_of: Option<HashSet<ActionGroup>>,
-    #[serde(rename = "appliesTo")]
+    /// Action groups usually do not apply to any principal or resource
+    #[serde(rename = "appliesTo", default)]
     pub applies_to: AppliesTo,
 }
 
+impl Action {
+    /// Returns the action groups the action is a direct member of.
+    pub fn member_of(&self) -> impl Iterator<Item = &ActionGroup> {
+        self.member_of.iter().flatten()
+    }
+}
+
 #[derive(Debug, Deserialize, Hash, PartialEq, Eq, Clone)]
 pub struct ActionGroup {
     id: EntityName,
@@ -26,7 +36,23 @@ pub struct ActionGroup {
     kind: Option<ActionGroupName>,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Clone)]
+impl ActionGroup {
+    /// Returns the UID of the action group.
+    ///
+    /// Without a type, the group is in the `Action` type of the given namespace.
+    pub fn uid(&self, namespace: &str) -> Result<cedar_policy::EntityUid, Box<ParseErrors>> {
+        let type_name = match &self.kind {
+            Some(kind) => cedar_policy::EntityTypeName::from_str(kind)?,
+            None => cedar_policy::EntityTypeName::from_str(&join_namespace(namespace, "Action"))?,
+        };
+        Ok(cedar_policy::EntityUid::from_type_name_and_id(
+            type_name,
+            cedar_policy::EntityId::new(&self.id),
+        ))
+    }
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, Clone)]
 pub struct AppliesTo {
     #[serde(rename = "principalTypes", default)]
     pub principal_types: HashSet<EntityName>,
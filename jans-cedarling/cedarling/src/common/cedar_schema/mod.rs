@@ -119,74 +119,89 @@ impl<'de> serde::Deserialize<'de> for CedarSchema {
             },
         };
 
-        // Need both of these because CedarSchema wants both.
-        let (schema_fragment, json_string) = match encoded_schema.content_type {
-            super::ContentType::Cedar => {
-                // parse cedar policy from the cedar representation
-                // TODO must log warnings or something
-                let (schema_fragment, _warning) =
-                    cedar_policy::SchemaFragment::from_cedarschema_str(&decoded_body).map_err(
-                        |err| {
-                            serde::de::Error::custom(format!(
-                                "{}: {}",
-                                deserialize::ParseCedarSchemaSetMessage::Parse,
-                                err
-                            ))
-                        },
-                    )?;
-
-                // urgh now recreate the json representation
-                let json_string = schema_fragment.to_json_string().map_err(|err| {
-                    serde::de::Error::custom(format!(
-                        "{}: {}",
-                        deserialize::ParseCedarSchemaSetMessage::CedarSchemaJsonFormat,
-                        err
-                    ))
-                })?;
+        match encoded_schema.content_type {
+            super::ContentType::Cedar => Self::from_cedar_str(&decoded_body),
+            super::ContentType::CedarJson => Self::from_json_str(&decoded_body),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
 
-                (schema_fragment, json_string)
-            },
-            super::ContentType::CedarJson => {
-                // parse cedar policy from the json representation
-                let schema_fragment = cedar_policy::SchemaFragment::from_json_str(&decoded_body)
-                    .map_err(|err| {
-                        serde::de::Error::custom(format!(
-                            "{}: {}",
-                            deserialize::ParseCedarSchemaSetMessage::CedarSchemaJsonFormat,
-                            err
-                        ))
-                    })?;
-                (schema_fragment, decoded_body)
-            },
-        };
+impl CedarSchema {
+    /// Builds the schema from the Cedar schema language.
+    pub(crate) fn from_cedar_str(cedar_schema: &str) -> Result<Self, String> {
+        // TODO must log warnings or something
+        let (schema_fragment, _warning) =
+            cedar_policy::SchemaFragment::from_cedarschema_str(cedar_schema).map_err(|err| {
+                format!(
+                    "{}: {}",
+                    deserialize::ParseCedarSchemaSetMessage::Parse,
+                    err
+                )
+            })?;
+        Self::from_fragment(schema_fragment)
+    }
+
+    /// Builds the schema from the Cedar JSON schema format.
+    pub(crate) fn from_json_str(json_schema: &str) -> Result<Self, String> {
+        let schema_fragment =
+            cedar_policy::SchemaFragment::from_json_str(json_schema).map_err(|err| {
+                format!(
+                    "{}: {}",
+                    deserialize::ParseCedarSchemaSetMessage::CedarSchemaJsonFormat,
+                    err
+                )
+            })?;
+        Self::from_fragment_and_json(schema_fragment, json_schema)
+    }
+
+    /// Builds the schema from a [`cedar_policy::SchemaFragment`].
+    pub(crate) fn from_fragment(
+        schema_fragment: cedar_policy::SchemaFragment,
+    ) -> Result<Self, String> {
+        // urgh now recreate the json representation
+        let json_string = schema_fragment.to_json_string().map_err(|err| {
+            format!(
+                "{}: {}",
+                deserialize::ParseCedarSchemaSetMessage::CedarSchemaJsonFormat,
+                err
+            )
+        })?;
+        Self::from_fragment_and_json(schema_fragment, &json_string)
+    }
 
+    /// Builds the schema from a fragment and its JSON representation, `CedarSchema`
+    /// needs both.
+    fn from_fragment_and_json(
+        schema_fragment: cedar_policy::SchemaFragment,
+        json_string: &str,
+    ) -> Result<Self, String> {
         // create the schema
-        let fragment_iter = std::iter::once(schema_fragment);
-        let schema = cedar_policy::Schema::from_schema_fragments(fragment_iter.into_iter())
+        let schema = cedar_policy::Schema::from_schema_fragments(std::iter::once(schema_fragment))
             .map_err(|err| {
-                serde::de::Error::custom(format!(
+                format!(
                     "{}: {}",
                     deserialize::ParseCedarSchemaSetMessage::Parse,
                     err
-                ))
+                )
             })?;
 
-        let json = serde_json::from_str(&json_string).map_err(|err| {
-            serde::de::Error::custom(format!(
+        let json = serde_json::from_str(json_string).map_err(|err| {
+            format!(
                 "{}: {}",
                 deserialize::ParseCedarSchemaSetMessage::CedarSchemaJsonFormat,
                 err
-            ))
+            )
         })?;
 
         let validator_schema =
-            ValidatorSchema::from_json_str(&json_string, Extensions::all_available()).map_err(
+            ValidatorSchema::from_json_str(json_string, Extensions::all_available()).map_err(
                 |err| {
-                    serde::de::Error::custom(format!(
+                    format!(
                         "{}: {}",
                         deserialize::ParseCedarSchemaSetMessage::ParseCedarSchemaJson,
                         err
-                    ))
+                    )
                 },
             )?;
 
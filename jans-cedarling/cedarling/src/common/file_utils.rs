@@ -0,0 +1,94 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Replaces the content of the file, the content is written to a temporary file
+/// first so the file is never left half written.
+pub(crate) fn write_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    write_file_with(path, content, options)
+}
+
+/// Same as [`write_file`], but on Unix the file can only be read by its owner.
+pub(crate) fn write_private_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    write_file_with(path, content, options)
+}
+
+/// The temporary file is always created anew, so a file left by a crash or created by
+/// someone else, with other permissions or as a symlink, is never written to.
+fn write_file_with(path: &Path, content: &[u8], mut options: OpenOptions) -> io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+
+    match fs::remove_file(&tmp_path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {},
+    }
+    let mut file = options.create_new(true).open(&tmp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+
+    fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn replaces_the_file_content() {
+        let path = std::env::temp_dir().join(format!(
+            "cedarling-write-file-{}.json",
+            crate::log::gen_uuid7()
+        ));
+
+        write_file(&path, b"first").expect("should write the file");
+        write_private_file(&path, b"second").expect("should replace the file");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600, "only the owner should read the file");
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn replaces_a_stale_temporary_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!(
+            "cedarling-write-file-{}.json",
+            crate::log::gen_uuid7()
+        ));
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, b"stale").unwrap();
+        fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private_file(&path, b"secret").expect("should write the file");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "secret");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600, "only the owner should read the file");
+        assert!(!Path::new(&tmp_path).exists());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -6,6 +6,7 @@
 use datalogic_rs::Rule;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashSet;
 
 /// JsonLogic rule using [JsonLogic](https://jsonlogic.com/)
 /// Default implementation:
@@ -51,6 +52,39 @@ impl JsonRule {
     pub(crate) fn rule(&self) -> &Rule {
         &self.rule
     }
+
+    /// Returns the names of the variables used by the rule.
+    pub(crate) fn vars(&self) -> HashSet<&str> {
+        let mut vars = HashSet::new();
+        collect_vars(&self.value, &mut vars);
+        vars
+    }
+}
+
+fn collect_vars<'a>(value: &'a Value, vars: &mut HashSet<&'a str>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                if key == "var" {
+                    // `{"var": "name"}` or `{"var": ["name", default]}`
+                    let name = match value {
+                        Value::Array(args) => args.first(),
+                        value => Some(value),
+                    };
+                    if let Some(Value::String(name)) = name {
+                        vars.insert(name);
+                    }
+                }
+                collect_vars(value, vars);
+            }
+        },
+        Value::Array(values) => {
+            for value in values {
+                collect_vars(value, vars);
+            }
+        },
+        _ => {},
+    }
 }
 
 impl Default for JsonRule {
@@ -3,21 +3,39 @@
 //
 // Copyright (c) 2024, Gluu, Inc.
 
+mod builder;
 mod claim_mapping;
+mod claim_path;
+mod claim_ref;
+mod membership_mapping;
+mod policy_index;
+mod role_hierarchy;
+mod template_link;
 #[cfg(test)]
 mod test;
 mod token_entity_metadata;
 
+use super::json_rules::JsonRule;
 use super::{PartitionResult, cedar_schema::CedarSchema};
 use cedar_policy::{Policy, PolicyId};
 use semver::Version;
 use serde::{Deserialize, Deserializer, de};
 use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 use url::Url;
 
+pub use builder::{PolicyStoreBuilder, PolicyStoreBuilderError};
 pub(crate) use claim_mapping::ClaimMappings;
+pub(crate) use claim_path::ClaimPath;
+pub use claim_ref::ClaimRef;
+pub(crate) use membership_mapping::{MembershipMapping, MembershipTarget};
+pub(crate) use role_hierarchy::RoleHierarchy;
+pub use role_hierarchy::RoleHierarchyCycleError;
+pub use template_link::{TemplateLink, TemplateLinkError};
 pub use token_entity_metadata::TokenEntityMetadata;
 
+use policy_index::PolicyIndex;
+
 /// Default maximum number of entities allowed
 const DEFAULT_MAX_ENTITIES: usize = 1000;
 /// Default maximum size of base64-encoded strings in bytes
@@ -58,14 +76,15 @@ fn validate_default_entities(
     // Check base64 size limit for each entity
     for (entity_id, entity_data) in entities {
         if let Some(entity_str) = entity_data.as_str()
-            && entity_str.len() > limits.max_base64_size {
-                return Err(format!(
-                    "Base64 string size ({}) for entity '{}' exceeds maximum allowed size ({})",
-                    entity_str.len(),
-                    entity_id,
-                    limits.max_base64_size
-                ));
-            }
+            && entity_str.len() > limits.max_base64_size
+        {
+            return Err(format!(
+                "Base64 string size ({}) for entity '{}' exceeds maximum allowed size ({})",
+                entity_str.len(),
+                entity_id,
+                limits.max_base64_size
+            ));
+        }
     }
 
     Ok(())
@@ -86,11 +105,11 @@ impl<'de> Deserialize<'de> for AgamaPolicyStore {
     {
         // First try to deserialize into a Value to get better error messages
         let value = serde_json::Value::deserialize(deserializer)?;
-        
+
         // Check for required fields
-        let obj = value.as_object().ok_or_else(|| {
-            de::Error::custom("policy store must be a JSON object")
-        })?;
+        let obj = value
+            .as_object()
+            .ok_or_else(|| de::Error::custom("policy store must be a JSON object"))?;
 
         // Check cedar_version field
         let cedar_version = obj.get("cedar_version").ok_or_else(|| {
@@ -104,23 +123,19 @@ impl<'de> Deserialize<'de> for AgamaPolicyStore {
 
         // Now deserialize the actual struct
         let mut store = AgamaPolicyStore {
-            cedar_version: parse_cedar_version(cedar_version).map_err(|e| {
-                de::Error::custom(format!("invalid cedar_version format: {}", e))
-            })?,
+            cedar_version: parse_cedar_version(cedar_version)
+                .map_err(|e| de::Error::custom(format!("invalid cedar_version format: {}", e)))?,
             policy_stores: HashMap::new(),
         };
 
         // Deserialize policy stores
-        let stores_obj = policy_stores.as_object().ok_or_else(|| {
-            de::Error::custom("'policy_stores' must be a JSON object")
-        })?;
+        let stores_obj = policy_stores
+            .as_object()
+            .ok_or_else(|| de::Error::custom("'policy_stores' must be a JSON object"))?;
 
         for (key, value) in stores_obj {
             let policy_store = PolicyStore::deserialize(value).map_err(|e| {
-                de::Error::custom(format!(
-                    "error parsing policy store '{}': {}",
-                    key, e
-                ))
+                de::Error::custom(format!("error parsing policy store '{}': {}", key, e))
             })?;
             store.policy_stores.insert(key.clone(), policy_store);
         }
@@ -166,6 +181,18 @@ pub struct PolicyStore {
     /// This optional field can be used to specify default entities that should be included
     /// in the policy evaluation context.
     pub default_entities: Option<HashMap<String, serde_json::Value>>,
+
+    /// Role hierarchy for the policy store.
+    ///
+    /// Maps a role ID to the role IDs it is a member of. The hierarchy is used to set
+    /// the parents of the `Role` entities built for each request.
+    pub role_hierarchy: Option<RoleHierarchy>,
+
+    /// Principal boolean operations of the actions, keyed by the UID of the action or
+    /// of an action group.
+    ///
+    /// They are used instead of the `CEDARLING_PRINCIPAL_BOOLEAN_OPERATION` bootstrap property.
+    pub principal_bool_operations: Option<HashMap<String, JsonRule>>,
 }
 
 impl PolicyStore {
@@ -184,16 +211,16 @@ impl PolicyStore {
                 max_entities: max_entities.unwrap_or(DEFAULT_MAX_ENTITIES),
                 max_base64_size: max_base64_size.unwrap_or(DEFAULT_MAX_BASE64_SIZE),
             };
-            
+
             validate_default_entities(default_entities, &limits)?;
         }
-        
+
         Ok(())
     }
 }
 
 /// Wrapper around [`PolicyStore`] to have access to it and ID of policy store
-#[derive(Clone, derive_more::Deref)]
+#[derive(Debug, Clone, PartialEq, derive_more::Deref)]
 pub struct PolicyStoreWithID {
     /// ID of policy store
     pub id: String,
@@ -268,25 +295,27 @@ impl Default for &TrustedIssuer {
 
 impl TrustedIssuer {
     /// Retrieves the claim that defines the `Role` for a given token type.
-    pub fn get_role_mapping(&self, token_name: &str) -> Option<&str> {
+    pub fn get_role_mapping(&self, token_name: &str) -> Option<&ClaimRef> {
         self.token_metadata
             .get(token_name)
-            .and_then(|x| x.role_mapping.as_deref())
+            .and_then(|x| x.role_mapping.as_ref())
     }
 
     /// Retrieves the claim that defines the `User` for a given token type.
-    pub fn get_user_mapping(&self, token_name: &str) -> Option<&str> {
+    pub fn get_user_mapping(&self, token_name: &str) -> Option<&ClaimRef> {
         self.token_metadata
             .get(token_name)
-            .and_then(|x| x.user_id.as_deref())
+            .and_then(|x| x.user_id.as_ref())
     }
 
+    /// Retrieves the claim mappings of a given token type.
     pub fn get_claim_mapping(&self, token_name: &str) -> Option<&ClaimMappings> {
         self.token_metadata
             .get(token_name)
             .map(|x| &x.claim_mapping)
     }
 
+    /// Retrieves the metadata of a given token type.
     pub fn get_token_metadata(&self, token_name: &str) -> Option<&TokenEntityMetadata> {
         self.token_metadata.get(token_name)
     }
@@ -414,29 +443,212 @@ struct RawPolicy {
 /// Container to decode policy stores into container
 ///
 /// Contain compiled [`cedar_policy::PolicySet`] and raw policy info to get description or other information.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct PoliciesContainer {
     /// HasMap to store raw policy info
     /// Is used to get policy description by ID
     // In HasMap ID is ID of policy
     raw_policy_info: HashMap<String, RawPolicy>,
 
+    /// Raw info of the policy templates, by template ID
+    raw_template_info: HashMap<String, RawPolicy>,
+
+    /// Links of the policy templates, by link ID
+    template_links: HashMap<String, TemplateLink>,
+
     /// compiled `cedar_policy`` Policy set
     policy_set: cedar_policy::PolicySet,
+
+    /// Index of the policies by the scope, built from `policy_set`.
+    /// It is reset when the policy set changes.
+    index: Arc<OnceLock<PolicyIndex>>,
+}
+
+impl PartialEq for PoliciesContainer {
+    fn eq(&self, other: &Self) -> bool {
+        // the index is not compared since it is built from the policy set
+        self.raw_policy_info == other.raw_policy_info
+            && self.raw_template_info == other.raw_template_info
+            && self.template_links == other.template_links
+            && self.policy_set == other.policy_set
+    }
 }
 
 impl PoliciesContainer {
+    /// Creates the container from a policy set built in code.
+    ///
+    /// The policies and templates are described by their `@description` annotation,
+    /// the policies linked from a template become template links.
+    pub(crate) fn from_policy_set(policy_set: cedar_policy::PolicySet) -> Self {
+        let raw_policy = |body: String, description: Option<&str>| RawPolicy {
+            policy_content: MaybeEncoded::Tagged(EncodedPolicy {
+                encoding: super::Encoding::None,
+                content_type: PolicyContentType::Cedar,
+                body,
+            }),
+            description: description.unwrap_or_default().to_string(),
+        };
+
+        let mut raw_policy_info = HashMap::new();
+        let mut template_links = HashMap::new();
+        for policy in policy_set.policies() {
+            match (policy.template_id(), policy.template_links()) {
+                (Some(template_id), Some(values)) => {
+                    let link = TemplateLink {
+                        template_id: template_id.to_string(),
+                        values: values
+                            .iter()
+                            .map(|(slot, uid)| (slot.to_string(), uid.to_string()))
+                            .collect(),
+                    };
+                    template_links.insert(policy.id().to_string(), link);
+                },
+                _ => {
+                    let raw = raw_policy(policy.to_string(), policy.annotation("description"));
+                    raw_policy_info.insert(policy.id().to_string(), raw);
+                },
+            }
+        }
+
+        let raw_template_info = policy_set
+            .templates()
+            .map(|template| {
+                let raw = raw_policy(template.to_string(), template.annotation("description"));
+                (template.id().to_string(), raw)
+            })
+            .collect();
+
+        let policies = Self {
+            raw_policy_info,
+            raw_template_info,
+            template_links,
+            policy_set,
+            index: Default::default(),
+        };
+        policies.index();
+        policies
+    }
+
     /// Get [`cedar_policy::PolicySet`]
     pub fn get_set(&self) -> &cedar_policy::PolicySet {
         &self.policy_set
     }
 
+    fn index(&self) -> &PolicyIndex {
+        self.index
+            .get_or_init(|| PolicyIndex::new(&self.policy_set))
+    }
+
+    /// Get the policies that can apply to a request with the given principal type,
+    /// action and resource type, the decisions are the same as with [`Self::get_set`].
+    ///
+    /// `action_groups` returns the action groups the action is a member of.
+    pub(crate) fn policies_for(
+        &self,
+        principal_type: &cedar_policy::EntityTypeName,
+        action: &cedar_policy::EntityUid,
+        resource_type: &cedar_policy::EntityTypeName,
+        action_groups: impl FnOnce() -> Vec<cedar_policy::EntityUid>,
+    ) -> Arc<cedar_policy::PolicySet> {
+        self.index().policies_for(
+            &self.policy_set,
+            principal_type,
+            action,
+            resource_type,
+            action_groups,
+        )
+    }
+
     /// Get policy description based on id of policy
+    ///
+    /// Policies linked from a template have the description of the template.
     pub fn get_policy_description(&self, id: &str) -> Option<&str> {
-        self.raw_policy_info.get(id).map(|v| v.description.as_str())
+        self.raw_policy_info
+            .get(id)
+            .or_else(|| {
+                self.template_links
+                    .get(id)
+                    .and_then(|link| self.raw_template_info.get(&link.template_id))
+            })
+            .map(|v| v.description.as_str())
     }
 
+    /// Get the template link of a linked policy
+    pub fn get_template_link(&self, id: &str) -> Option<&TemplateLink> {
+        self.template_links.get(id)
+    }
+
+    /// Get all the template links, by link ID
+    pub fn template_links(&self) -> &HashMap<String, TemplateLink> {
+        &self.template_links
+    }
 
+    /// Adds the templates to the policy set.
+    fn add_templates<'de, D>(
+        &mut self,
+        templates: HashMap<String, RawPolicy>,
+    ) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        for (id, template_raw) in templates {
+            let body = decode_policy_content::<D>(&template_raw)?;
+            let template =
+                cedar_policy::Template::parse(Some(PolicyId::new(&id)), body).map_err(|err| {
+                    de::Error::custom(format!(
+                        "unable to decode template with id: {id}, error: {}: {err}",
+                        ParsePolicySetMessage::HumanReadable
+                    ))
+                })?;
+            self.policy_set.add_template(template).map_err(|err| {
+                de::Error::custom(format!(
+                    "unable to add template with id: {id}, error: {err}"
+                ))
+            })?;
+            self.raw_template_info.insert(id, template_raw);
+        }
+        Ok(())
+    }
+
+    /// Links a template, the linked policy gets the ID of the link.
+    pub(crate) fn link_template(
+        &mut self,
+        link_id: &str,
+        link: TemplateLink,
+    ) -> Result<(), TemplateLinkError> {
+        let values = link.cedar_values()?;
+        self.policy_set
+            .link(
+                PolicyId::new(&link.template_id),
+                PolicyId::new(link_id),
+                values,
+            )
+            .map_err(|err| TemplateLinkError::Link {
+                link_id: link_id.to_string(),
+                template_id: link.template_id.clone(),
+                err: Box::new(err),
+            })?;
+        self.index = Default::default();
+        self.template_links.insert(link_id.to_string(), link);
+        Ok(())
+    }
+
+    /// Removes a policy linked from a template.
+    pub(crate) fn unlink_template(
+        &mut self,
+        link_id: &str,
+    ) -> Result<TemplateLink, TemplateLinkError> {
+        if !self.template_links.contains_key(link_id) {
+            return Err(TemplateLinkError::UnknownLink(link_id.to_string()));
+        }
+        self.policy_set
+            .unlink(PolicyId::new(link_id))
+            .map_err(|err| TemplateLinkError::Unlink(link_id.to_string(), Box::new(err)))?;
+        self.index = Default::default();
+        self.template_links
+            .remove(link_id)
+            .ok_or_else(|| TemplateLinkError::UnknownLink(link_id.to_string()))
+    }
 }
 
 /// Custom deserializer for converting base64-encoded policies into a `PolicySet`.
@@ -480,6 +692,9 @@ impl<'de> serde::Deserialize<'de> for PoliciesContainer {
         Ok(PoliciesContainer {
             policy_set,
             raw_policy_info: policies,
+            raw_template_info: HashMap::new(),
+            template_links: HashMap::new(),
+            index: Default::default(),
         })
     }
 }
@@ -492,6 +707,21 @@ fn parse_single_policy<'de, D>(
     id: &str,
     policy_raw: &RawPolicy,
 ) -> Result<cedar_policy::Policy, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let decoded_body = decode_policy_content::<D>(policy_raw)?;
+
+    let policy =
+        cedar_policy::Policy::parse(Some(PolicyId::new(id)), decoded_body).map_err(|err| {
+            serde::de::Error::custom(format!("{}: {err}", ParsePolicySetMessage::HumanReadable))
+        })?;
+
+    Ok(policy)
+}
+
+/// Decodes the `policy_content` of a policy or template to the Cedar policy language.
+fn decode_policy_content<'de, D>(policy_raw: &RawPolicy) -> Result<String, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -521,26 +751,43 @@ where
         },
     };
 
-    let policy = match policy_with_metadata.content_type {
-        // see comments for PolicyContentType
-        PolicyContentType::Cedar => {
-            cedar_policy::Policy::parse(Some(PolicyId::new(id)), decoded_body).map_err(|err| {
-                serde::de::Error::custom(format!("{}: {err}", ParsePolicySetMessage::HumanReadable))
-            })?
-        },
-    };
-
-    Ok(policy)
+    // see comments for PolicyContentType
+    match policy_with_metadata.content_type {
+        PolicyContentType::Cedar => Ok(decoded_body),
+    }
 }
 
-/// Custom parser for an Option<String> which returns `None` if the string is empty.
-pub fn parse_option_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+/// Parses the policies, the policy templates and the template links of a policy store entry.
+fn parse_policies_and_templates<'de, D>(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    policies: &serde_json::Value,
+) -> Result<PoliciesContainer, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let value = Option::<String>::deserialize(deserializer)?;
+    let mut policies = PoliciesContainer::deserialize(policies)
+        .map_err(|e| de::Error::custom(format!("error parsing policies: {}", e)))?;
 
-    Ok(value.filter(|s| !s.is_empty()))
+    if let Some(templates) = obj.get("policy_templates") {
+        let templates = HashMap::<String, RawPolicy>::deserialize(templates)
+            .map_err(|e| de::Error::custom(format!("error parsing policy templates: {}", e)))?;
+        policies.add_templates::<D>(templates)?;
+    }
+
+    if let Some(links) = obj.get("template_links") {
+        let links = HashMap::<String, TemplateLink>::deserialize(links)
+            .map_err(|e| de::Error::custom(format!("error parsing template links: {}", e)))?;
+        for (link_id, link) in links {
+            policies
+                .link_template(&link_id, link)
+                .map_err(|e| de::Error::custom(format!("error parsing template links: {}", e)))?;
+        }
+    }
+
+    // the policies are indexed when the policy store is loaded, not on the first request
+    policies.index();
+
+    Ok(policies)
 }
 
 /// Custom deserializer for PolicyStore that provides better error messages
@@ -551,29 +798,39 @@ impl<'de> Deserialize<'de> for PolicyStore {
     {
         // First try to deserialize into a Value to get better error messages
         let value = serde_json::Value::deserialize(deserializer)?;
-        
+
         // Check for required fields
-        let obj = value.as_object().ok_or_else(|| {
-            de::Error::custom("policy store entry must be a JSON object")
-        })?;
+        let obj = value
+            .as_object()
+            .ok_or_else(|| de::Error::custom("policy store entry must be a JSON object"))?;
 
         // Check name field
         let name = obj.get("name").ok_or_else(|| {
             de::Error::custom("missing required field 'name' in policy store entry")
         })?;
-        let name = name.as_str().ok_or_else(|| {
-            de::Error::custom("'name' must be a string")
-        })?;
+        let name = name
+            .as_str()
+            .ok_or_else(|| de::Error::custom("'name' must be a string"))?;
 
         // Check schema field
-        let schema = obj.get("schema").or_else(|| obj.get("cedar_schema")).ok_or_else(|| {
-            de::Error::custom("missing required field 'schema' or 'cedar_schema' in policy store entry")
-        })?;
+        let schema = obj
+            .get("schema")
+            .or_else(|| obj.get("cedar_schema"))
+            .ok_or_else(|| {
+                de::Error::custom(
+                    "missing required field 'schema' or 'cedar_schema' in policy store entry",
+                )
+            })?;
 
         // Check policies field
-        let policies = obj.get("policies").or_else(|| obj.get("cedar_policies")).ok_or_else(|| {
-            de::Error::custom("missing required field 'policies' or 'cedar_policies' in policy store entry")
-        })?;
+        let policies = obj
+            .get("policies")
+            .or_else(|| obj.get("cedar_policies"))
+            .ok_or_else(|| {
+                de::Error::custom(
+                    "missing required field 'policies' or 'cedar_policies' in policy store entry",
+                )
+            })?;
 
         // Now deserialize the actual struct
         let store = PolicyStore {
@@ -593,9 +850,7 @@ impl<'de> Deserialize<'de> for PolicyStore {
             schema: CedarSchema::deserialize(schema).map_err(|e| {
                 de::Error::custom(format!("error parsing schema: {}", e))
             })?,
-            policies: PoliciesContainer::deserialize(policies).map_err(|e| {
-                de::Error::custom(format!("error parsing policies: {}", e))
-            })?,
+            policies: parse_policies_and_templates::<D>(obj, policies)?,
             trusted_issuers: obj.get("trusted_issuers")
                 .map(|v| {
                     HashMap::<String, TrustedIssuer>::deserialize(v)
@@ -664,6 +919,22 @@ impl<'de> Deserialize<'de> for PolicyStore {
                     Ok(decoded)
                 })
                 .transpose()?,
+            role_hierarchy: obj
+                .get("role_hierarchy")
+                .map(|v| {
+                    RoleHierarchy::deserialize(v).map_err(|e| {
+                        de::Error::custom(format!("error parsing role hierarchy: {}", e))
+                    })
+                })
+                .transpose()?,
+            principal_bool_operations: obj
+                .get("principal_bool_operations")
+                .map(|v| {
+                    HashMap::<String, JsonRule>::deserialize(v).map_err(|e| {
+                        de::Error::custom(format!("error parsing principal bool operations: {}", e))
+                    })
+                })
+                .transpose()?,
         };
 
         Ok(store)
@@ -684,26 +955,28 @@ mod tests {
 
         // Test valid entities
         let valid_entities = HashMap::from([
-            ("entity1".to_string(), json!("dGVzdA=="),),
-            ("entity2".to_string(), json!("dGVzdDI="),),
+            ("entity1".to_string(), json!("dGVzdA==")),
+            ("entity2".to_string(), json!("dGVzdDI=")),
         ]);
         assert!(validate_default_entities(&valid_entities, &limits).is_ok());
 
         // Test entity count limit
         let too_many_entities = HashMap::from([
-            ("entity1".to_string(), json!("dGVzdA=="),),
-            ("entity2".to_string(), json!("dGVzdDI="),),
-            ("entity3".to_string(), json!("dGVzdDM="),),
+            ("entity1".to_string(), json!("dGVzdA==")),
+            ("entity2".to_string(), json!("dGVzdDI=")),
+            ("entity3".to_string(), json!("dGVzdDM=")),
         ]);
         let result = validate_default_entities(&too_many_entities, &limits);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Maximum number of default entities (2) exceeded"));
+        assert!(
+            result
+                .unwrap_err()
+                .contains("Maximum number of default entities (2) exceeded")
+        );
 
         // Test base64 size limit
         let large_base64 = "dGVzdA==".repeat(20); // Much larger than 100 bytes
-        let large_entities = HashMap::from([
-            ("entity1".to_string(), json!(large_base64),),
-        ]);
+        let large_entities = HashMap::from([("entity1".to_string(), json!(large_base64))]);
         let result = validate_default_entities(&large_entities, &limits);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("Base64 string size"));
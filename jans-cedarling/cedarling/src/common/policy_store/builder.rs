@@ -0,0 +1,495 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Builder of a [`PolicyStore`] from Rust values, for the embedders which generate the
+//! policies in code instead of writing a policy store document.
+
+use std::collections::HashMap;
+
+use cedar_policy::{PolicyId, PolicySet, SchemaFragment};
+use semver::Version;
+use serde_json::Value;
+
+use super::{
+    DefaultEntitiesLimits, PoliciesContainer, PolicyStore, PolicyStoreWithID, RoleHierarchy,
+    RoleHierarchyCycleError, TemplateLink, TemplateLinkError, TrustedIssuer,
+    validate_default_entities,
+};
+use crate::common::cedar_schema::CedarSchema;
+use crate::common::json_rules::JsonRule;
+
+/// Source of the schema, it is only parsed when the policy store is built.
+enum SchemaSource {
+    Cedar(String),
+    Json(String),
+    Fragment(SchemaFragment),
+}
+
+/// Builds a [`PolicyStoreWithID`] which can be passed to Cedarling with
+/// [`PolicyStoreSource::InMemory`](crate::PolicyStoreSource::InMemory).
+///
+/// The values are only checked by [`Self::build`], so the calls can be chained.
+/// The schema can be given in the Cedar schema language, in the Cedar JSON format or as
+/// a [`SchemaFragment`]. A [`cedar_policy::Schema`] is not accepted since Cedarling also
+/// needs the JSON representation of the schema, which cannot be recovered from it.
+///
+/// ```
+/// use cedarling::PolicyStoreBuilder;
+///
+/// let policy_store = PolicyStoreBuilder::new("store_id", "Jans")
+///     .cedar_schema(
+///         r#"namespace Jans {
+///           entity User;
+///           entity Issue;
+///           action "Update" appliesTo { principal: [User], resource: [Issue] };
+///         }"#,
+///     )
+///     .policy(
+///         "update_issue",
+///         r#"permit(principal is Jans::User, action == Jans::Action::"Update", resource);"#,
+///     )
+///     .build()
+///     .expect("policy store should be valid");
+/// assert_eq!(policy_store.id, "store_id");
+/// ```
+pub struct PolicyStoreBuilder {
+    id: String,
+    name: String,
+    version: Option<String>,
+    description: Option<String>,
+    cedar_version: Option<Version>,
+    schema: Option<SchemaSource>,
+    policy_set: PolicySet,
+    policies: Vec<(String, String)>,
+    templates: Vec<(String, String)>,
+    template_links: Vec<(String, TemplateLink)>,
+    trusted_issuers: HashMap<String, TrustedIssuer>,
+    default_entities: HashMap<String, Value>,
+    role_hierarchy: Option<RoleHierarchy>,
+    principal_bool_operations: HashMap<String, JsonRule>,
+}
+
+/// Errors that can occur when building a policy store with [`PolicyStoreBuilder`].
+#[derive(Debug, thiserror::Error)]
+pub enum PolicyStoreBuilderError {
+    /// The schema was not set.
+    #[error("the policy store has no schema")]
+    MissingSchema,
+    /// The schema could not be parsed.
+    #[error("invalid schema: {0}")]
+    Schema(String),
+    /// A policy or template could not be parsed.
+    #[error("unable to parse the policy `{id}`: {err}")]
+    ParsePolicy {
+        /// ID of the policy or template
+        id: String,
+        /// Error from cedar-policy
+        err: Box<cedar_policy::ParseErrors>,
+    },
+    /// A policy or template could not be added to the policy set, for example
+    /// because its ID is already used.
+    #[error("unable to add the policy `{id}` to the policy set: {err}")]
+    AddPolicy {
+        /// ID of the policy or template
+        id: String,
+        /// Error from cedar-policy
+        err: Box<cedar_policy::PolicySetError>,
+    },
+    /// A template could not be linked.
+    #[error(transparent)]
+    TemplateLink(#[from] TemplateLinkError),
+    /// A default entity is not a JSON object.
+    #[error("the default entity `{0}` must be a JSON object")]
+    DefaultEntityNotObject(String),
+    /// There are too many default entities.
+    #[error("invalid default entities: {0}")]
+    DefaultEntities(String),
+    /// The role hierarchy contains a cycle.
+    #[error(transparent)]
+    RoleHierarchy(#[from] RoleHierarchyCycleError),
+}
+
+impl PolicyStoreBuilder {
+    /// Creates a builder of the policy store with the given ID and name.
+    pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            version: None,
+            description: None,
+            cedar_version: None,
+            schema: None,
+            policy_set: PolicySet::new(),
+            policies: Vec::new(),
+            templates: Vec::new(),
+            template_links: Vec::new(),
+            trusted_issuers: HashMap::new(),
+            default_entities: HashMap::new(),
+            role_hierarchy: None,
+            principal_bool_operations: HashMap::new(),
+        }
+    }
+
+    /// Sets the version of the policy store.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Sets the description of the policy store.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Sets the version of the Cedar language of the policy store.
+    pub fn cedar_version(mut self, cedar_version: Version) -> Self {
+        self.cedar_version = Some(cedar_version);
+        self
+    }
+
+    /// Sets the schema in the Cedar schema language.
+    pub fn cedar_schema(mut self, schema: impl Into<String>) -> Self {
+        self.schema = Some(SchemaSource::Cedar(schema.into()));
+        self
+    }
+
+    /// Sets the schema in the Cedar JSON schema format.
+    pub fn json_schema(mut self, schema: impl Into<String>) -> Self {
+        self.schema = Some(SchemaSource::Json(schema.into()));
+        self
+    }
+
+    /// Sets the schema from a [`SchemaFragment`].
+    pub fn schema_fragment(mut self, schema: SchemaFragment) -> Self {
+        self.schema = Some(SchemaSource::Fragment(schema));
+        self
+    }
+
+    /// Sets the policy set the policies, templates and template links are added to.
+    ///
+    /// The policies and templates are described by their `@description` annotation.
+    pub fn policy_set(mut self, policy_set: PolicySet) -> Self {
+        self.policy_set = policy_set;
+        self
+    }
+
+    /// Adds a policy in the Cedar policy language.
+    pub fn policy(mut self, id: impl Into<String>, policy: impl Into<String>) -> Self {
+        self.policies.push((id.into(), policy.into()));
+        self
+    }
+
+    /// Adds a policy template in the Cedar policy language.
+    pub fn template(mut self, id: impl Into<String>, template: impl Into<String>) -> Self {
+        self.templates.push((id.into(), template.into()));
+        self
+    }
+
+    /// Links a template, the linked policy gets the ID of the link.
+    pub fn template_link(mut self, link_id: impl Into<String>, link: TemplateLink) -> Self {
+        self.template_links.push((link_id.into(), link));
+        self
+    }
+
+    /// Adds a trusted issuer.
+    pub fn trusted_issuer(mut self, id: impl Into<String>, issuer: TrustedIssuer) -> Self {
+        self.trusted_issuers.insert(id.into(), issuer);
+        self
+    }
+
+    /// Adds a default entity, the value is the JSON object of the entity.
+    pub fn default_entity(mut self, id: impl Into<String>, entity: Value) -> Self {
+        self.default_entities.insert(id.into(), entity);
+        self
+    }
+
+    /// Makes `role` a member of `parent` in the role hierarchy.
+    pub fn role_parent(mut self, role: &str, parent: &str) -> Self {
+        self.role_hierarchy
+            .get_or_insert_with(RoleHierarchy::default)
+            .add_parent(role, parent);
+        self
+    }
+
+    /// Sets the principal boolean operation of an action or action group UID, such as
+    /// `Jans::Action::"Read"`.
+    pub fn principal_bool_operation(mut self, action: impl Into<String>, rule: JsonRule) -> Self {
+        self.principal_bool_operations.insert(action.into(), rule);
+        self
+    }
+
+    /// Parses the schema and the policies and builds the policy store.
+    pub fn build(self) -> Result<PolicyStoreWithID, PolicyStoreBuilderError> {
+        let schema = match self.schema.ok_or(PolicyStoreBuilderError::MissingSchema)? {
+            SchemaSource::Cedar(schema) => CedarSchema::from_cedar_str(&schema),
+            SchemaSource::Json(schema) => CedarSchema::from_json_str(&schema),
+            SchemaSource::Fragment(schema) => CedarSchema::from_fragment(schema),
+        }
+        .map_err(PolicyStoreBuilderError::Schema)?;
+
+        let mut policy_set = self.policy_set;
+        for (id, policy) in self.policies {
+            let policy =
+                cedar_policy::Policy::parse(Some(PolicyId::new(&id)), policy).map_err(|err| {
+                    PolicyStoreBuilderError::ParsePolicy {
+                        id: id.clone(),
+                        err: Box::new(err),
+                    }
+                })?;
+            policy_set
+                .add(policy)
+                .map_err(|err| PolicyStoreBuilderError::AddPolicy {
+                    id,
+                    err: Box::new(err),
+                })?;
+        }
+        for (id, template) in self.templates {
+            let template = cedar_policy::Template::parse(Some(PolicyId::new(&id)), template)
+                .map_err(|err| PolicyStoreBuilderError::ParsePolicy {
+                    id: id.clone(),
+                    err: Box::new(err),
+                })?;
+            policy_set.add_template(template).map_err(|err| {
+                PolicyStoreBuilderError::AddPolicy {
+                    id,
+                    err: Box::new(err),
+                }
+            })?;
+        }
+
+        let mut policies = PoliciesContainer::from_policy_set(policy_set);
+        for (link_id, link) in self.template_links {
+            policies.link_template(&link_id, link)?;
+        }
+
+        for (id, entity) in self.default_entities.iter() {
+            if !entity.is_object() {
+                return Err(PolicyStoreBuilderError::DefaultEntityNotObject(id.clone()));
+            }
+        }
+        validate_default_entities(&self.default_entities, &DefaultEntitiesLimits::default())
+            .map_err(PolicyStoreBuilderError::DefaultEntities)?;
+
+        if let Some(role_hierarchy) = &self.role_hierarchy {
+            role_hierarchy.validate()?;
+        }
+
+        let store = PolicyStore {
+            version: self.version,
+            name: self.name,
+            description: self.description,
+            cedar_version: self.cedar_version,
+            schema,
+            policies,
+            trusted_issuers: (!self.trusted_issuers.is_empty()).then_some(self.trusted_issuers),
+            default_entities: (!self.default_entities.is_empty()).then_some(self.default_entities),
+            role_hierarchy: self.role_hierarchy,
+            principal_bool_operations: (!self.principal_bool_operations.is_empty())
+                .then_some(self.principal_bool_operations),
+        };
+
+        Ok(PolicyStoreWithID { id: self.id, store })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    const SCHEMA: &str = r#"
+        namespace Jans {
+          entity Role;
+          entity User in [Role];
+          entity Issue;
+          action "Update" appliesTo {
+            principal: [User],
+            resource: [Issue],
+          };
+        }
+    "#;
+
+    fn builder() -> PolicyStoreBuilder {
+        PolicyStoreBuilder::new("store_id", "Jans").cedar_schema(SCHEMA)
+    }
+
+    #[test]
+    fn builds_policy_store() {
+        let policy_store = builder()
+            .version("1.0.0")
+            .policy(
+                "update_issue",
+                r#"permit(principal is Jans::User, action == Jans::Action::"Update", resource);"#,
+            )
+            .template(
+                "share_issue",
+                r#"@description("shares an issue")
+                permit(principal == ?principal, action, resource == ?resource);"#,
+            )
+            .template_link(
+                "alice_issue_1",
+                TemplateLink {
+                    template_id: "share_issue".to_string(),
+                    values: HashMap::from([
+                        (
+                            "?principal".to_string(),
+                            r#"Jans::User::"alice""#.to_string(),
+                        ),
+                        (
+                            "?resource".to_string(),
+                            r#"Jans::Issue::"issue_1""#.to_string(),
+                        ),
+                    ]),
+                },
+            )
+            .trusted_issuer("jans", TrustedIssuer::default())
+            .default_entity(
+                "admin",
+                json!({"uid": {"type": "Jans::Role", "id": "admin"}, "attrs": {}, "parents": []}),
+            )
+            .role_parent("admin", "editor")
+            .build()
+            .expect("policy store should be built");
+
+        assert_eq!(policy_store.id, "store_id");
+        assert_eq!(policy_store.name, "Jans");
+        assert_eq!(policy_store.get_store_version(), "1.0.0");
+        assert!(
+            policy_store
+                .schema
+                .json
+                .get_action("Jans", "Update")
+                .is_some()
+        );
+
+        let policies = &policy_store.policies;
+        assert_eq!(policies.get_set().policies().count(), 2);
+        assert_eq!(policies.get_policy_description("update_issue"), Some(""));
+        assert_eq!(
+            policies.get_policy_description("alice_issue_1"),
+            Some("shares an issue"),
+            "linked policies have the description of the template"
+        );
+        assert_eq!(
+            policies
+                .get_template_link("alice_issue_1")
+                .map(|link| link.template_id.as_str()),
+            Some("share_issue")
+        );
+
+        assert!(
+            policy_store
+                .trusted_issuers
+                .as_ref()
+                .is_some_and(|issuers| issuers.contains_key("jans"))
+        );
+        assert!(
+            policy_store
+                .default_entities
+                .as_ref()
+                .is_some_and(|entities| entities.contains_key("admin"))
+        );
+        assert_eq!(
+            policy_store
+                .role_hierarchy
+                .as_ref()
+                .unwrap()
+                .ancestors("admin"),
+            ["editor"].into()
+        );
+    }
+
+    #[test]
+    fn builds_policy_store_from_policy_set() {
+        let policy_set = PolicySet::from_str(
+            r#"
+            @description("users can update the issues")
+            permit(principal is Jans::User, action == Jans::Action::"Update", resource);
+            "#,
+        )
+        .unwrap();
+
+        let policy_store = builder()
+            .policy_set(policy_set.clone())
+            .build()
+            .expect("policy store should be built");
+
+        assert_eq!(policy_store.policies.get_set(), &policy_set);
+        assert_eq!(
+            policy_store.policies.get_policy_description("policy0"),
+            Some("users can update the issues")
+        );
+    }
+
+    #[test]
+    fn json_schema_matches_cedar_schema() {
+        let fragment = SchemaFragment::from_cedarschema_str(SCHEMA).unwrap().0;
+        let json_schema = fragment.to_json_string().unwrap();
+
+        let from_cedar = builder().build().unwrap();
+        let from_json = builder().json_schema(json_schema).build().unwrap();
+        let from_fragment = builder().schema_fragment(fragment).build().unwrap();
+
+        assert_eq!(from_cedar.schema, from_json.schema);
+        assert_eq!(from_cedar.schema, from_fragment.schema);
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let err = PolicyStoreBuilder::new("store_id", "Jans")
+            .build()
+            .expect_err("schema is required");
+        assert!(
+            matches!(err, PolicyStoreBuilderError::MissingSchema),
+            "{err}"
+        );
+
+        let err = PolicyStoreBuilder::new("store_id", "Jans")
+            .cedar_schema("namespace Jans {")
+            .build()
+            .expect_err("schema should be invalid");
+        assert!(matches!(err, PolicyStoreBuilderError::Schema(_)), "{err}");
+
+        let err = builder()
+            .policy("broken", "permit(")
+            .build()
+            .expect_err("policy should be invalid");
+        assert!(
+            matches!(&err, PolicyStoreBuilderError::ParsePolicy { id, .. } if id == "broken"),
+            "{err}"
+        );
+
+        let err = builder()
+            .policy("duplicate", "permit(principal, action, resource);")
+            .policy("duplicate", "forbid(principal, action, resource);")
+            .build()
+            .expect_err("policy IDs should be unique");
+        assert!(
+            matches!(&err, PolicyStoreBuilderError::AddPolicy { id, .. } if id == "duplicate"),
+            "{err}"
+        );
+
+        let err = builder()
+            .default_entity("admin", json!("YWRtaW4="))
+            .build()
+            .expect_err("default entities should be objects");
+        assert!(
+            matches!(&err, PolicyStoreBuilderError::DefaultEntityNotObject(id) if id == "admin"),
+            "{err}"
+        );
+
+        let err = builder()
+            .role_parent("admin", "editor")
+            .role_parent("editor", "admin")
+            .build()
+            .expect_err("role hierarchy should not contain cycles");
+        assert!(
+            matches!(err, PolicyStoreBuilderError::RoleHierarchy(_)),
+            "{err}"
+        );
+    }
+}
@@ -10,6 +10,8 @@ use regex::Regex;
 use serde::{Deserialize, de};
 use serde_json::Value;
 
+use super::claim_path::ClaimPath;
+
 /// Structure for storing `claim mappings`
 ///
 /// wrapper around hash map
@@ -89,12 +91,15 @@ impl ClaimMappingsBuilder {
 /// This enum can either be:
 /// - `Regex`: For extracting claims using regular expressions with fields.
 /// - `Json`: For extracting claims using a JSON parser.
+/// - `Path`: For extracting nested values using path expressions.
 #[derive(Debug, PartialEq, Clone)]
 pub enum ClaimMapping {
     /// Represents a claim mapping using regular expressions.
     Regex(RegexMapping),
     /// Represents a claim mapping using a JSON parser.
     Json,
+    /// Represents a claim mapping using path expressions.
+    Path(PathMapping),
 }
 
 impl ClaimMapping {
@@ -102,9 +107,12 @@ impl ClaimMapping {
     ///
     /// if `Regex` mapping value will be converted to json value, if has error on converting, return default value
     /// if `Json` mapping value convert JSON object to HashMap or return empty HashMap
+    /// if `Path` mapping each field is set to the value selected by its path, fields
+    /// without a match are omitted
     pub fn apply_mapping(&self, value: &serde_json::Value) -> HashMap<String, serde_json::Value> {
         match self {
             ClaimMapping::Regex(regexp_mapping) => regexp_mapping.apply_mapping(value),
+            ClaimMapping::Path(path_mapping) => path_mapping.apply_mapping(value),
             ClaimMapping::Json => {
                 // convert JSON object to HashMap or return empty HashMap
                 value
@@ -116,11 +124,42 @@ impl ClaimMapping {
     }
 
     pub fn apply_mapping_value(&self, value: &serde_json::Value) -> serde_json::Value {
+        // a `Path` mapping with a top-level `path` replaces the value itself
+        // instead of building a record
+        if let ClaimMapping::Path(PathMapping {
+            path: Some(path), ..
+        }) = self
+        {
+            return path.select(value).unwrap_or(Value::Null);
+        }
+
         // this should always be a valid JSON since the input is a valid JSON
         serde_json::to_value(self.apply_mapping(value)).expect("a valid JSON")
     }
 }
 
+/// Represents a claim mapping using path expressions.
+///
+/// The root of each path (`$`) is the value of the mapped claim.
+///
+/// # Fields
+/// - `path`: if set, the claim value is replaced with the value selected by this path.
+/// - `fields`: A map of record attribute names to the path used to select their value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathMapping {
+    path: Option<ClaimPath>,
+    fields: HashMap<String, ClaimPath>,
+}
+
+impl PathMapping {
+    fn apply_mapping(&self, value: &serde_json::Value) -> HashMap<String, serde_json::Value> {
+        self.fields
+            .iter()
+            .filter_map(|(attr, path)| path.select(value).map(|v| (attr.clone(), v)))
+            .collect()
+    }
+}
+
 /// Represents a claim mapping using regular expressions.
 ///
 /// # Fields
@@ -194,15 +233,16 @@ impl PartialEq for RegexMapping {
 impl<'de> Deserialize<'de> for ClaimMapping {
     /// Custom deserialization logic for `ClaimMapping`.
     ///
-    /// Parses a JSON object to determine whether the parser type is `regex` or `json`.
-    /// Depending on the parser type, it deserializes the corresponding fields into
-    /// either a `Regex` or `Json` variant of the `ClaimMapping` enum.
+    /// Parses a JSON object to determine whether the parser type is `regex`, `json`
+    /// or `jsonpath`. Depending on the parser type, it deserializes the corresponding
+    /// fields into a `Regex`, `Json` or `Path` variant of the `ClaimMapping` enum.
     ///
     /// # Errors
     /// Returns a deserialization error if:
     /// - The `parser` field is missing.
     /// - The `type` field is missing.
     /// - The `regex_expression` field is missing for `regex` type.
+    /// - Neither `path` nor any entry in `fields` is given for `jsonpath` type.
     /// - The parser type is unrecognized.
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -211,6 +251,8 @@ impl<'de> Deserialize<'de> for ClaimMapping {
         const PARSER_KEY: &str = "parser";
         const TYPE_KEY: &str = "type";
         const REGEX_EXPRESSION_KEY: &str = "regex_expression";
+        const PATH_KEY: &str = "path";
+        const FIELDS_KEY: &str = "fields";
 
         let value = Value::deserialize(deserializer)?;
 
@@ -248,6 +290,35 @@ impl<'de> Deserialize<'de> for ClaimMapping {
                 }))
             },
             "json" => Ok(ClaimMapping::Json),
+            "jsonpath" => {
+                let path = value
+                    .get(PATH_KEY)
+                    .map(|v| ClaimPath::deserialize(v).map_err(de::Error::custom))
+                    .transpose()?;
+
+                let mut fields = HashMap::new();
+                if let Some(value) = value.get(FIELDS_KEY) {
+                    let obj = value.as_object().ok_or_else(|| {
+                        de::Error::custom(format!("`{FIELDS_KEY}` must be an object"))
+                    })?;
+                    for (key, val) in obj.iter() {
+                        let field = ClaimPath::deserialize(val).map_err(|err| {
+                            de::Error::custom(format!(
+                                "could not parse path for field '{key}': {err}"
+                            ))
+                        })?;
+                        fields.insert(key.clone(), field);
+                    }
+                }
+
+                if path.is_none() && fields.is_empty() {
+                    return Err(de::Error::custom(
+                        "the `jsonpath` parser requires a `path` or at least one field",
+                    ));
+                }
+
+                Ok(ClaimMapping::Path(PathMapping { path, fields }))
+            },
             _ => Err(de::Error::custom("unknown parser type")),
         }
     }
@@ -451,6 +522,72 @@ mod test {
         );
     }
 
+    /// Tests if a claim mapping with a JSONPath parser can be parsed and applied
+    #[test]
+    fn can_parse_and_apply_jsonpath() {
+        let mapping = serde_json::from_value::<ClaimMapping>(json!({
+            "parser": "jsonpath",
+            "type": "Acme::ResourceAccess",
+            "fields": {
+                "client_roles": "$['my-client'].roles",
+                "admin_groups": "$.groups[?(@.admin == true)].name",
+                "type": "$.type",
+            },
+        }))
+        .expect("should parse claim mapping");
+
+        let claim = json!({
+            "my-client": { "roles": ["editor", "viewer"] },
+            "groups": [
+                { "name": "finance", "admin": true },
+                { "name": "sales", "admin": false },
+            ],
+            "type": "internal",
+        });
+
+        assert_eq!(
+            mapping.apply_mapping_value(&claim),
+            json!({
+                "client_roles": ["editor", "viewer"],
+                "admin_groups": ["finance"],
+                "type": "internal",
+            })
+        );
+
+        let mapping = serde_json::from_value::<ClaimMapping>(json!({
+            "parser": "jsonpath",
+            "type": "Set<String>",
+            "path": "$.roles",
+        }))
+        .expect("should parse claim mapping");
+
+        assert_eq!(
+            mapping.apply_mapping_value(&json!({"roles": ["admin"]})),
+            json!(["admin"])
+        );
+    }
+
+    /// Tests if an error is thrown for an invalid path expression
+    #[test]
+    fn errors_on_invalid_jsonpath() {
+        let parsed = serde_json::from_value::<ClaimMapping>(json!({
+            "parser": "jsonpath",
+            "type": "Acme::Dolphin",
+            "fields": { "roles": "roles[" },
+        }));
+        assert!(
+            matches!(parsed, Err(ref e) if e.to_string().contains("could not parse path for field 'roles'")),
+            "Expected an error when encountering an invalid path: {:?}",
+            parsed
+        );
+
+        let parsed = serde_json::from_value::<ClaimMapping>(json!({
+            "parser": "jsonpath",
+            "type": "Acme::Dolphin",
+        }));
+        assert!(parsed.is_err(), "Expected an error without any path");
+    }
+
     /// Tests if an error is thrown for an unknown parser type
     #[test]
     fn errors_on_unkown_parser_type() {
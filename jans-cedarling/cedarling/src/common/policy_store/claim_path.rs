@@ -0,0 +1,496 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Path expressions used to pick nested values out of JWT claims.
+//!
+//! The supported syntax is a subset of JSONPath:
+//! - `$` refers to the root value
+//! - `.name` or `['name']` selects an object member (use the bracket form for names
+//!   containing `.`, `[` or spaces)
+//! - `[n]` selects an array element, negative indexes count from the end
+//! - `.*` or `[*]` selects every member of an object or every element of an array
+//! - `[?(@.key == 'value')]`, `[?(@.key != 'value')]` and `[?(@.key)]` filter the
+//!   elements of an array (or the members of an object) by a relative path
+//!
+//! For example, Keycloak roles can be selected with `$.realm_access.roles` or
+//! `$.resource_access['my-client'].roles`.
+
+use serde::{Deserialize, de};
+use serde_json::Value;
+use std::fmt::Display;
+
+/// First character of a path expression, the root value.
+const ROOT: char = '$';
+
+/// A parsed path expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaimPath {
+    expression: String,
+    segments: Vec<PathSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    /// Selects an object member by name
+    Member(String),
+    /// Selects an array element by index
+    Index(i64),
+    /// Selects all members of an object or all elements of an array
+    Wildcard,
+    /// Keeps only the children for which the predicate holds
+    Filter(FilterExpr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FilterExpr {
+    /// `@.path` – the relative path selects at least one value
+    Exists(Vec<PathSegment>),
+    /// `@.path == literal`
+    Eq(Vec<PathSegment>, Value),
+    /// `@.path != literal`
+    Ne(Vec<PathSegment>, Value),
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[error("invalid path expression '{expression}' at position {position}: {reason}")]
+pub struct ParseClaimPathError {
+    pub expression: String,
+    pub position: usize,
+    pub reason: String,
+}
+
+impl ClaimPath {
+    /// Parses a path expression such as `$.realm_access.roles`.
+    pub fn parse(expression: &str) -> Result<Self, ParseClaimPathError> {
+        let mut parser = Parser::new(expression);
+        parser.expect_char(ROOT)?;
+        let segments = parser.parse_segments(false)?;
+        if !parser.is_eof() {
+            return Err(parser.error("unexpected trailing characters"));
+        }
+
+        Ok(Self {
+            expression: expression.to_string(),
+            segments,
+        })
+    }
+
+    /// Returns every value selected by the path.
+    pub fn select_all<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        select_segments(&self.segments, value)
+    }
+
+    /// Returns the value selected by the path.
+    ///
+    /// - `None` if nothing matched
+    /// - the matched value if the path does not contain a wildcard or a filter
+    /// - an array of all matched values otherwise
+    pub fn select(&self, value: &Value) -> Option<Value> {
+        let selected = self.select_all(value);
+        if self.is_singular() {
+            return selected.first().map(|v| (*v).clone());
+        }
+        if selected.is_empty() {
+            return None;
+        }
+        Some(Value::Array(selected.into_iter().cloned().collect()))
+    }
+
+    /// Selects a value from the claims of a token where `$` refers to the
+    /// whole claims object.
+    pub fn select_from_claims(
+        &self,
+        claims: &std::collections::HashMap<String, Value>,
+    ) -> Option<Value> {
+        let Some((first, rest)) = self.segments.split_first() else {
+            return Some(Value::Object(
+                claims.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            ));
+        };
+
+        // avoid cloning the whole claims object when the path starts with a
+        // member name, which is by far the most common case
+        let roots: Vec<&Value> = match first {
+            PathSegment::Member(name) => claims.get(name).into_iter().collect(),
+            PathSegment::Wildcard => claims.values().collect(),
+            _ => return None,
+        };
+
+        let selected: Vec<&Value> = roots
+            .into_iter()
+            .flat_map(|root| select_segments(rest, root))
+            .collect();
+
+        if self.is_singular() {
+            return selected.first().map(|v| (*v).clone());
+        }
+        if selected.is_empty() {
+            return None;
+        }
+        Some(Value::Array(selected.into_iter().cloned().collect()))
+    }
+
+    /// Returns `true` if the path can select at most one value.
+    fn is_singular(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|s| matches!(s, PathSegment::Member(_) | PathSegment::Index(_)))
+    }
+}
+
+impl Display for ClaimPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl<'de> Deserialize<'de> for ClaimPath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let expression = String::deserialize(deserializer)?;
+        ClaimPath::parse(&expression).map_err(de::Error::custom)
+    }
+}
+
+fn select_segments<'a>(segments: &[PathSegment], value: &'a Value) -> Vec<&'a Value> {
+    let mut current = vec![value];
+
+    for segment in segments {
+        let mut next = Vec::new();
+        for value in current {
+            match segment {
+                PathSegment::Member(name) => {
+                    if let Some(v) = value.get(name.as_str()) {
+                        next.push(v);
+                    }
+                },
+                PathSegment::Index(idx) => {
+                    if let Some(arr) = value.as_array() {
+                        let idx = if *idx < 0 {
+                            arr.len() as i64 + idx
+                        } else {
+                            *idx
+                        };
+                        if let Some(v) = usize::try_from(idx).ok().and_then(|i| arr.get(i)) {
+                            next.push(v);
+                        }
+                    }
+                },
+                PathSegment::Wildcard => next.extend(children(value)),
+                PathSegment::Filter(filter) => {
+                    next.extend(children(value).filter(|child| filter.matches(child)))
+                },
+            }
+        }
+        current = next;
+    }
+
+    current
+}
+
+fn children(value: &Value) -> Box<dyn Iterator<Item = &Value> + '_> {
+    match value {
+        Value::Array(values) => Box::new(values.iter()),
+        Value::Object(map) => Box::new(map.values()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+impl FilterExpr {
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            FilterExpr::Exists(path) => !select_segments(path, value).is_empty(),
+            FilterExpr::Eq(path, expected) => select_segments(path, value)
+                .into_iter()
+                .any(|v| v == expected),
+            FilterExpr::Ne(path, expected) => select_segments(path, value)
+                .into_iter()
+                .all(|v| v != expected),
+        }
+    }
+}
+
+/// Simple recursive descent parser for path expressions.
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn error(&self, reason: &str) -> ParseClaimPathError {
+        ParseClaimPathError {
+            expression: self.src.to_string(),
+            position: self.pos,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos >= self.src.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn expect_char(&mut self, expected: char) -> Result<(), ParseClaimPathError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            },
+            _ => Err(self.error(&format!("expected '{expected}'"))),
+        }
+    }
+
+    /// Parses segments until the end of the input, or until a character that
+    /// can't start a segment when parsing a relative path inside a filter.
+    fn parse_segments(&mut self, relative: bool) -> Result<Vec<PathSegment>, ParseClaimPathError> {
+        let mut segments = Vec::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                '.' => {
+                    self.bump();
+                    if self.peek() == Some('*') {
+                        self.bump();
+                        segments.push(PathSegment::Wildcard);
+                    } else {
+                        segments.push(PathSegment::Member(self.parse_name()?));
+                    }
+                },
+                '[' => {
+                    self.bump();
+                    segments.push(self.parse_bracket()?);
+                },
+                _ if relative => break,
+                _ => return Err(self.error("expected '.' or '['")),
+            }
+        }
+
+        Ok(segments)
+    }
+
+    fn parse_name(&mut self) -> Result<String, ParseClaimPathError> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if matches!(c, '.' | '[' | ']' | ')' | '=' | '!') || c.is_whitespace() {
+                break;
+            }
+            self.bump();
+        }
+        if start == self.pos {
+            return Err(self.error("expected a member name"));
+        }
+        Ok(self.src[start..self.pos].to_string())
+    }
+
+    fn parse_bracket(&mut self) -> Result<PathSegment, ParseClaimPathError> {
+        self.skip_whitespace();
+        let segment = match self.peek() {
+            Some('*') => {
+                self.bump();
+                PathSegment::Wildcard
+            },
+            Some('\'') | Some('"') => PathSegment::Member(self.parse_quoted()?),
+            Some('?') => {
+                self.bump();
+                PathSegment::Filter(self.parse_filter()?)
+            },
+            Some(c) if c == '-' || c.is_ascii_digit() => PathSegment::Index(self.parse_index()?),
+            _ => return Err(self.error("expected an index, a quoted name, '*' or a filter")),
+        };
+        self.skip_whitespace();
+        self.expect_char(']')?;
+        Ok(segment)
+    }
+
+    fn parse_quoted(&mut self) -> Result<String, ParseClaimPathError> {
+        let quote = self.bump().expect("checked by the caller");
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                Some('\\') => match self.bump() {
+                    Some(c) => out.push(c),
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some(c) if c == quote => return Ok(out),
+                Some(c) => out.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn parse_index(&mut self) -> Result<i64, ParseClaimPathError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.bump();
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        self.src[start..self.pos]
+            .parse()
+            .map_err(|_| self.error("invalid array index"))
+    }
+
+    fn parse_filter(&mut self) -> Result<FilterExpr, ParseClaimPathError> {
+        self.skip_whitespace();
+        self.expect_char('(')?;
+        self.skip_whitespace();
+        self.expect_char('@')?;
+        let path = self.parse_segments(true)?;
+        self.skip_whitespace();
+
+        let filter = match self.peek() {
+            Some(')') => FilterExpr::Exists(path),
+            Some('=') | Some('!') => {
+                let negate = self.bump() == Some('!');
+                self.expect_char('=')?;
+                self.skip_whitespace();
+                let literal = self.parse_literal()?;
+                if negate {
+                    FilterExpr::Ne(path, literal)
+                } else {
+                    FilterExpr::Eq(path, literal)
+                }
+            },
+            _ => return Err(self.error("expected '==', '!=' or ')'")),
+        };
+
+        self.skip_whitespace();
+        self.expect_char(')')?;
+        Ok(filter)
+    }
+
+    fn parse_literal(&mut self) -> Result<Value, ParseClaimPathError> {
+        if matches!(self.peek(), Some('\'') | Some('"')) {
+            return Ok(Value::String(self.parse_quoted()?));
+        }
+
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !c.is_whitespace() && c != ')') {
+            self.bump();
+        }
+        serde_json::from_str::<Value>(&self.src[start..self.pos])
+            .ok()
+            .filter(|v| !v.is_object() && !v.is_array())
+            .ok_or_else(|| self.error("expected a string, number, boolean or null literal"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+    use test_utils::assert_eq;
+
+    fn keycloak_token() -> Value {
+        json!({
+            "sub": "some_sub",
+            "realm_access": { "roles": ["admin", "offline_access"] },
+            "resource_access": {
+                "my-client": { "roles": ["editor"] },
+                "account": { "roles": ["view-profile"] },
+            },
+            "groups": [
+                { "name": "finance", "primary": true },
+                { "name": "sales", "primary": false },
+            ],
+        })
+    }
+
+    #[test]
+    fn can_select_nested_members() {
+        let token = keycloak_token();
+
+        let path = ClaimPath::parse("$.realm_access.roles").expect("should parse path");
+        assert_eq!(
+            path.select(&token),
+            Some(json!(["admin", "offline_access"]))
+        );
+
+        let path =
+            ClaimPath::parse("$.resource_access['my-client'].roles").expect("should parse path");
+        assert_eq!(path.select(&token), Some(json!(["editor"])));
+
+        let path =
+            ClaimPath::parse("$.resource_access.my-client.roles[0]").expect("should parse path");
+        assert_eq!(path.select(&token), Some(json!("editor")));
+
+        let path = ClaimPath::parse("$.realm_access.roles[-1]").expect("should parse path");
+        assert_eq!(path.select(&token), Some(json!("offline_access")));
+
+        let path = ClaimPath::parse("$.missing.roles").expect("should parse path");
+        assert_eq!(path.select(&token), None);
+    }
+
+    #[test]
+    fn can_select_with_wildcards_and_filters() {
+        let token = keycloak_token();
+
+        let path = ClaimPath::parse("$.resource_access.*.roles[*]").expect("should parse path");
+        let mut selected = path
+            .select_all(&token)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        selected.sort_by_key(|v| v.to_string());
+        assert_eq!(selected, vec![json!("editor"), json!("view-profile")]);
+
+        let path =
+            ClaimPath::parse("$.groups[?(@.primary == true)].name").expect("should parse path");
+        assert_eq!(path.select(&token), Some(json!(["finance"])));
+
+        let path =
+            ClaimPath::parse("$.groups[?(@.name != 'finance')].name").expect("should parse path");
+        assert_eq!(path.select(&token), Some(json!(["sales"])));
+
+        let path = ClaimPath::parse("$.groups[?(@.missing)].name").expect("should parse path");
+        assert_eq!(path.select(&token), None);
+    }
+
+    #[test]
+    fn can_select_from_claims() {
+        let claims = serde_json::from_value(keycloak_token()).expect("valid claims");
+        let path = ClaimPath::parse("$.realm_access.roles[0]").expect("should parse path");
+        assert_eq!(path.select_from_claims(&claims), Some(json!("admin")));
+    }
+
+    #[test]
+    fn errors_on_invalid_expressions() {
+        for expr in [
+            "realm_access.roles",
+            "$.",
+            "$.roles[",
+            "$.roles['unterminated]",
+            "$.roles[?(@.a ~ 1)]",
+            "$.roles[abc]",
+        ] {
+            assert!(
+                ClaimPath::parse(expr).is_err(),
+                "expected '{expr}' to fail parsing"
+            );
+        }
+    }
+}
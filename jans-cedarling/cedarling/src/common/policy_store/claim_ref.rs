@@ -0,0 +1,173 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use serde::{Deserialize, de};
+use serde_json::Value;
+
+use super::claim_path::ClaimPath;
+
+/// Reference to the claim of a token.
+///
+/// It is either the name of a top-level claim, given as a string, or a path
+/// expression selecting a nested value, given as an object with a `path` key:
+///
+/// ```json
+/// { "path": "$.resource_access['my-client'].roles" }
+/// ```
+///
+/// Path expressions are parsed when the policy store is loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaimRef(ClaimRefKind);
+
+#[derive(Debug, Clone, PartialEq)]
+enum ClaimRefKind {
+    Name(String),
+    Path(ClaimPath),
+}
+
+impl ClaimRef {
+    /// Returns the name of the claim, or `None` for a path expression.
+    pub(crate) fn name(&self) -> Option<&str> {
+        match &self.0 {
+            ClaimRefKind::Name(name) => Some(name),
+            ClaimRefKind::Path(_) => None,
+        }
+    }
+
+    /// Returns the path expression, or `None` for a claim name.
+    pub(crate) fn path(&self) -> Option<&ClaimPath> {
+        match &self.0 {
+            ClaimRefKind::Name(_) => None,
+            ClaimRefKind::Path(path) => Some(path),
+        }
+    }
+
+    /// Gets the value of the referenced claim from the given claims.
+    pub(crate) fn select_from_claims(&self, claims: &HashMap<String, Value>) -> Option<Value> {
+        match &self.0 {
+            ClaimRefKind::Name(name) => claims.get(name).cloned(),
+            ClaimRefKind::Path(path) => path.select_from_claims(claims),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.name().is_some_and(str::is_empty)
+    }
+}
+
+impl From<&str> for ClaimRef {
+    fn from(name: &str) -> Self {
+        Self(ClaimRefKind::Name(name.to_string()))
+    }
+}
+
+impl From<String> for ClaimRef {
+    fn from(name: String) -> Self {
+        Self(ClaimRefKind::Name(name))
+    }
+}
+
+impl Display for ClaimRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            ClaimRefKind::Name(name) => f.write_str(name),
+            ClaimRefKind::Path(path) => path.fmt(f),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ClaimRef {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawClaimRef {
+            Name(String),
+            Path(RawClaimPath),
+        }
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct RawClaimPath {
+            path: String,
+        }
+
+        match RawClaimRef::deserialize(deserializer).map_err(|_| {
+            de::Error::custom("expected a claim name or an object with a `path` expression")
+        })? {
+            RawClaimRef::Name(name) => Ok(Self(ClaimRefKind::Name(name))),
+            RawClaimRef::Path(RawClaimPath { path }) => ClaimPath::parse(&path)
+                .map(|path| Self(ClaimRefKind::Path(path)))
+                .map_err(de::Error::custom),
+        }
+    }
+}
+
+/// Custom parser for an optional claim reference which returns `None` if the claim
+/// name is empty.
+pub fn parse_option_claim_ref<'de, D>(deserializer: D) -> Result<Option<ClaimRef>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let value = Option::<ClaimRef>::deserialize(deserializer)?;
+
+    Ok(value.filter(|claim| !claim.is_empty()))
+}
+
+/// Custom parser for a required claim reference.
+pub fn parse_claim_ref<'de, D>(deserializer: D) -> Result<ClaimRef, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let value = ClaimRef::deserialize(deserializer)?;
+
+    if value.is_empty() {
+        return Err(de::Error::custom("the claim cannot be an empty string"));
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_claim_names_and_paths() {
+        let claim = serde_json::from_value::<ClaimRef>(json!("role")).unwrap();
+        assert_eq!(claim.name(), Some("role"));
+
+        // a string is always a claim name, even if it looks like a path expression
+        let claim = serde_json::from_value::<ClaimRef>(json!("$.role")).unwrap();
+        assert_eq!(claim.name(), Some("$.role"));
+
+        let claim =
+            serde_json::from_value::<ClaimRef>(json!({"path": "$.realm_access.roles"})).unwrap();
+        assert_eq!(claim.name(), None);
+        assert_eq!(
+            claim.path().map(ToString::to_string).as_deref(),
+            Some("$.realm_access.roles")
+        );
+    }
+
+    #[test]
+    fn errors_on_invalid_paths() {
+        let err = serde_json::from_value::<ClaimRef>(json!({"path": "$.realm_access["}))
+            .expect_err("should not parse an invalid path expression");
+        assert!(
+            err.to_string().contains("invalid path expression"),
+            "unexpected error: {err}"
+        );
+
+        serde_json::from_value::<ClaimRef>(json!({"path": "$.role", "claim": "role"}))
+            .expect_err("should not accept unknown keys");
+    }
+}
@@ -0,0 +1,196 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use super::claim_ref::{ClaimRef, parse_claim_ref};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, de};
+
+/// Name of the regex capture group used as the entity ID.
+const ID_CAPTURE_GROUP: &str = "id";
+
+/// Maps the values of a token claim to Cedar entities which become parents of a
+/// principal.
+///
+/// For example, the following makes every value of the `groups` claim a
+/// `Jans::Group` entity and the `User` a member of it:
+///
+/// ```json
+/// {
+///     "claim": "groups",
+///     "entity_type": "Group",
+///     "id_regex": "^cn=(?P<id>[^,]+)",
+///     "parent_of": "user"
+/// }
+/// ```
+///
+/// The `role_mapping` of a token is the same as a membership mapping of its claim
+/// to the `Role` entity with `"parent_of": "user"`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MembershipMapping {
+    /// The claim holding the entity IDs, see [`ClaimRef`].
+    #[serde(deserialize_with = "parse_claim_ref")]
+    pub claim: ClaimRef,
+    /// The Cedar entity type of the created entities.
+    ///
+    /// If the type is not qualified with a namespace, `namespace` or the namespace
+    /// of the policy store will be prepended.
+    pub entity_type: String,
+    /// Namespace used if `entity_type` is not qualified.
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Regex used to extract the entity ID from each claim value.
+    ///
+    /// The `id` capture group is used if present, otherwise the whole match.
+    /// Values which don't match are skipped.
+    #[serde(default)]
+    pub id_regex: Option<IdRegex>,
+    /// The principal that the created entities are parents of.
+    #[serde(default)]
+    pub parent_of: MembershipTarget,
+}
+
+/// The principal that membership entities become parents of.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MembershipTarget {
+    #[default]
+    User,
+    Workload,
+}
+
+impl MembershipMapping {
+    /// Returns the membership mapping equivalent to the `role_mapping` of a token.
+    ///
+    /// The role entity type name is used as is, even if it is not qualified.
+    pub fn role(claim: ClaimRef, role_type_name: &str) -> Self {
+        Self {
+            claim,
+            entity_type: role_type_name.to_string(),
+            // an empty namespace keeps the type name from being qualified
+            namespace: Some(String::new()),
+            id_regex: None,
+            parent_of: MembershipTarget::User,
+        }
+    }
+
+    /// Returns the fully qualified entity type name.
+    pub fn entity_type_name(&self, default_namespace: Option<&str>) -> String {
+        if self.entity_type.contains("::") {
+            return self.entity_type.clone();
+        }
+
+        match self
+            .namespace
+            .as_deref()
+            .or(default_namespace)
+            .filter(|ns| !ns.is_empty())
+        {
+            Some(namespace) => format!("{}::{}", namespace, self.entity_type),
+            None => self.entity_type.clone(),
+        }
+    }
+
+    /// Applies the `id_regex` to a claim value to get the entity ID.
+    ///
+    /// Returns `None` if the value doesn't match.
+    pub fn entity_id<'a>(&self, value: &'a str) -> Option<&'a str> {
+        let Some(regex) = &self.id_regex else {
+            return Some(value).filter(|v| !v.is_empty());
+        };
+
+        let captures = regex.0.captures(value)?;
+        captures
+            .name(ID_CAPTURE_GROUP)
+            .or_else(|| captures.get(0))
+            .map(|m| m.as_str())
+            .filter(|id| !id.is_empty())
+    }
+}
+
+/// Wrapper around [`Regex`] to be able to compare mappings.
+#[derive(Debug, Clone)]
+pub struct IdRegex(Regex);
+
+impl PartialEq for IdRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl<'de> Deserialize<'de> for IdRegex {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let src = String::deserialize(deserializer)?;
+        let regex = Regex::new(&src).map_err(|err| {
+            de::Error::custom(format!(
+                "could not parse `id_regex` as regular expression: {err}"
+            ))
+        })?;
+        Ok(Self(regex))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn can_parse_membership_mapping() {
+        let mapping = serde_json::from_value::<MembershipMapping>(json!({
+            "claim": "memberOf",
+            "entity_type": "Group",
+            "id_regex": "^cn=(?P<id>[^,]+)",
+            "parent_of": "workload",
+        }))
+        .expect("should parse membership mapping");
+
+        assert_eq!(mapping.parent_of, MembershipTarget::Workload);
+        assert_eq!(mapping.entity_type_name(Some("Jans")), "Jans::Group");
+        assert_eq!(
+            mapping.entity_id("cn=finance,ou=groups,dc=jans,dc=io"),
+            Some("finance")
+        );
+        assert_eq!(mapping.entity_id("ou=groups"), None);
+
+        let mapping = serde_json::from_value::<MembershipMapping>(json!({
+            "claim": {"path": "$.realm_access.groups"},
+            "entity_type": "Group",
+            "namespace": "Acme",
+        }))
+        .expect("should parse membership mapping");
+
+        assert_eq!(mapping.parent_of, MembershipTarget::User);
+        assert_eq!(mapping.entity_type_name(Some("Jans")), "Acme::Group");
+        assert_eq!(mapping.entity_id("finance"), Some("finance"));
+        assert_eq!(mapping.entity_id(""), None);
+    }
+
+    #[test]
+    fn errors_on_invalid_membership_mapping() {
+        let err = serde_json::from_value::<MembershipMapping>(json!({
+            "claim": "groups",
+            "entity_type": "Group",
+            "id_regex": "^cn=(",
+        }))
+        .expect_err("should fail to parse an invalid regex");
+        assert!(
+            err.to_string().contains("could not parse `id_regex`"),
+            "unexpected error: {err}"
+        );
+
+        let err = serde_json::from_value::<MembershipMapping>(json!({
+            "claim": {"path": "$.groups["},
+            "entity_type": "Group",
+        }))
+        .expect_err("should fail to parse an invalid path expression");
+        assert!(
+            err.to_string().contains("invalid path expression"),
+            "unexpected error: {err}"
+        );
+    }
+}
@@ -0,0 +1,369 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! # Policy index
+//! Partitions the policies by the action, principal type and resource type of their scope,
+//! so a request is evaluated only against the policies whose scope can match it.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use cedar_policy::{
+    ActionConstraint, EntityTypeName, EntityUid, Policy, PolicyId, PolicySet, PrincipalConstraint,
+    ResourceConstraint,
+};
+
+const SUBSETS_LOCK_EXPECT_MESSAGE: &str = "policy index lock should not be poisoned";
+
+/// Entity types a policy can apply to, taken from its scope.
+///
+/// `None` means the policy applies to any type, as with `principal` or `principal in ...`.
+#[derive(Debug)]
+struct PolicyScope {
+    id: PolicyId,
+    principal_type: Option<EntityTypeName>,
+    resource_type: Option<EntityTypeName>,
+}
+
+impl PolicyScope {
+    fn new(policy: &Policy) -> Self {
+        let principal_type = match policy.principal_constraint() {
+            PrincipalConstraint::Eq(uid) => Some(uid.type_name().clone()),
+            PrincipalConstraint::Is(type_name) | PrincipalConstraint::IsIn(type_name, _) => {
+                Some(type_name)
+            },
+            PrincipalConstraint::Any | PrincipalConstraint::In(_) => None,
+        };
+        let resource_type = match policy.resource_constraint() {
+            ResourceConstraint::Eq(uid) => Some(uid.type_name().clone()),
+            ResourceConstraint::Is(type_name) | ResourceConstraint::IsIn(type_name, _) => {
+                Some(type_name)
+            },
+            ResourceConstraint::Any | ResourceConstraint::In(_) => None,
+        };
+
+        Self {
+            id: policy.id().clone(),
+            principal_type,
+            resource_type,
+        }
+    }
+
+    fn matches(&self, principal_type: &EntityTypeName, resource_type: &EntityTypeName) -> bool {
+        self.principal_type
+            .as_ref()
+            .is_none_or(|type_name| type_name == principal_type)
+            && self
+                .resource_type
+                .as_ref()
+                .is_none_or(|type_name| type_name == resource_type)
+    }
+}
+
+type SubsetKey = (EntityTypeName, EntityUid, EntityTypeName);
+
+/// Index of the policies of a [`PolicySet`] by the action of their scope.
+///
+/// The policy sets of each principal type, action and resource type are built
+/// on the first request that needs them and are reused afterwards.
+pub(crate) struct PolicyIndex {
+    /// Policies that apply to any action
+    any_action: Vec<PolicyScope>,
+    /// Policies by the action, or the action group, of their scope
+    by_action: HashMap<EntityUid, Vec<PolicyScope>>,
+    subsets: RwLock<HashMap<SubsetKey, Arc<PolicySet>>>,
+}
+
+impl PolicyIndex {
+    pub fn new(policy_set: &PolicySet) -> Self {
+        let mut any_action = Vec::new();
+        let mut by_action = HashMap::<EntityUid, Vec<PolicyScope>>::new();
+
+        for policy in policy_set.policies() {
+            match policy.action_constraint() {
+                ActionConstraint::Any => any_action.push(PolicyScope::new(policy)),
+                ActionConstraint::Eq(action) => {
+                    by_action
+                        .entry(action)
+                        .or_default()
+                        .push(PolicyScope::new(policy));
+                },
+                ActionConstraint::In(actions) => {
+                    for action in actions {
+                        by_action
+                            .entry(action)
+                            .or_default()
+                            .push(PolicyScope::new(policy));
+                    }
+                },
+            }
+        }
+
+        Self {
+            any_action,
+            by_action,
+            subsets: Default::default(),
+        }
+    }
+
+    /// Returns the policies of `policy_set` whose scope can match a request with the
+    /// given principal type, action and resource type.
+    ///
+    /// `action_groups` returns the action groups the action is a member of, it is only
+    /// called the first time the policies of the request are looked up.
+    pub fn policies_for(
+        &self,
+        policy_set: &PolicySet,
+        principal_type: &EntityTypeName,
+        action: &EntityUid,
+        resource_type: &EntityTypeName,
+        action_groups: impl FnOnce() -> Vec<EntityUid>,
+    ) -> Arc<PolicySet> {
+        let key = (
+            principal_type.clone(),
+            action.clone(),
+            resource_type.clone(),
+        );
+        if let Some(subset) = self
+            .subsets
+            .read()
+            .expect(SUBSETS_LOCK_EXPECT_MESSAGE)
+            .get(&key)
+        {
+            return subset.clone();
+        }
+
+        let actions = std::iter::once(action.clone()).chain(action_groups());
+        let mut ids = HashSet::new();
+        let scopes = actions
+            .filter_map(|action| self.by_action.get(&action))
+            .flatten()
+            .chain(self.any_action.iter())
+            .filter(|scope| scope.matches(principal_type, resource_type))
+            .filter(|scope| ids.insert(&scope.id));
+
+        // the whole policy set is used if the subset cannot be built
+        let subset = Arc::new(
+            build_subset(policy_set, scopes.map(|scope| &scope.id))
+                .unwrap_or_else(|| policy_set.clone()),
+        );
+
+        self.subsets
+            .write()
+            .expect(SUBSETS_LOCK_EXPECT_MESSAGE)
+            .insert(key, subset.clone());
+        subset
+    }
+}
+
+/// Builds a policy set with the given policies of `policy_set`, including the templates
+/// of the linked policies. Returns `None` if cedar rejects the subset.
+fn build_subset<'a>(
+    policy_set: &PolicySet,
+    ids: impl Iterator<Item = &'a PolicyId>,
+) -> Option<PolicySet> {
+    let mut subset = PolicySet::new();
+    for id in ids {
+        let Some(policy) = policy_set.policy(id) else {
+            continue;
+        };
+
+        match (policy.template_id(), policy.template_links()) {
+            (Some(template_id), Some(values)) => {
+                if subset.template(template_id).is_none()
+                    && let Some(template) = policy_set.template(template_id)
+                {
+                    subset.add_template(template.clone()).ok()?;
+                }
+                subset.link(template_id.clone(), id.clone(), values).ok()?;
+            },
+            _ => subset.add(policy.clone()).ok()?,
+        }
+    }
+    Some(subset)
+}
+
+impl std::fmt::Debug for PolicyIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PolicyIndex")
+            .field("any_action", &self.any_action)
+            .field("by_action", &self.by_action)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use cedar_policy::{Authorizer, Context, Entities, Request, SlotId, Template};
+
+    use super::*;
+
+    const POLICIES: [(&str, &str); 5] = [
+        (
+            "read_issue",
+            r#"permit(principal is Jans::User, action == Jans::Action::"Read", resource is Jans::Issue);"#,
+        ),
+        (
+            "write_issue",
+            r#"permit(
+                principal is Jans::User,
+                action in [Jans::Action::"Update", Jans::Action::"Delete"],
+                resource == Jans::Issue::"issue_1"
+            );"#,
+        ),
+        (
+            "admin",
+            r#"permit(principal == Jans::Workload::"admin", action, resource);"#,
+        ),
+        (
+            "forbid_document",
+            r#"forbid(principal, action == Jans::Action::"Read", resource is Jans::Document);"#,
+        ),
+        (
+            "group",
+            r#"permit(principal in Jans::Role::"admin", action in Jans::Action::"Manage", resource);"#,
+        ),
+    ];
+
+    fn uid(uid: &str) -> EntityUid {
+        EntityUid::from_str(uid).unwrap()
+    }
+
+    fn type_name(type_name: &str) -> EntityTypeName {
+        EntityTypeName::from_str(type_name).unwrap()
+    }
+
+    fn policy_ids(policy_set: &PolicySet) -> Vec<String> {
+        let mut ids = policy_set
+            .policies()
+            .map(|policy| policy.id().to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    fn policy_set() -> PolicySet {
+        let policies = POLICIES
+            .iter()
+            .map(|(id, src)| Policy::parse(Some(PolicyId::new(id)), *src).unwrap());
+        let mut policy_set = PolicySet::from_policies(policies).unwrap();
+        let template = Template::parse(
+            Some(PolicyId::new("owner")),
+            "permit(principal == ?principal, action == Jans::Action::\"Delete\", resource);",
+        )
+        .unwrap();
+        policy_set.add_template(template).unwrap();
+        policy_set
+            .link(
+                PolicyId::new("owner"),
+                PolicyId::new("owner_user_1"),
+                HashMap::from([(SlotId::principal(), uid("Jans::User::\"user_1\""))]),
+            )
+            .unwrap();
+        policy_set
+    }
+
+    #[test]
+    fn selects_the_policies_of_the_request() {
+        let policy_set = policy_set();
+        let index = PolicyIndex::new(&policy_set);
+        let policies_for = |principal, action, resource, groups: Vec<&str>| {
+            let subset = index.policies_for(
+                &policy_set,
+                &type_name(principal),
+                &uid(action),
+                &type_name(resource),
+                || groups.into_iter().map(uid).collect(),
+            );
+            policy_ids(&subset)
+        };
+
+        assert_eq!(
+            policies_for(
+                "Jans::User",
+                "Jans::Action::\"Read\"",
+                "Jans::Issue",
+                vec![]
+            ),
+            vec!["read_issue"]
+        );
+        assert_eq!(
+            policies_for(
+                "Jans::User",
+                "Jans::Action::\"Delete\"",
+                "Jans::Issue",
+                vec![]
+            ),
+            vec!["owner_user_1", "write_issue"]
+        );
+        assert_eq!(
+            policies_for(
+                "Jans::Workload",
+                "Jans::Action::\"Read\"",
+                "Jans::Document",
+                vec![]
+            ),
+            vec!["admin", "forbid_document"]
+        );
+        assert_eq!(
+            policies_for(
+                "Jans::User",
+                "Jans::Action::\"Archive\"",
+                "Jans::Document",
+                vec!["Jans::Action::\"Manage\""]
+            ),
+            vec!["group"]
+        );
+    }
+
+    #[test]
+    fn gives_the_same_decisions_as_the_policy_set() {
+        let policy_set = policy_set();
+        let index = PolicyIndex::new(&policy_set);
+        let authorizer = Authorizer::new();
+
+        let principals = [
+            "Jans::User::\"user_1\"",
+            "Jans::User::\"user_2\"",
+            "Jans::Workload::\"admin\"",
+        ];
+        let actions = [
+            "Jans::Action::\"Read\"",
+            "Jans::Action::\"Update\"",
+            "Jans::Action::\"Delete\"",
+        ];
+        let resources = ["Jans::Issue::\"issue_1\"", "Jans::Document::\"doc_1\""];
+
+        for principal in principals {
+            for action in actions {
+                for resource in resources {
+                    let (principal, action, resource) =
+                        (uid(principal), uid(action), uid(resource));
+                    let subset = index.policies_for(
+                        &policy_set,
+                        principal.type_name(),
+                        &action,
+                        resource.type_name(),
+                        Vec::new,
+                    );
+                    let request =
+                        Request::new(principal, action, resource, Context::empty(), None).unwrap();
+
+                    let expected =
+                        authorizer.is_authorized(&request, &policy_set, &Entities::empty());
+                    let response = authorizer.is_authorized(&request, &subset, &Entities::empty());
+                    assert_eq!(response.decision(), expected.decision(), "{request}");
+                    assert_eq!(
+                        response.diagnostics().reason().collect::<HashSet<_>>(),
+                        expected.diagnostics().reason().collect::<HashSet<_>>(),
+                        "{request}"
+                    );
+                }
+            }
+        }
+    }
+}
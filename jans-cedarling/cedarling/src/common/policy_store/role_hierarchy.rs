@@ -0,0 +1,211 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use serde::{Deserialize, Deserializer, de};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Graph of role memberships declared in the policy store.
+///
+/// Each key is a role ID and the value is the list of role IDs it is a member of.
+/// For example `{"admin": ["editor"], "editor": ["viewer"]}` makes
+/// `Role::"admin"` a member of `Role::"editor"`, which is a member of `Role::"viewer"`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RoleHierarchy(HashMap<String, BTreeSet<String>>);
+
+/// The role hierarchy contains a cycle, the roles of the cycle are listed in order.
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[error("the role hierarchy contains a cycle: {}", .0.join(" -> "))]
+pub struct RoleHierarchyCycleError(pub Vec<String>);
+
+impl RoleHierarchy {
+    /// Returns `true` if no role has a parent.
+    pub fn is_empty(&self) -> bool {
+        self.0.values().all(|parents| parents.is_empty())
+    }
+
+    /// Adds an edge which makes `role` a member of `parent`.
+    pub fn add_parent(&mut self, role: &str, parent: &str) {
+        self.0
+            .entry(role.to_string())
+            .or_default()
+            .insert(parent.to_string());
+    }
+
+    /// Merges the edges of another hierarchy into this one.
+    pub fn extend(&mut self, other: &RoleHierarchy) {
+        for (role, parents) in other.0.iter() {
+            for parent in parents {
+                self.add_parent(role, parent);
+            }
+        }
+    }
+
+    /// Returns the direct parents of a role.
+    pub fn parents(&self, role: &str) -> impl Iterator<Item = &str> {
+        self.0
+            .get(role)
+            .into_iter()
+            .flat_map(|parents| parents.iter().map(String::as_str))
+    }
+
+    /// Returns every role the given role is transitively a member of, not
+    /// including the role itself.
+    pub fn ancestors(&self, role: &str) -> BTreeSet<&str> {
+        let mut ancestors = BTreeSet::new();
+        let mut stack = self.parents(role).collect::<Vec<_>>();
+        while let Some(parent) = stack.pop() {
+            if ancestors.insert(parent) {
+                stack.extend(self.parents(parent));
+            }
+        }
+        ancestors
+    }
+
+    /// Checks that the hierarchy is acyclic.
+    ///
+    /// Returns the first cycle found as the list of role IDs that form it.
+    pub fn validate(&self) -> Result<(), RoleHierarchyCycleError> {
+        let mut finished = HashSet::new();
+
+        // sort the roles so the reported cycle is deterministic
+        let mut roles = self.0.keys().map(String::as_str).collect::<Vec<_>>();
+        roles.sort_unstable();
+
+        for role in roles {
+            let mut path = Vec::new();
+            self.visit(role, &mut path, &mut finished)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit<'a>(
+        &'a self,
+        role: &'a str,
+        path: &mut Vec<&'a str>,
+        finished: &mut HashSet<&'a str>,
+    ) -> Result<(), RoleHierarchyCycleError> {
+        if finished.contains(role) {
+            return Ok(());
+        }
+
+        if let Some(start) = path.iter().position(|r| *r == role) {
+            let mut cycle = path[start..]
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<_>>();
+            cycle.push(role.to_string());
+            return Err(RoleHierarchyCycleError(cycle));
+        }
+
+        path.push(role);
+        for parent in self.parents(role) {
+            self.visit(parent, path, finished)?;
+        }
+        path.pop();
+        finished.insert(role);
+
+        Ok(())
+    }
+}
+
+impl From<HashMap<String, BTreeSet<String>>> for RoleHierarchy {
+    fn from(graph: HashMap<String, BTreeSet<String>>) -> Self {
+        Self(graph)
+    }
+}
+
+impl<'de> Deserialize<'de> for RoleHierarchy {
+    /// Deserializes and validates the role hierarchy.
+    ///
+    /// Each role's parents can be given as a single role ID or a list of role IDs.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Parents {
+            One(String),
+            Many(BTreeSet<String>),
+        }
+
+        let raw = HashMap::<String, Parents>::deserialize(deserializer)?;
+        let graph = raw
+            .into_iter()
+            .map(|(role, parents)| {
+                let parents = match parents {
+                    Parents::One(parent) => BTreeSet::from([parent]),
+                    Parents::Many(parents) => parents,
+                };
+                (role, parents)
+            })
+            .collect::<HashMap<_, _>>();
+
+        if let Some((role, _)) = graph.iter().find(|(role, parents)| {
+            role.trim().is_empty() || parents.iter().any(|p| p.trim().is_empty())
+        }) {
+            return Err(de::Error::custom(format!(
+                "role IDs in the role hierarchy cannot be empty (found in the entry for '{role}')"
+            )));
+        }
+
+        let hierarchy = RoleHierarchy(graph);
+        hierarchy.validate().map_err(de::Error::custom)?;
+
+        Ok(hierarchy)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn can_parse_role_hierarchy() {
+        let hierarchy = serde_json::from_value::<RoleHierarchy>(json!({
+            "admin": ["editor", "auditor"],
+            "editor": "viewer",
+        }))
+        .expect("should parse role hierarchy");
+
+        assert_eq!(
+            hierarchy.parents("admin").collect::<Vec<_>>(),
+            vec!["auditor", "editor"]
+        );
+        assert_eq!(
+            hierarchy.ancestors("admin"),
+            BTreeSet::from(["auditor", "editor", "viewer"])
+        );
+        assert_eq!(hierarchy.ancestors("viewer"), BTreeSet::new());
+    }
+
+    #[test]
+    fn errors_on_cycles() {
+        let err = serde_json::from_value::<RoleHierarchy>(json!({
+            "admin": ["editor"],
+            "editor": ["viewer"],
+            "viewer": ["admin"],
+        }))
+        .expect_err("should fail to parse a cyclic role hierarchy");
+        assert!(
+            err.to_string().contains(
+                "the role hierarchy contains a cycle: admin -> editor -> viewer -> admin"
+            ),
+            "unexpected error: {err}"
+        );
+
+        let mut hierarchy = RoleHierarchy::default();
+        hierarchy.add_parent("admin", "admin");
+        assert_eq!(
+            hierarchy.validate(),
+            Err(RoleHierarchyCycleError(vec![
+                "admin".to_string(),
+                "admin".to_string()
+            ]))
+        );
+    }
+}
@@ -0,0 +1,136 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use cedar_policy::{EntityUid, PolicySetError, SlotId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Link of a policy template to the values of its slots.
+///
+/// The linked policy gets the ID of the link. For example, linking the template
+/// `permit(principal == ?principal, action, resource == ?resource);` with
+/// `{"?principal": "Jans::User::\"alice\"", "?resource": "Jans::Document::\"42\""}`
+/// creates a policy that lets `alice` access the document `42`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateLink {
+    /// ID of the linked template.
+    pub template_id: String,
+    /// Entity UIDs for the slots of the template, keyed by the slot name:
+    /// `?principal` or `?resource`.
+    pub values: HashMap<String, String>,
+}
+
+impl TemplateLink {
+    /// Parses the slot values to the form expected by [`cedar_policy::PolicySet::link`].
+    pub(crate) fn cedar_values(&self) -> Result<HashMap<SlotId, EntityUid>, TemplateLinkError> {
+        self.values
+            .iter()
+            .map(|(slot, value)| {
+                let slot_id = match slot.as_str() {
+                    "?principal" => SlotId::principal(),
+                    "?resource" => SlotId::resource(),
+                    _ => return Err(TemplateLinkError::UnknownSlot(slot.clone())),
+                };
+                let uid = EntityUid::from_str(value).map_err(|err| {
+                    TemplateLinkError::InvalidSlotValue {
+                        slot: slot.clone(),
+                        value: value.clone(),
+                        err: err.to_string(),
+                    }
+                })?;
+                Ok((slot_id, uid))
+            })
+            .collect()
+    }
+}
+
+/// Errors that can occur when adding or removing a [`TemplateLink`].
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateLinkError {
+    /// The slot name is not `?principal` or `?resource`.
+    #[error("unknown template slot `{0}`, expected `?principal` or `?resource`")]
+    UnknownSlot(String),
+    /// The value of a slot is not a valid entity UID.
+    #[error("invalid entity UID `{value}` for the template slot `{slot}`: {err}")]
+    InvalidSlotValue {
+        /// Name of the slot
+        slot: String,
+        /// Value of the slot
+        value: String,
+        /// Parsing error
+        err: String,
+    },
+    /// Cedar failed to link the template, for example because the template does not
+    /// exist, a slot has no value or the link ID is already used by another policy.
+    #[error("failed to link the template `{template_id}` as `{link_id}`: {err}")]
+    Link {
+        /// ID of the link
+        link_id: String,
+        /// ID of the template
+        template_id: String,
+        /// Error from cedar-policy
+        err: Box<PolicySetError>,
+    },
+    /// There is no template link with the given ID.
+    #[error("there is no template link with the ID `{0}`")]
+    UnknownLink(String),
+    /// Cedar failed to remove the linked policy.
+    #[error("failed to remove the template link `{0}`: {1}")]
+    Unlink(String, Box<PolicySetError>),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_slot_values() {
+        let link = TemplateLink {
+            template_id: "share".to_string(),
+            values: HashMap::from([
+                (
+                    "?principal".to_string(),
+                    "Jans::User::\"alice\"".to_string(),
+                ),
+                (
+                    "?resource".to_string(),
+                    "Jans::Document::\"42\"".to_string(),
+                ),
+            ]),
+        };
+
+        let values = link.cedar_values().expect("slot values should be valid");
+        assert_eq!(
+            values[&SlotId::principal()].to_string(),
+            "Jans::User::\"alice\""
+        );
+        assert_eq!(
+            values[&SlotId::resource()].to_string(),
+            "Jans::Document::\"42\""
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_slots() {
+        let link = TemplateLink {
+            template_id: "share".to_string(),
+            values: HashMap::from([("?action".to_string(), "Jans::Action::\"Read\"".to_string())]),
+        };
+        assert!(matches!(
+            link.cedar_values(),
+            Err(TemplateLinkError::UnknownSlot(slot)) if slot == "?action"
+        ));
+
+        let link = TemplateLink {
+            template_id: "share".to_string(),
+            values: HashMap::from([("?principal".to_string(), "alice".to_string())]),
+        };
+        assert!(matches!(
+            link.cedar_values(),
+            Err(TemplateLinkError::InvalidSlotValue { slot, .. }) if slot == "?principal"
+        ));
+    }
+}
@@ -10,7 +10,8 @@ use serde::Deserialize;
 use serde_json::json;
 use test_utils::assert_eq;
 
-use super::{AgamaPolicyStore, ParsePolicySetMessage, PolicyStore, parse_option_string};
+use super::claim_ref::{ClaimRef, parse_option_claim_ref};
+use super::{AgamaPolicyStore, ParsePolicySetMessage, PolicyStore};
 use crate::common::policy_store::parse_cedar_version;
 
 /// Tests successful deserialization of a valid policy store JSON.
@@ -195,11 +196,11 @@ fn test_invalid_version_format_with_v() {
 }
 
 #[test]
-fn test_parse_option_string() {
+fn test_parse_option_claim_ref() {
     #[derive(Deserialize)]
     struct Data {
-        #[serde(deserialize_with = "parse_option_string", default)]
-        maybe_string: Option<String>,
+        #[serde(deserialize_with = "parse_option_claim_ref", default)]
+        maybe_string: Option<ClaimRef>,
     }
 
     // If key can not be found in the JSON, we expect it to be
@@ -225,7 +226,7 @@ fn test_parse_option_string() {
 
     });
     let deserialized = serde_json::from_value::<Data>(json).expect("Should parse JSON");
-    assert_eq!(deserialized.maybe_string, Some("some_string".to_string()));
+    assert_eq!(deserialized.maybe_string, Some("some_string".into()));
 }
 
 #[test]
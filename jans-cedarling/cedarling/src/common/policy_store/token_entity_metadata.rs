@@ -7,7 +7,8 @@
 use std::collections::{HashMap, HashSet};
 
 use super::claim_mapping::ClaimMappings;
-use super::parse_option_string;
+use super::claim_ref::{ClaimRef, parse_option_claim_ref};
+use super::membership_mapping::MembershipMapping;
 use serde::Deserialize;
 use typed_builder::TypedBuilder;
 
@@ -32,23 +33,29 @@ pub struct TokenEntityMetadata {
     #[serde(default = "default_token_id")]
     #[builder(default = default_token_id())]
     pub token_id: String,
-    /// The claim used to create the user id
-    #[serde(deserialize_with = "parse_option_string", default)]
+    /// The claim used to create the user id, see [`ClaimRef`].
+    #[serde(deserialize_with = "parse_option_claim_ref", default)]
     #[builder(default)]
-    pub user_id: Option<String>,
-    /// An optional string indicating the role mapping for the user.
-    #[serde(deserialize_with = "parse_option_string", default)]
+    pub user_id: Option<ClaimRef>,
+    /// An optional claim indicating the role mapping for the user, see [`ClaimRef`].
+    #[serde(deserialize_with = "parse_option_claim_ref", default)]
     #[builder(default)]
-    pub role_mapping: Option<String>,
-    #[serde(deserialize_with = "parse_option_string", default)]
+    pub role_mapping: Option<ClaimRef>,
+    /// The claim used to create the workload id, see [`ClaimRef`].
+    #[serde(deserialize_with = "parse_option_claim_ref", default)]
     #[builder(default)]
-    pub workload_id: Option<String>,
+    pub workload_id: Option<ClaimRef>,
     /// An optional mapping of claims to their values. Each claim is represented
     /// by a key-value pair where the key is the claim name and the value is
     /// a `ClaimMapping` struct.
     #[serde(default)]
     #[builder(default)]
     pub claim_mapping: ClaimMappings,
+    /// Mappings of claims to entities which become parents of the `User` or
+    /// `Workload` entity, e.g. to build `Group` entities from a `groups` claim.
+    #[serde(default)]
+    #[builder(default)]
+    pub membership_mapping: Vec<MembershipMapping>,
     /// The claims in this Vec will be required on token validation and will be
     /// validated if it is a registered claim listed in [`RFC 7519, Section 4.1`] (https://datatracker.ietf.org/doc/html/rfc7519#section-4.1)
     #[serde(default)]
@@ -74,6 +81,7 @@ impl TokenEntityMetadata {
             user_id: None,
             role_mapping: None,
             claim_mapping: HashMap::new().into(),
+            membership_mapping: Vec::new(),
             required_claims: HashSet::from(["iss".into(), "exp".into(), "jti".into()]),
             entity_type_name: "Jans::Access_token".into(),
             principal_mapping: HashSet::from(["Jans::Workload".into()]),
@@ -89,6 +97,7 @@ impl TokenEntityMetadata {
             user_id: Some("aud".into()),
             role_mapping: None,
             claim_mapping: HashMap::new().into(),
+            membership_mapping: Vec::new(),
             required_claims: HashSet::from([
                 "iss".into(),
                 "sub".into(),
@@ -109,6 +118,7 @@ impl TokenEntityMetadata {
             user_id: Some("aud".into()),
             role_mapping: None,
             claim_mapping: HashMap::new().into(),
+            membership_mapping: Vec::new(),
             required_claims: HashSet::from([
                 "iss".into(),
                 "sub".into(),
@@ -200,4 +210,41 @@ mod test {
              TokenEntityMetadata"
         );
     }
+
+    /// Test that path expressions are accepted for claim references and
+    /// validated on deserialization.
+    #[test]
+    fn can_parse_path_expressions() {
+        let json = json!({
+            "entity_type_name": "Jans::Access_token",
+            "role_mapping": {"path": "$.resource_access['my-client'].roles"},
+            "workload_id": "azp",
+        });
+        let parsed = serde_json::from_value::<TokenEntityMetadata>(json)
+            .expect("Failed to parse JSON object with path expressions");
+        assert_eq!(
+            parsed
+                .role_mapping
+                .as_ref()
+                .and_then(|claim| claim.path())
+                .map(ToString::to_string)
+                .as_deref(),
+            Some("$.resource_access['my-client'].roles")
+        );
+        assert_eq!(
+            parsed.workload_id.as_ref().and_then(|claim| claim.name()),
+            Some("azp")
+        );
+
+        let json = json!({
+            "entity_type_name": "Jans::Access_token",
+            "role_mapping": {"path": "$.realm_access["},
+        });
+        let err = serde_json::from_value::<TokenEntityMetadata>(json)
+            .expect_err("should fail to parse an invalid path expression");
+        assert!(
+            err.to_string().contains("invalid path expression"),
+            "unexpected error: {err}"
+        );
+    }
 }
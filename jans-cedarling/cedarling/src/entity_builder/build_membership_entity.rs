@@ -0,0 +1,443 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use super::entity_id_getters::*;
+use super::*;
+use crate::common::policy_store::{MembershipMapping, MembershipTarget};
+use cedar_policy::Entity;
+use smol_str::SmolStr;
+use std::collections::HashSet;
+
+/// Tokens whose `role_mapping` is used to build the `Role` entities, the `role`
+/// claim is used if the token has no `role_mapping`.
+const ROLE_MAPPING_TOKENS: &[&str] = &["userinfo_token", "id_token"];
+const DEFAULT_ROLE_CLAIM: &str = "role";
+
+/// Entities built from the membership mappings.
+#[derive(Default)]
+pub struct BuiltMemberships {
+    /// `Role` entities, including the roles inherited through the role hierarchy.
+    pub roles: Vec<Entity>,
+    /// Every other membership entity, e.g. `Group` entities.
+    pub entities: Vec<Entity>,
+    /// Parents of the `User` entity.
+    pub user_parents: HashSet<EntityUid>,
+    /// Parents of the `Workload` entity.
+    pub workload_parents: HashSet<EntityUid>,
+    built_uids: HashSet<EntityUid>,
+}
+
+impl BuiltMemberships {
+    /// Adds the entity if it was not added yet.
+    pub(super) fn insert(&mut self, entity: Entity, role_type_name: &str) {
+        if !self.built_uids.insert(entity.uid()) {
+            return;
+        }
+        if entity.uid().type_name().to_string() == role_type_name {
+            self.roles.push(entity);
+        } else {
+            self.entities.push(entity);
+        }
+    }
+}
+
+impl EntityBuilder {
+    /// Builds the entities declared by the `role_mapping` and `membership_mapping`
+    /// of each token.
+    ///
+    /// The `role_mapping` of a token is built as a membership mapping to the
+    /// `Role` entity, see [`MembershipMapping::role`]. Mappings are skipped if the
+    /// principal they are parents of is not built.
+    pub fn build_membership_entities(
+        &self,
+        tokens: &HashMap<String, Arc<Token>>,
+    ) -> Result<BuiltMemberships, BuildEntityError> {
+        let mut memberships = BuiltMemberships::default();
+
+        if self.config.build_user {
+            for token in ROLE_MAPPING_TOKENS
+                .iter()
+                .filter_map(|name| tokens.get(*name))
+            {
+                let claim = token
+                    .get_metadata()
+                    .and_then(|metadata| metadata.role_mapping.clone())
+                    .unwrap_or_else(|| DEFAULT_ROLE_CLAIM.into());
+                let mapping = MembershipMapping::role(claim, &self.config.entity_names.role);
+                let claim_values =
+                    collect_all_valid_entity_ids(&[EntityIdSrc::claim_ref(token, &mapping.claim)]);
+                let parents =
+                    self.build_mapped_entities(&mapping, &claim_values, &mut memberships)?;
+                memberships.user_parents.extend(parents);
+            }
+        }
+
+        // sort the tokens so the entities are always built in the same order
+        let mut tokens = tokens.iter().collect::<Vec<_>>();
+        tokens.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (_, token) in tokens {
+            let Some(metadata) = token.get_metadata() else {
+                continue;
+            };
+
+            for mapping in metadata.membership_mapping.iter() {
+                let build_principal = match mapping.parent_of {
+                    MembershipTarget::User => self.config.build_user,
+                    MembershipTarget::Workload => self.config.build_workload,
+                };
+                if !build_principal {
+                    continue;
+                }
+
+                let claim_values =
+                    collect_all_valid_entity_ids(&[EntityIdSrc::claim_ref(token, &mapping.claim)]);
+                let parents =
+                    self.build_mapped_entities(mapping, &claim_values, &mut memberships)?;
+                match mapping.parent_of {
+                    MembershipTarget::User => memberships.user_parents.extend(parents),
+                    MembershipTarget::Workload => memberships.workload_parents.extend(parents),
+                }
+            }
+        }
+
+        Ok(memberships)
+    }
+
+    /// Builds the entities declared by the `membership_mapping` of the trusted
+    /// issuers for a principal of the unsigned interface, the attributes of the
+    /// principal are used as the claims.
+    ///
+    /// Only the mappings to the type of the principal, either the `User` or the
+    /// `Workload`, are used. Returns the parents of the principal.
+    pub(super) fn build_membership_entities_unsigned(
+        &self,
+        principal: &EntityData,
+        memberships: &mut BuiltMemberships,
+    ) -> Result<HashSet<EntityUid>, BuildEntityError> {
+        let type_name = principal.cedar_mapping.entity_type.as_str();
+        let target = if type_name == self.config.entity_names.user {
+            MembershipTarget::User
+        } else if type_name == self.config.entity_names.workload {
+            MembershipTarget::Workload
+        } else {
+            return Ok(HashSet::new());
+        };
+
+        let mut parents = HashSet::new();
+        for mapping in self
+            .membership_mappings
+            .iter()
+            .filter(|mapping| mapping.parent_of == target)
+        {
+            let claim_values = mapping
+                .claim
+                .select_from_claims(&principal.attributes)
+                .map(claim_to_ids)
+                .unwrap_or_default();
+            parents.extend(self.build_mapped_entities(mapping, &claim_values, memberships)?);
+        }
+
+        Ok(parents)
+    }
+
+    /// Builds the entities of a single mapping from the values of its claim and
+    /// returns the UIDs of the mapped entities.
+    ///
+    /// Mappings to the `Role` entity type go through the role hierarchy, the
+    /// inherited roles are built but only the mapped roles are returned since Cedar
+    /// resolves the rest through the role entities themselves.
+    fn build_mapped_entities(
+        &self,
+        mapping: &MembershipMapping,
+        claim_values: &[SmolStr],
+        memberships: &mut BuiltMemberships,
+    ) -> Result<Vec<EntityUid>, BuildEntityError> {
+        let ids = claim_values
+            .iter()
+            .filter_map(|value| mapping.entity_id(value))
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let role_type_name = &self.config.entity_names.role;
+        let type_name = mapping.entity_type_name(self.namespace.as_deref());
+        let entities = if &type_name == role_type_name {
+            self.build_role_entities_from_ids(ids.iter().copied())?
+        } else {
+            ids.iter()
+                .map(|id| build_cedar_entity(&type_name, id, HashMap::new(), HashSet::new()))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        let mut parents = Vec::with_capacity(ids.len());
+        for entity in entities {
+            if ids.contains(&entity.uid().id().unescaped()) {
+                parents.push(entity.uid());
+            }
+            memberships.insert(entity, role_type_name);
+        }
+
+        Ok(parents)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::*;
+    use super::*;
+    use crate::CedarEntityMapping;
+    use crate::common::policy_store::{RoleHierarchy, TokenEntityMetadata, TrustedIssuer};
+    use crate::log::TEST_LOGGER;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn iss_with_membership_mapping(mapping: Value) -> Arc<TrustedIssuer> {
+        let metadata = serde_json::from_value::<TokenEntityMetadata>(json!({
+            "entity_type_name": "Jans::Id_token",
+            "membership_mapping": mapping,
+        }))
+        .expect("should parse token metadata");
+
+        Arc::new(TrustedIssuer {
+            token_metadata: HashMap::from([("id_token".to_string(), metadata)]),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn can_build_membership_entities() {
+        let iss = iss_with_membership_mapping(json!([
+            {
+                "claim": "memberOf",
+                "entity_type": "Group",
+                "id_regex": "^cn=(?P<id>[^,]+)",
+            },
+            {
+                "claim": {"path": "$.realm_access.roles"},
+                "entity_type": "Role",
+                "parent_of": "workload",
+            },
+        ]));
+        let builder = EntityBuilder::new(
+            EntityBuilderConfig::default(),
+            &HashMap::new(),
+            None,
+            None,
+            Some("Jans"),
+            TEST_LOGGER.clone(),
+        )
+        .expect("should init entity builder")
+        .with_role_hierarchy(Some(&RoleHierarchy::from(HashMap::from([(
+            "admin".to_string(),
+            ["editor".to_string()].into(),
+        )]))))
+        .expect("should add role hierarchy");
+
+        let id_token = Token::new(
+            "id_token",
+            json!({
+                "memberOf": [
+                    "cn=finance,ou=groups,dc=jans,dc=io",
+                    "cn=audit,ou=groups,dc=jans,dc=io",
+                    "ou=groups,dc=jans,dc=io",
+                ],
+                "realm_access": {"roles": ["admin"]},
+            })
+            .into(),
+            Some(iss),
+        );
+        let tokens = HashMap::from([("id_token".to_string(), Arc::new(id_token))]);
+
+        let memberships = builder
+            .build_membership_entities(&tokens)
+            .expect("should build membership entities");
+
+        let uids = |entities: &[Entity]| {
+            entities
+                .iter()
+                .map(|e| e.uid().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            uids(&memberships.entities),
+            vec!["Jans::Group::\"finance\"", "Jans::Group::\"audit\""]
+        );
+        assert_eq!(
+            uids(&memberships.roles),
+            vec!["Jans::Role::\"admin\"", "Jans::Role::\"editor\""]
+        );
+        assert_entity_eq(
+            &memberships.roles[0],
+            json!({
+                "uid": {"type": "Jans::Role", "id": "admin"},
+                "attrs": {},
+                "parents": [{"type": "Jans::Role", "id": "editor"}],
+            }),
+            None,
+        );
+
+        let mut user_parents = memberships
+            .user_parents
+            .iter()
+            .map(|uid| uid.to_string())
+            .collect::<Vec<_>>();
+        user_parents.sort();
+        assert_eq!(
+            user_parents,
+            vec!["Jans::Group::\"audit\"", "Jans::Group::\"finance\""]
+        );
+        assert_eq!(
+            memberships
+                .workload_parents
+                .iter()
+                .map(|uid| uid.to_string())
+                .collect::<Vec<_>>(),
+            vec!["Jans::Role::\"admin\""]
+        );
+    }
+
+    #[test]
+    fn skips_missing_membership_claims() {
+        let iss = iss_with_membership_mapping(json!([
+            {"claim": "groups", "entity_type": "Acme::Group"},
+        ]));
+        let builder = EntityBuilder::new(
+            EntityBuilderConfig::default(),
+            &HashMap::new(),
+            None,
+            None,
+            None,
+            TEST_LOGGER.clone(),
+        )
+        .expect("should init entity builder");
+
+        let id_token = Token::new("id_token", json!({"sub": "some_sub"}).into(), Some(iss));
+        let tokens = HashMap::from([("id_token".to_string(), Arc::new(id_token))]);
+
+        let memberships = builder
+            .build_membership_entities(&tokens)
+            .expect("should build membership entities");
+
+        assert!(memberships.entities.is_empty());
+        assert!(memberships.roles.is_empty());
+        assert!(memberships.user_parents.is_empty());
+        assert!(memberships.workload_parents.is_empty());
+    }
+
+    #[test]
+    fn builds_the_role_mapping_as_a_membership_mapping() {
+        let metadata = serde_json::from_value::<TokenEntityMetadata>(json!({
+            "entity_type_name": "Jans::Userinfo_token",
+            "role_mapping": {"path": "$.realm_access.roles"},
+            "membership_mapping": [
+                {"claim": "groups", "entity_type": "Group", "parent_of": "workload"},
+            ],
+        }))
+        .expect("should parse token metadata");
+        let iss = Arc::new(TrustedIssuer {
+            token_metadata: HashMap::from([("userinfo_token".to_string(), metadata)]),
+            ..Default::default()
+        });
+        let builder = EntityBuilder::new(
+            EntityBuilderConfig {
+                build_workload: false,
+                ..Default::default()
+            },
+            &HashMap::new(),
+            None,
+            None,
+            Some("Jans"),
+            TEST_LOGGER.clone(),
+        )
+        .expect("should init entity builder");
+
+        let userinfo_token = Token::new(
+            "userinfo_token",
+            json!({
+                "realm_access": {"roles": ["admin", "viewer"]},
+                "groups": ["finance"],
+            })
+            .into(),
+            Some(iss),
+        );
+        let tokens = HashMap::from([("userinfo_token".to_string(), Arc::new(userinfo_token))]);
+
+        let memberships = builder
+            .build_membership_entities(&tokens)
+            .expect("should build membership entities");
+
+        let mut user_parents = memberships
+            .user_parents
+            .iter()
+            .map(|uid| uid.to_string())
+            .collect::<Vec<_>>();
+        user_parents.sort();
+        assert_eq!(
+            user_parents,
+            vec!["Jans::Role::\"admin\"", "Jans::Role::\"viewer\""]
+        );
+        assert_eq!(memberships.roles.len(), 2, "should build the mapped roles");
+        assert!(
+            memberships.entities.is_empty() && memberships.workload_parents.is_empty(),
+            "should skip the mappings of a workload which is not built"
+        );
+    }
+
+    #[test]
+    fn can_build_membership_entities_unsigned() {
+        let metadata = serde_json::from_value::<TokenEntityMetadata>(json!({
+            "entity_type_name": "Jans::Id_token",
+            "membership_mapping": [
+                {"claim": "memberOf", "entity_type": "Group", "id_regex": "^cn=(?P<id>[^,]+)"},
+                {"claim": "teams", "entity_type": "Team", "parent_of": "workload"},
+            ],
+        }))
+        .expect("should parse token metadata");
+        let trusted_issuers = HashMap::from([(
+            "some_iss".to_string(),
+            TrustedIssuer {
+                token_metadata: HashMap::from([("id_token".to_string(), metadata)]),
+                ..Default::default()
+            },
+        )]);
+        let builder = EntityBuilder::new(
+            EntityBuilderConfig::default(),
+            &trusted_issuers,
+            None,
+            None,
+            Some("Jans"),
+            TEST_LOGGER.clone(),
+        )
+        .expect("should init entity builder");
+
+        let principal = EntityData {
+            cedar_mapping: CedarEntityMapping {
+                entity_type: "Jans::User".to_string(),
+                id: "some_user".to_string(),
+            },
+            attributes: HashMap::from([
+                ("memberOf".to_string(), json!(["cn=finance,dc=jans,dc=io"])),
+                ("teams".to_string(), json!(["red"])),
+            ]),
+        };
+
+        let mut memberships = BuiltMemberships::default();
+        let parents = builder
+            .build_membership_entities_unsigned(&principal, &mut memberships)
+            .expect("should build membership entities");
+
+        assert_eq!(
+            parents
+                .iter()
+                .map(|uid| uid.to_string())
+                .collect::<Vec<_>>(),
+            vec!["Jans::Group::\"finance\""],
+            "only the mappings to users should be used"
+        );
+        assert_eq!(memberships.entities.len(), 1);
+    }
+}
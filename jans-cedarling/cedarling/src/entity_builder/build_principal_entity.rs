@@ -89,11 +89,6 @@ struct ExtractedAttrsResult {
     errs: Vec<BuildAttrsErrorVec>,
 }
 
-pub struct BuiltPrincipalUnsigned {
-    pub principal: Entity,
-    pub parents: Vec<Entity>,
-}
-
 /// Information on how to get a principal's ID.
 ///
 /// Used to define default values for the resolvers.
@@ -8,19 +8,32 @@ use cedar_policy::Entity;
 use std::collections::HashSet;
 
 impl EntityBuilder {
+    /// Builds a principal of the unsigned interface.
+    ///
+    /// The `Role` entities from the attributes of the principal and the entities
+    /// from the `membership_mapping` of the trusted issuers become its parents and
+    /// are added to `memberships`.
     pub fn build_principal_unsigned(
         &self,
         principal: &EntityData,
         built_entities: &BuiltEntities,
-    ) -> Result<BuiltPrincipalUnsigned, BuildUnsignedEntityError> {
+        memberships: &mut BuiltMemberships,
+    ) -> Result<Entity, BuildUnsignedEntityError> {
         let type_name: &str = &principal.cedar_mapping.entity_type;
         let id_srcs = vec![EntityIdSrc::String(&principal.cedar_mapping.id)];
 
         let roles = self.build_role_entities_unsigned(principal)?;
-        let role_uids = roles
+        let mut parents = roles
             .iter()
             .map(|role| role.uid())
             .collect::<HashSet<EntityUid>>();
+        for role in roles {
+            memberships.insert(role, &self.config.entity_names.role);
+        }
+        parents.extend(
+            self.build_membership_entities_unsigned(principal, memberships)
+                .map_err(Box::new)?,
+        );
         let attrs_srcs = vec![AttrSrc::Unsigned(&principal.attributes)];
 
         let principal = self
@@ -30,19 +43,18 @@ impl EntityBuilder {
                 attrs_srcs,
                 &TokenPrincipalMappings::default(),
                 built_entities,
-                role_uids,
+                parents,
             )
             .map_err(Box::new)?;
 
-        Ok(BuiltPrincipalUnsigned {
-            principal,
-            parents: roles,
-        })
+        Ok(principal)
     }
 
     /// Builds the role entities from the `role` or `roles` attributes from the
     /// [`UnsignedPrincipal`].
     ///
+    /// Roles inherited through the role hierarchy are also built.
+    ///
     /// Used for the [`authorize_unsigned`] interface.
     ///
     /// [`authorize_unsigned`]: crate::Cedarling::authorize_unsigned
@@ -56,17 +68,9 @@ impl EntityBuilder {
             role_ids.append(&mut extract_roles_from_value(role)?);
         }
 
-        let mut role_entities = Vec::with_capacity(role_ids.len());
-        for id in role_ids.iter() {
-            let role_entity = build_cedar_entity(
-                &self.config.entity_names.role,
-                id,
-                HashMap::new(),
-                HashSet::new(),
-            )
+        let role_entities = self
+            .build_role_entities_from_ids(role_ids.iter().map(String::as_str))
             .map_err(Box::new)?;
-            role_entities.push(role_entity);
-        }
 
         Ok(role_entities)
     }
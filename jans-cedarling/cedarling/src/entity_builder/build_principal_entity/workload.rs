@@ -18,6 +18,7 @@ impl EntityBuilder {
         tokens: &HashMap<String, Arc<Token>>,
         tkn_principal_mappings: &TokenPrincipalMappings,
         built_entities: &BuiltEntities,
+        parents: HashSet<EntityUid>,
     ) -> Result<Entity, BuildEntityError> {
         let type_name: &str = self.config.entity_names.workload.as_ref();
 
@@ -48,7 +49,7 @@ impl EntityBuilder {
             attrs_srcs,
             tkn_principal_mappings,
             built_entities,
-            HashSet::default(),
+            parents,
         )
     }
 }
@@ -95,14 +96,14 @@ impl WorkloadIdSrcResolver {
                 let claim = if let Some(claim) =
                     token.get_metadata().and_then(|m| m.workload_id.as_ref())
                 {
-                    eid_srcs.push(EntityIdSrc::Token { token, claim });
+                    eid_srcs.push(EntityIdSrc::claim_ref(token, claim));
                     Some(claim)
                 } else {
                     None
                 };
 
                 // then we add the fallbacks in-case the token does not have the claims.
-                if claim.map(|claim| claim == src.claim).unwrap_or(false) {
+                if claim.is_some_and(|claim| claim.name() == Some(src.claim)) {
                     continue;
                 }
                 eid_srcs.push(EntityIdSrc::Token {
@@ -141,7 +142,12 @@ mod test {
             &EntityUid::from_str("Jans::Access_token::\"some_jti\"").expect("a valid EntityUid"),
         );
         let entity = builder
-            .build_workload_entity(&tokens, tkn_principal_mappings, &built_entities)
+            .build_workload_entity(
+                &tokens,
+                tkn_principal_mappings,
+                &built_entities,
+                HashSet::new(),
+            )
             .expect("should build workload entity");
 
         assert_entity_eq(&entity, expected, schema);
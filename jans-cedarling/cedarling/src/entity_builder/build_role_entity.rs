@@ -3,28 +3,47 @@
 //
 // Copyright (c) 2024, Gluu, Inc.
 
-use super::entity_id_getters::*;
 use super::*;
 use cedar_policy::Entity;
-use derive_more::derive::Deref;
 use std::collections::HashSet;
 
 impl EntityBuilder {
-    pub fn build_role_entities(
+    /// Builds a `Role` entity for each of the given IDs along with every role they
+    /// are transitively a member of in the role hierarchy.
+    ///
+    /// The parents of each built role are set from the role hierarchy so Cedar can
+    /// resolve `principal in Role::"..."` through the whole graph.
+    pub(super) fn build_role_entities_from_ids<'a>(
         &self,
-        tokens: &HashMap<String, Arc<Token>>,
+        role_ids: impl IntoIterator<Item = &'a str>,
     ) -> Result<Vec<Entity>, BuildEntityError> {
-        // Build Role entities
-        let role_id_srcs = RoleIdSrcs::resolve(tokens);
-        let role_ids = collect_all_valid_entity_ids(&role_id_srcs);
-        let mut role_entities = Vec::with_capacity(role_ids.len());
+        let mut seen = HashSet::new();
+        let mut ids = Vec::new();
         for id in role_ids {
-            let role_entity = build_cedar_entity(
-                &self.config.entity_names.role,
-                &id,
-                HashMap::new(),
-                HashSet::new(),
-            )?;
+            if seen.insert(id) {
+                ids.push(id);
+            }
+        }
+
+        // roles that are only inherited are added after the roles from the request
+        let mut ancestors = ids
+            .iter()
+            .flat_map(|id| self.role_hierarchy.ancestors(id))
+            .filter(|id| !seen.contains(id))
+            .collect::<Vec<_>>();
+        ancestors.sort_unstable();
+        ancestors.dedup();
+        ids.extend(ancestors);
+
+        let type_name = &self.config.entity_names.role;
+        let mut role_entities = Vec::with_capacity(ids.len());
+        for id in ids {
+            let parents = self
+                .role_hierarchy
+                .parents(id)
+                .map(|parent| build_role_uid(type_name, parent))
+                .collect::<Result<HashSet<_>, _>>()?;
+            let role_entity = build_cedar_entity(type_name, id, HashMap::new(), parents)?;
             role_entities.push(role_entity);
         }
 
@@ -32,43 +51,12 @@ impl EntityBuilder {
     }
 }
 
-#[derive(Deref)]
-struct RoleIdSrcs<'a>(Vec<EntityIdSrc<'a>>);
-
-#[derive(Clone, Copy)]
-struct RoleIdSrc<'a> {
-    token: &'a str,
-    claim: &'a str,
-}
-
-impl<'a> RoleIdSrcs<'a> {
-    fn resolve(tokens: &'a HashMap<String, Arc<Token>>) -> Self {
-        const DEFAULT_ROLE_ID_SRCS: &[RoleIdSrc] = &[
-            RoleIdSrc {
-                token: "userinfo_token",
-                claim: "role",
-            },
-            RoleIdSrc {
-                token: "id_token",
-                claim: "role",
-            },
-        ];
-
-        Self(
-            DEFAULT_ROLE_ID_SRCS
-                .iter()
-                .filter_map(|src| {
-                    tokens.get(src.token).map(|token| {
-                        let claim = token
-                            .get_metadata()
-                            .and_then(|m| m.role_mapping.as_deref())
-                            .unwrap_or(src.claim);
-                        EntityIdSrc::Token { token, claim }
-                    })
-                })
-                .collect::<Vec<_>>(),
-        )
-    }
+fn build_role_uid(type_name: &str, id: &str) -> Result<EntityUid, BuildEntityError> {
+    EntityUid::from_str(&format!("{}::\"{}\"", type_name, id)).map_err(
+        |e: cedar_policy::ParseErrors| {
+            BuildEntityErrorKind::from(Box::new(e)).while_building(type_name)
+        },
+    )
 }
 
 #[cfg(test)]
@@ -76,7 +64,7 @@ mod test {
     use super::super::test::*;
     use super::super::*;
     use super::*;
-    use crate::common::policy_store::TrustedIssuer;
+    use crate::common::policy_store::{RoleHierarchy, TrustedIssuer};
     use crate::log::TEST_LOGGER;
     use cedar_policy::Schema;
     use serde_json::json;
@@ -113,8 +101,9 @@ mod test {
         let tokens = HashMap::from([("id_token".into(), Arc::new(id_token))]);
 
         let token_entities = builder
-            .build_role_entities(&tokens)
-            .expect("successfully build role entity");
+            .build_membership_entities(&tokens)
+            .expect("successfully build role entity")
+            .roles;
 
         assert_eq!(token_entities.len(), 1, "one token entity");
 
@@ -159,8 +148,9 @@ mod test {
         let tokens = HashMap::from([("id_token".into(), Arc::new(id_token))]);
 
         let token_entities = builder
-            .build_role_entities(&tokens)
-            .expect("successfully build role entity");
+            .build_membership_entities(&tokens)
+            .expect("successfully build role entity")
+            .roles;
 
         assert_eq!(token_entities.len(), 2, "two token entities");
 
@@ -185,6 +175,114 @@ mod test {
         );
     }
 
+    #[test]
+    fn can_build_role_entities_with_hierarchy() {
+        let schema_src = r#"
+            namespace Jans {
+                entity Role in [Role];
+            }
+        "#;
+        let schema = Schema::from_str(schema_src).expect("build cedar Schema");
+        let validator_schema =
+            ValidatorSchema::from_str(schema_src).expect("build cedar ValidatorSchema");
+        let iss = Arc::new(TrustedIssuer::default());
+        let role_hierarchy = serde_json::from_value::<RoleHierarchy>(json!({
+            "admin": ["editor"],
+            "editor": ["viewer"],
+        }))
+        .expect("valid role hierarchy");
+
+        let builder = EntityBuilder::new(
+            EntityBuilderConfig::default().with_workload(),
+            &HashMap::new(),
+            Some(&validator_schema),
+            None,
+            None,
+            TEST_LOGGER.clone(),
+        )
+        .expect("should init entity builder")
+        .with_role_hierarchy(Some(&role_hierarchy))
+        .expect("should add role hierarchy");
+
+        let id_token = Token::new(
+            "id_token",
+            HashMap::from([("role".to_string(), json!("admin"))]).into(),
+            Some(iss),
+        );
+        let tokens = HashMap::from([("id_token".into(), Arc::new(id_token))]);
+
+        let role_entities = builder
+            .build_membership_entities(&tokens)
+            .expect("successfully build role entities")
+            .roles;
+
+        assert_eq!(role_entities.len(), 3, "the role and its two ancestors");
+
+        assert_entity_eq(
+            &role_entities[0],
+            json!({
+                "uid": {"type": "Jans::Role", "id": "admin"},
+                "attrs": {},
+                "parents": [{"type": "Jans::Role", "id": "editor"}],
+            }),
+            Some(&schema),
+        );
+
+        assert_entity_eq(
+            &role_entities[1],
+            json!({
+                "uid": {"type": "Jans::Role", "id": "editor"},
+                "attrs": {},
+                "parents": [{"type": "Jans::Role", "id": "viewer"}],
+            }),
+            Some(&schema),
+        );
+
+        assert_entity_eq(
+            &role_entities[2],
+            json!({
+                "uid": {"type": "Jans::Role", "id": "viewer"},
+                "attrs": {},
+                "parents": [],
+            }),
+            Some(&schema),
+        );
+    }
+
+    #[test]
+    fn errors_on_role_hierarchy_cycle_from_default_entities() {
+        let default_entities = HashMap::from([(
+            "admin".to_string(),
+            json!({
+                "uid": {"type": "Jans::Role", "id": "admin"},
+                "attrs": {},
+                "parents": [{"type": "Jans::Role", "id": "editor"}],
+            }),
+        )]);
+        let role_hierarchy = serde_json::from_value::<RoleHierarchy>(json!({
+            "editor": ["admin"],
+        }))
+        .expect("valid role hierarchy");
+
+        let err = EntityBuilder::new(
+            EntityBuilderConfig::default().with_workload(),
+            &HashMap::new(),
+            None,
+            Some(&default_entities),
+            Some("Jans"),
+            TEST_LOGGER.clone(),
+        )
+        .expect("should init entity builder")
+        .with_role_hierarchy(Some(&role_hierarchy))
+        .err()
+        .expect("should error on the role hierarchy cycle");
+
+        assert!(
+            matches!(err, InitEntityBuilderError::RoleHierarchy(_)),
+            "expected a role hierarchy error, got: {err:?}"
+        );
+    }
+
     #[test]
     fn builds_role_entities_from_multiple_tokens() {
         let schema_src = r#"
@@ -223,8 +321,9 @@ mod test {
         ]);
 
         let token_entities = builder
-            .build_role_entities(&tokens)
-            .expect("successfully build role entity");
+            .build_membership_entities(&tokens)
+            .expect("successfully build role entity")
+            .roles;
 
         assert_eq!(token_entities.len(), 2, "two token entities");
 
@@ -61,14 +61,14 @@ impl<'a> TokenIdSrcs<'a> {
         for src in DEFAULT_TKN_ID_SRCS.iter() {
             // if a `token_id` is availble in the token's entity metadata
             let claim = if let Some(claim) = token.get_metadata().and_then(|m| m.user_id.as_ref()) {
-                eid_srcs.push(EntityIdSrc::Token { token, claim });
+                eid_srcs.push(EntityIdSrc::claim_ref(token, claim));
                 Some(claim)
             } else {
                 None
             };
 
             // then we add the fallbacks in-case the token does not have the claims.
-            if claim.map(|claim| claim == src.claim).unwrap_or(false) {
+            if claim.is_some_and(|claim| claim.name() == Some(src.claim)) {
                 continue;
             }
             eid_srcs.push(EntityIdSrc::Token {
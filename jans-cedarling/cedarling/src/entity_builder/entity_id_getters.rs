@@ -4,53 +4,81 @@
 // Copyright (c) 2024, Gluu, Inc.
 
 use super::BuildEntityErrorKind;
+use crate::common::policy_store::{ClaimPath, ClaimRef};
 use crate::jwt::Token;
 use serde_json::Value;
 use smol_str::{SmolStr, ToSmolStr};
+use std::borrow::Cow;
 use std::fmt::Display;
 
 pub enum EntityIdSrc<'a> {
-    Token { token: &'a Token, claim: &'a str },
+    Token {
+        token: &'a Token,
+        claim: &'a str,
+    },
+    TokenPath {
+        token: &'a Token,
+        path: &'a ClaimPath,
+    },
     String(&'a str),
 }
 
+impl<'a> EntityIdSrc<'a> {
+    /// Returns the source of a claim reference of the token entity metadata
+    pub fn claim_ref(token: &'a Token, claim: &'a ClaimRef) -> Self {
+        match claim.path() {
+            Some(path) => Self::TokenPath { token, path },
+            None => Self::Token {
+                token,
+                claim: claim.name().unwrap_or_default(),
+            },
+        }
+    }
+}
+
 pub fn get_first_valid_entity_id(id_srcs: &[EntityIdSrc]) -> Result<SmolStr, BuildEntityErrorKind> {
     let mut errors = Vec::new();
 
     for src in id_srcs.iter() {
-        match src {
-            EntityIdSrc::Token {
+        let (token, claim_name, claim) = match src {
+            EntityIdSrc::Token { token, claim } => (
                 token,
-                claim: claim_name,
-            } => {
-                let claim = match token.get_claim_val(claim_name) {
-                    Some(claim) => claim,
-                    None => {
-                        errors.push(GetEntityIdError {
-                            token: token.name.clone(),
-                            claim: claim_name.to_string(),
-                            reason: GetEntityIdErrorReason::MissingClaim,
-                        });
-                        continue;
-                    },
-                };
-
-                let claim = claim.to_string();
-                let id = claim.trim_matches('"');
-
-                if id.is_empty() {
-                    errors.push(GetEntityIdError {
-                        token: token.name.clone(),
-                        claim: claim_name.to_string(),
-                        reason: GetEntityIdErrorReason::EmptyString,
-                    });
-                    continue;
-                }
-
-                return Ok(id.to_smolstr());
-            },
+                claim.to_string(),
+                token.get_claim_val(claim).map(Cow::Borrowed),
+            ),
+            EntityIdSrc::TokenPath { token, path } => (
+                token,
+                path.to_string(),
+                token.select_claim_val(path).map(Cow::Owned),
+            ),
             EntityIdSrc::String(eid) => return Ok(eid.to_smolstr()),
+        };
+
+        let claim = match claim {
+            Some(claim) => claim,
+            None => {
+                errors.push(GetEntityIdError {
+                    token: token.name.clone(),
+                    claim: claim_name,
+                    reason: GetEntityIdErrorReason::MissingClaim,
+                });
+                continue;
+            },
+        };
+
+        let claim = claim.to_string();
+        let id = claim.trim_matches('"');
+
+        if id.is_empty() {
+            errors.push(GetEntityIdError {
+                token: token.name.clone(),
+                claim: claim_name,
+                reason: GetEntityIdErrorReason::EmptyString,
+            });
+            continue;
         }
+
+        return Ok(id.to_smolstr());
     }
 
     Err(BuildEntityErrorKind::MissingEntityId(errors.into()))
@@ -61,6 +89,7 @@ pub fn collect_all_valid_entity_ids(id_srcs: &[EntityIdSrc]) -> Vec<SmolStr> {
         .iter()
         .filter_map(|src| match src {
             EntityIdSrc::Token { token, claim } => token.get_claim_val(claim).cloned(),
+            EntityIdSrc::TokenPath { token, path } => token.select_claim_val(path),
             EntityIdSrc::String(eid) => id_str_src_to_value(eid),
         })
         .flat_map(claim_to_ids)
@@ -78,7 +107,7 @@ fn id_str_src_to_value(eid: &str) -> Option<Value> {
     }
 }
 
-fn claim_to_ids(claim: Value) -> Vec<SmolStr> {
+pub fn claim_to_ids(claim: Value) -> Vec<SmolStr> {
     let mut ids = Vec::with_capacity(1 + claim.as_array().map(|v| v.len()).unwrap_or_default());
     match claim {
         serde_json::Value::Number(number) => {
@@ -298,4 +327,44 @@ mod test {
         ]);
         assert_eq!(ids, vec!["role1", "role2"]);
     }
+
+    #[test]
+    fn can_collect_entity_ids_using_path_expressions() {
+        let token = Token::new(
+            "tkn",
+            HashMap::from([
+                ("realm_access".into(), json!({"roles": ["admin", "editor"]})),
+                (
+                    "resource_access".into(),
+                    json!({"my-client": {"roles": ["viewer"]}}),
+                ),
+                ("user".into(), json!({"id": "some_user"})),
+            ])
+            .into(),
+            None,
+        );
+
+        let path = |expression: &str| ClaimPath::parse(expression).expect("valid path");
+        let realm_roles = path("$.realm_access.roles");
+        let client_roles = path("$.resource_access['my-client'].roles");
+        let ids = collect_all_valid_entity_ids(&[
+            EntityIdSrc::TokenPath {
+                token: &token,
+                path: &realm_roles,
+            },
+            EntityIdSrc::TokenPath {
+                token: &token,
+                path: &client_roles,
+            },
+        ]);
+        assert_eq!(ids, vec!["admin", "editor", "viewer"]);
+
+        let user_id = path("$.user.id");
+        let id = get_first_valid_entity_id(&[EntityIdSrc::TokenPath {
+            token: &token,
+            path: &user_id,
+        }])
+        .expect("should get entity id from a nested claim");
+        assert_eq!(id, "some_user");
+    }
 }
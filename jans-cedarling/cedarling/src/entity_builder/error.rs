@@ -8,6 +8,7 @@ use std::fmt::Display;
 use super::build_expr::{BuildExprError, BuildExprErrorVec};
 use super::entity_id_getters::GetEntityIdErrors;
 use super::schema::BuildMappingSchemaError;
+use crate::common::policy_store::RoleHierarchyCycleError;
 use cedar_policy::ExpressionConstructionError;
 use smol_str::SmolStr;
 use thiserror::Error;
@@ -18,6 +19,8 @@ pub enum InitEntityBuilderError {
     BuildIssEntities(BuildEntityErrors),
     #[error("error while initializing the mapping schema: {0}")]
     BuildMappingSchema(#[from] BuildMappingSchemaError),
+    #[error("invalid role hierarchy: {0}")]
+    RoleHierarchy(#[from] RoleHierarchyCycleError),
 }
 
 #[derive(Debug, Error)]
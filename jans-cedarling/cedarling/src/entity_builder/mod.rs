@@ -10,6 +10,7 @@
 mod build_entity_attrs;
 mod build_expr;
 mod build_iss_entity;
+mod build_membership_entity;
 mod build_principal_entity;
 mod build_resource_entity;
 mod build_role_entity;
@@ -24,14 +25,14 @@ use crate::authz::AuthorizeEntitiesData;
 use crate::authz::request::EntityData;
 use crate::common::PartitionResult;
 use crate::common::issuer_utils::normalize_issuer;
-use crate::common::policy_store::{ClaimMappings, TrustedIssuer};
-use crate::entity_builder::build_principal_entity::BuiltPrincipalUnsigned;
+use crate::common::policy_store::{ClaimMappings, MembershipMapping, RoleHierarchy, TrustedIssuer};
 use crate::jwt::Token;
 use crate::log::interface::LogWriter;
 use crate::log::{LogEntry, LogType, Logger};
-use crate::{LogLevel, RequestUnsigned, entity_builder_config::*};
+use crate::{LogLevel, entity_builder_config::*};
 use build_entity_attrs::*;
 use build_iss_entity::build_iss_entity;
+use build_membership_entity::BuiltMemberships;
 use cedar_policy::{Entity, EntityUid, RestrictedExpression};
 use cedar_policy_validator::ValidatorSchema;
 use schema::MappingSchema;
@@ -288,6 +289,31 @@ fn parse_default_entities(
     Ok(default_entities)
 }
 
+/// Collects the memberships between entities of the given role type.
+fn role_hierarchy_from_entities<'a>(
+    entities: impl Iterator<Item = &'a Entity>,
+    role_type_name: &str,
+) -> RoleHierarchy {
+    let mut role_hierarchy = RoleHierarchy::default();
+
+    for entity in entities {
+        let uid = entity.uid();
+        if uid.type_name().to_string() != role_type_name {
+            continue;
+        }
+
+        let (_, _, parents) = entity.clone().into_inner();
+        for parent in parents
+            .iter()
+            .filter(|parent| parent.type_name().to_string() == role_type_name)
+        {
+            role_hierarchy.add_parent(uid.id().unescaped(), parent.id().unescaped());
+        }
+    }
+
+    role_hierarchy
+}
+
 fn build_entity_type_name(entity_type_from_uid: &str, namespace: &Option<&str>) -> String {
     if entity_type_from_uid.contains("::") {
         entity_type_from_uid.to_string()
@@ -305,6 +331,12 @@ pub struct EntityBuilder {
     iss_entities: HashMap<Origin, Entity>,
     schema: Option<MappingSchema>,
     default_entities: HashMap<EntityUid, Entity>,
+    role_hierarchy: RoleHierarchy,
+    /// Namespace used for entity types which are not qualified
+    namespace: Option<String>,
+    /// The `membership_mapping` of the trusted issuers, used to build the
+    /// memberships of the principals of the unsigned interface
+    membership_mappings: Vec<MembershipMapping>,
 }
 
 impl EntityBuilder {
@@ -340,14 +372,55 @@ impl EntityBuilder {
             HashMap::new()
         };
 
+        // role memberships declared through the parents of default `Role` entities
+        let role_hierarchy = role_hierarchy_from_entities(
+            default_entities.values(),
+            &config.entity_names.role,
+        );
+        role_hierarchy.validate()?;
+
+        // sort the mappings so the entities are always built in the same order
+        let mut trusted_issuers = trusted_issuers.iter().collect::<Vec<_>>();
+        trusted_issuers.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        let mut membership_mappings = Vec::new();
+        for (_, iss) in trusted_issuers {
+            let mut token_metadata = iss.token_metadata.iter().collect::<Vec<_>>();
+            token_metadata.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+            for (_, metadata) in token_metadata {
+                for mapping in metadata.membership_mapping.iter() {
+                    if !membership_mappings.contains(mapping) {
+                        membership_mappings.push(mapping.clone());
+                    }
+                }
+            }
+        }
+
         Ok(Self {
             config,
             iss_entities,
             schema,
             default_entities,
+            role_hierarchy,
+            namespace: namespace.map(String::from),
+            membership_mappings,
         })
     }
 
+    /// Adds the role hierarchy declared in the policy store to the builder.
+    ///
+    /// The hierarchy is merged with the memberships declared by the default
+    /// `Role` entities and the result is checked for cycles.
+    pub fn with_role_hierarchy(
+        mut self,
+        role_hierarchy: Option<&RoleHierarchy>,
+    ) -> Result<Self, InitEntityBuilderError> {
+        if let Some(role_hierarchy) = role_hierarchy {
+            self.role_hierarchy.extend(role_hierarchy);
+            self.role_hierarchy.validate()?;
+        }
+        Ok(self)
+    }
+
     pub fn build_entities(
         &self,
         tokens: &HashMap<String, Arc<Token>>,
@@ -380,26 +453,33 @@ impl EntityBuilder {
             token_entities.insert(tkn_name.to_string(), tkn_entity);
         }
 
+        let memberships = self.build_membership_entities(tokens)?;
+        for entity in memberships.roles.iter().chain(memberships.entities.iter()) {
+            built_entities.insert(&entity.uid());
+        }
+
         let workload = if self.config.build_workload {
-            let workload_entity =
-                self.build_workload_entity(tokens, &tkn_principal_mappings, &built_entities)?;
+            let workload_entity = self.build_workload_entity(
+                tokens,
+                &tkn_principal_mappings,
+                &built_entities,
+                memberships.workload_parents,
+            )?;
             Some(workload_entity)
         } else {
             None
         };
 
-        let (user, roles) = if self.config.build_user {
-            let roles = self.build_role_entities(tokens)?;
-            let role_uids = roles.iter().map(|e| e.uid()).collect();
+        let user = if self.config.build_user {
             let user = self.build_user_entity(
                 tokens,
                 &tkn_principal_mappings,
                 &built_entities,
-                role_uids,
+                memberships.user_parents,
             )?;
-            (Some(user), roles)
+            Some(user)
         } else {
-            (None, Vec::new())
+            None
         };
 
         let mut resource = self.build_resource_entity(resource_data)?;
@@ -415,7 +495,8 @@ impl EntityBuilder {
             workload,
             user,
             resource,
-            roles,
+            roles: memberships.roles,
+            memberships: memberships.entities,
             tokens: token_entities,
             default_entities: self.default_entities.clone(),
         })
@@ -424,32 +505,34 @@ impl EntityBuilder {
     /// Builds the entities using the unsigned interface
     pub fn build_entities_unsigned(
         &self,
-        request: &RequestUnsigned,
+        principals_data: &[EntityData],
+        resource_data: &EntityData,
     ) -> Result<BuiltEntitiesUnsigned, BuildUnsignedEntityError> {
         let mut built_entities = BuiltEntities::default();
 
-        let mut principals = Vec::with_capacity(request.principals.len());
-        let mut roles = Vec::<Entity>::new();
-        for principal in request.principals.iter() {
-            let BuiltPrincipalUnsigned { principal, parents } =
-                self.build_principal_unsigned(principal, &built_entities)?;
+        let mut principals = Vec::with_capacity(principals_data.len());
+        // principals can share parents, but each entity must only be added once
+        let mut memberships = BuiltMemberships::default();
+        for principal in principals_data.iter() {
+            let principal =
+                self.build_principal_unsigned(principal, &built_entities, &mut memberships)?;
 
             built_entities.insert(&principal.uid());
-            for role in roles.iter() {
-                built_entities.insert(&role.uid());
+            for entity in memberships.roles.iter().chain(memberships.entities.iter()) {
+                built_entities.insert(&entity.uid());
             }
 
             principals.push(principal);
-            roles.extend(parents);
         }
 
         let resource = self
-            .build_resource_entity(&request.resource)
+            .build_resource_entity(resource_data)
             .map_err(Box::new)?;
 
         Ok(BuiltEntitiesUnsigned {
             principals,
-            roles,
+            roles: memberships.roles,
+            memberships: memberships.entities,
             resource,
             built_entities,
         })
@@ -478,6 +561,8 @@ impl EntityBuilder {
 pub struct BuiltEntitiesUnsigned {
     pub principals: Vec<Entity>,
     pub roles: Vec<Entity>,
+    /// Entities built from the `membership_mapping` of the trusted issuers
+    pub memberships: Vec<Entity>,
     pub resource: Entity,
     pub built_entities: BuiltEntities,
 }
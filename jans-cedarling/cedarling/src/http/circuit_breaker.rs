@@ -0,0 +1,45 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use http_utils::{CircuitBreaker, CircuitState, Sender};
+
+use crate::log::interface::LogWriter;
+use crate::log::{LogEntry, LogLevel, LogType};
+
+/// Creates the [`CircuitBreaker`] of an endpoint, which logs its state changes.
+pub(crate) fn circuit_breaker(
+    endpoint: &str,
+    logger: impl LogWriter + Send + Sync + 'static,
+) -> CircuitBreaker {
+    CircuitBreaker::default_for(endpoint).on_state_change(move |endpoint, state| {
+        let level = match state {
+            CircuitState::Open => LogLevel::WARN,
+            CircuitState::HalfOpen | CircuitState::Closed => LogLevel::INFO,
+        };
+        logger.log_any(
+            LogEntry::new_with_data(LogType::System, None)
+                .set_level(level)
+                .set_message(format!(
+                    "the circuit breaker for '{}' is now {}",
+                    endpoint, state
+                )),
+        );
+    })
+}
+
+/// Logs the failed attempts of the [`Sender`] before they are retried.
+pub(crate) fn log_failed_attempts(
+    sender: Sender,
+    logger: impl LogWriter + Send + Sync + 'static,
+) -> Sender {
+    sender.on_failed_attempt(move |url, err| {
+        logger.log_any(
+            LogEntry::new_with_data(LogType::System, None)
+                .set_level(LogLevel::WARN)
+                .set_message(format!("HTTP request to '{url}' failed"))
+                .set_error(err.to_string()),
+        );
+    })
+}
@@ -0,0 +1,165 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use crate::HttpClientConfig;
+use reqwest::{Client, ClientBuilder};
+use std::io;
+use std::path::PathBuf;
+
+/// Creates a [`ClientBuilder`] with the options of the [`HttpClientConfig`].
+///
+/// Every outbound HTTP client of Cedarling is created with this function so they
+/// all honor the `CEDARLING_HTTP_CLIENT` bootstrap property.
+pub fn client_builder(config: &HttpClientConfig) -> Result<ClientBuilder, HttpClientBuildError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let mut builder =
+            Client::builder().danger_accept_invalid_certs(config.accept_invalid_certs);
+
+        if let Some(path) = &config.ca_bundle {
+            let certificates = reqwest::Certificate::from_pem_bundle(&read_file(path)?)
+                .map_err(|err| HttpClientBuildError::InvalidCaBundle(path.clone(), err))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(client_cert) = &config.client_cert {
+            let identity = reqwest::Identity::from_pkcs8_pem(
+                &read_file(&client_cert.cert_file)?,
+                &read_file(&client_cert.key_file)?,
+            )
+            .map_err(|err| {
+                HttpClientBuildError::InvalidClientCert(client_cert.cert_file.clone(), err)
+            })?;
+            builder = builder.identity(identity);
+        }
+
+        if let Some(proxy_url) = &config.proxy {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|err| HttpClientBuildError::InvalidProxy(proxy_url.clone(), err))?
+                .no_proxy(
+                    config
+                        .no_proxy
+                        .as_deref()
+                        .and_then(reqwest::NoProxy::from_string),
+                );
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(timeout) = config.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = config.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+
+        Ok(builder)
+    }
+
+    // NOTE: the browser handles the TLS and the proxy in WASM builds and
+    // `.danger_accept_invalid_certs` is not available, so the flag is ignored
+    #[cfg(target_arch = "wasm32")]
+    {
+        let unsupported = [
+            ("ca_bundle", config.ca_bundle.is_some()),
+            ("client_cert", config.client_cert.is_some()),
+            ("proxy", config.proxy.is_some()),
+            ("timeout", config.timeout.is_some()),
+            ("connect_timeout", config.connect_timeout.is_some()),
+        ];
+        if let Some((option, _)) = unsupported.into_iter().find(|(_, is_set)| *is_set) {
+            return Err(HttpClientBuildError::Unsupported(option));
+        }
+
+        Ok(Client::builder())
+    }
+}
+
+/// Creates a [`Client`] with the options of the [`HttpClientConfig`].
+pub fn build_client(config: &HttpClientConfig) -> Result<Client, HttpClientBuildError> {
+    client_builder(config)?
+        .build()
+        .map_err(HttpClientBuildError::Build)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_file(path: &std::path::Path) -> Result<Vec<u8>, HttpClientBuildError> {
+    std::fs::read(path).map_err(|err| HttpClientBuildError::ReadFile(path.to_path_buf(), err))
+}
+
+/// Error type for failing to create an HTTP client from its config
+#[derive(Debug, thiserror::Error)]
+pub enum HttpClientBuildError {
+    #[error("failed to read '{}': {1}", .0.display())]
+    ReadFile(PathBuf, #[source] io::Error),
+    #[error("invalid CA bundle '{}': {1}", .0.display())]
+    InvalidCaBundle(PathBuf, #[source] reqwest::Error),
+    #[error("invalid client certificate '{}': {1}", .0.display())]
+    InvalidClientCert(PathBuf, #[source] reqwest::Error),
+    #[error("invalid proxy URL '{0}': {1}")]
+    InvalidProxy(String, #[source] reqwest::Error),
+    #[error("the `{0}` HTTP client option is not supported in WASM")]
+    Unsupported(&'static str),
+    #[error("failed to initialize the HTTP client: {0}")]
+    Build(#[source] reqwest::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::HttpClientCertConfig;
+    use mockito::{Matcher, Server};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn sends_the_requests_through_the_proxy() {
+        let mut proxy = Server::new_async().await;
+        let mock = proxy
+            .mock("GET", Matcher::Regex("/jwks$".to_string()))
+            .with_status(200)
+            .with_body("{}")
+            .expect(1)
+            .create();
+
+        let client = build_client(&HttpClientConfig {
+            proxy: Some(proxy.url()),
+            timeout: Some(Duration::from_secs(5)),
+            ..Default::default()
+        })
+        .expect("should build the client");
+        let response = client
+            .get("http://test.jans.io/jwks")
+            .send()
+            .await
+            .expect("should send the request through the proxy");
+
+        assert!(response.status().is_success());
+        mock.assert();
+    }
+
+    #[test]
+    fn errors_on_invalid_options() {
+        let err = build_client(&HttpClientConfig {
+            proxy: Some("not a url".to_string()),
+            ..Default::default()
+        })
+        .expect_err("the proxy URL should be invalid");
+        assert!(
+            matches!(err, HttpClientBuildError::InvalidProxy(_, _)),
+            "{err}"
+        );
+
+        let err = build_client(&HttpClientConfig {
+            client_cert: Some(HttpClientCertConfig {
+                cert_file: "/missing/client.crt".into(),
+                key_file: "/missing/client.key".into(),
+            }),
+            ..Default::default()
+        })
+        .expect_err("the certificate file should be missing");
+        assert!(matches!(err, HttpClientBuildError::ReadFile(_, _)), "{err}");
+    }
+}
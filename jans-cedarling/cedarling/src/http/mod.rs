@@ -3,12 +3,18 @@
 //
 // Copyright (c) 2024, Gluu, Inc.
 
+mod circuit_breaker;
+mod client_builder;
 mod spawn_task;
+mod timeout;
 
+pub(crate) use circuit_breaker::*;
+pub use client_builder::*;
 pub use spawn_task::*;
+pub use timeout::*;
 
-use reqwest::Client;
-use serde::Deserialize;
+use crate::HttpClientConfig;
+use reqwest::header::HeaderMap;
 use std::time::Duration;
 
 /// A wrapper around `reqwest::blocking::Client` providing HTTP request functionality
@@ -25,10 +31,12 @@ pub struct HttpClient {
 }
 
 impl HttpClient {
-    pub fn new(max_retries: u32, retry_delay: Duration) -> Result<Self, HttpClientError> {
-        let client = Client::builder()
-            .build()
-            .map_err(HttpClientError::Initialization)?;
+    pub fn new(
+        config: &HttpClientConfig,
+        max_retries: u32,
+        retry_delay: Duration,
+    ) -> Result<Self, HttpClientError> {
+        let client = build_client(config)?;
 
         Ok(Self {
             client,
@@ -70,6 +78,7 @@ impl HttpClient {
             .map_err(HttpClientError::HttpStatus)?;
 
         Ok(Response {
+            headers: response.headers().clone(),
             text: response
                 .text()
                 .await
@@ -80,15 +89,17 @@ impl HttpClient {
 
 #[derive(Debug)]
 pub struct Response {
+    headers: HeaderMap,
     text: String,
 }
 
 impl Response {
-    pub fn json<'a, T>(&'a self) -> Result<T, serde_json::Error>
-    where
-        T: Deserialize<'a>,
-    {
-        serde_json::from_str::<'a, T>(&self.text)
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
     }
 }
 
@@ -97,7 +108,7 @@ impl Response {
 pub enum HttpClientError {
     /// Indicates failure to initialize the HTTP client.
     #[error("Failed to initilize HTTP client: {0}")]
-    Initialization(#[source] reqwest::Error),
+    Initialization(#[from] HttpClientBuildError),
     /// Indicates an HTTP error response received from an endpoint.
     #[error("Received error HTTP status: {0}")]
     HttpStatus(#[source] reqwest::Error),
@@ -111,6 +122,7 @@ pub enum HttpClientError {
 
 #[cfg(test)]
 mod test {
+    use crate::HttpClientConfig;
     use crate::http::{HttpClient, HttpClientError};
 
     use mockito::Server;
@@ -136,16 +148,15 @@ mod test {
             .expect(1)
             .create_async();
 
-        let client =
-            HttpClient::new(3, Duration::from_millis(1)).expect("Should create HttpClient.");
+        let client = HttpClient::new(&HttpClientConfig::default(), 3, Duration::from_millis(1))
+            .expect("Should create HttpClient.");
 
         let link = &format!("{}/.well-known/openid-configuration", mock_server.url());
         let req_fut = client.get(link);
         let (req_result, mock_result) = join!(req_fut, mock_endpoint);
 
-        let response = req_result
-            .expect("Should get response")
-            .json::<serde_json::Value>()
+        let response = req_result.expect("Should get response");
+        let response = serde_json::from_str::<serde_json::Value>(response.text())
             .expect("Should deserialize JSON response.");
 
         assert_eq!(
@@ -158,8 +169,8 @@ mod test {
 
     #[tokio::test]
     async fn errors_when_max_http_retries_exceeded() {
-        let client =
-            HttpClient::new(3, Duration::from_millis(1)).expect("Should create HttpClient");
+        let client = HttpClient::new(&HttpClientConfig::default(), 3, Duration::from_millis(1))
+            .expect("Should create HttpClient");
         let response = client.get("0.0.0.0").await;
 
         assert!(
@@ -178,8 +189,8 @@ mod test {
             .expect(1)
             .create_async();
 
-        let client =
-            HttpClient::new(3, Duration::from_millis(1)).expect("Should create HttpClient.");
+        let client = HttpClient::new(&HttpClientConfig::default(), 3, Duration::from_millis(1))
+            .expect("Should create HttpClient.");
 
         let link = &format!("{}/.well-known/openid-configuration", mock_server.url());
         let client_fut = client.get(link);
@@ -0,0 +1,56 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use std::future::Future;
+use std::time::Duration;
+
+/// Error returned when a future does not complete before its timeout
+#[derive(Debug, thiserror::Error)]
+#[error("the operation timed out after {0:?}")]
+pub struct TimeoutError(pub Duration);
+
+/// Helper function for awaiting a future for at most `duration`
+///
+/// Use this instead of [`tokio::time::timeout`].
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, TimeoutError> {
+    tokio::time::timeout(duration, future)
+        .await
+        .map_err(|_| TimeoutError(duration))
+}
+
+/// Helper function for awaiting a future for at most `duration`
+///
+/// Use this instead of [`tokio::time::timeout`].
+#[cfg(any(target_arch = "wasm32", target_arch = "wasm64"))]
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, TimeoutError> {
+    use futures::future::{Either, select};
+
+    match select(std::pin::pin!(future), std::pin::pin!(sleep(duration))).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(TimeoutError(duration)),
+    }
+}
+
+/// Completes after `duration`, using the `setTimeout` function of the JS global scope
+/// so it works in browsers, web workers and Node.js.
+#[cfg(any(target_arch = "wasm32", target_arch = "wasm64"))]
+async fn sleep(duration: Duration) {
+    use wasm_bindgen_futures::wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_futures::{JsFuture, js_sys};
+
+    // without `setTimeout` the promise never resolves, so the future is awaited until
+    // it completes
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let set_timeout = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .ok()
+            .and_then(|set_timeout| set_timeout.dyn_into::<js_sys::Function>().ok());
+        if let Some(set_timeout) = set_timeout {
+            let millis = JsValue::from_f64(duration.as_millis() as f64);
+            _ = set_timeout.call2(&JsValue::UNDEFINED, &resolve, &millis);
+        }
+    });
+    _ = JsFuture::from(promise).await;
+}
@@ -7,9 +7,17 @@
 //! Part of Cedarling that main purpose is:
 //! - read boostrap properties
 //! - load Cedar Policies
+//! - validate the policy store documents against the policy store schema
+//! - verify the signature of the policy store
+//! - poll the policy store of `CEDARLING_POLICY_STORE_URI`
 //! - get keys for JWT validation
 
 pub(crate) mod policy_store;
+pub(crate) mod policy_store_layout;
+pub(crate) mod policy_store_poller;
+pub(crate) mod policy_store_schema;
+pub(crate) mod policy_store_signature;
+pub(crate) mod policy_validation;
 pub(crate) mod service_config;
 pub(crate) mod service_factory;
 
@@ -3,13 +3,36 @@
 //
 // Copyright (c) 2024, Gluu, Inc.
 
-use std::path::Path;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::{fs, io};
 
-use crate::bootstrap_config::policy_store_config::{PolicyStoreConfig, PolicyStoreSource};
-use crate::common::policy_store::{AgamaPolicyStore, PolicyStoreWithID};
+use super::policy_store_layout::{PolicyStoreFileError, load_archive, load_directory};
+use super::policy_store_schema::{PolicyStoreSchemaError, validate_document};
+use super::policy_store_signature::{
+    PolicyStoreSignatureError, check_signed_version, detached_signature_location, is_compact_jws,
+    verify_detached_signature, verify_signature,
+};
+use super::policy_validation::PolicyValidationIssue;
+use crate::HttpClientConfig;
+use crate::bootstrap_config::policy_store_config::{
+    PolicyStoreConfig, PolicyStoreSignatureConfig, PolicyStoreSource,
+};
+use crate::common::file_utils::write_file;
+use crate::common::policy_store::{
+    AgamaPolicyStore, PolicyStoreWithID, TemplateLink, TemplateLinkError,
+};
 use crate::http::{HttpClient, HttpClientError};
+use http_utils::{HttpRequestError, Sender};
+use reqwest::header::{
+    ETAG, HeaderMap, HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::{Client, StatusCode};
+use ring::digest::{SHA256, digest};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 /// Errors that can occur when loading a policy store.
 #[derive(Debug, thiserror::Error)]
@@ -20,10 +43,32 @@ pub enum PolicyStoreLoadError {
     ParseYaml(#[from] serde_yml::Error),
     #[error("failed to fetch the policy store from the lock server")]
     FetchFromLockServer(#[from] HttpClientError),
+    #[error("failed to poll the policy store: {0}")]
+    Poll(#[from] HttpRequestError),
+    #[error("failed to read the polled policy store: {0}")]
+    ReadPolledStore(#[source] reqwest::Error),
     #[error("Policy Store does not contain correct structure: {0}")]
     InvalidStore(String),
+    #[error(
+        "the policy store does not match the policy store schema: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    InvalidDocument(Vec<PolicyStoreSchemaError>),
     #[error("Failed to load policy store from {0}: {1}")]
     ParseFile(Box<Path>, io::Error),
+    #[error("Failed to read the policy store archive {0}: {1}")]
+    ReadArchive(Box<Path>, String),
+    #[error("invalid policy store file {0}")]
+    InvalidFile(#[from] PolicyStoreFileError),
+    #[error("failed to verify the policy store signature: {0}")]
+    Signature(#[from] PolicyStoreSignatureError),
+    #[error(
+        "policies do not validate against the schema: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    Validation(Vec<PolicyValidationIssue>),
+    #[error("failed to apply the template links saved to {0}: {1}")]
+    TemplateLinks(Box<Path>, String),
 }
 
 // AgamaPolicyStore contains the structure to accommodate several policies,
@@ -58,41 +103,125 @@ fn extract_first_policy_store(
     }
 }
 
+/// Identifies the last policy store fetched from a URI, so the polls only apply a
+/// policy store that changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct RemoteStoreVersion {
+    /// Value of the `ETag` header, sent as `If-None-Match`
+    pub etag: Option<String>,
+    /// Value of the `Last-Modified` header, sent as `If-Modified-Since`
+    pub last_modified: Option<String>,
+    /// SHA-256 digest of the body
+    pub digest: Vec<u8>,
+}
+
+impl RemoteStoreVersion {
+    fn new(headers: &HeaderMap, body: &str) -> Self {
+        let header = |name: HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+
+        Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            digest: digest(&SHA256, body.as_bytes()).as_ref().to_vec(),
+        }
+    }
+}
+
+/// Result of polling a policy store from a URI.
+#[derive(Debug)]
+pub(crate) enum PolledPolicyStore {
+    /// The server responded with a `304` or with the same body.
+    Unchanged(RemoteStoreVersion),
+    /// The server responded with a different policy store.
+    Changed(Box<PolicyStoreWithID>, RemoteStoreVersion),
+}
+
 /// Loads the policy store based on the provided configuration.
 ///
-/// This function supports multiple sources for loading policies.
+/// This function supports multiple sources for loading policies, the remote ones are
+/// fetched with an HTTP client created from `http_config`. If `signature_config` is set,
+/// the policy store must be signed with one of its trusted keys. The JSON and YAML
+/// documents are checked against the policy store schema, with `reject_unknown_keys`
+/// the keys that are not in the schema are errors.
 pub(crate) async fn load_policy_store(
     config: &PolicyStoreConfig,
+    http_config: &HttpClientConfig,
+    signature_config: Option<&PolicyStoreSignatureConfig>,
+    reject_unknown_keys: bool,
 ) -> Result<PolicyStoreWithID, PolicyStoreLoadError> {
-    let policy_store = match &config.source {
+    load_policy_store_with_version(config, http_config, signature_config, reject_unknown_keys)
+        .await
+        .map(|(policy_store, _)| policy_store)
+}
+
+/// Loads the policy store like [`load_policy_store`], along with the version of the
+/// policy stores fetched from a URI.
+pub(crate) async fn load_policy_store_with_version(
+    config: &PolicyStoreConfig,
+    http_config: &HttpClientConfig,
+    signature_config: Option<&PolicyStoreSignatureConfig>,
+    reject_unknown_keys: bool,
+) -> Result<(PolicyStoreWithID, Option<RemoteStoreVersion>), PolicyStoreLoadError> {
+    let (policy_store, version) = match &config.source {
         PolicyStoreSource::Json(policy_json) => {
-            let agama_policy_store = serde_json::from_str::<AgamaPolicyStore>(policy_json)
-                .map_err(PolicyStoreLoadError::ParseJson)?;
-            extract_first_policy_store(&agama_policy_store)?
+            let policy_json = signed_content(policy_json, None, signature_config)?;
+            let policy_store = parse_json_policy_store(&policy_json, reject_unknown_keys)?;
+            (policy_store, None)
         },
         PolicyStoreSource::Yaml(policy_yaml) => {
-            let agama_policy_store = serde_yml::from_str::<AgamaPolicyStore>(policy_yaml)
-                .map_err(PolicyStoreLoadError::ParseYaml)?;
-            extract_first_policy_store(&agama_policy_store)?
+            let policy_yaml = signed_content(policy_yaml, None, signature_config)?;
+            let policy_store = parse_yaml_policy_store(&policy_yaml, reject_unknown_keys)?;
+            (policy_store, None)
         },
         PolicyStoreSource::LockServer(policy_store_uri) => {
-            load_policy_store_from_lock_master(policy_store_uri).await?
+            let (policy_store, version) = load_policy_store_from_lock_master(
+                policy_store_uri,
+                http_config,
+                signature_config,
+                reject_unknown_keys,
+            )
+            .await?;
+            (policy_store, Some(version))
         },
         PolicyStoreSource::FileJson(path) => {
-            let policy_json = fs::read_to_string(path)
-                .map_err(|e| PolicyStoreLoadError::ParseFile(path.clone().into(), e))?;
-            let agama_policy_store = serde_json::from_str::<AgamaPolicyStore>(&policy_json)?;
-            extract_first_policy_store(&agama_policy_store)?
+            let policy_json = read_policy_store_file(path, signature_config)?;
+            let mut policy_store = parse_json_policy_store(&policy_json, reject_unknown_keys)?;
+            apply_saved_template_links(&mut policy_store, &config.source, signature_config)?;
+            (policy_store, None)
         },
         PolicyStoreSource::FileYaml(path) => {
-            let policy_yaml = fs::read_to_string(path)
-                .map_err(|e| PolicyStoreLoadError::ParseFile(path.clone().into(), e))?;
-            let agama_policy_store = serde_yml::from_str::<AgamaPolicyStore>(&policy_yaml)?;
-            extract_first_policy_store(&agama_policy_store)?
+            let policy_yaml = read_policy_store_file(path, signature_config)?;
+            let mut policy_store = parse_yaml_policy_store(&policy_yaml, reject_unknown_keys)?;
+            apply_saved_template_links(&mut policy_store, &config.source, signature_config)?;
+            (policy_store, None)
+        },
+        PolicyStoreSource::Directory(path) => {
+            if signature_config.is_some() {
+                Err(PolicyStoreSignatureError::UnsignableDirectory)?;
+            }
+            (load_directory(path)?, None)
+        },
+        PolicyStoreSource::Archive(path) => {
+            let archive = read_policy_store_archive(path, signature_config)?;
+            (load_archive(path, &archive)?, None)
+        },
+        PolicyStoreSource::InMemory(policy_store) => {
+            if signature_config.is_some() {
+                Err(PolicyStoreSignatureError::UnsignableInMemory)?;
+            }
+            (policy_store.clone(), None)
         },
     };
 
-    Ok(policy_store)
+    if let Some(signature_config) = signature_config {
+        check_signed_version(&policy_store, signature_config, None)?;
+    }
+    Ok((policy_store, version))
 }
 
 /// Loads the policy store from the Lock Master.
@@ -100,20 +229,314 @@ pub(crate) async fn load_policy_store(
 /// The URI is from the `CEDARLING_POLICY_STORE_URI` bootstrap property.
 async fn load_policy_store_from_lock_master(
     uri: &str,
+    http_config: &HttpClientConfig,
+    signature_config: Option<&PolicyStoreSignatureConfig>,
+    reject_unknown_keys: bool,
+) -> Result<(PolicyStoreWithID, RemoteStoreVersion), PolicyStoreLoadError> {
+    let client = HttpClient::new(http_config, 3, Duration::from_secs(3))?;
+    let response = client.get(uri).await?;
+    let signature = if needs_detached_signature(response.text(), signature_config) {
+        Some(client.get(&detached_signature_location(uri)).await?)
+    } else {
+        None
+    };
+    let policy_json = signed_content(
+        response.text(),
+        signature.as_ref().map(|signature| signature.text()),
+        signature_config,
+    )?;
+    let policy_store = parse_json_policy_store(&policy_json, reject_unknown_keys)?;
+    let version = RemoteStoreVersion::new(response.headers(), response.text());
+    Ok((policy_store, version))
+}
+
+/// Parses a JSON policy store document, see [`parse_policy_store_document`].
+fn parse_json_policy_store(
+    content: &str,
+    reject_unknown_keys: bool,
+) -> Result<PolicyStoreWithID, PolicyStoreLoadError> {
+    let document = serde_json::from_str::<Value>(content)?;
+    parse_policy_store_document(document, reject_unknown_keys)
+}
+
+/// Parses a YAML policy store document, see [`parse_policy_store_document`].
+fn parse_yaml_policy_store(
+    content: &str,
+    reject_unknown_keys: bool,
+) -> Result<PolicyStoreWithID, PolicyStoreLoadError> {
+    let document = serde_yml::from_str::<Value>(content)?;
+    parse_policy_store_document(document, reject_unknown_keys)
+}
+
+/// Checks the policy store document against the policy store schema, then extracts
+/// its policy store.
+fn parse_policy_store_document(
+    document: Value,
+    reject_unknown_keys: bool,
 ) -> Result<PolicyStoreWithID, PolicyStoreLoadError> {
-    let client = HttpClient::new(3, Duration::from_secs(3))?;
-    let agama_policy_store = client.get(uri).await?.json::<AgamaPolicyStore>()?;
+    validate_document(&document, reject_unknown_keys)
+        .map_err(PolicyStoreLoadError::InvalidDocument)?;
+    let agama_policy_store = AgamaPolicyStore::deserialize(document)
+        .map_err(|err| PolicyStoreLoadError::InvalidStore(err.to_string()))?;
     extract_first_policy_store(&agama_policy_store)
 }
 
+/// Reads a policy store file, along with its detached signature if it is required.
+fn read_policy_store_file(
+    path: &Path,
+    signature_config: Option<&PolicyStoreSignatureConfig>,
+) -> Result<String, PolicyStoreLoadError> {
+    let read = |path: &Path| {
+        fs::read_to_string(path).map_err(|e| PolicyStoreLoadError::ParseFile(path.into(), e))
+    };
+
+    let content = read(path)?;
+    let signature = if needs_detached_signature(&content, signature_config) {
+        let signature_path = detached_signature_location(&path.to_string_lossy());
+        Some(read(Path::new(&signature_path))?)
+    } else {
+        None
+    };
+    let content = signed_content(&content, signature.as_deref(), signature_config)?.into_owned();
+    Ok(content)
+}
+
+/// Reads a policy store archive and verifies its detached signature if it is required.
+fn read_policy_store_archive(
+    path: &Path,
+    signature_config: Option<&PolicyStoreSignatureConfig>,
+) -> Result<Vec<u8>, PolicyStoreLoadError> {
+    let archive = fs::read(path).map_err(|e| PolicyStoreLoadError::ParseFile(path.into(), e))?;
+    if let Some(signature_config) = signature_config {
+        let signature_path = detached_signature_location(&path.to_string_lossy());
+        let signature = fs::read_to_string(&signature_path)
+            .map_err(|e| PolicyStoreLoadError::ParseFile(Path::new(&signature_path).into(), e))?;
+        verify_detached_signature(&archive, &signature, signature_config)?;
+    }
+    Ok(archive)
+}
+
+/// Returns `true` if signatures are required and the policy store is not a JWS.
+fn needs_detached_signature(
+    content: &str,
+    signature_config: Option<&PolicyStoreSignatureConfig>,
+) -> bool {
+    signature_config.is_some() && !is_compact_jws(content)
+}
+
+/// Returns the signed policy store if signatures are required, otherwise the content
+/// as is.
+fn signed_content<'a>(
+    content: &'a str,
+    detached_signature: Option<&str>,
+    signature_config: Option<&PolicyStoreSignatureConfig>,
+) -> Result<Cow<'a, str>, PolicyStoreSignatureError> {
+    match signature_config {
+        Some(signature_config) => verify_signature(content, detached_signature, signature_config),
+        None => Ok(Cow::Borrowed(content)),
+    }
+}
+
+/// Fetches the policy store from the URI again with a conditional request.
+///
+/// The policy store is only parsed if the server responds with a different body than
+/// the one of `current`. If `signature_config` is set, the policy store must be signed
+/// with one of its trusted keys.
+pub(crate) async fn poll_policy_store(
+    sender: &mut Sender,
+    client: &Client,
+    uri: &str,
+    current: &RemoteStoreVersion,
+    signature_config: Option<&PolicyStoreSignatureConfig>,
+    reject_unknown_keys: bool,
+) -> Result<PolledPolicyStore, PolicyStoreLoadError> {
+    let response = sender
+        .send_request(|| {
+            let mut request = client.get(uri);
+            if let Some(etag) = &current.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &current.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+            request
+        })
+        .await?;
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(PolledPolicyStore::Unchanged(current.clone()));
+    }
+
+    let headers = response.headers().clone();
+    let body = response
+        .text()
+        .await
+        .map_err(PolicyStoreLoadError::ReadPolledStore)?;
+    let version = RemoteStoreVersion::new(&headers, &body);
+    if version.digest == current.digest {
+        return Ok(PolledPolicyStore::Unchanged(version));
+    }
+
+    let signature = if needs_detached_signature(&body, signature_config) {
+        let signature_uri = detached_signature_location(uri);
+        let response = sender.send_request(|| client.get(&signature_uri)).await?;
+        let signature = response
+            .text()
+            .await
+            .map_err(PolicyStoreLoadError::ReadPolledStore)?;
+        Some(signature)
+    } else {
+        None
+    };
+    let policy_json = signed_content(&body, signature.as_deref(), signature_config)?;
+    let policy_store = parse_json_policy_store(&policy_json, reject_unknown_keys)?;
+    if let Some(signature_config) = signature_config {
+        check_signed_version(&policy_store, signature_config, None)?;
+    }
+    Ok(PolledPolicyStore::Changed(Box::new(policy_store), version))
+}
+
+/// Template links saved next to a policy store file, see [`save_template_links`].
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedTemplateLinks<L> {
+    /// ID of the policy store the links belong to
+    policy_store_id: String,
+    template_links: L,
+}
+
+/// Returns the file the template links of the policy store source are saved to.
+///
+/// Only the policy store files have one, named after the file with a `.links.json`
+/// suffix. Signed policy stores have none: the links file is not covered by the
+/// signature, so the links are only kept in memory.
+fn template_links_location(
+    source: &PolicyStoreSource,
+    signature_config: Option<&PolicyStoreSignatureConfig>,
+) -> Option<PathBuf> {
+    if signature_config.is_some() {
+        return None;
+    }
+    match source {
+        PolicyStoreSource::FileJson(path) | PolicyStoreSource::FileYaml(path) => {
+            let mut links_path = path.as_os_str().to_owned();
+            links_path.push(".links.json");
+            Some(links_path.into())
+        },
+        PolicyStoreSource::Json(_)
+        | PolicyStoreSource::Yaml(_)
+        | PolicyStoreSource::LockServer(_)
+        | PolicyStoreSource::Directory(_)
+        | PolicyStoreSource::Archive(_)
+        | PolicyStoreSource::InMemory(_) => None,
+    }
+}
+
+/// Saves the template links of a policy store file to the links file next to it, the
+/// policy store file itself is never changed.
+///
+/// Returns `false` for the sources that have no links file, see
+/// [`template_links_location`].
+pub(crate) fn save_template_links(
+    source: &PolicyStoreSource,
+    signature_config: Option<&PolicyStoreSignatureConfig>,
+    policy_store_id: &str,
+    links: &HashMap<String, TemplateLink>,
+) -> io::Result<bool> {
+    let Some(path) = template_links_location(source, signature_config) else {
+        return Ok(false);
+    };
+
+    let saved = SavedTemplateLinks {
+        policy_store_id: policy_store_id.to_string(),
+        // sorted, so the file does not change on each save
+        template_links: links.iter().collect::<BTreeMap<_, _>>(),
+    };
+    let content = serde_json::to_string_pretty(&saved).map_err(io::Error::other)?;
+    write_file(&path, content.as_bytes())?;
+    Ok(true)
+}
+
+/// Applies the template links saved by [`save_template_links`] to the policy store
+/// loaded from a file.
+///
+/// The saved links replace the ones of the policy store file. Links saved for another
+/// policy store ID are ignored.
+fn apply_saved_template_links(
+    policy_store: &mut PolicyStoreWithID,
+    source: &PolicyStoreSource,
+    signature_config: Option<&PolicyStoreSignatureConfig>,
+) -> Result<(), PolicyStoreLoadError> {
+    let Some(path) = template_links_location(source, signature_config) else {
+        return Ok(());
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(PolicyStoreLoadError::ParseFile(path.into(), err)),
+    };
+    let saved = serde_json::from_str::<SavedTemplateLinks<HashMap<String, TemplateLink>>>(&content)
+        .map_err(|err| PolicyStoreLoadError::TemplateLinks(path.clone().into(), err.to_string()))?;
+    if saved.policy_store_id != policy_store.id {
+        return Ok(());
+    }
+
+    let policies = &mut policy_store.store.policies;
+    let link_err = |err: TemplateLinkError| {
+        PolicyStoreLoadError::TemplateLinks(path.clone().into(), err.to_string())
+    };
+    let stale_links = policies
+        .template_links()
+        .iter()
+        .filter(|(link_id, link)| saved.template_links.get(*link_id) != Some(link))
+        .map(|(link_id, _)| link_id.clone())
+        .collect::<Vec<_>>();
+    for link_id in stale_links {
+        policies.unlink_template(&link_id).map_err(link_err)?;
+    }
+    for (link_id, link) in saved.template_links {
+        if !policies.template_links().contains_key(&link_id) {
+            policies.link_template(&link_id, link).map_err(link_err)?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use std::path::Path;
+    use std::time::Duration;
+
+    use http_utils::{Backoff, Sender};
+    use mockito::{Matcher, Server};
+    use reqwest::Client;
+    use reqwest::header::HeaderMap;
 
-    use mockito::Server;
+    use super::{
+        PolicyStoreLoadError, PolledPolicyStore, RemoteStoreVersion, load_policy_store,
+        poll_policy_store,
+    };
+    use crate::init::policy_store_signature::PolicyStoreSignatureError;
+    use crate::init::policy_store_signature::test::{sign, signature_config};
+    use crate::{HttpClientConfig, PolicyStoreConfig};
 
-    use super::load_policy_store;
-    use crate::PolicyStoreConfig;
+    static LOCK_MASTER_POLICY_STORE: &str =
+        include_str!("../../../test_files/policy-store_lock_master_ok.json");
+
+    /// Returns the readable policy store with the given `version`
+    fn versioned_policy_store(version: &str) -> String {
+        let mut policy_store: serde_json::Value = serde_json::from_str(include_str!(
+            "../../../test_files/policy-store_readable.json"
+        ))
+        .unwrap();
+        for store in policy_store["policy_stores"]
+            .as_object_mut()
+            .unwrap()
+            .values_mut()
+        {
+            store["version"] = version.into();
+        }
+        policy_store.to_string()
+    }
 
     // NOTE: we probably don't need to test if the deserialization for JSON and YAML
     // works correctly anymore here since we already have tests for those in
@@ -121,26 +544,64 @@ mod test {
 
     #[tokio::test]
     async fn can_load_from_json_file() {
-        load_policy_store(&PolicyStoreConfig {
-            source: crate::PolicyStoreSource::FileJson(
-                Path::new("../test_files/policy-store_generated.json").into(),
-            ),
-        })
+        load_policy_store(
+            &PolicyStoreConfig {
+                source: crate::PolicyStoreSource::FileJson(
+                    Path::new("../test_files/policy-store_generated.json").into(),
+                ),
+            },
+            &HttpClientConfig::default(),
+            None,
+            false,
+        )
         .await
         .expect("Should load policy store from JSON file");
     }
 
     #[tokio::test]
     async fn can_load_from_yaml_file() {
-        load_policy_store(&PolicyStoreConfig {
-            source: crate::PolicyStoreSource::FileYaml(
-                Path::new("../test_files/policy-store_ok.yaml").into(),
-            ),
-        })
+        load_policy_store(
+            &PolicyStoreConfig {
+                source: crate::PolicyStoreSource::FileYaml(
+                    Path::new("../test_files/policy-store_ok.yaml").into(),
+                ),
+            },
+            &HttpClientConfig::default(),
+            None,
+            false,
+        )
         .await
         .expect("Should load policy store from YAML file");
     }
 
+    #[tokio::test]
+    async fn rejects_unknown_keys_in_strict_mode() {
+        let store_id = "8ec39ee717927663db9b18246f0479419051bb5af15a";
+        let mut policy_store: serde_json::Value =
+            serde_json::from_str(&versioned_policy_store("1.0.0")).unwrap();
+        policy_store["policy_stores"][store_id]["trusted_isuers"] = serde_json::json!({});
+
+        for source in [
+            crate::PolicyStoreSource::Json(policy_store.to_string()),
+            crate::PolicyStoreSource::Yaml(serde_yml::to_string(&policy_store).unwrap()),
+        ] {
+            let config = PolicyStoreConfig { source };
+            load_policy_store(&config, &HttpClientConfig::default(), None, false)
+                .await
+                .expect("unknown keys should be ignored");
+
+            let err = load_policy_store(&config, &HttpClientConfig::default(), None, true)
+                .await
+                .expect_err("unknown keys should be rejected");
+            let PolicyStoreLoadError::InvalidDocument(errors) = &err else {
+                panic!("expected a schema error, got: {err}");
+            };
+            assert_eq!(errors.len(), 1, "{err}");
+            assert_eq!(errors[0].path, format!("/policy_stores/{store_id}"));
+            assert!(errors[0].message.contains("trusted_isuers"), "{err}");
+        }
+    }
+
     #[tokio::test]
     async fn can_load_from_lock_master() {
         let mut mock_server = Server::new_async().await;
@@ -158,12 +619,226 @@ mod test {
 
         let uri = format!("{}/policy-store", mock_server.url()).to_string();
 
-        load_policy_store(&PolicyStoreConfig {
-            source: crate::PolicyStoreSource::LockServer(uri),
-        })
+        load_policy_store(
+            &PolicyStoreConfig {
+                source: crate::PolicyStoreSource::LockServer(uri),
+            },
+            &HttpClientConfig::default(),
+            None,
+            false,
+        )
         .await
         .expect("Should load policy store from Lock Master file");
 
         mock_endpoint.assert();
     }
+
+    fn sender() -> Sender {
+        Sender::new(Backoff::new_fixed(Duration::from_millis(1), Some(1)))
+    }
+
+    #[tokio::test]
+    async fn poll_sends_conditional_request() {
+        let mut mock_server = Server::new_async().await;
+        let mock_endpoint = mock_server
+            .mock("GET", "/policy-store")
+            .match_header("if-none-match", "\"v1\"")
+            .match_header("if-modified-since", "Wed, 21 Oct 2015 07:28:00 GMT")
+            .with_status(304)
+            .expect(1)
+            .create();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("etag", "\"v1\"".parse().unwrap());
+        headers.insert(
+            "last-modified",
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        let current = RemoteStoreVersion::new(&headers, LOCK_MASTER_POLICY_STORE);
+
+        let uri = format!("{}/policy-store", mock_server.url());
+        let polled = poll_policy_store(&mut sender(), &Client::new(), &uri, &current, None, false)
+            .await
+            .expect("should poll the policy store");
+
+        assert!(matches!(polled, PolledPolicyStore::Unchanged(_)));
+        mock_endpoint.assert();
+    }
+
+    #[tokio::test]
+    async fn poll_ignores_same_body() {
+        let mut mock_server = Server::new_async().await;
+        mock_server
+            .mock("GET", "/policy-store")
+            .match_header("if-none-match", Matcher::Missing)
+            .with_status(200)
+            .with_body(LOCK_MASTER_POLICY_STORE)
+            .create();
+
+        let current = RemoteStoreVersion::new(&HeaderMap::new(), LOCK_MASTER_POLICY_STORE);
+        let uri = format!("{}/policy-store", mock_server.url());
+        let polled = poll_policy_store(&mut sender(), &Client::new(), &uri, &current, None, false)
+            .await
+            .expect("should poll the policy store");
+
+        assert!(
+            matches!(polled, PolledPolicyStore::Unchanged(_)),
+            "a policy store with the same body should not be parsed again"
+        );
+    }
+
+    #[tokio::test]
+    async fn poll_returns_changed_policy_store() {
+        let mut mock_server = Server::new_async().await;
+        mock_server
+            .mock("GET", "/policy-store")
+            .with_status(200)
+            .with_header("etag", "\"v2\"")
+            .with_body(LOCK_MASTER_POLICY_STORE)
+            .create();
+
+        let current = RemoteStoreVersion::new(&HeaderMap::new(), "{}");
+        let uri = format!("{}/policy-store", mock_server.url());
+        let polled = poll_policy_store(&mut sender(), &Client::new(), &uri, &current, None, false)
+            .await
+            .expect("should poll the policy store");
+
+        let PolledPolicyStore::Changed(policy_store, version) = polled else {
+            panic!("the policy store should have changed");
+        };
+        assert_eq!(policy_store.id, "gICAgcHJpbmNpcGFsIGlz");
+        assert_eq!(version.etag.as_deref(), Some("\"v2\""));
+    }
+
+    #[tokio::test]
+    async fn can_load_signed_policy_store_from_file() {
+        let path = std::env::temp_dir().join(format!(
+            "cedarling-signed-store-{}.json",
+            crate::log::gen_uuid7()
+        ));
+        let signature_path = path.with_extension("json.jws");
+        let policy_store = versioned_policy_store("1.0.0");
+        let (_, detached) = sign(&policy_store);
+        std::fs::write(&path, &policy_store).unwrap();
+        std::fs::write(&signature_path, detached).unwrap();
+
+        let config = PolicyStoreConfig {
+            source: crate::PolicyStoreSource::FileJson(path.clone()),
+        };
+        let signature_config = signature_config(None);
+        load_policy_store(
+            &config,
+            &HttpClientConfig::default(),
+            Some(&signature_config),
+            false,
+        )
+        .await
+        .expect("Should load the policy store with a valid detached signature");
+
+        std::fs::write(&path, versioned_policy_store("1.0.1")).unwrap();
+        let err = load_policy_store(
+            &config,
+            &HttpClientConfig::default(),
+            Some(&signature_config),
+            false,
+        )
+        .await
+        .expect_err("a modified policy store should be rejected");
+        assert!(
+            matches!(
+                err,
+                PolicyStoreLoadError::Signature(PolicyStoreSignatureError::InvalidSignature)
+            ),
+            "{err}"
+        );
+
+        std::fs::remove_file(&signature_path).unwrap();
+        let err = load_policy_store(
+            &config,
+            &HttpClientConfig::default(),
+            Some(&signature_config),
+            false,
+        )
+        .await
+        .expect_err("the signature should be required");
+        assert!(matches!(err, PolicyStoreLoadError::ParseFile(..)), "{err}");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn can_load_signed_policy_store_from_lock_master() {
+        let mut mock_server = Server::new_async().await;
+        let (jws, _) = sign(&versioned_policy_store("1.2.0"));
+        mock_server
+            .mock("GET", "/policy-store")
+            .with_status(200)
+            .with_body(jws)
+            .create();
+
+        let config = PolicyStoreConfig {
+            source: crate::PolicyStoreSource::LockServer(format!(
+                "{}/policy-store",
+                mock_server.url()
+            )),
+        };
+        let policy_store = load_policy_store(
+            &config,
+            &HttpClientConfig::default(),
+            Some(&signature_config(Some("1.2.0"))),
+            false,
+        )
+        .await
+        .expect("Should load the policy store from the JWS");
+        assert_eq!(policy_store.version.as_deref(), Some("1.2.0"));
+
+        let err = load_policy_store(
+            &config,
+            &HttpClientConfig::default(),
+            Some(&signature_config(Some("2.0.0"))),
+            false,
+        )
+        .await
+        .expect_err("an older policy store should be rejected");
+        assert!(
+            matches!(
+                err,
+                PolicyStoreLoadError::Signature(PolicyStoreSignatureError::Rollback { .. })
+            ),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn poll_verifies_detached_signature() {
+        let mut mock_server = Server::new_async().await;
+        let policy_store = versioned_policy_store("1.0.0");
+        let (_, detached) = sign(&policy_store);
+        mock_server
+            .mock("GET", "/policy-store")
+            .with_status(200)
+            .with_body(&policy_store)
+            .create();
+        let signature_endpoint = mock_server
+            .mock("GET", "/policy-store.jws")
+            .with_status(200)
+            .with_body(detached)
+            .expect(1)
+            .create();
+
+        let current = RemoteStoreVersion::new(&HeaderMap::new(), "{}");
+        let uri = format!("{}/policy-store", mock_server.url());
+        let polled = poll_policy_store(
+            &mut sender(),
+            &Client::new(),
+            &uri,
+            &current,
+            Some(&signature_config(None)),
+            false,
+        )
+        .await
+        .expect("should poll the signed policy store");
+
+        assert!(matches!(polled, PolledPolicyStore::Changed(..)));
+        signature_endpoint.assert();
+    }
 }
@@ -0,0 +1,532 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Loading of a policy store from a directory or a zip archive of native Cedar files.
+//!
+//! The policy store has the following layout, in the directory or at the root of the
+//! archive (the archive can also contain a single top directory with the files):
+//!
+//! ```txt
+//! manifest.json            id, name, version... of the policy store
+//! schema.cedarschema       Cedar schema
+//! policies/*.cedar         policies and policy templates
+//! trusted-issuers/*.json   one trusted issuer per file
+//! entities/*.json          one default entity per file
+//! ```
+//!
+//! The ID of a policy is its `@id` annotation, or the name of its file without the
+//! extension if the file only has one policy. The ID of the trusted issuers and default
+//! entities is the name of their file. The other files are ignored.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use cedar_policy::{PolicySet, Schema};
+use miette::Diagnostic;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use zip::ZipArchive;
+
+use super::policy_store::PolicyStoreLoadError;
+use super::policy_validation::{SourceSpan, line_and_column};
+use crate::common::policy_store::{PolicyStore, PolicyStoreWithID, TrustedIssuer};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const SCHEMA_FILE: &str = "schema.cedarschema";
+const POLICIES_DIR: &str = "policies";
+const TRUSTED_ISSUERS_DIR: &str = "trusted-issuers";
+const ENTITIES_DIR: &str = "entities";
+
+/// Files of an archive larger than this are rejected, so a small archive cannot
+/// decompress to an arbitrary amount of memory.
+const MAX_ARCHIVE_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// An error in a file of a policy store directory or archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyStoreFileError {
+    /// Path of the file, relative to the root of the policy store.
+    pub file: String,
+    /// Location of the error in the file, if it is known.
+    pub span: Option<SourceSpan>,
+    /// Description of the error.
+    pub message: String,
+}
+
+impl PolicyStoreFileError {
+    fn new(file: &str, message: impl ToString) -> Self {
+        Self {
+            file: file.to_string(),
+            span: None,
+            message: message.to_string(),
+        }
+    }
+
+    fn at(mut self, span: Option<SourceSpan>) -> Self {
+        self.span = span;
+        self
+    }
+}
+
+impl Display for PolicyStoreFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.span {
+            Some(span) => write!(
+                f,
+                "{}:{}:{}: {}",
+                self.file, span.line, span.column, self.message
+            ),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+impl std::error::Error for PolicyStoreFileError {}
+
+/// `manifest.json` of a policy store directory or archive.
+#[derive(Deserialize)]
+struct Manifest {
+    id: String,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    cedar_version: Option<String>,
+    #[serde(default)]
+    role_hierarchy: Option<Value>,
+    #[serde(default)]
+    principal_bool_operations: Option<Value>,
+}
+
+/// Loads a policy store from a directory.
+pub(crate) fn load_directory(root: &Path) -> Result<PolicyStoreWithID, PolicyStoreLoadError> {
+    let read = |path: &Path| {
+        fs::read_to_string(path).map_err(|e| PolicyStoreLoadError::ParseFile(path.into(), e))
+    };
+
+    let mut files = BTreeMap::new();
+    for name in [MANIFEST_FILE, SCHEMA_FILE] {
+        let path = root.join(name);
+        if path.is_file() {
+            files.insert(name.to_string(), read(&path)?);
+        }
+    }
+    for dir in [POLICIES_DIR, TRUSTED_ISSUERS_DIR, ENTITIES_DIR] {
+        let dir_path = root.join(dir);
+        if !dir_path.is_dir() {
+            continue;
+        }
+        let entries = fs::read_dir(&dir_path)
+            .map_err(|e| PolicyStoreLoadError::ParseFile(dir_path.clone().into(), e))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| PolicyStoreLoadError::ParseFile(dir_path.clone().into(), e))?
+                .path();
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if path.is_file() {
+                files.insert(format!("{dir}/{file_name}"), read(&path)?);
+            }
+        }
+    }
+
+    build_policy_store(&files)
+}
+
+/// Loads a policy store from the content of a zip archive.
+pub(crate) fn load_archive(
+    path: &Path,
+    content: &[u8],
+) -> Result<PolicyStoreWithID, PolicyStoreLoadError> {
+    let archive_err = |err: zip::result::ZipError| {
+        PolicyStoreLoadError::ReadArchive(path.into(), err.to_string())
+    };
+    let mut archive = ZipArchive::new(Cursor::new(content)).map_err(archive_err)?;
+
+    // the files are either at the root or in the top directory with the manifest
+    let prefix = archive
+        .file_names()
+        .filter_map(|name| name.strip_suffix(MANIFEST_FILE))
+        .filter(|prefix| prefix.is_empty() || prefix.ends_with('/'))
+        .min_by_key(|prefix| prefix.len())
+        .unwrap_or_default()
+        .to_string();
+
+    let mut files = BTreeMap::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index(i).map_err(archive_err)?;
+        let Some(name) = file.name().strip_prefix(&prefix).map(str::to_string) else {
+            continue;
+        };
+        if file.is_dir() || !is_layout_file(&name) {
+            continue;
+        }
+        if file.size() > MAX_ARCHIVE_FILE_SIZE {
+            return Err(PolicyStoreFileError::new(
+                &name,
+                format!("the file is larger than {MAX_ARCHIVE_FILE_SIZE} bytes"),
+            )
+            .into());
+        }
+
+        let mut content = String::new();
+        file.take(MAX_ARCHIVE_FILE_SIZE)
+            .read_to_string(&mut content)
+            .map_err(|err| PolicyStoreFileError::new(&name, err))?;
+        files.insert(name, content);
+    }
+
+    build_policy_store(&files)
+}
+
+/// Returns `true` if the path, relative to the root of the policy store, is part of
+/// the layout.
+fn is_layout_file(path: &str) -> bool {
+    match path.split_once('/') {
+        None => path == MANIFEST_FILE || path == SCHEMA_FILE,
+        Some((dir, name)) => {
+            [POLICIES_DIR, TRUSTED_ISSUERS_DIR, ENTITIES_DIR].contains(&dir)
+                && !name.is_empty()
+                && !name.contains('/')
+        },
+    }
+}
+
+/// Builds the policy store from the files, keyed by their path relative to the root.
+///
+/// Each file is checked on its own first, so the errors point at the file, then the
+/// policy store is deserialized like the JSON and YAML policy stores.
+fn build_policy_store(
+    files: &BTreeMap<String, String>,
+) -> Result<PolicyStoreWithID, PolicyStoreLoadError> {
+    let manifest_src = required_file(files, MANIFEST_FILE)?;
+    let manifest = parse_json::<Manifest>(MANIFEST_FILE, manifest_src)?;
+
+    let schema_src = required_file(files, SCHEMA_FILE)?;
+    Schema::from_cedarschema_str(schema_src)
+        .map(|_| ())
+        .map_err(|err| diagnostic_error(SCHEMA_FILE, schema_src, &err))?;
+
+    let (policies, templates) = read_policies(files)?;
+
+    let mut trusted_issuers = serde_json::Map::new();
+    for (file, src) in files_in(files, TRUSTED_ISSUERS_DIR, "json") {
+        parse_json::<TrustedIssuer>(file, src)?;
+        trusted_issuers.insert(file_stem(file), parse_json::<Value>(file, src)?);
+    }
+
+    let mut default_entities = serde_json::Map::new();
+    for (file, src) in files_in(files, ENTITIES_DIR, "json") {
+        let entity = parse_json::<Value>(file, src)?;
+        if !entity.is_object() {
+            return Err(PolicyStoreFileError::new(file, "the entity must be a JSON object").into());
+        }
+        // the default entities of the other formats are base64 encoded
+        let entity = BASE64_STANDARD.encode(entity.to_string());
+        default_entities.insert(file_stem(file), entity.into());
+    }
+
+    let mut store = json!({
+        "name": manifest.name,
+        "schema": {
+            "encoding": "none",
+            "content_type": "cedar",
+            "body": schema_src,
+        },
+        "policies": policies,
+        "policy_templates": templates,
+    });
+    let optional_fields = [
+        ("description", manifest.description.map(Value::from)),
+        ("version", manifest.version.map(Value::from)),
+        ("cedar_version", manifest.cedar_version.map(Value::from)),
+        ("role_hierarchy", manifest.role_hierarchy),
+        (
+            "principal_bool_operations",
+            manifest.principal_bool_operations,
+        ),
+        (
+            "trusted_issuers",
+            (!trusted_issuers.is_empty()).then_some(trusted_issuers.into()),
+        ),
+        (
+            "default_entities",
+            (!default_entities.is_empty()).then_some(default_entities.into()),
+        ),
+    ];
+    for (key, value) in optional_fields {
+        if let Some(value) = value {
+            store[key] = value;
+        }
+    }
+
+    let store = PolicyStore::deserialize(store)
+        .map_err(|err| PolicyStoreLoadError::InvalidStore(err.to_string()))?;
+    Ok(PolicyStoreWithID {
+        id: manifest.id,
+        store,
+    })
+}
+
+/// Policies or policy templates keyed by their ID, in the format of the policy store
+type PolicyEntries = serde_json::Map<String, Value>;
+
+/// Reads the policies and the policy templates of the `policies` directory.
+fn read_policies(
+    files: &BTreeMap<String, String>,
+) -> Result<(PolicyEntries, PolicyEntries), PolicyStoreLoadError> {
+    let mut policies = serde_json::Map::new();
+    let mut templates = serde_json::Map::new();
+    let mut policy_files = HashMap::<String, &str>::new();
+
+    for (file, src) in files_in(files, POLICIES_DIR, "cedar") {
+        let policy_set =
+            PolicySet::from_str(src).map_err(|err| diagnostic_error(file, src, &err))?;
+
+        let sources = policy_set
+            .policies()
+            .map(|policy| (policy.annotation("id"), policy.to_string(), false))
+            .chain(
+                policy_set
+                    .templates()
+                    .map(|template| (template.annotation("id"), template.to_string(), true)),
+            )
+            .collect::<Vec<_>>();
+        let single_policy = sources.len() == 1;
+
+        for (id, body, is_template) in sources {
+            let span = src
+                .find(&body)
+                .and_then(|offset| line_and_column(src, offset));
+            let id = match id {
+                Some(id) => id.to_string(),
+                None if single_policy => file_stem(file),
+                None => {
+                    return Err(PolicyStoreFileError::new(
+                        file,
+                        "the file has several policies, each one needs an `@id` annotation",
+                    )
+                    .at(span)
+                    .into());
+                },
+            };
+            if let Some(other_file) = policy_files.insert(id.clone(), file) {
+                return Err(PolicyStoreFileError::new(
+                    file,
+                    format!("the policy ID `{id}` is also used in {other_file}"),
+                )
+                .at(span)
+                .into());
+            }
+
+            let policy = json!({
+                "description": "",
+                "policy_content": {
+                    "encoding": "none",
+                    "content_type": "cedar",
+                    "body": body,
+                },
+            });
+            if is_template {
+                templates.insert(id, policy);
+            } else {
+                policies.insert(id, policy);
+            }
+        }
+    }
+
+    Ok((policies, templates))
+}
+
+fn required_file<'a>(
+    files: &'a BTreeMap<String, String>,
+    name: &str,
+) -> Result<&'a str, PolicyStoreFileError> {
+    files
+        .get(name)
+        .map(String::as_str)
+        .ok_or_else(|| PolicyStoreFileError::new(name, "the file is missing"))
+}
+
+/// Returns the files of a directory of the layout with the given extension.
+fn files_in<'a>(
+    files: &'a BTreeMap<String, String>,
+    dir: &'a str,
+    extension: &'a str,
+) -> impl Iterator<Item = (&'a str, &'a str)> {
+    files.iter().filter_map(move |(path, src)| {
+        let name = path.strip_prefix(dir)?.strip_prefix('/')?;
+        let is_match = Path::new(name).extension().and_then(|ext| ext.to_str()) == Some(extension);
+        is_match.then_some((path.as_str(), src.as_str()))
+    })
+}
+
+fn file_stem(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn parse_json<T: DeserializeOwned>(file: &str, src: &str) -> Result<T, PolicyStoreFileError> {
+    serde_json::from_str(src).map_err(|err| {
+        let span = (err.line() > 0).then(|| SourceSpan {
+            line: err.line(),
+            column: err.column(),
+        });
+        PolicyStoreFileError::new(file, err).at(span)
+    })
+}
+
+fn diagnostic_error<E: Diagnostic>(file: &str, src: &str, err: &E) -> PolicyStoreFileError {
+    let span = err
+        .labels()
+        .and_then(|mut labels| labels.next())
+        .and_then(|label| line_and_column(src, label.offset()));
+    PolicyStoreFileError::new(file, err).at(span)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn layout_files(policies: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut files = vec![
+            (
+                MANIFEST_FILE.to_string(),
+                r#"{"id": "store_1", "name": "Jans", "version": "1.0.0"}"#.to_string(),
+            ),
+            (
+                SCHEMA_FILE.to_string(),
+                "namespace Jans {\n  entity User;\n  entity Issue;\n  action \"Update\" \
+                 appliesTo {\n    principal: [User],\n    resource: [Issue]\n  };\n}\n"
+                    .to_string(),
+            ),
+            (
+                "entities/org_1.json".to_string(),
+                r#"{"entity_id": "org_1", "entity_type": "Jans::Issue"}"#.to_string(),
+            ),
+        ];
+        files.extend(
+            policies
+                .iter()
+                .map(|(name, src)| (format!("policies/{name}"), src.to_string())),
+        );
+        files
+    }
+
+    fn write_archive(files: &[(String, String)], prefix: &str) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer
+                .start_file(format!("{prefix}{name}"), SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn build(files: &[(String, String)]) -> Result<PolicyStoreWithID, PolicyStoreLoadError> {
+        build_policy_store(&files.iter().cloned().collect())
+    }
+
+    #[test]
+    fn builds_policy_store_from_files() {
+        let files = layout_files(&[
+            (
+                "allow_update.cedar",
+                "permit(principal, action == Jans::Action::\"Update\", resource);",
+            ),
+            (
+                "shared.cedar",
+                "@id(\"share_issue\")\npermit(principal == ?principal, action, resource == \
+                 ?resource);\n\n@id(\"deny_all\")\nforbid(principal, action, resource) when \
+                 { false };",
+            ),
+        ]);
+
+        let policy_store = build(&files).expect("should build the policy store");
+        assert_eq!(policy_store.id, "store_1");
+        assert_eq!(policy_store.version.as_deref(), Some("1.0.0"));
+        let policy_set = policy_store.policies.get_set();
+        assert!(
+            policy_set
+                .policy(&"allow_update".parse().unwrap())
+                .is_some(),
+            "the ID should be the name of the file"
+        );
+        assert!(policy_set.policy(&"deny_all".parse().unwrap()).is_some());
+        assert!(
+            policy_set
+                .template(&"share_issue".parse().unwrap())
+                .is_some()
+        );
+        assert!(
+            policy_store
+                .default_entities
+                .as_ref()
+                .is_some_and(|entities| entities.contains_key("org_1"))
+        );
+    }
+
+    #[test]
+    fn errors_point_at_file_and_line() {
+        let files = layout_files(&[(
+            "broken.cedar",
+            "permit(\n  principal,\n  action ==,\n  resource\n);",
+        )]);
+        let err = build(&files).expect_err("the policy should not parse");
+        let PolicyStoreLoadError::InvalidFile(err) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(err.file, "policies/broken.cedar");
+        assert_eq!(err.span.map(|span| span.line), Some(3), "{err}");
+
+        let mut files = layout_files(&[]);
+        files[0].1 = "{\n  \"id\": \"store_1\",\n  \"name\": 1\n}".to_string();
+        let err = build(&files).expect_err("the manifest should not parse");
+        assert!(err.to_string().contains("manifest.json:3:"), "{err}");
+
+        let files = layout_files(&[(
+            "two.cedar",
+            "permit(principal, action, resource);\nforbid(principal, action, resource);",
+        )]);
+        let err = build(&files).expect_err("the policies need an @id");
+        let PolicyStoreLoadError::InvalidFile(err) = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(err.file, "policies/two.cedar");
+        assert!(err.span.is_some(), "the error should point at the policy");
+    }
+
+    #[test]
+    fn loads_archive_with_top_directory() {
+        let files = layout_files(&[("allow_update.cedar", "permit(principal, action, resource);")]);
+        let archive = write_archive(&files, "my-store/");
+
+        let policy_store = load_archive(Path::new("my-store.zip"), &archive)
+            .expect("should load the policy store from the archive");
+        assert_eq!(policy_store.id, "store_1");
+        assert!(
+            policy_store
+                .policies
+                .get_set()
+                .policy(&"allow_update".parse().unwrap())
+                .is_some()
+        );
+    }
+}
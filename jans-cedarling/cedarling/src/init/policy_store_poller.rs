@@ -0,0 +1,248 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! The [`PolicyStorePoller`] runs in the background and fetches the policy store of
+//! `CEDARLING_POLICY_STORE_URI` again on an interval.
+//!
+//! The requests are conditional (`If-None-Match` and `If-Modified-Since`) and a policy
+//! store is only applied if its body changed and it passes the same checks as on
+//! startup. Otherwise the current policy store is kept. If the trusted issuers changed,
+//! the JWTs are validated with the trusted issuers of the new policy store from then on.
+
+use super::policy_store::{
+    PolicyStoreLoadError, PolledPolicyStore, RemoteStoreVersion, poll_policy_store,
+};
+use super::policy_store_signature::{PolicyStoreSignatureError, check_signed_version};
+use super::policy_validation::check_policy_store;
+use super::service_factory::build_entity_builder;
+use crate::PolicyStoreSource;
+use crate::authz::Authz;
+use crate::bootstrap_config::{
+    BootstrapConfig, EntityBuilderConfig, HttpClientConfig, JwtConfig, PolicyStoreSignatureConfig,
+    PolicyValidationConfig,
+};
+use crate::common::policy_store::PolicyStoreWithID;
+use crate::entity_builder::InitEntityBuilderError;
+use crate::http::{HttpClientBuildError, build_client, circuit_breaker, log_failed_attempts};
+use crate::jwt::{JwtService, JwtServiceInitError};
+use crate::log::interface::LogWriter;
+use crate::log::{LogEntry, LogLevel, LogType, Logger};
+use http_utils::{Backoff, Sender};
+use rand::Rng;
+use reqwest::Client;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+
+/// Each interval is randomized by up to 20% so a fleet of Cedarling instances does not
+/// poll the server at the same time.
+const POLL_JITTER: f64 = 0.2;
+
+/// The base duration to wait for if a poll request fails.
+#[cfg(not(test))]
+const POLL_RETRY_DUR: Duration = Duration::from_secs(1);
+// We implement a faster retry for the tests
+#[cfg(test)]
+const POLL_RETRY_DUR: Duration = Duration::from_millis(10);
+
+/// Polls the policy store and replaces the policy store of [`Authz`] when it changes.
+pub(crate) struct PolicyStorePoller {
+    uri: String,
+    interval: Duration,
+    client: Client,
+    sender: Sender,
+    version: RemoteStoreVersion,
+    authz: Weak<Authz>,
+    validation_config: PolicyValidationConfig,
+    signature_config: Option<PolicyStoreSignatureConfig>,
+    entity_builder_config: EntityBuilderConfig,
+    jwt_config: JwtConfig,
+    idp_http_config: HttpClientConfig,
+    token_cache_max_ttl_secs: usize,
+    log: Logger,
+}
+
+impl PolicyStorePoller {
+    /// Creates the poller if `CEDARLING_POLICY_STORE_POLL_INTERVAL` is set and the
+    /// policy store was fetched from a URI.
+    pub fn new(
+        bootstrap: &BootstrapConfig,
+        version: Option<RemoteStoreVersion>,
+        authz: &Arc<Authz>,
+        log: Logger,
+    ) -> Result<Option<Self>, HttpClientBuildError> {
+        let Some(interval) = bootstrap.policy_store_poll_interval else {
+            return Ok(None);
+        };
+        let (PolicyStoreSource::LockServer(uri), Some(version)) =
+            (&bootstrap.policy_store_config.source, version)
+        else {
+            log.log_any(
+                LogEntry::new_with_data(LogType::System, None)
+                    .set_level(LogLevel::WARN)
+                    .set_message(
+                        "CEDARLING_POLICY_STORE_POLL_INTERVAL is ignored, only the policy store \
+                         of CEDARLING_POLICY_STORE_URI is polled"
+                            .to_string(),
+                    ),
+            );
+            return Ok(None);
+        };
+
+        let sender =
+            Sender::new(Backoff::new_exponential(POLL_RETRY_DUR, Some(3)).with_jitter(POLL_JITTER))
+                .with_circuit_breaker(circuit_breaker(uri, Some(log.clone())));
+        let sender = log_failed_attempts(sender, Some(log.clone()));
+
+        Ok(Some(Self {
+            uri: uri.clone(),
+            interval,
+            client: build_client(&bootstrap.http_config.policy_store)?,
+            sender,
+            version,
+            authz: Arc::downgrade(authz),
+            validation_config: bootstrap.policy_validation_config.clone(),
+            signature_config: bootstrap.policy_store_signature_config.clone(),
+            entity_builder_config: bootstrap.entity_builder_config.clone(),
+            jwt_config: bootstrap.jwt_config.clone(),
+            idp_http_config: bootstrap.http_config.idp.clone(),
+            token_cache_max_ttl_secs: bootstrap.token_cache_max_ttl_secs,
+            log,
+        }))
+    }
+
+    /// Polls the policy store until the token is cancelled or Cedarling is dropped.
+    pub async fn run(mut self, cancel_tkn: CancellationToken) {
+        loop {
+            tokio::select! {
+                _ = sleep(jittered(self.interval)) => {},
+                _ = cancel_tkn.cancelled() => break,
+            }
+
+            let Some(authz) = self.authz.upgrade() else {
+                break;
+            };
+            self.poll(&authz).await;
+        }
+    }
+
+    async fn poll(&mut self, authz: &Authz) {
+        let result = poll_policy_store(
+            &mut self.sender,
+            &self.client,
+            &self.uri,
+            &self.version,
+            self.signature_config.as_ref(),
+            self.validation_config.reject_unknown_keys,
+        )
+        .await;
+
+        match result {
+            Ok(PolledPolicyStore::Unchanged(version)) => {
+                self.version = version;
+                self.log_system(
+                    LogLevel::DEBUG,
+                    "the polled policy store did not change",
+                    None,
+                );
+            },
+            Ok(PolledPolicyStore::Changed(policy_store, version)) => {
+                // a rejected policy store is not checked again until its body changes
+                self.version = version;
+                let store_version = policy_store.get_store_version().to_string();
+                match self.apply(authz, *policy_store).await {
+                    Ok(()) => self.log_system(
+                        LogLevel::INFO,
+                        &format!("applied version `{store_version}` of the polled policy store"),
+                        None,
+                    ),
+                    Err(err) => self.log_system(
+                        LogLevel::ERROR,
+                        "the polled policy store was rejected, keeping the current policy store",
+                        Some(err.to_string()),
+                    ),
+                }
+            },
+            Err(err) => self.log_system(
+                LogLevel::ERROR,
+                &format!(
+                    "failed to poll the policy store from '{}', keeping the current policy store",
+                    self.uri
+                ),
+                Some(err.to_string()),
+            ),
+        }
+    }
+
+    /// Checks the policy store like on startup then replaces the current one.
+    ///
+    /// A signed policy store must not be older than the current one. If the trusted
+    /// issuers changed, they are initialized again and the policy store is rejected if
+    /// that fails.
+    async fn apply(
+        &self,
+        authz: &Authz,
+        policy_store: PolicyStoreWithID,
+    ) -> Result<(), ApplyError> {
+        if let Some(signature_config) = &self.signature_config {
+            check_signed_version(&policy_store, signature_config, Some(&authz.policy_store()))?;
+        }
+        check_policy_store(&policy_store, &self.validation_config, &self.log)?;
+        let entity_builder =
+            build_entity_builder(&self.entity_builder_config, &policy_store, self.log.clone())?;
+
+        let jwt_service = if policy_store.trusted_issuers != authz.policy_store().trusted_issuers {
+            let jwt_service = JwtService::new(
+                &self.jwt_config,
+                &self.idp_http_config,
+                policy_store.trusted_issuers.clone(),
+                Some(self.log.clone()),
+                self.token_cache_max_ttl_secs,
+            )
+            .await?;
+            self.log_system(
+                LogLevel::INFO,
+                "the trusted issuers of the polled policy store changed and were initialized \
+                 again",
+                None,
+            );
+            Some(Arc::new(jwt_service))
+        } else {
+            None
+        };
+
+        authz.replace_policy_store(policy_store, Arc::new(entity_builder), jwt_service);
+        Ok(())
+    }
+
+    fn log_system(&self, level: LogLevel, msg: &str, err: Option<String>) {
+        let mut entry = LogEntry::new_with_data(LogType::System, None)
+            .set_level(level)
+            .set_message(msg.to_string());
+        if let Some(err) = err {
+            entry = entry.set_error(err);
+        }
+        self.log.log_any(entry);
+    }
+}
+
+/// Randomizes the interval by up to [`POLL_JITTER`].
+fn jittered(interval: Duration) -> Duration {
+    interval.mul_f64(rand::thread_rng().gen_range(1.0 - POLL_JITTER..=1.0 + POLL_JITTER))
+}
+
+/// Error that makes the poller reject a polled policy store.
+#[derive(Debug, thiserror::Error)]
+enum ApplyError {
+    #[error(transparent)]
+    Check(#[from] PolicyStoreLoadError),
+    #[error(transparent)]
+    Signature(#[from] PolicyStoreSignatureError),
+    #[error("failed to create the entity builder: {0}")]
+    EntityBuilder(#[from] InitEntityBuilderError),
+    #[error("failed to initialize the trusted issuers: {0}")]
+    JwtService(#[from] JwtServiceInitError),
+}
@@ -0,0 +1,213 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Validation of the JSON and YAML policy store documents against
+//! `schema/policy_store_schema.json`.
+//!
+//! The documents are checked before they are deserialized, so every mistake is
+//! reported with the JSON pointer of the invalid value. The schema closes its objects
+//! with `"additionalProperties": false`; unless the unknown keys are rejected, these
+//! are removed from the schema so the unknown keys are ignored like before.
+
+use std::fmt::Display;
+use std::sync::LazyLock;
+
+use jsonschema::Validator;
+use serde_json::Value;
+
+const POLICY_STORE_SCHEMA: &str = include_str!("../../../schema/policy_store_schema.json");
+
+static VALIDATOR: LazyLock<Validator> = LazyLock::new(|| build_validator(false));
+static STRICT_VALIDATOR: LazyLock<Validator> = LazyLock::new(|| build_validator(true));
+
+/// A value of a policy store document that does not match the policy store schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyStoreSchemaError {
+    /// JSON pointer to the value, e.g. `/policy_stores/abc/trusted_issuers/jans`.
+    /// Empty for the document itself.
+    pub path: String,
+    /// Description of the error.
+    pub message: String,
+}
+
+impl Display for PolicyStoreSchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Checks a policy store document against the policy store schema and returns all the
+/// errors.
+pub(crate) fn validate_document(
+    document: &Value,
+    reject_unknown_keys: bool,
+) -> Result<(), Vec<PolicyStoreSchemaError>> {
+    let validator = if reject_unknown_keys {
+        &*STRICT_VALIDATOR
+    } else {
+        &*VALIDATOR
+    };
+
+    let errors = validator
+        .iter_errors(document)
+        .map(|err| PolicyStoreSchemaError {
+            path: err.instance_path.to_string(),
+            // the values are masked, they can be a whole policy store entry
+            message: err.masked().to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn build_validator(reject_unknown_keys: bool) -> Validator {
+    let mut schema = serde_json::from_str::<Value>(POLICY_STORE_SCHEMA)
+        .expect("the policy store schema should be valid JSON");
+    if !reject_unknown_keys {
+        allow_unknown_keys(&mut schema);
+    }
+    jsonschema::draft202012::new(&schema)
+        .expect("the policy store schema should be a valid JSON schema")
+}
+
+/// Removes the `"additionalProperties": false` of the schema, so its objects accept
+/// any key.
+fn allow_unknown_keys(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            if object.get("additionalProperties") == Some(&Value::Bool(false)) {
+                object.remove("additionalProperties");
+            }
+            object.values_mut().for_each(allow_unknown_keys);
+        },
+        Value::Array(items) => items.iter_mut().for_each(allow_unknown_keys),
+        _ => {},
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn document(trusted_issuer: Value) -> Value {
+        json!({
+            "cedar_version": "v4.0.0",
+            "policy_stores": {
+                "store_id": {
+                    "name": "Jans",
+                    "schema": "bmFtZXNwYWNlIEphbnMge30=",
+                    "policies": {},
+                    "trusted_issuers": {
+                        "jans": trusted_issuer,
+                    },
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn unknown_keys_are_only_rejected_in_strict_mode() {
+        let document = document(json!({
+            "name": "Jans",
+            "description": "",
+            "openid_configuration_endpoint": "https://test.jans.org/.well-known/openid-configuration",
+            "token_metdata": {},
+        }));
+
+        validate_document(&document, false).expect("unknown keys should be ignored");
+
+        let errors =
+            validate_document(&document, true).expect_err("unknown keys should be rejected");
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert_eq!(
+            errors[0].path,
+            "/policy_stores/store_id/trusted_issuers/jans"
+        );
+        assert!(errors[0].message.contains("token_metdata"), "{}", errors[0]);
+    }
+
+    #[test]
+    fn reports_every_error_with_its_path() {
+        let document = document(json!({
+            "name": "Jans",
+            "openid_configuration_endpoint": 42,
+            "token_metadata": {
+                "access_token": {
+                    "trusted": "yes",
+                },
+            },
+        }));
+
+        let errors = validate_document(&document, false).expect_err("document should be invalid");
+        let mut paths = errors
+            .iter()
+            .map(|err| err.path.as_str())
+            .collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                // missing `description`
+                "/policy_stores/store_id/trusted_issuers/jans",
+                "/policy_stores/store_id/trusted_issuers/jans/openid_configuration_endpoint",
+                // missing `entity_type_name`
+                "/policy_stores/store_id/trusted_issuers/jans/token_metadata/access_token",
+                "/policy_stores/store_id/trusted_issuers/jans/token_metadata/access_token/trusted",
+            ],
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn accepts_claim_ref_paths() {
+        let document = document(json!({
+            "name": "Jans",
+            "description": "",
+            "openid_configuration_endpoint": "https://test.jans.org/.well-known/openid-configuration",
+            "token_metadata": {
+                "access_token": {
+                    "entity_type_name": "Jans::Access_token",
+                    "user_id": {"path": "$.user.id"},
+                    "role_mapping": {"path": "$.realm_access.roles"},
+                    "workload_id": "client_id",
+                    "membership_mapping": [{
+                        "claim": {"path": "$.resource_access['my-client'].groups"},
+                        "entity_type": "Group",
+                    }],
+                },
+            },
+        }));
+
+        validate_document(&document, false).expect("claim ref paths should be valid");
+        validate_document(&document, true).expect("claim ref paths should be valid");
+    }
+
+    #[test]
+    fn accepts_test_policy_stores() {
+        for policy_store in [
+            include_str!("../../../test_files/policy-store_ok.yaml"),
+            include_str!("../../../test_files/policy-store_ok_abac.yaml"),
+            include_str!("../../../test_files/policy-store_entity_mapping.yaml"),
+        ] {
+            let document = serde_yml::from_str::<Value>(policy_store).unwrap();
+            validate_document(&document, false).expect("policy store should be valid");
+        }
+
+        let document = serde_json::from_str::<Value>(include_str!(
+            "../../../test_files/policy-store_lock_master_ok.json"
+        ))
+        .unwrap();
+        validate_document(&document, false).expect("policy store should be valid");
+    }
+}
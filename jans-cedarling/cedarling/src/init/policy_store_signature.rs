@@ -0,0 +1,344 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Verification of signed policy stores.
+//!
+//! When `CEDARLING_POLICY_STORE_TRUSTED_KEYS` is set, a policy store is only loaded if it
+//! is signed with one of the trusted keys, either:
+//!
+//! - as a JWS in compact serialization with the policy store as its payload, or
+//! - with a detached JWS ([RFC 7515, Appendix F]) read from the location of the policy
+//!   store with the `.jws` suffix, e.g. `policy-store.json.jws`.
+//!
+//! The `version` of the policy store is part of the signed payload, so it must be set to
+//! a semantic version. It is checked against `CEDARLING_POLICY_STORE_MIN_VERSION` and,
+//! when the policy store is polled, against the version of the current policy store so
+//! an older signed policy store cannot be served again.
+//!
+//! [RFC 7515, Appendix F]: https://datatracker.ietf.org/doc/html/rfc7515#appendix-F
+
+use std::borrow::Cow;
+
+use crate::bootstrap_config::PolicyStoreSignatureConfig;
+use crate::common::policy_store::PolicyStoreWithID;
+use crate::jwt::cast_to_algorithm;
+use base64::Engine;
+use base64::prelude::BASE64_URL_SAFE_NO_PAD;
+use jsonwebtoken::{Algorithm, DecodingKey, Header, crypto};
+use semver::Version;
+
+/// Suffix added to the location of a policy store to get its detached signature.
+const DETACHED_SIGNATURE_SUFFIX: &str = ".jws";
+
+/// Errors that can occur when verifying a signed policy store.
+#[derive(Debug, thiserror::Error)]
+pub enum PolicyStoreSignatureError {
+    #[error("the policy store is not signed")]
+    MissingSignature,
+    #[error("a policy store directory cannot be signed, use a policy store archive instead")]
+    UnsignableDirectory,
+    #[error("a policy store built in memory cannot be signed")]
+    UnsignableInMemory,
+    #[error("the signature is not a valid JWS: {0}")]
+    MalformedJws(String),
+    #[error("no trusted key matches the `{alg:?}` signature with kid {kid:?}")]
+    UnknownKey { kid: Option<String>, alg: Algorithm },
+    #[error("the signature does not match the policy store")]
+    InvalidSignature,
+    #[error("the payload of the JWS is not valid UTF-8")]
+    InvalidPayload,
+    #[error("a signed policy store must have a semantic `version`: {0}")]
+    InvalidVersion(String),
+    #[error("the policy store version `{version}` is lower than `{min_version}`")]
+    Rollback {
+        version: Version,
+        min_version: Version,
+    },
+}
+
+/// Returns the location of the detached signature of a policy store.
+pub(crate) fn detached_signature_location(location: &str) -> String {
+    format!("{location}{DETACHED_SIGNATURE_SUFFIX}")
+}
+
+/// Returns `true` if the policy store is a JWS in compact serialization, otherwise it
+/// needs a detached signature.
+pub(crate) fn is_compact_jws(content: &str) -> bool {
+    let content = content.trim();
+    content.split('.').count() == 3
+        && content
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
+
+/// Verifies the signature of a policy store and returns the signed policy store.
+///
+/// `detached_signature` is only used if `content` is not a JWS.
+pub(crate) fn verify_signature<'a>(
+    content: &'a str,
+    detached_signature: Option<&str>,
+    config: &PolicyStoreSignatureConfig,
+) -> Result<Cow<'a, str>, PolicyStoreSignatureError> {
+    if is_compact_jws(content) {
+        let (header, payload, signature) = split_jws(content.trim())?;
+        verify_jws(header, payload, signature, config)?;
+        let payload = BASE64_URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|err| PolicyStoreSignatureError::MalformedJws(err.to_string()))?;
+        let payload =
+            String::from_utf8(payload).map_err(|_| PolicyStoreSignatureError::InvalidPayload)?;
+        return Ok(Cow::Owned(payload));
+    }
+
+    let signature = detached_signature.ok_or(PolicyStoreSignatureError::MissingSignature)?;
+    verify_detached_signature(content.as_bytes(), signature, config)?;
+    Ok(Cow::Borrowed(content))
+}
+
+/// Verifies a detached signature of the content, which can be a policy store or an
+/// archive.
+pub(crate) fn verify_detached_signature(
+    content: &[u8],
+    signature: &str,
+    config: &PolicyStoreSignatureConfig,
+) -> Result<(), PolicyStoreSignatureError> {
+    let (header, payload, signature) = split_jws(signature.trim())?;
+    if !payload.is_empty() {
+        return Err(PolicyStoreSignatureError::MalformedJws(
+            "the payload of a detached signature must be empty".to_string(),
+        ));
+    }
+    let payload = BASE64_URL_SAFE_NO_PAD.encode(content);
+    verify_jws(header, &payload, signature, config)
+}
+
+/// Checks the `version` of a signed policy store, which must not be lower than
+/// `CEDARLING_POLICY_STORE_MIN_VERSION` nor than the version of the `current` policy
+/// store.
+pub(crate) fn check_signed_version(
+    policy_store: &PolicyStoreWithID,
+    config: &PolicyStoreSignatureConfig,
+    current: Option<&PolicyStoreWithID>,
+) -> Result<(), PolicyStoreSignatureError> {
+    let version = policy_store
+        .version
+        .as_deref()
+        .ok_or_else(|| PolicyStoreSignatureError::InvalidVersion("it is not set".to_string()))
+        .and_then(|version| {
+            parse_version(version)
+                .map_err(|err| PolicyStoreSignatureError::InvalidVersion(err.to_string()))
+        })?;
+    let current_version = current
+        .and_then(|current| current.version.as_deref())
+        .and_then(|version| parse_version(version).ok());
+
+    let min_versions = [config.min_version.as_ref(), current_version.as_ref()];
+    match min_versions.into_iter().flatten().max() {
+        Some(min_version) if version < *min_version => Err(PolicyStoreSignatureError::Rollback {
+            version,
+            min_version: min_version.clone(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn parse_version(version: &str) -> Result<Version, semver::Error> {
+    Version::parse(version.strip_prefix('v').unwrap_or(version))
+}
+
+fn split_jws(jws: &str) -> Result<(&str, &str, &str), PolicyStoreSignatureError> {
+    let mut parts = jws.split('.');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(header), Some(payload), Some(signature), None) => Ok((header, payload, signature)),
+        _ => Err(PolicyStoreSignatureError::MalformedJws(
+            "expected three parts separated by dots".to_string(),
+        )),
+    }
+}
+
+/// Verifies the signature of `header.payload` with the trusted keys that match the
+/// `alg` and `kid` of the header.
+fn verify_jws(
+    encoded_header: &str,
+    encoded_payload: &str,
+    signature: &str,
+    config: &PolicyStoreSignatureConfig,
+) -> Result<(), PolicyStoreSignatureError> {
+    let header = BASE64_URL_SAFE_NO_PAD
+        .decode(encoded_header)
+        .map_err(|err| PolicyStoreSignatureError::MalformedJws(err.to_string()))?;
+    let header = serde_json::from_slice::<Header>(&header)
+        .map_err(|err| PolicyStoreSignatureError::MalformedJws(err.to_string()))?;
+
+    let keys = config
+        .trusted_keys
+        .keys
+        .iter()
+        .filter(|jwk| {
+            jwk.common
+                .key_algorithm
+                .and_then(|alg| cast_to_algorithm(alg).ok())
+                == Some(header.alg)
+                && (header.kid.is_none() || jwk.common.key_id == header.kid)
+        })
+        .filter_map(|jwk| DecodingKey::from_jwk(jwk).ok())
+        .collect::<Vec<_>>();
+    if keys.is_empty() {
+        return Err(PolicyStoreSignatureError::UnknownKey {
+            kid: header.kid,
+            alg: header.alg,
+        });
+    }
+
+    let message = format!("{encoded_header}.{encoded_payload}");
+    let is_valid = keys
+        .iter()
+        .any(|key| crypto::verify(signature, message.as_bytes(), key, header.alg).unwrap_or(false));
+    if is_valid {
+        Ok(())
+    } else {
+        Err(PolicyStoreSignatureError::InvalidSignature)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::common::policy_store::PolicyStore;
+    use jsonwebtoken::EncodingKey;
+    use jsonwebtoken::jwk::JwkSet;
+    use serde_json::json;
+
+    pub(crate) const SECRET: &[u8] = b"policy-store-signing-secret-for-tests";
+
+    /// Returns the signature config with the key of [`sign`].
+    pub(crate) fn signature_config(min_version: Option<&str>) -> PolicyStoreSignatureConfig {
+        let trusted_keys = serde_json::from_value::<JwkSet>(json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": "policy-store-key",
+                "alg": "HS256",
+                "k": BASE64_URL_SAFE_NO_PAD.encode(SECRET),
+            }]
+        }))
+        .unwrap();
+        PolicyStoreSignatureConfig {
+            trusted_keys,
+            min_version: min_version.map(|version| Version::parse(version).unwrap()),
+        }
+    }
+
+    /// Signs the content and returns the JWS and its detached form.
+    pub(crate) fn sign(content: &str) -> (String, String) {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("policy-store-key".to_string());
+        let header = BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header).unwrap());
+        let payload = BASE64_URL_SAFE_NO_PAD.encode(content);
+        let signature = crypto::sign(
+            format!("{header}.{payload}").as_bytes(),
+            &EncodingKey::from_secret(SECRET),
+            Algorithm::HS256,
+        )
+        .unwrap();
+        (
+            format!("{header}.{payload}.{signature}"),
+            format!("{header}..{signature}"),
+        )
+    }
+
+    fn policy_store(version: Option<&str>) -> PolicyStoreWithID {
+        let mut store = serde_json::from_value::<PolicyStore>(json!({
+            "name": "Jans",
+            "policies": {},
+            "schema": {
+                "encoding": "none",
+                "content_type": "cedar",
+                "body": "namespace Jans {}",
+            },
+        }))
+        .unwrap();
+        store.version = version.map(str::to_string);
+        PolicyStoreWithID {
+            id: "store".to_string(),
+            store,
+        }
+    }
+
+    #[test]
+    fn verifies_enveloped_and_detached_signatures() {
+        let config = signature_config(None);
+        let content = r#"{"cedar_version": "v4.0.0"}"#;
+        let (jws, detached) = sign(content);
+
+        assert!(is_compact_jws(&jws));
+        assert!(!is_compact_jws(content));
+        assert_eq!(verify_signature(&jws, None, &config).unwrap(), content);
+        assert_eq!(
+            verify_signature(content, Some(&detached), &config).unwrap(),
+            content
+        );
+
+        let err = verify_signature(content, None, &config).unwrap_err();
+        assert!(
+            matches!(err, PolicyStoreSignatureError::MissingSignature),
+            "{err}"
+        );
+        let err = verify_signature(r#"{"cedar_version": "v4.0.1"}"#, Some(&detached), &config)
+            .unwrap_err();
+        assert!(
+            matches!(err, PolicyStoreSignatureError::InvalidSignature),
+            "a modified policy store should be rejected: {err}"
+        );
+        let err = verify_signature(content, Some(&jws), &config).unwrap_err();
+        assert!(
+            matches!(err, PolicyStoreSignatureError::MalformedJws(_)),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let mut config = signature_config(None);
+        config.trusted_keys.keys[0].common.key_id = Some("other-key".to_string());
+        let (jws, _) = sign("{}");
+
+        let err = verify_signature(&jws, None, &config).unwrap_err();
+        assert!(
+            matches!(err, PolicyStoreSignatureError::UnknownKey { .. }),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn rejects_older_versions() {
+        let config = signature_config(Some("1.2.0"));
+
+        check_signed_version(&policy_store(Some("1.2.0")), &config, None)
+            .expect("the minimum version should be accepted");
+        check_signed_version(&policy_store(Some("v1.10.0")), &config, None)
+            .expect("versions should be compared as semantic versions");
+
+        let err = check_signed_version(&policy_store(Some("1.1.9")), &config, None).unwrap_err();
+        assert!(
+            matches!(err, PolicyStoreSignatureError::Rollback { .. }),
+            "{err}"
+        );
+        let err = check_signed_version(
+            &policy_store(Some("1.3.0")),
+            &config,
+            Some(&policy_store(Some("1.4.0"))),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, PolicyStoreSignatureError::Rollback { .. }),
+            "the current policy store should not be replaced by an older one: {err}"
+        );
+        let err = check_signed_version(&policy_store(None), &config, None).unwrap_err();
+        assert!(
+            matches!(err, PolicyStoreSignatureError::InvalidVersion(_)),
+            "{err}"
+        );
+    }
+}
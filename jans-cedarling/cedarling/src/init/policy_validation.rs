@@ -0,0 +1,306 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Validation of the policies against the schema of the policy store.
+
+use std::fmt::Display;
+
+use cedar_policy::{PolicyId, PolicySet, ValidationMode, Validator};
+use miette::Diagnostic;
+
+use super::policy_store::PolicyStoreLoadError;
+use crate::bootstrap_config::{
+    PolicyValidationConfig, PolicyValidationLevel, PolicyValidationMode,
+};
+use crate::common::policy_store::PolicyStore;
+use crate::log::interface::LogWriter;
+use crate::log::{LogEntry, LogLevel, LogType, Logger};
+
+/// An issue found by the Cedar validator in a policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyValidationIssue {
+    /// ID of the policy that has the issue.
+    pub policy_id: String,
+    /// Location of the issue in the policy source, if the validator reported one.
+    pub span: Option<SourceSpan>,
+    /// Description of the issue.
+    pub message: String,
+}
+
+/// Location of a [`PolicyValidationIssue`] in the source of the policy.
+///
+/// Lines and columns start at 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceSpan {
+    /// Line of the issue.
+    pub line: usize,
+    /// Column of the issue, in characters.
+    pub column: usize,
+}
+
+impl Display for PolicyValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.span {
+            Some(span) => write!(
+                f,
+                "policy `{}` at {}:{}: {}",
+                self.policy_id, span.line, span.column, self.message
+            ),
+            None => write!(f, "policy `{}`: {}", self.policy_id, self.message),
+        }
+    }
+}
+
+/// Result of validating the policies of a policy store.
+#[derive(Debug, Default)]
+pub(crate) struct PolicyValidationReport {
+    /// Issues that make the policies invalid for the schema.
+    pub errors: Vec<PolicyValidationIssue>,
+    /// Issues that do not make the policies invalid but likely are mistakes,
+    /// for example a policy that can never be satisfied.
+    pub warnings: Vec<PolicyValidationIssue>,
+}
+
+/// Validates the policies of the policy store against its schema.
+pub(crate) fn validate_policies(
+    policy_store: &PolicyStore,
+    mode: PolicyValidationMode,
+) -> PolicyValidationReport {
+    let mode = match mode {
+        PolicyValidationMode::Strict => ValidationMode::Strict,
+        PolicyValidationMode::Permissive => ValidationMode::Permissive,
+    };
+    let policy_set = policy_store.policies.get_set();
+    let validator = Validator::new(policy_store.schema.schema.clone());
+    let result = validator.validate(policy_set, mode);
+
+    PolicyValidationReport {
+        errors: result
+            .validation_errors()
+            .map(|err| to_issue(policy_set, err.policy_id(), err))
+            .collect(),
+        warnings: result
+            .validation_warnings()
+            .map(|warn| to_issue(policy_set, warn.policy_id(), warn))
+            .collect(),
+    }
+}
+
+/// Validates the policies of the policy store according to the bootstrap config.
+///
+/// Validator warnings are only logged. Validator errors are logged when the level is
+/// [`PolicyValidationLevel::Warn`] and fail loading when it is [`PolicyValidationLevel::Error`].
+pub(crate) fn check_policy_store(
+    policy_store: &PolicyStore,
+    config: &PolicyValidationConfig,
+    log: &Logger,
+) -> Result<(), PolicyStoreLoadError> {
+    if config.level == PolicyValidationLevel::Off {
+        return Ok(());
+    }
+
+    let report = validate_policies(policy_store, config.mode);
+
+    for warning in &report.warnings {
+        log_issue(log, "policy validation warning", warning);
+    }
+
+    if report.errors.is_empty() {
+        return Ok(());
+    }
+
+    match config.level {
+        PolicyValidationLevel::Error => Err(PolicyStoreLoadError::Validation(report.errors)),
+        _ => {
+            for err in &report.errors {
+                log_issue(log, "policy does not validate against the schema", err);
+            }
+            Ok(())
+        },
+    }
+}
+
+fn log_issue(log: &Logger, message: &str, issue: &PolicyValidationIssue) {
+    log.log_any(
+        LogEntry::new_with_data(LogType::System, None)
+            .set_level(LogLevel::WARN)
+            .set_message(format!("{message}: {issue}")),
+    );
+}
+
+fn to_issue<E: Diagnostic>(
+    policy_set: &PolicySet,
+    policy_id: &PolicyId,
+    err: &E,
+) -> PolicyValidationIssue {
+    // the spans are offsets in the source of the policy, each policy of the store
+    // is parsed from its own source
+    let span = policy_set
+        .policy(policy_id)
+        .and_then(|policy| policy.to_cedar())
+        .zip(err.labels().and_then(|mut labels| labels.next()))
+        .and_then(|(src, label)| line_and_column(&src, label.offset()));
+
+    PolicyValidationIssue {
+        policy_id: policy_id.to_string(),
+        span,
+        message: err.to_string(),
+    }
+}
+
+/// Converts a byte offset in `src` to the line and column of the character at that offset.
+pub(crate) fn line_and_column(src: &str, offset: usize) -> Option<SourceSpan> {
+    let before = src.get(..offset)?;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+    Some(SourceSpan {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static POLICY_STORE: &str = r#"
+cedar_version: v4.0.0
+policy_stores:
+  a1bf93115de86de760ee0bea1d529b521489e5a11747:
+    cedar_version: v4.0.0
+    name: "Jans"
+    description: A test policy store.
+    policies:
+      valid_policy:
+        description: a policy that uses the schema correctly
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal is Jans::User,
+                action == Jans::Action::"Update",
+                resource is Jans::Issue
+            ) when {
+                principal.country == resource.country
+            };
+      invalid_policy:
+        description: a policy that reads an attribute missing from the schema
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal is Jans::User,
+                action == Jans::Action::"Update",
+                resource is Jans::Issue
+            ) when {
+                principal.missing_attr == "value"
+            };
+    schema:
+      encoding: none
+      content_type: cedar
+      body: |-
+        namespace Jans {
+          entity User = {"country": String};
+          entity Issue = {"country": String};
+          action "Update" appliesTo {
+            principal: [User],
+            resource: [Issue],
+            context: {}
+          };
+        }
+"#;
+
+    fn policy_store() -> PolicyStore {
+        let agama_store =
+            serde_yml::from_str::<crate::common::policy_store::AgamaPolicyStore>(POLICY_STORE)
+                .expect("should parse policy store");
+        agama_store
+            .policy_stores
+            .into_values()
+            .next()
+            .expect("should have a policy store")
+    }
+
+    #[test]
+    fn reports_invalid_policy_with_span() {
+        let report = validate_policies(&policy_store(), PolicyValidationMode::Strict);
+
+        assert!(!report.errors.is_empty(), "expected validation errors");
+        for issue in &report.errors {
+            assert_eq!(issue.policy_id, "invalid_policy");
+        }
+        let span = report.errors[0].span.expect("issue should have a span");
+        assert_eq!(
+            span.line, 6,
+            "the issue is on the line of the `when` condition"
+        );
+    }
+
+    #[test]
+    fn permissive_mode_reports_invalid_policy() {
+        let report = validate_policies(&policy_store(), PolicyValidationMode::Permissive);
+
+        assert!(
+            report
+                .errors
+                .iter()
+                .all(|issue| issue.policy_id == "invalid_policy"),
+            "only the invalid policy should be reported: {:?}",
+            report.errors
+        );
+        assert!(!report.errors.is_empty(), "expected validation errors");
+    }
+
+    #[test]
+    fn error_level_fails_loading() {
+        let log = crate::log::TEST_LOGGER.clone();
+        let config = PolicyValidationConfig {
+            level: PolicyValidationLevel::Error,
+            mode: PolicyValidationMode::Strict,
+            ..Default::default()
+        };
+
+        let err =
+            check_policy_store(&policy_store(), &config, &log).expect_err("loading should fail");
+        assert!(
+            matches!(&err, PolicyStoreLoadError::Validation(issues) if !issues.is_empty()),
+            "expected a validation error, got: {err:?}"
+        );
+        assert!(err.to_string().contains("invalid_policy"));
+    }
+
+    #[test]
+    fn off_and_warn_levels_do_not_fail_loading() {
+        let log = crate::log::TEST_LOGGER.clone();
+        for level in [PolicyValidationLevel::Off, PolicyValidationLevel::Warn] {
+            let config = PolicyValidationConfig {
+                level,
+                mode: PolicyValidationMode::Strict,
+                ..Default::default()
+            };
+            check_policy_store(&policy_store(), &config, &log)
+                .unwrap_or_else(|err| panic!("{level:?} should not fail loading: {err}"));
+        }
+    }
+
+    #[test]
+    fn converts_offset_to_line_and_column() {
+        let src = "permit(\n  principal,\n  action,\n  resource\n);";
+        let offset = src.find("action").unwrap();
+        assert_eq!(
+            line_and_column(src, offset),
+            Some(SourceSpan { line: 3, column: 3 })
+        );
+        assert_eq!(
+            line_and_column(src, 0),
+            Some(SourceSpan { line: 1, column: 1 })
+        );
+        assert_eq!(line_and_column(src, src.len() + 1), None);
+    }
+}
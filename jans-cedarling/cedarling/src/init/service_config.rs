@@ -5,15 +5,24 @@
  * Copyright (c) 2024, Gluu, Inc.
  */
 
-use super::policy_store::{PolicyStoreLoadError, load_policy_store};
+use super::policy_store::{
+    PolicyStoreLoadError, RemoteStoreVersion, load_policy_store, load_policy_store_with_version,
+};
+use super::policy_validation::check_policy_store;
 use crate::bootstrap_config;
 use crate::common::policy_store::PolicyStoreWithID;
-use bootstrap_config::BootstrapConfig;
+use crate::log::interface::LogWriter;
+use crate::log::{LogEntry, LogLevel, LogType, Logger};
+use bootstrap_config::{BootstrapConfig, PolicyStoreConfig};
 
 /// Configuration that hold validated infomation from bootstrap config
 #[derive(Clone)]
 pub(crate) struct ServiceConfig {
     pub policy_store: PolicyStoreWithID,
+    /// Version of the policy store if it was fetched from a URI, the polls start from it
+    pub policy_store_version: Option<RemoteStoreVersion>,
+    /// Policy store the requests are also evaluated against, without affecting the decisions
+    pub shadow_policy_store: Option<PolicyStoreWithID>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -24,9 +33,67 @@ pub enum ServiceConfigError {
 }
 
 impl ServiceConfig {
-    pub async fn new(bootstrap: &BootstrapConfig) -> Result<Self, ServiceConfigError> {
-        let policy_store = load_policy_store(&bootstrap.policy_store_config).await?;
+    pub async fn new(
+        bootstrap: &BootstrapConfig,
+        log: &Logger,
+    ) -> Result<Self, ServiceConfigError> {
+        let (policy_store, policy_store_version) = load_policy_store_with_version(
+            &bootstrap.policy_store_config,
+            &bootstrap.http_config.policy_store,
+            bootstrap.policy_store_signature_config.as_ref(),
+            bootstrap.policy_validation_config.reject_unknown_keys,
+        )
+        .await?;
+        check_policy_store(&policy_store, &bootstrap.policy_validation_config, log)?;
 
-        Ok(Self { policy_store })
+        let shadow_policy_store = match &bootstrap.shadow_policy_store_config {
+            Some(config) => load_shadow_policy_store(bootstrap, config, log).await,
+            None => None,
+        };
+
+        Ok(Self {
+            policy_store,
+            policy_store_version,
+            shadow_policy_store,
+        })
+    }
+}
+
+/// Loads the shadow policy store.
+///
+/// The shadow policy store does not affect the decisions, so Cedarling starts without it
+/// if it cannot be loaded.
+async fn load_shadow_policy_store(
+    bootstrap: &BootstrapConfig,
+    config: &PolicyStoreConfig,
+    log: &Logger,
+) -> Option<PolicyStoreWithID> {
+    let result = load_policy_store(
+        config,
+        &bootstrap.http_config.policy_store,
+        bootstrap.policy_store_signature_config.as_ref(),
+        bootstrap.policy_validation_config.reject_unknown_keys,
+    )
+    .await
+    .and_then(|policy_store| {
+        check_policy_store(&policy_store, &bootstrap.policy_validation_config, log)?;
+        Ok(policy_store)
+    });
+
+    match result {
+        Ok(policy_store) => Some(policy_store),
+        Err(err) => {
+            log.log_any(
+                LogEntry::new_with_data(LogType::System, None)
+                    .set_level(LogLevel::ERROR)
+                    .set_message(
+                        "could not load the shadow policy store, requests are not evaluated \
+                         against it"
+                            .to_string(),
+                    )
+                    .set_error(err.to_string()),
+            );
+            None
+        },
     }
 }
@@ -9,9 +9,10 @@
 
 use super::service_config::ServiceConfig;
 use crate::authz::{Authz, AuthzConfig, AuthzServiceInitError};
-use crate::bootstrap_config::BootstrapConfig;
+use crate::bootstrap_config::{BootstrapConfig, EntityBuilderConfig};
 use crate::common::policy_store::PolicyStoreWithID;
 use crate::entity_builder::*;
+use crate::http::HttpClientBuildError;
 use crate::jwt::{JwtService, JwtServiceInitError};
 use crate::log;
 use std::sync::Arc;
@@ -68,6 +69,7 @@ impl<'a> ServiceFactory<'a> {
             let service = Arc::new(
                 JwtService::new(
                     config,
+                    &self.bootstrap_config.http_config.idp,
                     trusted_issuers,
                     Some(logger),
                     self.bootstrap_config.token_cache_max_ttl_secs,
@@ -86,22 +88,9 @@ impl<'a> ServiceFactory<'a> {
         }
 
         let logger = self.log_service();
-
-        let config = &self.bootstrap_config.entity_builder_config;
-        let trusted_issuers = self
-            .policy_store()
-            .trusted_issuers
-            .clone()
-            .unwrap_or_default();
-        let schema = &self.policy_store().schema.validator_schema;
-        let policy_store = &self.policy_store().store;
-        let namespace = Some(policy_store.name.as_str());
-        let entity_builder = EntityBuilder::new(
-            config.clone(),
-            &trusted_issuers,
-            Some(schema),
-            policy_store.default_entities.as_ref(),
-            namespace,
+        let entity_builder = build_entity_builder(
+            &self.bootstrap_config.entity_builder_config,
+            &self.policy_store(),
             logger,
         )?;
         let service = Arc::new(entity_builder);
@@ -116,18 +105,43 @@ impl<'a> ServiceFactory<'a> {
         } else {
             let config = AuthzConfig {
                 log_service: self.log_service(),
-                policy_store: self.policy_store(),
+                policy_store_source: self.bootstrap_config.policy_store_config.source.clone(),
+                policy_store_signature_config: self
+                    .bootstrap_config
+                    .policy_store_signature_config
+                    .clone(),
                 jwt_service: self.jwt_service().await?,
                 entity_builder: self.entity_builder()?,
                 authorization: self.bootstrap_config.authorization_config.clone(),
+                shadow_policy_store: self.service_config.shadow_policy_store.clone(),
             };
-            let service = Arc::new(Authz::new(config)?);
+            let service = Arc::new(Authz::new(config, self.policy_store())?);
             self.container.authz_service = Some(service.clone());
             Ok(service)
         }
     }
 }
 
+/// Creates the [`EntityBuilder`] for the entities of the policy store.
+pub(crate) fn build_entity_builder(
+    config: &EntityBuilderConfig,
+    policy_store: &PolicyStoreWithID,
+    logger: log::Logger,
+) -> Result<EntityBuilder, InitEntityBuilderError> {
+    let trusted_issuers = policy_store.trusted_issuers.clone().unwrap_or_default();
+    let schema = &policy_store.schema.validator_schema;
+    let namespace = Some(policy_store.name.as_str());
+    EntityBuilder::new(
+        config.clone(),
+        &trusted_issuers,
+        Some(schema),
+        policy_store.default_entities.as_ref(),
+        namespace,
+        logger,
+    )?
+    .with_role_hierarchy(policy_store.role_hierarchy.as_ref())
+}
+
 /// Error type for failing to initialize a service
 #[derive(Debug, thiserror::Error)]
 pub enum ServiceInitError {
@@ -137,4 +151,6 @@ pub enum ServiceInitError {
     JwtService(#[from] JwtServiceInitError),
     #[error(transparent)]
     EntityBuilder(#[from] InitEntityBuilderError),
+    #[error("failed to initialize the HTTP client of the policy store poller: {0}")]
+    PolicyStorePoller(#[from] HttpClientBuildError),
 }
@@ -7,6 +7,7 @@ use super::http_utils::HttpError;
 use super::key_service;
 use super::status_list::UpdateStatusListError;
 use super::validation::ValidateJwtError;
+use crate::http::HttpClientBuildError;
 
 #[derive(Debug, thiserror::Error)]
 pub enum JwtProcessingError {
@@ -42,4 +43,8 @@ pub enum JwtServiceInitError {
     GetOpenidConfigurations(#[from] HttpError),
     #[error("failed to update JWT status list: {0}")]
     UpdateStatusList(#[from] UpdateStatusListError),
+    #[error("the initialization of the trusted issuer `{0}` timed out after {1} seconds")]
+    TrustedIssuerTimeout(String, u64),
+    #[error("failed to initialize the HTTP client for the trusted issuers: {0}")]
+    HttpClient(#[from] HttpClientBuildError),
 }
@@ -3,27 +3,13 @@
 //
 // Copyright (c) 2024, Gluu, Inc.
 
-use std::sync::LazyLock;
-
 use super::key_service::JwkSet;
 use super::status_list::StatusListJwtStr;
-use async_trait::async_trait;
-use reqwest::{Client, header::ToStrError};
+use ::http_utils::{HttpRequestError, Sender};
+use reqwest::{Client, RequestBuilder, Response, header::ToStrError};
 use serde::{Deserialize, Deserializer, de};
 use url::Url;
 
-static HTTP_CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
-
-// async_traits are Send by default but wasm-bindgen doesn't support those
-// so we opt out of it for the wasm bindings to compile.
-//
-// see this relevant discussion: https://github.com/rustwasm/wasm-bindgen/issues/2409
-#[async_trait(?Send)]
-pub trait GetFromUrl<T> {
-    /// Send a get request to receive the resource from a URL
-    async fn get_from_url(url: &Url) -> Result<T, HttpError>;
-}
-
 #[derive(Deserialize)]
 pub struct OpenIdConfig {
     pub issuer: String,
@@ -58,10 +44,12 @@ where
     Ok(url)
 }
 
-#[async_trait(?Send)]
-impl GetFromUrl<OpenIdConfig> for OpenIdConfig {
-    async fn get_from_url(url: &Url) -> Result<Self, HttpError> {
-        let openid_config = HTTP_CLIENT
+// NOTE: these are plain async functions instead of an async_trait so the futures are
+// Send, the trusted issuers are also initialized from the policy store poller task.
+impl OpenIdConfig {
+    /// Send a get request to receive the resource from a URL
+    pub async fn get_from_url(client: &Client, url: &Url) -> Result<Self, HttpError> {
+        let openid_config = client
             .get(url.as_str())
             .send()
             .await
@@ -76,10 +64,10 @@ impl GetFromUrl<OpenIdConfig> for OpenIdConfig {
     }
 }
 
-#[async_trait(?Send)]
-impl GetFromUrl<JwkSet> for JwkSet {
-    async fn get_from_url(url: &Url) -> Result<Self, HttpError> {
-        let jwk_set = HTTP_CLIENT
+impl JwkSet {
+    /// Send a get request to receive the resource from a URL
+    pub async fn get_from_url(client: &Client, url: &Url) -> Result<Self, HttpError> {
+        let jwk_set = client
             .get(url.as_str())
             .send()
             .await
@@ -94,19 +82,35 @@ impl GetFromUrl<JwkSet> for JwkSet {
     }
 }
 
-// NOTE: we cant use the async_trait here since this is called from another aysnc 
+// NOTE: we cant use the async_trait here since this is called from another aysnc
 // function which requires this to be Send.
 impl StatusListJwtStr {
-    pub async fn get_from_url(url: &Url) -> Result<Self, HttpError> {
-        let response = HTTP_CLIENT
-            .get(url.as_str())
-            .header("Content-Type", "application/statuslist+jwt")
+    pub async fn get_from_url(client: &Client, url: &Url) -> Result<Self, HttpError> {
+        let response = status_list_request(client, url)
             .send()
             .await
             .map_err(HttpError::GetRequest)?
             .error_for_status()
             .map_err(HttpError::ErrorCode)?;
 
+        Self::from_response(response, url).await
+    }
+
+    /// Gets the status list with the retries and the circuit breaker of the [`Sender`].
+    pub async fn get_with_sender(
+        sender: &mut Sender,
+        client: &Client,
+        url: &Url,
+    ) -> Result<Self, HttpError> {
+        let response = sender
+            .send_request(|| status_list_request(client, url))
+            .await
+            .map_err(HttpError::Request)?;
+
+        Self::from_response(response, url).await
+    }
+
+    async fn from_response(response: Response, url: &Url) -> Result<Self, HttpError> {
         if let Some(content_type) = response.headers().get("Content-Type") {
             let content_type = content_type
                 .to_str()
@@ -126,12 +130,20 @@ impl StatusListJwtStr {
     }
 }
 
+fn status_list_request(client: &Client, url: &Url) -> RequestBuilder {
+    client
+        .get(url.as_str())
+        .header("Content-Type", "application/statuslist+jwt")
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum HttpError {
     #[error("failed to complete GET request: {0}")]
     GetRequest(#[source] reqwest::Error),
     #[error("received an error response: {0}")]
     ErrorCode(#[source] reqwest::Error),
+    #[error("failed to GET the resource: {0}")]
+    Request(#[source] HttpRequestError),
     #[error("failed to deserialize respose from JSON: {0}")]
     JsonDeserializeResponse(#[source] reqwest::Error),
     #[error("failed to read the respose text: {0}")]
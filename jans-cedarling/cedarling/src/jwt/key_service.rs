@@ -12,6 +12,7 @@ use crate::log::Logger;
 use super::http_utils::*;
 use jsonwebtoken::jwk::{Jwk, KeyAlgorithm};
 use jsonwebtoken::{Algorithm, DecodingKey};
+use reqwest::Client;
 use serde::Deserialize;
 
 #[derive(Debug, Hash, Eq, PartialEq)]
@@ -95,9 +96,10 @@ impl KeyService {
     pub async fn get_keys_using_oidc(
         &mut self,
         openid_config: &OpenIdConfig,
+        client: &Client,
         logger: &Option<Logger>,
     ) -> Result<(), KeyServiceError> {
-        let jwks = JwkSet::get_from_url(&openid_config.jwks_uri)
+        let jwks = JwkSet::get_from_url(client, &openid_config.jwks_uri)
             .await
             .map_err(KeyServiceError::GetJwks)?;
 
@@ -157,6 +159,11 @@ impl KeyService {
     pub fn has_keys(&self) -> bool {
         !self.keys.is_empty()
     }
+
+    /// Adds the keys of another [`KeyService`]
+    pub fn extend(&mut self, other: KeyService) {
+        self.keys.extend(other.keys);
+    }
 }
 
 /// An alternative implementation of [`jsonwebtoken::jwk::JwkSet`].
@@ -187,7 +194,7 @@ impl JwkSet {
 
 /// Tries to Casts a [`jsonwebtoken::jwk::KeyAlgorithm`] into a [`jsonwebtoken::Algorithm`].
 #[inline]
-fn cast_to_algorithm(
+pub(crate) fn cast_to_algorithm(
     key_alg: jsonwebtoken::jwk::KeyAlgorithm,
 ) -> Result<jsonwebtoken::Algorithm, jsonwebtoken::jwk::KeyAlgorithm> {
     match key_alg {
@@ -344,11 +351,11 @@ mod test {
         let mut key_service = KeyService::default();
 
         key_service
-            .get_keys_using_oidc(&server1.openid_config(), &None)
+            .get_keys_using_oidc(&server1.openid_config(), &Client::new(), &None)
             .await
             .expect("fetch keys for issuer 1");
         key_service
-            .get_keys_using_oidc(&server2.openid_config(), &None)
+            .get_keys_using_oidc(&server2.openid_config(), &Client::new(), &None)
             .await
             .expect("fetch keys for issuer 2");
 
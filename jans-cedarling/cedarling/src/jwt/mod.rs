@@ -84,6 +84,9 @@ pub use decode::*;
 pub use error::*;
 pub use token::{Token, TokenClaimTypeError, TokenClaims};
 
+pub(crate) use key_service::cast_to_algorithm;
+
+use crate::HttpClientConfig;
 use crate::JwtConfig;
 use crate::LogLevel;
 use crate::LogWriter;
@@ -91,15 +94,19 @@ use crate::common::issuer_utils::normalize_issuer;
 use crate::common::policy_store::TrustedIssuer;
 use crate::log::Logger;
 use chrono::Utc;
+use futures::StreamExt;
 use http_utils::*;
 use key_service::*;
 use log_entry::*;
+use reqwest::Client;
 use serde_json::json;
 use sparkv::SparKV;
 use status_list::*;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::OnceLock;
 use std::sync::RwLock;
+use url::Url;
 use validation::*;
 
 /// The value of the `iss` claim from a JWT
@@ -110,6 +117,9 @@ pub struct JwtService {
     validators: JwtValidatorCache,
     key_service: Arc<KeyService>,
     issuer_configs: HashMap<IssClaim, IssuerConfig>,
+    /// IDs of the trusted issuers that failed to initialize, by the `iss` claim
+    /// of their tokens
+    unavailable_issuers: HashMap<IssClaim, String>,
     logger: Option<Logger>,
     token_cache: Arc<RwLock<SparKV<Arc<Token>>>>,
     token_cache_max_ttl: usize,
@@ -123,43 +133,90 @@ struct IssuerConfig {
     openid_config: Option<OpenIdConfig>,
 }
 
+/// A trusted issuer with its keys and validators, to be added to the [`JwtService`]
+struct InitializedIssuer {
+    iss_claim: IssClaim,
+    config: IssuerConfig,
+    key_service: KeyService,
+    validators: JwtValidatorCache,
+}
+
 impl JwtService {
     pub async fn new(
         jwt_config: &JwtConfig,
+        http_config: &HttpClientConfig,
         trusted_issuers: Option<HashMap<String, TrustedIssuer>>,
         logger: Option<Logger>,
         token_cache_max_ttl_sec: usize,
     ) -> Result<Self, JwtServiceInitError> {
-        let mut status_lists = StatusListCache::default();
+        let status_lists = StatusListCache::default();
         let mut issuer_configs = HashMap::default();
         let mut validators = JwtValidatorCache::default();
         let mut key_service = KeyService::new();
-
-        for (issuer_id, iss) in trusted_issuers.unwrap_or_default().into_iter() {
-            // this is what we expect to find in the JWT `iss` claim
-            let mut iss_claim = iss.oidc_endpoint.origin().ascii_serialization();
-
-            let mut iss_config = IssuerConfig {
-                issuer_id,
-                policy: Arc::new(iss),
-                openid_config: None,
-            };
-
-            if jwt_config.jwt_sig_validation || jwt_config.jwt_status_validation {
-                iss_claim = update_openid_config(&mut iss_config, &logger).await?;
-            }
-
-            insert_keys(&mut key_service, jwt_config, &iss_config, &logger).await?;
-
-            validators.init_for_iss(&iss_config, jwt_config, &status_lists, logger.clone());
-
-            if jwt_config.jwt_status_validation {
-                status_lists
-                    .init_for_iss(&iss_config, &validators, &key_service, logger.clone())
-                    .await?;
+        let mut unavailable_issuers = HashMap::new();
+        let http_client = crate::http::build_client(http_config)?;
+
+        let init_config = jwt_config.trusted_issuer_init;
+        let timeout = std::time::Duration::from_secs(init_config.timeout_secs);
+
+        // the trusted issuers are initialized concurrently, each one with its own timeout
+        let mut results = futures::stream::iter(trusted_issuers.unwrap_or_default())
+            .map(|(issuer_id, iss)| {
+                let status_lists = &status_lists;
+                let http_client = &http_client;
+                let logger = &logger;
+                async move {
+                    // used to mark the trusted issuer as unavailable if it fails, the
+                    // `issuer` of the OpenID configuration is used once it is fetched
+                    let expected_iss_claim = expected_iss_claim(&iss.oidc_endpoint);
+                    let discovered_iss_claim = OnceLock::new();
+                    let init = init_trusted_issuer(
+                        issuer_id.clone(),
+                        iss,
+                        jwt_config,
+                        status_lists,
+                        http_client,
+                        logger,
+                        &discovered_iss_claim,
+                    );
+                    let result = crate::http::timeout(timeout, init)
+                        .await
+                        .unwrap_or_else(|_| {
+                            Err(JwtServiceInitError::TrustedIssuerTimeout(
+                                issuer_id.clone(),
+                                init_config.timeout_secs,
+                            ))
+                        });
+                    let iss_claim = discovered_iss_claim
+                        .into_inner()
+                        .unwrap_or(expected_iss_claim);
+                    (issuer_id, iss_claim, result)
+                }
+            })
+            .buffer_unordered(init_config.max_concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        results.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+
+        for (issuer_id, iss_claim, result) in results {
+            match result {
+                Ok(issuer) => {
+                    key_service.extend(issuer.key_service);
+                    validators.extend(issuer.validators);
+                    issuer_configs.insert(normalize_issuer(&issuer.iss_claim), issuer.config);
+                },
+                Err(err) if init_config.allow_partial => {
+                    logger.log_any(JwtLogEntry::new(
+                        format!(
+                            "the trusted issuer '{issuer_id}' is unavailable since it failed to \
+                             initialize: {err}"
+                        ),
+                        Some(LogLevel::WARN),
+                    ));
+                    unavailable_issuers.insert(normalize_issuer(&iss_claim), issuer_id);
+                },
+                Err(err) => return Err(err),
             }
-
-            issuer_configs.insert(normalize_issuer(&iss_claim), iss_config);
         }
 
         // quick check so we don't get surprised if the program runs but can't validate
@@ -173,6 +230,7 @@ impl JwtService {
             validators,
             key_service,
             issuer_configs,
+            unavailable_issuers,
             logger,
             token_cache: Arc::new(RwLock::new(SparKV::new())),
             token_cache_max_ttl: token_cache_max_ttl_sec,
@@ -308,6 +366,12 @@ impl JwtService {
 
         // get validator
         let normalized_iss = decoded_jwt.iss().map(normalize_issuer);
+        if let Some(issuer_id) = normalized_iss
+            .as_ref()
+            .and_then(|iss| self.unavailable_issuers.get(iss))
+        {
+            return Err(ValidateJwtError::UnavailableIssuer(issuer_id.clone()));
+        }
         let validator_key = ValidatorInfo {
             iss: normalized_iss.as_deref(),
             token_kind: TokenKind::AuthzRequestInput(&token_name),
@@ -344,11 +408,85 @@ impl JwtService {
     }
 }
 
+/// Fetches the OpenID configuration, the keys and the status list of a trusted issuer
+/// and initializes its validators.
+async fn init_trusted_issuer(
+    issuer_id: String,
+    iss: TrustedIssuer,
+    jwt_config: &JwtConfig,
+    status_lists: &StatusListCache,
+    http_client: &Client,
+    logger: &Option<Logger>,
+    discovered_iss_claim: &OnceLock<IssClaim>,
+) -> Result<InitializedIssuer, JwtServiceInitError> {
+    // this is what we expect to find in the JWT `iss` claim
+    let mut iss_claim = iss.oidc_endpoint.origin().ascii_serialization();
+
+    let mut iss_config = IssuerConfig {
+        issuer_id,
+        policy: Arc::new(iss),
+        openid_config: None,
+    };
+
+    if jwt_config.jwt_sig_validation || jwt_config.jwt_status_validation {
+        iss_claim = update_openid_config(&mut iss_config, http_client, logger).await?;
+        _ = discovered_iss_claim.set(iss_claim.clone());
+    }
+
+    let mut key_service = KeyService::new();
+    insert_keys(
+        &mut key_service,
+        jwt_config,
+        &iss_config,
+        http_client,
+        logger,
+    )
+    .await?;
+
+    let mut validators = JwtValidatorCache::default();
+    validators.init_for_iss(&iss_config, jwt_config, status_lists, logger.clone());
+
+    if jwt_config.jwt_status_validation {
+        status_lists
+            .init_for_iss(
+                &iss_config,
+                &validators,
+                &key_service,
+                http_client,
+                logger.clone(),
+            )
+            .await?;
+    }
+
+    Ok(InitializedIssuer {
+        iss_claim,
+        config: iss_config,
+        key_service,
+        validators,
+    })
+}
+
+/// Returns the `iss` claim expected in the tokens of a trusted issuer before its
+/// OpenID configuration is fetched.
+///
+/// This is the `oidc_endpoint` without the `/.well-known/openid-configuration` suffix,
+/// as defined by OpenID Connect Discovery, or its origin for another endpoint.
+fn expected_iss_claim(oidc_endpoint: &Url) -> IssClaim {
+    const WELL_KNOWN_SUFFIX: &str = "/.well-known/openid-configuration";
+
+    oidc_endpoint
+        .as_str()
+        .strip_suffix(WELL_KNOWN_SUFFIX)
+        .map(ToString::to_string)
+        .unwrap_or_else(|| oidc_endpoint.origin().ascii_serialization())
+}
+
 async fn update_openid_config(
     iss_config: &mut IssuerConfig,
+    http_client: &Client,
     logger: &Option<Logger>,
 ) -> Result<String, JwtServiceInitError> {
-    let openid_config = OpenIdConfig::get_from_url(&iss_config.policy.oidc_endpoint)
+    let openid_config = OpenIdConfig::get_from_url(http_client, &iss_config.policy.oidc_endpoint)
         .await
         .inspect_err(|e| {
             logger.log_any(JwtLogEntry::new(
@@ -370,6 +508,7 @@ async fn insert_keys(
     key_service: &mut KeyService,
     jwt_config: &JwtConfig,
     iss_config: &IssuerConfig,
+    http_client: &Client,
     logger: &Option<Logger>,
 ) -> Result<(), KeyServiceError> {
     if !jwt_config.jwt_sig_validation {
@@ -382,7 +521,7 @@ async fn insert_keys(
 
     if let Some(openid_config) = iss_config.openid_config.as_ref() {
         key_service
-            .get_keys_using_oidc(openid_config, logger)
+            .get_keys_using_oidc(openid_config, http_client, logger)
             .await?;
     }
 
@@ -436,8 +575,12 @@ fn hash_str(s: &str) -> String {
 #[cfg(test)]
 mod test {
     use super::test_utils::*;
-    use super::{JwtService, Token};
-    use crate::JwtConfig;
+    use super::{
+        JwtProcessingError, JwtService, JwtServiceInitError, Token, ValidateJwtError,
+        expected_iss_claim,
+    };
+    use crate::common::policy_store::TrustedIssuer;
+    use crate::{HttpClientConfig, JwtConfig, TrustedIssuerInitConfig};
     use jsonwebtoken::Algorithm;
     use serde_json::{Value, json};
     use std::collections::{HashMap, HashSet};
@@ -488,7 +631,9 @@ mod test {
                 jwt_sig_validation: true,
                 jwt_status_validation: false,
                 signature_algorithms_supported: HashSet::from_iter([Algorithm::HS256]),
+                ..Default::default()
             },
+            &HttpClientConfig::default(),
             Some(HashMap::from([("Jans".into(), iss.clone())])),
             None,
             0,
@@ -541,4 +686,122 @@ mod test {
             &Token::new("userinfo_token", expected_claims.into(), Some(iss))
         );
     }
+
+    fn jwt_config(trusted_issuer_init: TrustedIssuerInitConfig) -> JwtConfig {
+        JwtConfig {
+            jwks: None,
+            jwt_sig_validation: true,
+            jwt_status_validation: false,
+            signature_algorithms_supported: HashSet::from_iter([Algorithm::HS256]),
+            trusted_issuer_init,
+        }
+    }
+
+    /// Returns a trusted issuer whose OpenID configuration endpoint accepts connections
+    /// but never responds, with the listener that has to be kept alive.
+    fn unresponsive_trusted_issuer() -> (TrustedIssuer, std::net::TcpListener) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/.well-known/openid-configuration",
+            listener.local_addr().unwrap()
+        );
+        let iss = TrustedIssuer {
+            oidc_endpoint: url.parse().unwrap(),
+            ..Default::default()
+        };
+        (iss, listener)
+    }
+
+    #[test]
+    async fn can_continue_without_unavailable_issuers() {
+        let mut server = MockServer::new_with_defaults().await.unwrap();
+        let mut failing_server = MockServer::new_with_defaults().await.unwrap();
+        let failing_iss = TrustedIssuer {
+            oidc_endpoint: format!("{}/missing-openid-configuration", failing_server.issuer())
+                .parse()
+                .unwrap(),
+            ..Default::default()
+        };
+        let (unresponsive_iss, _listener) = unresponsive_trusted_issuer();
+
+        let jwt_service = JwtService::new(
+            &jwt_config(TrustedIssuerInitConfig::new(2, 1, true)),
+            &HttpClientConfig::default(),
+            Some(HashMap::from([
+                ("Jans".into(), server.trusted_issuer()),
+                ("Failing".into(), failing_iss),
+                ("Unresponsive".into(), unresponsive_iss),
+            ])),
+            None,
+            0,
+        )
+        .await
+        .expect("Should create JwtService without the unavailable issuers");
+
+        let mut claims = json!({"sub": "some_sub", "jti": 1231231231, "exp": u64::MAX});
+        let access_tkn = server
+            .generate_token_with_hs256sig(&mut claims.clone(), None)
+            .unwrap();
+        let failing_access_tkn = failing_server
+            .generate_token_with_hs256sig(&mut claims, None)
+            .unwrap();
+
+        let tokens = HashMap::from([("access_token".to_string(), access_tkn)]);
+        jwt_service
+            .validate_tokens(&tokens)
+            .await
+            .expect("should validate the token of the available issuer");
+
+        let tokens = HashMap::from([("access_token".to_string(), failing_access_tkn)]);
+        let err = jwt_service
+            .validate_tokens(&tokens)
+            .await
+            .expect_err("should reject the token of the unavailable issuer");
+        let JwtProcessingError::ValidateJwt(_, ValidateJwtError::UnavailableIssuer(id)) = &err
+        else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(id, "Failing");
+    }
+
+    #[test]
+    async fn errors_when_an_issuer_times_out() {
+        let server = MockServer::new_with_defaults().await.unwrap();
+        let (unresponsive_iss, _listener) = unresponsive_trusted_issuer();
+
+        let result = JwtService::new(
+            &jwt_config(TrustedIssuerInitConfig::new(0, 1, false)),
+            &HttpClientConfig::default(),
+            Some(HashMap::from([
+                ("Jans".into(), server.trusted_issuer()),
+                ("Unresponsive".into(), unresponsive_iss),
+            ])),
+            None,
+            0,
+        )
+        .await;
+
+        assert!(
+            matches!(
+                &result,
+                Err(JwtServiceInitError::TrustedIssuerTimeout(id, 1)) if id == "Unresponsive"
+            ),
+            "expected a timeout of the unresponsive issuer"
+        );
+    }
+
+    #[test]
+    async fn expects_the_issuer_of_the_oidc_endpoint() {
+        let endpoint = "https://idp.example.com/realms/jans/.well-known/openid-configuration";
+        assert_eq!(
+            expected_iss_claim(&endpoint.parse().unwrap()),
+            "https://idp.example.com/realms/jans"
+        );
+
+        let endpoint = "https://idp.example.com/openid-configuration";
+        assert_eq!(
+            expected_iss_claim(&endpoint.parse().unwrap()),
+            "https://idp.example.com"
+        );
+    }
 }
@@ -5,15 +5,18 @@
 
 use std::{
     collections::HashMap,
-    sync::{Arc, RwLock},
+    sync::{Arc, RwLock, Weak},
     time::Duration,
 };
 
+use http_utils::{Backoff, Sender};
 use jsonwebtoken::DecodingKey;
+use reqwest::Client;
 use url::Url;
 
 use crate::{
     LogLevel, LogWriter,
+    http::{circuit_breaker, log_failed_attempts},
     jwt::{
         IssuerConfig,
         decode::{DecodeJwtError, decode_jwt},
@@ -38,10 +41,11 @@ pub struct StatusListCache {
 impl StatusListCache {
     /// Initializes the statuslist for the given issuer
     pub async fn init_for_iss(
-        &mut self,
+        &self,
         iss_config: &IssuerConfig,
         validators: &JwtValidatorCache,
         key_service: &KeyService,
+        client: &Client,
         logger: Option<Logger>,
     ) -> Result<(), UpdateStatusListError> {
         let openid_config = iss_config
@@ -52,7 +56,7 @@ impl StatusListCache {
             .status_list_endpoint
             .as_ref()
             .ok_or(UpdateStatusListError::MissingStatusListUri)?;
-        let status_list_jwt = StatusListJwtStr::get_from_url(status_list_url)
+        let status_list_jwt = StatusListJwtStr::get_from_url(client, status_list_url)
             .await
             .map_err(UpdateStatusListError::GetStatusListJwt)?;
 
@@ -93,9 +97,10 @@ impl StatusListCache {
             crate::http::spawn_task(keep_status_list_updated(
                 ttl,
                 status_list_url.clone(),
+                client.clone(),
                 decoding_key.cloned(),
-                validator,
-                self.status_lists.clone(),
+                Arc::downgrade(&validator),
+                Arc::downgrade(&self.status_lists),
                 logger,
             ));
         }
@@ -113,18 +118,36 @@ impl StatusListCache {
 }
 
 /// Keeps the statuslist form the given URL updated based on the TTL
+///
+/// The task stops once the validator or the cache is dropped, e.g. when the
+/// [`JwtService`] is replaced along with a polled policy store.
+///
+/// [`JwtService`]: crate::jwt::JwtService
 async fn keep_status_list_updated(
     mut ttl: u64,
     status_list_url: Url,
+    client: Client,
     decoding_key: Option<DecodingKey>,
-    validator: Arc<RwLock<JwtValidator>>,
-    status_lists: Arc<RwLock<HashMap<String, StatusList>>>,
+    validator: Weak<RwLock<JwtValidator>>,
+    status_lists: Weak<RwLock<HashMap<String, StatusList>>>,
     logger: Option<Logger>,
 ) {
+    let backoff = Backoff::new_exponential(Duration::from_secs(1), Some(3)).with_jitter(0.2);
+    let sender = Sender::new(backoff)
+        .with_circuit_breaker(circuit_breaker(status_list_url.as_str(), logger.clone()));
+    let mut sender = log_failed_attempts(sender, logger.clone());
+
     loop {
         tokio::time::sleep(Duration::from_secs(ttl)).await;
 
-        let status_list_jwt = match StatusListJwtStr::get_from_url(&status_list_url).await {
+        let (Some(validator), Some(status_lists)) = (validator.upgrade(), status_lists.upgrade())
+        else {
+            return;
+        };
+
+        let response =
+            StatusListJwtStr::get_with_sender(&mut sender, &client, &status_list_url).await;
+        let status_list_jwt = match response {
             Ok(jwt) => jwt,
             Err(e) => {
                 logger.log_any(JwtLogEntry::new(
@@ -238,12 +261,12 @@ mod test {
         let mut key_service = KeyService::default();
         let mut mock_server = MockServer::new_with_defaults().await.unwrap();
         key_service
-            .get_keys_using_oidc(&mock_server.openid_config(), &None)
+            .get_keys_using_oidc(&mock_server.openid_config(), &Client::new(), &None)
             .await
             .unwrap();
         // we initialize the status list with a 1 sec ttl
         mock_server.generate_status_list_endpoint(1u8.try_into().unwrap(), &[0b1111_1110], Some(1));
-        let mut status_list = StatusListCache::default();
+        let status_list = StatusListCache::default();
         let iss_config = IssuerConfig {
             issuer_id: "some_iss_id".into(),
             policy: Arc::new(TrustedIssuer {
@@ -261,12 +284,13 @@ mod test {
                 jwt_sig_validation: false,
                 jwt_status_validation: true,
                 signature_algorithms_supported: HashSet::from([Algorithm::HS256]),
+                ..Default::default()
             },
             &status_list,
             None,
         );
         status_list
-            .init_for_iss(&iss_config, &validators, &key_service, None)
+            .init_for_iss(&iss_config, &validators, &key_service, &Client::new(), None)
             .await
             .unwrap();
 
@@ -3,7 +3,7 @@
 //
 // Copyright (c) 2024, Gluu, Inc.
 
-use crate::common::policy_store::{ClaimMappings, TokenEntityMetadata, TrustedIssuer};
+use crate::common::policy_store::{ClaimMappings, ClaimPath, TokenEntityMetadata, TrustedIssuer};
 use serde::Deserialize;
 use serde_json::Value;
 use std::{collections::HashMap, sync::Arc};
@@ -40,6 +40,11 @@ impl Token {
         self.claims.claims.get(name)
     }
 
+    /// Gets the value selected by a path expression, where `$` is the whole payload.
+    pub fn select_claim_val(&self, path: &ClaimPath) -> Option<Value> {
+        path.select_from_claims(&self.claims.claims)
+    }
+
     pub fn logging_info<'a>(&'a self, claim: &'a str) -> HashMap<&'a str, &'a serde_json::Value> {
         self.claims.logging_info(claim)
     }
@@ -266,6 +266,8 @@ pub enum ValidateJwtError {
         "failed to validate JWT {0:?}: no validator was initialized. this may be due to an untrusted issuer or an unsupported algorithm"
     )]
     MissingValidator(OwnedValidatorInfo),
+    #[error("the trusted issuer '{0}' of the JWT is unavailable since it failed to initialize")]
+    UnavailableIssuer(String),
     #[error("failed to validate the JWT: {0}")]
     ValidateJwt(#[from] jwt::errors::Error),
     #[error("validation failed since the JWT is missing the following required claims: {0:#?}")]
@@ -309,6 +311,7 @@ mod test {
             role_mapping: None,
             workload_id: None,
             claim_mapping: ClaimMappings::default(),
+            membership_mapping: Vec::new(),
             required_claims: HashSet::from(["exp".into(), "nbf".into()]),
         });
 
@@ -100,6 +100,13 @@ impl JwtValidatorCache {
         }
     }
 
+    /// Adds the validators of another [`JwtValidatorCache`]
+    pub fn extend(&mut self, other: JwtValidatorCache) {
+        for (key, validators) in other.validators {
+            self.validators.entry(key).or_default().extend(validators);
+        }
+    }
+
     /// Inserts a new validator into the store.
     ///
     /// If a validator with the same `ValidatorKeyHash` already exists, it is
@@ -275,6 +282,7 @@ mod test {
                 role_mapping: None,
                 workload_id: Some("aud".into()),
                 claim_mapping: ClaimMappings::default(),
+                membership_mapping: Vec::new(),
                 required_claims: HashSet::new(),
             },
             jsonwebtoken::Algorithm::HS256,
@@ -30,30 +30,44 @@ pub mod blocking;
 #[cfg(test)]
 mod tests;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub use crate::common::json_rules::JsonRule;
+pub use crate::common::policy_store::{
+    PolicyStoreBuilder, PolicyStoreBuilderError, PolicyStoreWithID, RoleHierarchyCycleError,
+    TemplateLink, TemplateLinkError, TokenEntityMetadata, TrustedIssuer,
+};
 #[cfg(test)]
 use authz::AuthorizeEntitiesData;
 use authz::Authz;
 pub use authz::request::{EntityData, Request, RequestUnsigned, CedarEntityMapping};
-pub use authz::{AuthorizeError, AuthorizeResult};
+pub use authz::{
+    AllowedActionsResult, AuthorizeError, AuthorizeResult, ConditionKind, FailedCondition,
+    PolicyAnnotations, PolicyEffect, PolicyTrace, RequestPrincipals, ShadowStats,
+};
 pub use bootstrap_config::*;
 use common::app_types::{self, ApplicationName};
 use init::ServiceFactory;
+use init::policy_store_poller::PolicyStorePoller;
 use init::service_config::{ServiceConfig, ServiceConfigError};
 use init::service_factory::ServiceInitError;
+pub use lock::DeregisterClientError;
 use lock::InitLockServiceError;
 use log::interface::LogWriter;
 use log::{LogEntry, LogType};
-pub use log::{LogLevel, LogStorage};
+pub use log::{
+    DEFAULT_LOG_SUBSCRIPTION_BUFFER_SIZE, LogFilter, LogLevel, LogOverflowPolicy, LogQuery,
+    LogQueryOrder, LogQueryResult, LogStorage, LogSubscription, LogSubscriptionConfig,
+};
+use tokio_util::sync::CancellationToken;
 
 #[doc(hidden)]
 pub mod bindings {
     pub use cedar_policy;
 
     pub use super::log::{
-        AuthorizationLogInfo, Decision, Diagnostics, LogEntry, PolicyEvaluationError,
+        AuthorizationLogInfo, Decision, Diagnostics, LogEntry, LogType, PolicyEvaluationError,
     };
     pub use crate::common::policy_store::PolicyStore;
 
@@ -89,6 +103,8 @@ pub enum InitCedarlingError {
 pub struct Cedarling {
     log: log::Logger,
     authz: Arc<Authz>,
+    /// Stops the background tasks that are not owned by the logger
+    cancel_tkn: CancellationToken,
 }
 
 impl Cedarling {
@@ -105,7 +121,12 @@ impl Cedarling {
 
     /// Create a new instance of the Cedarling application.
     pub async fn new(config: &BootstrapConfig) -> Result<Cedarling, InitCedarlingError> {
-        let pdp_id = app_types::PdpID::new();
+        let pdp_id = config
+            .pdp_id
+            .as_deref()
+            .map(app_types::PdpID::parse)
+            .transpose()?
+            .unwrap_or_else(app_types::PdpID::new);
         let app_name = (!config.application_name.is_empty())
             .then(|| ApplicationName(config.application_name.clone()));
 
@@ -114,10 +135,11 @@ impl Cedarling {
             pdp_id,
             app_name,
             config.lock_config.as_ref(),
+            &config.http_config.lock,
         )
         .await?;
 
-        let service_config = ServiceConfig::new(config)
+        let service_config = ServiceConfig::new(config, &log)
             .await
             .inspect(|_| {
                 log.log_any(
@@ -135,11 +157,22 @@ impl Cedarling {
                 )
             })?;
 
+        let policy_store_version = service_config.policy_store_version.clone();
         let mut service_factory = ServiceFactory::new(config, service_config, log.clone());
+        let authz = service_factory.authz_service().await?;
+
+        let cancel_tkn = CancellationToken::new();
+        if let Some(poller) =
+            PolicyStorePoller::new(config, policy_store_version, &authz, log.clone())
+                .map_err(ServiceInitError::from)?
+        {
+            http::spawn_task(poller.run(cancel_tkn.clone()));
+        }
 
         Ok(Cedarling {
             log,
-            authz: service_factory.authz_service().await?,
+            authz,
+            cancel_tkn,
         })
     }
 
@@ -149,6 +182,15 @@ impl Cedarling {
         self.authz.authorize(request).await
     }
 
+    /// Authorize request and return the evaluation trace of the policies,
+    /// see [`AuthorizeResult::explanations`].
+    pub async fn authorize_explain(
+        &self,
+        request: Request,
+    ) -> Result<AuthorizeResult, AuthorizeError> {
+        self.authz.authorize_explain(request).await
+    }
+
     /// Authorize request with unsigned data.
     /// makes authorization decision based on the [`RequestUnverified`]
     pub async fn authorize_unsigned(
@@ -158,6 +200,30 @@ impl Cedarling {
         self.authz.authorize_unsigned(request).await
     }
 
+    /// Authorize request with unsigned data and return the evaluation trace of the policies,
+    /// see [`AuthorizeResult::explanations`].
+    pub async fn authorize_unsigned_explain(
+        &self,
+        request: RequestUnsigned,
+    ) -> Result<AuthorizeResult, AuthorizeError> {
+        self.authz.authorize_unsigned_explain(request).await
+    }
+
+    /// Evaluates every action of the schema that applies to the principals and the resource.
+    ///
+    /// The entities are built once and shared by all the actions. The result contains
+    /// the allowed actions and the authorization result of each evaluated action.
+    pub async fn allowed_actions(
+        &self,
+        principals: RequestPrincipals,
+        resource: EntityData,
+        context: serde_json::Value,
+    ) -> Result<AllowedActionsResult, AuthorizeError> {
+        self.authz
+            .allowed_actions(principals, resource, context)
+            .await
+    }
+
     /// Get entites derived from `cedar-policy` schema and tokens for `authorize` request.
     #[doc(hidden)]
     #[cfg(test)]
@@ -169,10 +235,60 @@ impl Cedarling {
         self.authz.build_entities(request, &tokens)
     }
 
-    /// Closes the connections to the Lock Server and pushes all available logs.
+    /// Links a policy template of the policy store to the values of its slots.
+    ///
+    /// The linked policy gets the ID of the link and is used by the following requests.
+    /// If the policy store was loaded from a file, the links are saved to it, otherwise
+    /// they are only kept in memory.
+    pub fn add_template_link(
+        &self,
+        link_id: &str,
+        link: TemplateLink,
+    ) -> Result<(), TemplateLinkError> {
+        self.authz.add_template_link(link_id, link)
+    }
+
+    /// Removes a policy linked from a template and returns its link.
+    pub fn remove_template_link(&self, link_id: &str) -> Result<TemplateLink, TemplateLinkError> {
+        self.authz.remove_template_link(link_id)
+    }
+
+    /// Returns the template links of the policy store, by link ID.
+    pub fn template_links(&self) -> HashMap<String, TemplateLink> {
+        self.authz.policy_store().policies.template_links().clone()
+    }
+
+    /// Returns the counters of the evaluations against the shadow policy store,
+    /// or `None` if no shadow policy store is configured.
+    ///
+    /// The requests are evaluated against the shadow policy store in the background,
+    /// so the counters may not include the latest requests yet.
+    pub fn shadow_stats(&self) -> Option<ShadowStats> {
+        self.authz.shadow_stats()
+    }
+
+    /// Subscribes to the log entries logged from now on, whatever the log type.
+    ///
+    /// The subscription ends when Cedarling is shut down.
+    pub fn subscribe_logs(&self, config: LogSubscriptionConfig) -> LogSubscription {
+        self.log.subscribe(config)
+    }
+
+    /// Stops polling the policy store, closes the connections to the Lock Server and
+    /// pushes all available logs.
     pub async fn shut_down(&self) {
+        self.cancel_tkn.cancel();
         self.log.shut_down().await;
     }
+
+    /// Stops sending logs to the Lock Server then deletes the client Cedarling registered
+    /// with the IDP, along with its credentials saved in `CEDARLING_LOCK_CREDENTIALS_FILE`.
+    ///
+    /// The next startup registers a new client. Returns `false` if the Lock Server
+    /// integration is not enabled or was already shut down.
+    pub async fn deregister_lock_client(&self) -> Result<bool, DeregisterClientError> {
+        self.log.deregister_lock_client().await
+    }
 }
 
 // implements LogStorage for Cedarling
@@ -201,4 +317,8 @@ impl LogStorage for Cedarling {
     fn get_logs_by_request_id_and_tag(&self, id: &str, tag: &str) -> Vec<serde_json::Value> {
         self.log.get_logs_by_request_id_and_tag(id, tag)
     }
+
+    fn query_logs(&self, query: &LogQuery) -> LogQueryResult {
+        self.log.query_logs(query)
+    }
 }
@@ -0,0 +1,566 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! The [`ClientAuth`] authenticates Cedarling's client with the token endpoint of the IDP.
+//!
+//! Besides the client secret issued by the IDP, the client can authenticate with a JWT
+//! signed with its private key ([RFC 7523](https://datatracker.ietf.org/doc/html/rfc7523))
+//! or with a client certificate over mutual TLS
+//! ([RFC 8705](https://datatracker.ietf.org/doc/html/rfc8705)), so no shared secret is
+//! needed.
+
+use crate::http::HttpClientBuildError;
+use crate::log::gen_uuid7;
+use crate::{HttpClientConfig, LockClientAuthConfig};
+use chrono::Utc;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk, KeyAlgorithm, PublicKeyUse};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use reqwest::{Client, RequestBuilder};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const CLIENT_ASSERTION_TYPE: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+/// How long the client assertions are valid for.
+const CLIENT_ASSERTION_LIFETIME_SECS: i64 = 60;
+const SIGN_EXPECT_MESSAGE: &str = "the key pair is checked when the key is loaded";
+
+/// How the client authenticates with the token endpoint.
+// the variants are named after the `token_endpoint_auth_method`s
+#[allow(clippy::enum_variant_names)]
+pub enum ClientAuth {
+    /// With the client secret issued by the IDP
+    ClientSecretBasic,
+    /// With a JWT signed with the private key of the client
+    PrivateKeyJwt(SigningKey),
+    /// With a client certificate issued by a CA trusted by the IDP
+    TlsClientAuth {
+        certificate: ClientCertificate,
+        subject_dn: String,
+    },
+    /// With a self-signed client certificate registered in the `jwks` of the client
+    SelfSignedTlsClientAuth {
+        certificate: ClientCertificate,
+        key: SigningKey,
+    },
+}
+
+impl ClientAuth {
+    /// Loads the keys and certificates of the configured authentication method.
+    ///
+    /// With mutual TLS, the certificate of the method is the only one the HTTP client
+    /// presents, so the `client_cert` of the `lock` HTTP client options must not be set.
+    pub fn load(
+        config: &LockClientAuthConfig,
+        http_config: &HttpClientConfig,
+    ) -> Result<Self, ClientAuthError> {
+        let is_mutual_tls = matches!(
+            config,
+            LockClientAuthConfig::TlsClientAuth { .. }
+                | LockClientAuthConfig::SelfSignedTlsClientAuth { .. }
+        );
+        if is_mutual_tls && http_config.client_cert.is_some() {
+            return Err(ClientAuthError::ConflictingClientCert);
+        }
+
+        let client_auth = match config {
+            LockClientAuthConfig::ClientSecretBasic => Self::ClientSecretBasic,
+            LockClientAuthConfig::PrivateKeyJwt { key_file, jwk_file } => {
+                Self::PrivateKeyJwt(SigningKey::from_files(key_file, jwk_file)?)
+            },
+            LockClientAuthConfig::TlsClientAuth {
+                cert_file,
+                key_file,
+                subject_dn,
+            } => Self::TlsClientAuth {
+                certificate: ClientCertificate::from_files(cert_file, key_file)?,
+                subject_dn: subject_dn.clone(),
+            },
+            LockClientAuthConfig::SelfSignedTlsClientAuth {
+                cert_file,
+                key_file,
+                jwk_file,
+            } => Self::SelfSignedTlsClientAuth {
+                certificate: ClientCertificate::from_files(cert_file, key_file)?,
+                key: SigningKey::from_files(key_file, jwk_file)?,
+            },
+        };
+
+        Ok(client_auth)
+    }
+
+    /// The `token_endpoint_auth_method` of the client.
+    pub fn method(&self) -> &'static str {
+        match self {
+            Self::ClientSecretBasic => "client_secret_basic",
+            Self::PrivateKeyJwt(_) => "private_key_jwt",
+            Self::TlsClientAuth { .. } => "tls_client_auth",
+            Self::SelfSignedTlsClientAuth { .. } => "self_signed_tls_client_auth",
+        }
+    }
+
+    /// Builds an HTTP client with the given options that presents the client certificate
+    /// of the mutual TLS methods, or else the `client_cert` of the options.
+    pub fn http_client(&self, config: &HttpClientConfig) -> Result<Client, HttpClientBuildError> {
+        let builder = crate::http::client_builder(config)?;
+        let builder = match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::TlsClientAuth { certificate, .. }
+            | Self::SelfSignedTlsClientAuth { certificate, .. } => {
+                builder.identity(certificate.identity.clone())
+            },
+            _ => builder,
+        };
+        builder.build().map_err(HttpClientBuildError::Build)
+    }
+
+    /// Adds the metadata of the authentication method to the client metadata.
+    pub fn add_metadata(&self, metadata: &mut Value) {
+        metadata["token_endpoint_auth_method"] = json!(self.method());
+
+        match self {
+            Self::ClientSecretBasic => {},
+            Self::PrivateKeyJwt(key) => {
+                metadata["token_endpoint_auth_signing_alg"] = json!(key.alg);
+                metadata["jwks"] = json!({ "keys": [key.jwk] });
+            },
+            Self::TlsClientAuth { subject_dn, .. } => {
+                metadata["tls_client_auth_subject_dn"] = json!(subject_dn);
+            },
+            Self::SelfSignedTlsClientAuth { certificate, key } => {
+                let mut jwk = key.jwk.clone();
+                jwk.common.x509_chain = Some(certificate.chain.clone());
+                metadata["jwks"] = json!({ "keys": [jwk] });
+            },
+        }
+    }
+
+    /// Authenticates a request to the token endpoint, `form` is the form of the request.
+    pub fn authenticate(
+        &self,
+        request: RequestBuilder,
+        form: &mut HashMap<String, String>,
+        token_endpoint: &str,
+        client_id: &str,
+        client_secret: &str,
+    ) -> RequestBuilder {
+        match self {
+            Self::ClientSecretBasic => return request.basic_auth(client_id, Some(client_secret)),
+            Self::PrivateKeyJwt(key) => {
+                form.insert(
+                    "client_assertion_type".to_string(),
+                    CLIENT_ASSERTION_TYPE.to_string(),
+                );
+                form.insert(
+                    "client_assertion".to_string(),
+                    key.client_assertion(client_id, token_endpoint),
+                );
+            },
+            // the client is authenticated by the certificate of the TLS connection
+            Self::TlsClientAuth { .. } | Self::SelfSignedTlsClientAuth { .. } => {},
+        }
+
+        form.insert("client_id".to_string(), client_id.to_string());
+        request
+    }
+}
+
+/// A private key the client signs its assertions with.
+pub struct SigningKey {
+    key: EncodingKey,
+    alg: Algorithm,
+    /// The public key, published in the `jwks` of the client
+    jwk: Jwk,
+}
+
+impl SigningKey {
+    /// Loads the private key from a PEM file and its public key from a JWK file.
+    pub fn from_files(key_file: &Path, jwk_file: &Path) -> Result<Self, ClientAuthError> {
+        let invalid_jwk = |err| ClientAuthError::InvalidKey(jwk_file.to_path_buf(), err);
+        let invalid_key = |err| ClientAuthError::InvalidKey(key_file.to_path_buf(), err);
+
+        let mut jwk = serde_json::from_str::<Jwk>(&read_file(jwk_file)?)
+            .map_err(|err| invalid_jwk(KeyError::Jwk(err.to_string())))?;
+        let pem = read_file(key_file)?;
+
+        let (alg, key) = match &jwk.algorithm {
+            AlgorithmParameters::RSA(_) => {
+                (Algorithm::RS256, EncodingKey::from_rsa_pem(pem.as_bytes()))
+            },
+            AlgorithmParameters::EllipticCurve(params) => match params.curve {
+                EllipticCurve::P256 => (Algorithm::ES256, EncodingKey::from_ec_pem(pem.as_bytes())),
+                EllipticCurve::P384 => (Algorithm::ES384, EncodingKey::from_ec_pem(pem.as_bytes())),
+                ref curve => return Err(invalid_jwk(KeyError::Unsupported(format!("{curve:?}")))),
+            },
+            _ => {
+                return Err(invalid_jwk(KeyError::Unsupported(
+                    "non RSA or EC".to_string(),
+                )));
+            },
+        };
+        let key = key.map_err(|err| invalid_key(KeyError::Rejected(err.to_string())))?;
+
+        // only the public parameters of the JWK are deserialized, so a private JWK is
+        // never published
+        jwk.common.key_algorithm = Some(match alg {
+            Algorithm::ES256 => KeyAlgorithm::ES256,
+            Algorithm::ES384 => KeyAlgorithm::ES384,
+            _ => KeyAlgorithm::RS256,
+        });
+        if jwk.common.key_operations.is_none() {
+            jwk.common.public_key_use = Some(PublicKeyUse::Signature);
+        }
+
+        let key = Self { key, alg, jwk };
+        key.check_key_pair().map_err(invalid_key)?;
+        Ok(key)
+    }
+
+    /// Checks that the JWK is the public key of the private key, so the IDP can verify
+    /// the assertions.
+    fn check_key_pair(&self) -> Result<(), KeyError> {
+        let token = jsonwebtoken::encode(&Header::new(self.alg), &json!({}), &self.key)
+            .map_err(|err| KeyError::Rejected(err.to_string()))?;
+        let public_key =
+            DecodingKey::from_jwk(&self.jwk).map_err(|err| KeyError::Jwk(err.to_string()))?;
+
+        let mut validation = Validation::new(self.alg);
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+        jsonwebtoken::decode::<Value>(&token, &public_key, &validation)
+            .map(|_| ())
+            .map_err(|_| KeyError::Mismatch)
+    }
+
+    /// Creates a client assertion for the token endpoint
+    /// ([RFC 7523](https://datatracker.ietf.org/doc/html/rfc7523#section-3)).
+    fn client_assertion(&self, client_id: &str, token_endpoint: &str) -> String {
+        let now = Utc::now().timestamp();
+        let header = Header {
+            kid: self.jwk.common.key_id.clone(),
+            ..Header::new(self.alg)
+        };
+        let claims = json!({
+            "iss": client_id,
+            "sub": client_id,
+            "aud": token_endpoint,
+            "jti": gen_uuid7().to_string(),
+            "iat": now,
+            "exp": now + CLIENT_ASSERTION_LIFETIME_SECS,
+        });
+
+        jsonwebtoken::encode(&header, &claims, &self.key).expect(SIGN_EXPECT_MESSAGE)
+    }
+}
+
+/// The certificate the client presents for mutual TLS.
+pub struct ClientCertificate {
+    /// The base64 DER of the certificate chain, for the `x5c` of the JWK
+    chain: Vec<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    identity: reqwest::Identity,
+}
+
+impl ClientCertificate {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn from_files(cert_file: &Path, key_file: &Path) -> Result<Self, ClientAuthError> {
+        use base64::prelude::*;
+
+        let invalid_certificate =
+            |err: String| ClientAuthError::InvalidCertificate(cert_file.to_path_buf(), err);
+        let cert_pem = read_file(cert_file)?;
+        let key_pem = read_file(key_file)?;
+
+        let chain = pem::parse_many(&cert_pem)
+            .map_err(|err| invalid_certificate(err.to_string()))?
+            .into_iter()
+            .filter(|block| block.tag() == "CERTIFICATE")
+            .map(|block| BASE64_STANDARD.encode(block.contents()))
+            .collect::<Vec<_>>();
+        if chain.is_empty() {
+            return Err(invalid_certificate(
+                "the file has no certificate".to_string(),
+            ));
+        }
+
+        // the TLS backend only supports PKCS#8 keys
+        let identity = reqwest::Identity::from_pkcs8_pem(cert_pem.as_bytes(), key_pem.as_bytes())
+            .map_err(|err| invalid_certificate(err.to_string()))?;
+
+        Ok(Self { chain, identity })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn from_files(_cert_file: &Path, _key_file: &Path) -> Result<Self, ClientAuthError> {
+        Err(ClientAuthError::MtlsNotSupported)
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, ClientAuthError> {
+    fs::read_to_string(path).map_err(|err| ClientAuthError::Read(path.to_path_buf(), err))
+}
+
+#[derive(Debug, Error)]
+pub enum ClientAuthError {
+    #[error("failed to read '{}': {1}", .0.display())]
+    Read(PathBuf, #[source] io::Error),
+    #[error("invalid key in '{}': {1}", .0.display())]
+    InvalidKey(PathBuf, #[source] KeyError),
+    #[error("invalid client certificate in '{}': {1}", .0.display())]
+    InvalidCertificate(PathBuf, String),
+    #[error(
+        "the `client_cert` of the `lock` HTTP client options cannot be set with the mutual TLS \
+         client authentication methods, which present their own certificate"
+    )]
+    ConflictingClientCert,
+    #[cfg(target_arch = "wasm32")]
+    #[error("mutual TLS client authentication is not supported in WASM")]
+    MtlsNotSupported,
+}
+
+#[derive(Debug, Error)]
+pub enum KeyError {
+    #[error("malformed JWK: {0}")]
+    Jwk(String),
+    #[error("unsupported key `{0}`, only RSA and EC P-256 or P-384 keys are supported")]
+    Unsupported(String),
+    #[error("the key was rejected: {0}")]
+    Rejected(String),
+    #[error("the private key does not match the public JWK")]
+    Mismatch,
+}
+
+#[cfg(test)]
+impl SigningKey {
+    /// Generates an EC P-256 key.
+    pub fn generate() -> Self {
+        let (private_pem, public_jwk) = test::generate_p256(None);
+        let jwk = serde_json::from_value(public_jwk).expect("should be a valid JWK");
+        let key = Self {
+            key: EncodingKey::from_ec_pem(private_pem.as_bytes()).expect("should load the key"),
+            alg: Algorithm::ES256,
+            jwk,
+        };
+        key.check_key_pair().expect("should be a key pair");
+        key
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::HttpClientCertConfig;
+    use jsonwebkey::{JsonWebKey, Key};
+
+    /// Generates an EC P-256 key, returns the PKCS#8 PEM of the private key and the
+    /// public JWK
+    pub(super) fn generate_p256(kid: Option<&str>) -> (String, Value) {
+        let key = Key::generate_p256();
+        let private_pem = key.to_pem();
+        let mut jwk = JsonWebKey::new(key.to_public().expect("should be asymmetric").into_owned());
+        jwk.key_id = kid.map(str::to_string);
+        (
+            private_pem,
+            serde_json::to_value(&jwk).expect("should serialize the JWK"),
+        )
+    }
+
+    fn temp_file(content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("cedarling-client-key-{}", gen_uuid7()));
+        fs::write(&path, content).expect("should write the file");
+        path
+    }
+
+    fn load_private_key_jwt(key_pem: &str, jwk: &Value) -> Result<ClientAuth, ClientAuthError> {
+        let key_file = temp_file(key_pem);
+        let jwk_file = temp_file(&jwk.to_string());
+        let client_auth = ClientAuth::load(
+            &LockClientAuthConfig::PrivateKeyJwt {
+                key_file: key_file.clone(),
+                jwk_file: jwk_file.clone(),
+            },
+            &HttpClientConfig::default(),
+        );
+        fs::remove_file(key_file).unwrap();
+        fs::remove_file(jwk_file).unwrap();
+        client_auth
+    }
+
+    /// Returns the client assertion sent to the token endpoint
+    fn client_assertion(client_auth: &ClientAuth) -> String {
+        let mut form = HashMap::new();
+        let _request = client_auth.authenticate(
+            Client::new().post("https://test.jans.io/token"),
+            &mut form,
+            "https://test.jans.io/token",
+            "some_client_id",
+            "",
+        );
+
+        assert_eq!(form["client_id"], "some_client_id");
+        assert_eq!(form["client_assertion_type"], CLIENT_ASSERTION_TYPE);
+        form["client_assertion"].clone()
+    }
+
+    /// Verifies an assertion with the published JWK, like the IDP does
+    fn verify_assertion(assertion: &str, jwk: &Value) -> (Header, Value) {
+        let jwk = serde_json::from_value::<Jwk>(jwk.clone()).expect("should be a valid JWK");
+        let mut validation = Validation::new(Algorithm::ES256);
+        validation.set_audience(&["https://test.jans.io/token"]);
+        validation.set_issuer(&["some_client_id"]);
+        validation.set_required_spec_claims(&["iss", "sub", "aud", "exp"]);
+
+        let token = jsonwebtoken::decode::<Value>(
+            assertion,
+            &DecodingKey::from_jwk(&jwk).expect("should be a public key"),
+            &validation,
+        )
+        .expect("the assertion should be valid");
+        (token.header, token.claims)
+    }
+
+    #[test]
+    fn signs_client_assertions_that_verify_with_the_published_jwk() {
+        let (private_pem, public_jwk) = generate_p256(Some("some_kid"));
+        let client_auth = load_private_key_jwt(&private_pem, &public_jwk).expect("should load");
+
+        let mut metadata = json!({});
+        client_auth.add_metadata(&mut metadata);
+        assert_eq!(metadata["token_endpoint_auth_method"], "private_key_jwt");
+        assert_eq!(metadata["token_endpoint_auth_signing_alg"], "ES256");
+        let jwk = &metadata["jwks"]["keys"][0];
+        assert_eq!(jwk["kty"], "EC");
+        assert_eq!(jwk["crv"], "P-256");
+        assert_eq!(jwk["kid"], "some_kid");
+        assert_eq!(jwk["alg"], "ES256");
+        assert_eq!(jwk["x"], public_jwk["x"]);
+        assert_eq!(jwk["y"], public_jwk["y"]);
+
+        let (header, claims) = verify_assertion(&client_assertion(&client_auth), jwk);
+        assert_eq!(header.alg, Algorithm::ES256);
+        assert_eq!(header.kid.as_deref(), Some("some_kid"));
+        assert_eq!(claims["sub"], "some_client_id");
+        assert!(claims["jti"].is_string());
+        assert!(claims["exp"].as_i64().unwrap() > claims["iat"].as_i64().unwrap());
+
+        // every assertion has a new ID
+        let (_, other_claims) = verify_assertion(&client_assertion(&client_auth), jwk);
+        assert_ne!(claims["jti"], other_claims["jti"]);
+    }
+
+    #[test]
+    fn does_not_publish_the_private_key_of_a_jwk() {
+        let key = Key::generate_p256();
+        let private_jwk = serde_json::to_value(JsonWebKey::new(key.clone())).unwrap();
+        assert!(private_jwk.get("d").is_some());
+
+        let client_auth = load_private_key_jwt(&key.to_pem(), &private_jwk).expect("should load");
+        let mut metadata = json!({});
+        client_auth.add_metadata(&mut metadata);
+        let jwk = &metadata["jwks"]["keys"][0];
+        assert!(
+            jwk.get("d").is_none(),
+            "only the public key should be published"
+        );
+        assert!(jwk.get("kid").is_none());
+
+        let (header, _) = verify_assertion(&client_assertion(&client_auth), jwk);
+        assert_eq!(header.kid, None);
+    }
+
+    #[test]
+    fn rejects_a_jwk_of_another_key() {
+        let (private_pem, _) = generate_p256(None);
+        let (_, other_jwk) = generate_p256(None);
+
+        let err = load_private_key_jwt(&private_pem, &other_jwk)
+            .err()
+            .expect("the keys do not match");
+        assert!(
+            matches!(err, ClientAuthError::InvalidKey(_, KeyError::Mismatch)),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_keys() {
+        let (private_pem, _) = generate_p256(None);
+
+        let err = load_private_key_jwt(&private_pem, &json!({"kty": "oct", "k": "c2VjcmV0"}))
+            .err()
+            .expect("symmetric keys are not supported");
+        assert!(
+            matches!(
+                err,
+                ClientAuthError::InvalidKey(_, KeyError::Unsupported(_))
+            ),
+            "unexpected error: {err}"
+        );
+
+        let (_, public_jwk) = generate_p256(None);
+        let err = load_private_key_jwt("some key", &public_jwk)
+            .err()
+            .expect("the key is not a PEM file");
+        assert!(
+            matches!(err, ClientAuthError::InvalidKey(_, KeyError::Rejected(_))),
+            "unexpected error: {err}"
+        );
+
+        let err = ClientAuth::load(
+            &LockClientAuthConfig::PrivateKeyJwt {
+                key_file: "missing-client-key.pem".into(),
+                jwk_file: "missing-client-key.jwk".into(),
+            },
+            &HttpClientConfig::default(),
+        )
+        .err()
+        .expect("the key file does not exist");
+        assert!(
+            matches!(err, ClientAuthError::Read(..)),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn rejects_two_client_certificates() {
+        let http_config = HttpClientConfig {
+            client_cert: Some(HttpClientCertConfig {
+                cert_file: "/etc/cedarling/client.crt".into(),
+                key_file: "/etc/cedarling/client.key".into(),
+            }),
+            ..Default::default()
+        };
+
+        let mutual_tls_configs = [
+            LockClientAuthConfig::TlsClientAuth {
+                cert_file: "/etc/cedarling/lock-client.crt".into(),
+                key_file: "/etc/cedarling/lock-client.key".into(),
+                subject_dn: "CN=cedarling".to_string(),
+            },
+            LockClientAuthConfig::SelfSignedTlsClientAuth {
+                cert_file: "/etc/cedarling/lock-client.crt".into(),
+                key_file: "/etc/cedarling/lock-client.key".into(),
+                jwk_file: "/etc/cedarling/lock-client.jwk".into(),
+            },
+        ];
+        for config in mutual_tls_configs {
+            let err = ClientAuth::load(&config, &http_config)
+                .err()
+                .expect("only one client certificate can be presented");
+            assert!(
+                matches!(err, ClientAuthError::ConflictingClientCert),
+                "unexpected error: {err}"
+            );
+        }
+
+        // the other methods present the certificate of the HTTP client options
+        let client_auth = ClientAuth::load(&LockClientAuthConfig::ClientSecretBasic, &http_config)
+            .expect("the certificate of the options should be allowed");
+        assert_eq!(client_auth.method(), "client_secret_basic");
+    }
+}
@@ -0,0 +1,295 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! The [`CredentialStore`] saves the client credentials from the Dynamic Client
+//! Registration to an encrypted file so they can be reused after a restart.
+//!
+//! The file is encrypted with AES-256-GCM using a key derived from the
+//! `CEDARLING_LOCK_CREDENTIALS_KEY` passphrase with PBKDF2-HMAC-SHA256.
+
+use super::register_client::ClientRegistration;
+use crate::LockCredentialsStoreConfig;
+use crate::common::file_utils::write_private_file;
+use base64::prelude::*;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::num::NonZeroU32;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const FILE_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+#[cfg(not(test))]
+const PBKDF2_ITERATIONS: u32 = 600_000;
+// We use less iterations for the tests since deriving the key is slow on debug builds
+#[cfg(test)]
+const PBKDF2_ITERATIONS: u32 = 1_000;
+/// Iterations accepted when loading a file, so a tampered file can neither weaken
+/// the key derivation nor stall the startup
+const PBKDF2_ITERATIONS_RANGE: RangeInclusive<u32> = PBKDF2_ITERATIONS..=10 * PBKDF2_ITERATIONS;
+
+/// A client registered with the IDP.
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+pub struct StoredClient {
+    /// ID of the Cedarling instance the client was registered for
+    pub pdp_id: String,
+    /// The `.well-known/openid-configuration` endpoint of the IDP
+    pub oidc_endpoint: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// The RFC 7592 registration access token and client configuration endpoint
+    pub registration: Option<ClientRegistration>,
+}
+
+/// Content of the credentials file.
+#[derive(Serialize, Deserialize)]
+struct EncryptedFile {
+    version: u32,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Encrypted file where the client credentials are saved.
+pub struct CredentialStore {
+    path: PathBuf,
+    key: String,
+    rng: SystemRandom,
+}
+
+impl CredentialStore {
+    pub fn new(config: &LockCredentialsStoreConfig) -> Self {
+        Self {
+            path: config.path.clone(),
+            key: config.key.clone(),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Loads the saved client, [`None`] if no client was saved yet.
+    pub fn load(&self) -> Result<Option<StoredClient>, CredentialStoreError> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(CredentialStoreError::Read(self.path.clone(), err)),
+        };
+
+        let file = serde_json::from_str::<EncryptedFile>(&content)
+            .map_err(|err| CredentialStoreError::Malformed(err.to_string()))?;
+        if file.version != FILE_VERSION {
+            return Err(CredentialStoreError::Malformed(format!(
+                "unsupported version {}",
+                file.version
+            )));
+        }
+        let iterations = Some(file.iterations)
+            .filter(|iterations| PBKDF2_ITERATIONS_RANGE.contains(iterations))
+            .and_then(NonZeroU32::new)
+            .ok_or_else(|| {
+                CredentialStoreError::Malformed(format!(
+                    "the PBKDF2 iterations should be between {} and {}, got {}",
+                    PBKDF2_ITERATIONS_RANGE.start(),
+                    PBKDF2_ITERATIONS_RANGE.end(),
+                    file.iterations
+                ))
+            })?;
+        let salt = decode_base64(&file.salt)?;
+        let nonce = Nonce::try_assume_unique_for_key(&decode_base64(&file.nonce)?)
+            .map_err(|_| CredentialStoreError::Malformed("invalid nonce".to_string()))?;
+        let mut ciphertext = decode_base64(&file.ciphertext)?;
+
+        let plaintext = derive_key(&self.key, &salt, iterations)
+            .open_in_place(nonce, Aad::empty(), &mut ciphertext)
+            .map_err(|_| CredentialStoreError::Decrypt)?;
+        let client = serde_json::from_slice::<StoredClient>(plaintext)
+            .map_err(|err| CredentialStoreError::Malformed(err.to_string()))?;
+
+        Ok(Some(client))
+    }
+
+    /// Saves the client, replacing the previously saved client.
+    pub fn save(&self, client: &StoredClient) -> Result<(), CredentialStoreError> {
+        let mut salt = [0; SALT_LEN];
+        let mut nonce = [0; NONCE_LEN];
+        self.rng
+            .fill(&mut salt)
+            .and_then(|_| self.rng.fill(&mut nonce))
+            .map_err(|_| CredentialStoreError::Encrypt)?;
+
+        let iterations =
+            NonZeroU32::new(PBKDF2_ITERATIONS).expect("PBKDF2_ITERATIONS should not be zero");
+        let mut ciphertext =
+            serde_json::to_vec(client).expect("stored client should serialize to JSON");
+        derive_key(&self.key, &salt, iterations)
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut ciphertext,
+            )
+            .map_err(|_| CredentialStoreError::Encrypt)?;
+
+        let file = EncryptedFile {
+            version: FILE_VERSION,
+            iterations: iterations.get(),
+            salt: BASE64_STANDARD.encode(salt),
+            nonce: BASE64_STANDARD.encode(nonce),
+            ciphertext: BASE64_STANDARD.encode(ciphertext),
+        };
+        let content = serde_json::to_string_pretty(&file).expect("file should serialize to JSON");
+        write_private_file(&self.path, content.as_bytes())
+            .map_err(|err| CredentialStoreError::Write(self.path.clone(), err))
+    }
+
+    /// Removes the saved client, if any.
+    pub fn remove(&self) -> Result<(), CredentialStoreError> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                Err(CredentialStoreError::Write(self.path.clone(), err))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: NonZeroU32) -> LessSafeKey {
+    let mut key = [0; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key).expect("key should have the AES-256 length");
+    LessSafeKey::new(key)
+}
+
+fn decode_base64(value: &str) -> Result<Vec<u8>, CredentialStoreError> {
+    BASE64_STANDARD
+        .decode(value)
+        .map_err(|err| CredentialStoreError::Malformed(err.to_string()))
+}
+
+#[derive(Debug, Error)]
+pub enum CredentialStoreError {
+    #[error("failed to read the lock credentials file '{}': {1}", .0.display())]
+    Read(PathBuf, #[source] io::Error),
+    #[error("failed to write the lock credentials file '{}': {1}", .0.display())]
+    Write(PathBuf, #[source] io::Error),
+    #[error("the lock credentials file is malformed: {0}")]
+    Malformed(String),
+    #[error(
+        "failed to decrypt the lock credentials file, check the `CEDARLING_LOCK_CREDENTIALS_KEY`"
+    )]
+    Decrypt,
+    #[error("failed to encrypt the lock credentials")]
+    Encrypt,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn store(key: &str) -> CredentialStore {
+        let path = std::env::temp_dir().join(format!(
+            "cedarling-lock-credentials-{}.json",
+            crate::log::gen_uuid7()
+        ));
+        CredentialStore::new(&LockCredentialsStoreConfig {
+            path,
+            key: key.to_string(),
+        })
+    }
+
+    fn client() -> StoredClient {
+        StoredClient {
+            pdp_id: "0197a6c2-6c3b-4bfe-9a3f-6f1b2a1f0c2d".to_string(),
+            oidc_endpoint: "https://test.jans.io/.well-known/openid-configuration".to_string(),
+            client_id: "some_client_id".to_string(),
+            client_secret: "some_client_secret".to_string(),
+            registration: Some(ClientRegistration {
+                registration_access_token: "some_registration_token".to_string(),
+                registration_client_uri: "https://test.jans.io/register?client_id=some_client_id"
+                    .to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn can_save_and_load_a_client() {
+        let store = store("some passphrase");
+        assert!(
+            store.load().expect("should load").is_none(),
+            "no client should be saved yet"
+        );
+
+        store.save(&client()).expect("should save the client");
+        let loaded = store.load().expect("should load the client");
+        assert!(loaded == Some(client()), "should load the saved client");
+
+        let content = fs::read_to_string(store.path()).unwrap();
+        assert!(
+            !content.contains("some_client_secret"),
+            "the credentials should be encrypted"
+        );
+
+        store.remove().expect("should remove the client");
+        assert!(store.load().expect("should load").is_none());
+        store.remove().expect("removing twice should not fail");
+    }
+
+    #[test]
+    fn errors_with_the_wrong_key() {
+        let store = store("some passphrase");
+        store.save(&client()).expect("should save the client");
+
+        let other_store = CredentialStore::new(&LockCredentialsStoreConfig {
+            path: store.path().to_path_buf(),
+            key: "another passphrase".to_string(),
+        });
+        let err = other_store
+            .load()
+            .err()
+            .expect("should not decrypt with another key");
+        assert!(matches!(err, CredentialStoreError::Decrypt), "{err}");
+
+        store.remove().unwrap();
+    }
+
+    #[test]
+    fn errors_on_iterations_out_of_bounds() {
+        let store = store("some passphrase");
+        store.save(&client()).expect("should save the client");
+        let content = fs::read_to_string(store.path()).unwrap();
+
+        for iterations in [1, u32::MAX] {
+            let mut file = serde_json::from_str::<serde_json::Value>(&content).unwrap();
+            file["iterations"] = iterations.into();
+            fs::write(store.path(), file.to_string()).unwrap();
+
+            let err = store
+                .load()
+                .err()
+                .expect("should not load a file with too few or too many iterations");
+            assert!(
+                matches!(err, CredentialStoreError::Malformed(ref msg) if msg.contains("iterations")),
+                "{err}"
+            );
+        }
+
+        store.remove().unwrap();
+    }
+}
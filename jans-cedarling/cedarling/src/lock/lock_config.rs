@@ -8,8 +8,8 @@
 
 use std::str::FromStr;
 
-use super::init_http_client;
 use http_utils::{Backoff, HttpRequestError, Sender};
+use reqwest::Client;
 use serde::{Deserialize, Deserializer, de};
 
 #[derive(Debug, Deserialize, PartialEq, Clone)]
@@ -65,11 +65,8 @@ pub struct ConfigEndpoints {
 impl LockConfig {
     pub async fn get(
         lock_config_url: &url::Url,
-        accept_invalid_certs: bool,
+        client: &Client,
     ) -> Result<Self, HttpRequestError> {
-        let client = init_http_client(None, accept_invalid_certs)
-            .map_err(HttpRequestError::InitializeHttpClient)?;
-
         let mut sender = Sender::new(Backoff::default_exponential());
 
         let config: LockConfig = sender.send(|| client.get(lock_config_url.as_ref())).await?;
@@ -5,15 +5,21 @@
 
 //! The [`LogWorker`] runs in the background and is responsible for collecting and sending
 //! logs to the lock server's `/audit/log` endpoint.
+//!
+//! The logs that could not be sent are kept in the buffer and sent with the next batch.
+//! A [`CircuitBreaker`] stops the worker from retrying while the endpoint is down.
 
 use super::log_entry::LockLogEntry;
+use super::token_manager::TokenManager;
 use crate::log::{LogStrategy, LoggerWeak};
 use crate::LogWriter;
 
-use super::WORKER_HTTP_RETRY_DUR;
+use super::{WORKER_HTTP_MAX_RETRIES, WORKER_HTTP_RETRY_DUR};
+use crate::http::{circuit_breaker, log_failed_attempts};
 use futures::StreamExt;
 use futures::channel::mpsc;
-use reqwest::Client;
+use http_utils::{Backoff, HttpRequestError, Sender};
+use reqwest::{Client, StatusCode};
 use serde_json::Value;
 use std::collections::VecDeque;
 use std::sync::Arc;
@@ -35,7 +41,9 @@ pub struct LogWorker {
     log_buffer: VecDeque<Box<str>>,
     log_interval: Duration,
     http_client: Arc<Client>,
+    token_manager: Arc<TokenManager>,
     log_endpoint: Url,
+    sender: Sender,
     logger: Option<LoggerWeak>,
 }
 
@@ -43,14 +51,24 @@ impl LogWorker {
     pub fn new(
         log_interval: Duration,
         http_client: Arc<Client>,
+        token_manager: Arc<TokenManager>,
         log_endpoint: Url,
         logger: Option<LoggerWeak>,
     ) -> Self {
+        let sender = Sender::new(
+            Backoff::new_exponential(WORKER_HTTP_RETRY_DUR, Some(WORKER_HTTP_MAX_RETRIES))
+                .with_jitter(0.2),
+        )
+        .with_circuit_breaker(circuit_breaker(log_endpoint.as_str(), logger.clone()));
+        let sender = log_failed_attempts(sender, logger.clone());
+
         Self {
             log_interval,
             log_buffer: VecDeque::new(),
             http_client,
+            token_manager,
             log_endpoint,
+            sender,
             logger,
         }
     }
@@ -73,12 +91,12 @@ impl LogWorker {
                 // Send logs to the server
                 _ = sleep(self.log_interval) => {
                     let logger = self.logger.as_ref().and_then(|logger| logger.upgrade());
-                    post_logs(&mut self.log_buffer, &logger, self.http_client.clone(), &self.log_endpoint).await;
+                    post_logs(&mut self.log_buffer, &logger, &mut self.sender, &self.http_client, &self.token_manager, &self.log_endpoint).await;
                 },
 
                 _ = cancel_tkn.cancelled() => {
                     let logger = self.logger.as_ref().and_then(|logger| logger.upgrade());
-                    post_logs(&mut self.log_buffer, &logger, self.http_client.clone(), &self.log_endpoint).await;
+                    post_logs(&mut self.log_buffer, &logger, &mut self.sender, &self.http_client, &self.token_manager, &self.log_endpoint).await;
                     logger.log_any(LockLogEntry::info(
                         "gracefully shutting down lock log worker",
                     ));
@@ -89,10 +107,16 @@ impl LogWorker {
     }
 }
 
+/// POSTs the buffered logs to the lock server.
+///
+/// The logs stay in the buffer if the request fails after the retries of the
+/// [`Sender`], so they get sent on the next interval.
 async fn post_logs(
     log_buf: &mut VecDeque<Box<str>>,
     logger: &Option<Arc<LogStrategy>>,
-    http_client: Arc<Client>,
+    sender: &mut Sender,
+    http_client: &Client,
+    token_manager: &TokenManager,
     log_endpoint: &Url,
 ) {
     // save the length at the time the function is called
@@ -122,13 +146,29 @@ async fn post_logs(
         )));
     }
 
+    // whether the lock server rejected the access token on the previous attempt
+    let mut token_rejected = false;
     loop {
-        let resp = http_client
-            .post(log_endpoint.as_ref())
-            .body(logs.to_string())
-            .send()
-            .await
-            .and_then(|resp| resp.error_for_status());
+        let access_token = match token_manager.access_token().await {
+            Ok(access_token) => access_token,
+            Err(err) => {
+                logger.log_any(LockLogEntry::error(format!(
+                    "failed to get an access token to POST logs to '{}', will retry on the next interval: {}",
+                    log_endpoint.as_ref(),
+                    err
+                )));
+                return;
+            },
+        };
+
+        let resp = sender
+            .send_request(|| {
+                http_client
+                    .post(log_endpoint.as_ref())
+                    .bearer_auth(&access_token)
+                    .body(logs.to_string())
+            })
+            .await;
 
         match resp {
             Ok(_) => {
@@ -138,13 +178,35 @@ async fn post_logs(
                 )));
                 break;
             },
+            // only retry right away the first time the token gets rejected
+            Err(err) if err.status() == Some(StatusCode::UNAUTHORIZED) && !token_rejected => {
+                logger.log_any(LockLogEntry::warn(format!(
+                    "'{}' rejected the access token, requesting a new one",
+                    log_endpoint.as_ref(),
+                )));
+                token_rejected = true;
+                if let Err(err) = token_manager.refresh_rejected(&access_token).await {
+                    logger.log_any(LockLogEntry::error(format!(
+                        "failed to get a new access token for the lock server: {}",
+                        err
+                    )));
+                    return;
+                }
+            },
+            Err(err @ HttpRequestError::CircuitOpen(_)) => {
+                logger.log_any(LockLogEntry::warn(format!(
+                    "skipped sending {} logs, will retry on the next interval: {}",
+                    batch_size, err
+                )));
+                return;
+            },
             Err(err) => {
                 logger.log_any(LockLogEntry::error(format!(
-                    "failed to POST logs to '{}': {}",
+                    "failed to POST logs to '{}', will retry on the next interval: {}",
                     log_endpoint.as_ref(),
                     err
                 )));
-                sleep(WORKER_HTTP_RETRY_DUR).await;
+                return;
             },
         }
     }
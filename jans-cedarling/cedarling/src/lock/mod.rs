@@ -24,6 +24,11 @@
 //! - **LogWorker**: Background worker that sends logs to the Lock Server
 //! - **SSA Validation**: Validates Software Statement Assertion JWTs
 //! - **Client Registration**: Handles Dynamic Client Registration with the IDP
+//! - **TokenManager**: Keeps the access token that is shared by all the workers
+//! - **CredentialStore**: Saves the client credentials to an encrypted file so they are
+//!   reused after a restart
+//! - **ClientAuth**: Authenticates the client with the token endpoint, with the client
+//!   secret, a signed JWT (`private_key_jwt`) or a client certificate (mutual TLS)
 //!
 //! ### Flow
 //!
@@ -33,6 +38,18 @@
 //! 4. **Access Token**: Client credentials are obtained for Lock Server communication
 //! 5. **Logging**: Authorization decisions are sent to the Lock Server's audit endpoint
 //!
+//! The access token is refreshed shortly before it expires and whenever the Lock Server
+//! rejects it with a `401`. If the IDP no longer recognizes the client, the client is
+//! registered again.
+//!
+//! The workers retry the failed requests a few times with a jittered backoff and keep
+//! the logs for their next run. A circuit breaker per endpoint stops the requests while
+//! the endpoint keeps failing and logs when it opens and closes.
+//!
+//! If `CEDARLING_LOCK_CREDENTIALS_FILE` is set, the client is saved and reused on the next
+//! startups. The stored client is read with the client configuration endpoint of
+//! RFC 7592 and updated if its metadata changed.
+//!
 //! ## SSA JWT Validation
 //!
 //! Software Statement Assertion (SSA) JWTs provide a secure way to register clients
@@ -67,7 +84,9 @@
 //! - **InvalidSsaJwt**: SSA JWT validation failed
 //! - **GetLockConfig**: Failed to retrieve Lock Server configuration
 //! - **ClientRegistration**: Failed to register client with IDP
-//! - **InitHttpClient**: Failed to initialize HTTP client
+//! - **ClientAuth**: Failed to load the key or the certificate of the client
+//! - **InitHttpClient**: Failed to initialize HTTP client from the `lock` options of
+//!   `CEDARLING_HTTP_CLIENT`
 //!
 //! ## Integration with IDP
 //!
@@ -86,31 +105,44 @@
 //! - Issue access tokens for Lock Server communication
 //! - Provide JWKS endpoint for key validation
 
+mod client_auth;
+mod credential_store;
 mod lock_config;
 mod log_entry;
 mod log_worker;
 mod register_client;
 pub mod ssa_validation;
+mod token_manager;
 
 use crate::app_types::PdpID;
 use crate::common::issuer_utils::normalize_issuer;
+use crate::http::HttpClientBuildError;
 use crate::log::LoggerWeak;
 use crate::log::interface::Loggable;
-use crate::{LockServiceConfig, LogWriter};
+use crate::{HttpClientConfig, LockServiceConfig, LogWriter};
+use client_auth::{ClientAuth, ClientAuthError};
+use credential_store::CredentialStore;
 use futures::channel::mpsc;
 use lock_config::*;
 use log_entry::LockLogEntry;
 use log_worker::*;
-use register_client::{ClientRegistrationError, register_client};
-use reqwest::Client;
-use reqwest::header::{HeaderMap, HeaderValue};
+use register_client::ClientRegistrationError;
 use ssa_validation::validate_ssa_jwt;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
+pub use token_manager::DeregisterClientError;
+use token_manager::TokenManager;
 use tokio_util::sync::CancellationToken;
 
 /// The base duration to wait for if an http request fails for workers.
+#[cfg(not(test))]
 pub const WORKER_HTTP_RETRY_DUR: Duration = Duration::from_secs(10);
+// We implement a faster retry for the tests
+#[cfg(test)]
+pub const WORKER_HTTP_RETRY_DUR: Duration = Duration::from_millis(10);
+/// The number of times the workers retry a failed http request before giving up
+/// until their next run.
+pub const WORKER_HTTP_MAX_RETRIES: u32 = 3;
 
 #[derive(Debug)]
 struct WorkerSenderAndHandle {
@@ -122,47 +154,34 @@ struct WorkerSenderAndHandle {
 #[derive(Debug)]
 pub(crate) struct LockService {
     log_worker: Option<WorkerSenderAndHandle>,
+    token_manager: Arc<TokenManager>,
     logger: Option<LoggerWeak>,
     cancel_tkn: CancellationToken,
 }
 
-pub fn init_http_client(
-    default_headers: Option<HeaderMap>,
-    accept_invalid_certs: bool,
-) -> Result<Client, reqwest::Error> {
-    let headers = default_headers.unwrap_or_default();
-
-    if accept_invalid_certs {
-        // NOTE: WASM builds fail when calling .danger_accept_invalid_certs
-        #[cfg(any(target_arch = "wasm32", target_arch = "wasm64"))]
-        {
-            Client::builder().default_headers(headers).build()
-        }
-
-        #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
-        {
-            Client::builder()
-                .default_headers(headers)
-                .danger_accept_invalid_certs(true)
-                .build()
-        }
-    } else {
-        Client::builder().default_headers(headers).build()
-    }
-}
-
 impl LockService {
+    /// Starts the Lock service, the requests to the Lock Server and its IDP are sent
+    /// with an HTTP client created from `http_config`.
     pub async fn new(
         pdp_id: PdpID,
         bootstrap_conf: &LockServiceConfig,
+        http_config: &HttpClientConfig,
         logger: Option<LoggerWeak>,
     ) -> Result<Self, InitLockServiceError> {
+        // `CEDARLING_LOCK_ACCEPT_INVALID_CERTS` is kept for backward compatibility
+        let http_config = HttpClientConfig {
+            accept_invalid_certs: http_config.accept_invalid_certs
+                || bootstrap_conf.accept_invalid_certs,
+            ..http_config.clone()
+        };
+
+        // The client certificate is also presented to the lock server, since the IDP
+        // may bind the access tokens to it
+        let client_auth = ClientAuth::load(&bootstrap_conf.client_auth, &http_config)?;
+        let http_client = client_auth.http_client(&http_config)?;
+
         // Get lock config from the config uri endpoint first
-        let lock_config = LockConfig::get(
-            &bootstrap_conf.config_uri,
-            bootstrap_conf.accept_invalid_certs,
-        )
-        .await?;
+        let lock_config = LockConfig::get(&bootstrap_conf.config_uri, &http_client).await?;
 
         // Validate SSA JWT if provided
         if let Some(ssa_jwt) = &bootstrap_conf.ssa_jwt {
@@ -173,33 +192,32 @@ impl LockService {
             );
 
             // Validate the SSA JWT
-            validate_ssa_jwt(ssa_jwt, &jwks_uri, bootstrap_conf.accept_invalid_certs)
+            validate_ssa_jwt(ssa_jwt, &jwks_uri, &http_config)
                 .await
                 .map_err(|e| {
                     InitLockServiceError::InvalidSsaJwt(format!("SSA JWT validation failed: {}", e))
                 })?;
         }
 
-        // Register client
-        let client_creds = register_client(
-            pdp_id,
-            &lock_config.issuer_oidc_url,
-            bootstrap_conf.ssa_jwt.as_ref(),
-            bootstrap_conf.accept_invalid_certs,
-        )
-        .await?;
-
-        // Build a default http client that already has the access_token in the headers
-        let mut headers = HeaderMap::new();
-        let mut auth_header =
-            HeaderValue::from_str(&format!("Bearer {}", client_creds.access_token))
-                .expect("build header");
-        auth_header.set_sensitive(true);
-        headers.insert("Authorization", auth_header);
-        let http_client = Arc::new(init_http_client(
-            Some(headers),
-            bootstrap_conf.accept_invalid_certs,
-        )?);
+        // Register client, the access token is shared by all the workers
+        let token_manager = Arc::new(
+            TokenManager::new(
+                pdp_id,
+                lock_config.issuer_oidc_url.clone(),
+                bootstrap_conf.ssa_jwt.clone(),
+                http_client.clone(),
+                client_auth,
+                bootstrap_conf
+                    .credentials_store
+                    .as_ref()
+                    .map(CredentialStore::new),
+                logger.clone(),
+            )
+            .await?,
+        );
+
+        // The access token is set on each request since it gets refreshed
+        let http_client = Arc::new(http_client);
 
         let cancel_tkn = CancellationToken::new();
         let log_worker = match (bootstrap_conf.log_interval, lock_config.audit_endpoints.log) {
@@ -213,6 +231,7 @@ impl LockService {
                 let mut log_worker = LogWorker::new(
                     log_interval,
                     http_client.clone(),
+                    token_manager.clone(),
                     log_endpoint.0.clone(),
                     logger.clone(),
                 );
@@ -235,6 +254,7 @@ impl LockService {
 
         Ok(Self {
             log_worker,
+            token_manager,
             logger,
             cancel_tkn,
         })
@@ -246,6 +266,13 @@ impl LockService {
             _ = log_worker.handle.await_result().await;
         }
     }
+
+    /// Shuts down the workers then deletes the client from the IDP and from the
+    /// credential store.
+    pub async fn deregister(&mut self) -> Result<(), DeregisterClientError> {
+        self.shut_down().await;
+        self.token_manager.deregister().await
+    }
 }
 
 impl LogWriter for LockService {
@@ -288,15 +315,17 @@ pub enum InitLockServiceError {
     GetLockConfig(#[from] http_utils::HttpRequestError),
     #[error("failed to dynamically register client for the Lock server's auth: {0}")]
     ClientRegistration(#[from] ClientRegistrationError),
+    #[error("failed to load the credentials of the lock client: {0}")]
+    ClientAuth(#[from] ClientAuthError),
     #[error("failed to initialize the Lock logger's HttpClient: {0}")]
-    InitHttpClient(#[from] reqwest::Error),
+    InitHttpClient(#[from] HttpClientBuildError),
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::log::interface::Indexed;
-    use crate::{LogLevel, lock::register_client::DCR_SCOPE};
+    use crate::{LockClientAuthConfig, LogLevel, lock::register_client::DCR_SCOPE};
     use jsonwebtoken::{EncodingKey, Header, encode};
     use mockito::{Mock, Server, ServerGuard};
     use serde::Serialize;
@@ -347,10 +376,12 @@ mod test {
             listen_sse: false,
             log_level: LogLevel::TRACE,
             accept_invalid_certs: true, // Allow invalid certs for testing
+            credentials_store: None,
+            client_auth: LockClientAuthConfig::ClientSecretBasic,
         };
 
         // Test startup
-        let logger = LockService::new(pdp_id, &config, None)
+        let logger = LockService::new(pdp_id, &config, &HttpClientConfig::default(), None)
             .await
             .expect("build lock logger");
         lock_config_endpoint.assert();
@@ -398,10 +429,12 @@ mod test {
             listen_sse: false,
             log_level: LogLevel::TRACE,
             accept_invalid_certs: false,
+            credentials_store: None,
+            client_auth: LockClientAuthConfig::ClientSecretBasic,
         };
 
         // Test startup without SSA
-        let logger = LockService::new(pdp_id, &config, None)
+        let logger = LockService::new(pdp_id, &config, &HttpClientConfig::default(), None)
             .await
             .expect("build lock logger");
         lock_config_endpoint.assert();
@@ -445,10 +478,12 @@ mod test {
             listen_sse: false,
             log_level: LogLevel::TRACE,
             accept_invalid_certs: false,
+            credentials_store: None,
+            client_auth: LockClientAuthConfig::ClientSecretBasic,
         };
 
         // Test startup with invalid SSA should fail
-        let result = LockService::new(pdp_id, &config, None).await;
+        let result = LockService::new(pdp_id, &config, &HttpClientConfig::default(), None).await;
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
@@ -601,6 +636,7 @@ mod test {
 
         server
             .mock("POST", log_path)
+            .match_header("authorization", "Bearer some.access.token")
             .match_body(mockito::Matcher::PartialJson(json!([{
                 "level": "TRACE",
                 "id": "some_uuid_string",
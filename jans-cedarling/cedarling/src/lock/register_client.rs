@@ -3,15 +3,20 @@
 //
 // Copyright (c) 2024, Gluu, Inc.
 
-//! This module is responsible for Cedarling's Dynamic Client Registration (DCR) at startup.
+//! This module is responsible for Cedarling's Dynamic Client Registration (DCR) at startup
+//! and for managing the registered client with the client configuration endpoint of
+//! [RFC 7592](https://datatracker.ietf.org/doc/html/rfc7592).
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use super::{init_http_client, lock_config::Url};
+use super::client_auth::ClientAuth;
+use super::credential_store::CredentialStoreError;
+use super::lock_config::Url;
 use crate::app_types::PdpID;
 use http_utils::{Backoff, Sender};
-use serde::Deserialize;
-use serde_json::json;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 use thiserror::Error;
 
 pub const DCR_SCOPE: &str = "cedarling";
@@ -21,85 +26,337 @@ pub async fn register_client(
     pdp_id: PdpID,
     oidc_endpoint: &Url,
     ssa_jwt: Option<&String>,
-    accept_invalid_certs: bool,
+    client: &Client,
+    client_auth: &ClientAuth,
 ) -> Result<ClientCredentials, ClientRegistrationError> {
-    let client = init_http_client(None, accept_invalid_certs)?;
+    let mut sender = new_sender();
 
+    // Get openid config
+    let oidc = get_openid_config(client, &mut sender, oidc_endpoint).await?;
+
+    // Register client with SSA JWT if provided
+    let dcr_body = client_metadata(pdp_id, ssa_jwt, client_auth);
+    let RegisteredClient {
+        client_id,
+        client_secret,
+        registration,
+    } = sender
+        .send(|| {
+            client
+                .post(&oidc.registration_endpoint)
+                .header("Content-Type", "application/json")
+                .body(dcr_body.to_string())
+        })
+        .await
+        .map_err(ClientRegistrationError::RegisterLockClient)?;
+
+    // Get access token
+    let access_token = sender
+        .send(|| {
+            token_request(
+                client,
+                client_auth,
+                &oidc.token_endpoint,
+                &client_id,
+                &client_secret,
+            )
+        })
+        .await
+        .map_err(ClientRegistrationError::GetAccessToken)?;
+
+    Ok(ClientCredentials {
+        client_id,
+        client_secret,
+        token_endpoint: oidc.token_endpoint,
+        access_token,
+        registration,
+    })
+}
+
+fn new_sender() -> Sender {
     #[cfg(not(test))]
-    let mut sender = Sender::new(Backoff::default_exponential());
+    let sender = Sender::new(Backoff::default_exponential());
 
     // We implement a faster retry for the tests
     #[cfg(test)]
-    let mut sender = {
+    let sender = {
         use std::time::Duration;
         Sender::new(Backoff::new_exponential(Duration::from_millis(10), Some(3)))
     };
 
-    // Get openid config
-    let oidc: OpenidConfig = sender
+    sender
+}
+
+/// Gets the endpoints of the IDP from its `.well-known/openid-configuration`.
+pub async fn fetch_openid_config(
+    client: &Client,
+    oidc_endpoint: &Url,
+) -> Result<OpenidConfig, ClientRegistrationError> {
+    get_openid_config(client, &mut new_sender(), oidc_endpoint).await
+}
+
+async fn get_openid_config(
+    client: &Client,
+    sender: &mut Sender,
+    oidc_endpoint: &Url,
+) -> Result<OpenidConfig, ClientRegistrationError> {
+    sender
         .send(|| client.get(oidc_endpoint.0.as_str()))
         .await
-        .map_err(ClientRegistrationError::GetOpenidConfig)?;
+        .map_err(ClientRegistrationError::GetOpenidConfig)
+}
 
-    // Register client with SSA JWT if provided
-    let mut dcr_body = json!({
-        "token_endpoint_auth_method": "client_secret_basic",
+/// The metadata Cedarling registers its client with.
+pub fn client_metadata(pdp_id: PdpID, ssa_jwt: Option<&String>, client_auth: &ClientAuth) -> Value {
+    let mut metadata = json!({
         "grant_types": ["client_credentials"],
         "client_name": format!("cedarling-{}", pdp_id),
         "scope": DCR_SCOPE,
         "access_token_as_jwt": true,
     });
-    
+    client_auth.add_metadata(&mut metadata);
+
     // Add SSA JWT to the DCR request if provided
     if let Some(ssa_jwt) = ssa_jwt {
-        dcr_body["software_statement"] = json!(ssa_jwt);
+        metadata["software_statement"] = json!(ssa_jwt);
     }
-    
-    let ClientIdAndSecret {
+
+    metadata
+}
+
+/// Reads the metadata of a registered client from its client configuration endpoint
+/// ([RFC 7592](https://datatracker.ietf.org/doc/html/rfc7592#section-2.1)).
+///
+/// Returns [`None`] if the client no longer exists.
+pub async fn read_client(
+    client: &Client,
+    registration: &ClientRegistration,
+) -> Result<Option<Value>, ClientManagementError> {
+    let response = client
+        .get(&registration.registration_client_uri)
+        .bearer_auth(&registration.registration_access_token)
+        .send()
+        .await?;
+
+    if is_unknown_client(response.status()) {
+        return Ok(None);
+    }
+    let metadata = response
+        .error_for_status()
+        .map_err(ClientManagementError::ErrorResponse)?
+        .json::<Value>()
+        .await?;
+
+    Ok(Some(metadata))
+}
+
+/// Returns `true` if the registered client already has the given metadata.
+///
+/// The scopes and grant types may have been extended by the IDP, so the client only has
+/// to have the ones from `metadata`. The fields the IDP does not return, like the SSA,
+/// are ignored, including the fields of the objects like the keys of the `jwks`.
+pub fn has_metadata(client: &Value, metadata: &Value) -> bool {
+    let Some(metadata) = metadata.as_object() else {
+        return true;
+    };
+
+    metadata
+        .iter()
+        .all(|(key, value)| has_value(key, value, client.get(key)))
+}
+
+fn has_value(key: &str, value: &Value, client_value: Option<&Value>) -> bool {
+    match (key, value, client_value) {
+        ("scope", Value::String(scopes), Some(Value::String(client_scopes))) => {
+            let client_scopes = client_scopes.split_whitespace().collect::<HashSet<_>>();
+            scopes
+                .split_whitespace()
+                .all(|scope| client_scopes.contains(scope))
+        },
+        (_, Value::Array(values), Some(Value::Array(client_values))) => {
+            values.iter().all(|value| {
+                client_values
+                    .iter()
+                    .any(|client_value| has_value(key, value, Some(client_value)))
+            })
+        },
+        (_, Value::Object(_), Some(client_value @ Value::Object(_))) => {
+            has_metadata(client_value, value)
+        },
+        // not every IDP returns all the metadata of the client
+        (_, _, None) => true,
+        (_, value, Some(client_value)) => client_value == value,
+    }
+}
+
+/// Updates the metadata of a registered client
+/// ([RFC 7592](https://datatracker.ietf.org/doc/html/rfc7592#section-2.2)).
+///
+/// `client_metadata` is the current metadata of the client, the fields of `metadata`
+/// replace its fields. Returns [`None`] if the client no longer exists.
+pub async fn update_client(
+    client: &Client,
+    registration: &ClientRegistration,
+    client_metadata: Value,
+    metadata: &Value,
+) -> Result<Option<RegisteredClient>, ClientManagementError> {
+    let mut body = client_metadata;
+    if let (Some(body), Some(metadata)) = (body.as_object_mut(), metadata.as_object()) {
+        // these fields must not be sent to the client configuration endpoint
+        for key in [
+            "registration_access_token",
+            "registration_client_uri",
+            "client_secret_expires_at",
+            "client_id_issued_at",
+        ] {
+            body.remove(key);
+        }
+        body.extend(metadata.clone());
+    }
+
+    let response = client
+        .put(&registration.registration_client_uri)
+        .bearer_auth(&registration.registration_access_token)
+        .json(&body)
+        .send()
+        .await?;
+
+    if is_unknown_client(response.status()) {
+        return Ok(None);
+    }
+    let updated = response
+        .error_for_status()
+        .map_err(ClientManagementError::ErrorResponse)?
+        .json::<RegisteredClient>()
+        .await?;
+
+    Ok(Some(updated))
+}
+
+/// Deletes a registered client
+/// ([RFC 7592](https://datatracker.ietf.org/doc/html/rfc7592#section-2.3)).
+///
+/// Succeeds if the client was already deleted.
+pub async fn delete_client(
+    client: &Client,
+    registration: &ClientRegistration,
+) -> Result<(), ClientManagementError> {
+    let response = client
+        .delete(&registration.registration_client_uri)
+        .bearer_auth(&registration.registration_access_token)
+        .send()
+        .await?;
+
+    if !is_unknown_client(response.status()) {
+        response
+            .error_for_status()
+            .map_err(ClientManagementError::ErrorResponse)?;
+    }
+
+    Ok(())
+}
+
+/// The client configuration endpoint responds with `401` if the client does not exist
+/// anymore. Some IDPs respond with `403` or `404` instead.
+fn is_unknown_client(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND
+    )
+}
+
+/// Requests a new access token for an already registered client.
+///
+/// Unlike [`register_client`], the request is only sent once so the caller can tell
+/// when the client is no longer registered with the IDP.
+pub async fn request_access_token(
+    client: &Client,
+    client_auth: &ClientAuth,
+    token_endpoint: &str,
+    client_id: &str,
+    client_secret: &str,
+) -> Result<AccessToken, TokenRequestError> {
+    let response = token_request(
+        client,
+        client_auth,
+        token_endpoint,
         client_id,
         client_secret,
-    } = sender
-        .send(|| {
-            client
-                .post(&oidc.registration_endpoint)
-                .header("Content-Type", "application/json")
-                .body(dcr_body.to_string())
-        })
+    )
+    .send()
+    .await?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response.json::<AccessToken>().await?);
+    }
+
+    let error = response
+        .json::<TokenErrorResponse>()
         .await
-        .map_err(ClientRegistrationError::RegisterLockClient)?;
+        .ok()
+        .map(|resp| resp.error);
+    if status == StatusCode::UNAUTHORIZED || error.as_deref() == Some("invalid_client") {
+        Err(TokenRequestError::InvalidClient(status))
+    } else {
+        Err(TokenRequestError::ErrorResponse(
+            status,
+            error.unwrap_or_else(|| "no error code".to_string()),
+        ))
+    }
+}
 
-    // Get access token
-    let form_data = serde_json::from_value::<HashMap<String, String>>(json!({
+fn token_request(
+    client: &Client,
+    client_auth: &ClientAuth,
+    token_endpoint: &str,
+    client_id: &str,
+    client_secret: &str,
+) -> RequestBuilder {
+    let mut form_data = serde_json::from_value::<HashMap<String, String>>(json!({
         "grant_type": "client_credentials",
         "scope": ACCESS_TKN_SCOPE,
     }))
     // this should never fail since this is a hard-coded valid JSON
     .expect("serialize form data");
-    
-    let AccessToken { access_token } = sender
-        .send(|| {
-            client
-                .post(&oidc.token_endpoint)
-                .basic_auth(&client_id, Some(&client_secret))
-                .header("Content-Type", "application/x-www-form-urlencoded")
-                .form(&form_data)
-        })
-        .await
-        .map_err(ClientRegistrationError::GetAccessToken)?;
 
-    Ok(ClientCredentials {
-        client_id,
-        client_secret,
-        access_token,
-    })
+    let request = client
+        .post(token_endpoint)
+        .header("Content-Type", "application/x-www-form-urlencoded");
+    client_auth
+        .authenticate(
+            request,
+            &mut form_data,
+            token_endpoint,
+            client_id,
+            client_secret,
+        )
+        .form(&form_data)
 }
 
 #[derive(Debug, PartialEq, Clone)]
-#[allow(dead_code)]
 pub struct ClientCredentials {
     pub client_id: String,
     pub client_secret: String,
+    pub token_endpoint: String,
+    pub access_token: AccessToken,
+    pub registration: Option<ClientRegistration>,
+}
+
+/// What is needed to manage a registered client, it is only returned by IDPs that
+/// support [RFC 7592](https://datatracker.ietf.org/doc/html/rfc7592).
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ClientRegistration {
+    pub registration_access_token: String,
+    pub registration_client_uri: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct AccessToken {
     pub access_token: String,
+    /// The lifetime of the access token in seconds.
+    #[serde(default)]
+    pub expires_in: Option<u64>,
 }
 
 #[derive(Debug, Error)]
@@ -110,32 +367,59 @@ pub enum ClientRegistrationError {
     RegisterLockClient(#[source] http_utils::HttpRequestError),
     #[error("failed to get access token: {0}")]
     GetAccessToken(#[source] http_utils::HttpRequestError),
-    #[error("failed to initialize HTTP client: {0}")]
-    InitializeHttpClient(#[from] reqwest::Error),
+    #[error("failed to get access token for the stored client: {0}")]
+    GetStoredClientAccessToken(#[source] TokenRequestError),
+    #[error("failed to read or update the stored client: {0}")]
+    ManageStoredClient(#[source] ClientManagementError),
+    #[error(transparent)]
+    CredentialStore(#[from] CredentialStoreError),
+}
+
+#[derive(Debug, Error)]
+pub enum TokenRequestError {
+    #[error("the client is no longer registered with the IDP, the token endpoint returned {0}")]
+    InvalidClient(StatusCode),
+    #[error("the token endpoint returned {0}: {1}")]
+    ErrorResponse(StatusCode, String),
+    #[error("failed to request an access token: {0}")]
+    Request(#[from] reqwest::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum ClientManagementError {
+    #[error("the client configuration endpoint returned an error: {0}")]
+    ErrorResponse(#[source] reqwest::Error),
+    #[error("failed to send the request to the client configuration endpoint: {0}")]
+    Request(#[from] reqwest::Error),
 }
 
 #[derive(Debug, Deserialize)]
-struct OpenidConfig {
+pub struct OpenidConfig {
     registration_endpoint: String,
-    token_endpoint: String,
+    pub token_endpoint: String,
 }
 
+/// The response of the registration and client configuration endpoints.
 #[derive(Debug, Deserialize)]
-struct ClientIdAndSecret {
-    client_id: String,
-    client_secret: String,
+pub struct RegisteredClient {
+    pub client_id: String,
+    /// Empty if the client does not authenticate with a client secret
+    #[serde(default)]
+    pub client_secret: String,
+    #[serde(flatten)]
+    pub registration: Option<ClientRegistration>,
 }
 
 #[derive(Debug, Deserialize)]
-struct AccessToken {
-    #[serde(rename = "access_token")]
-    access_token: String,
+struct TokenErrorResponse {
+    error: String,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mockito::{Mock, Server, ServerGuard};
+    use crate::lock::client_auth::SigningKey;
+    use mockito::{Matcher, Mock, Server, ServerGuard};
     use serde_json::json;
 
     #[tokio::test]
@@ -157,7 +441,8 @@ mod tests {
             pdp_id,
             &super::super::lock_config::Url(oidc_url),
             Some(&ssa_jwt.to_string()),
-            false,
+            &Client::new(),
+            &ClientAuth::ClientSecretBasic,
         )
         .await;
 
@@ -185,7 +470,8 @@ mod tests {
             pdp_id,
             &super::super::lock_config::Url(oidc_url),
             None,
-            false,
+            &Client::new(),
+            &ClientAuth::ClientSecretBasic,
         )
         .await;
 
@@ -195,6 +481,79 @@ mod tests {
         token_endpoint.assert();
     }
 
+    #[tokio::test]
+    async fn test_register_client_with_private_key_jwt() {
+        let pdp_id = PdpID::new();
+        let client_auth = ClientAuth::PrivateKeyJwt(SigningKey::generate());
+
+        let mut mock_server = Server::new_async().await;
+
+        let oidc_endpoint = mock_oidc_endpoint(&mut mock_server);
+        let dcr_endpoint = mock_server
+            .mock("POST", "/jans-auth/restv1/register")
+            .match_body(Matcher::PartialJson(json!({
+                "token_endpoint_auth_method": "private_key_jwt",
+                "token_endpoint_auth_signing_alg": "ES256",
+                "client_name": format!("cedarling-{}", pdp_id),
+            })))
+            .with_body(json!({"client_id": "some_client_id"}).to_string())
+            .expect(1)
+            .create();
+        let token_endpoint = mock_server
+            .mock("POST", "/jans-auth/restv1/token")
+            .match_header("authorization", Matcher::Missing)
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("client_id".into(), "some_client_id".into()),
+                Matcher::UrlEncoded(
+                    "client_assertion_type".into(),
+                    "urn:ietf:params:oauth:client-assertion-type:jwt-bearer".into(),
+                ),
+                Matcher::Regex("client_assertion=".into()),
+            ]))
+            .with_body(json!({"access_token": "some.access.token"}).to_string())
+            .expect(1)
+            .create();
+
+        let oidc_url: url::Url = format!("{}/.well-known/openid-configuration", mock_server.url())
+            .parse()
+            .expect("valid URL");
+
+        let creds = register_client(
+            pdp_id,
+            &super::super::lock_config::Url(oidc_url),
+            None,
+            &Client::new(),
+            &client_auth,
+        )
+        .await
+        .expect("should register the client");
+
+        assert_eq!(creds.client_secret, "", "no client secret should be issued");
+        oidc_endpoint.assert();
+        dcr_endpoint.assert();
+        token_endpoint.assert();
+    }
+
+    #[test]
+    fn test_has_metadata_ignores_the_fields_added_by_the_idp() {
+        let metadata = json!({
+            "scope": "cedarling",
+            "jwks": {"keys": [{"kty": "EC", "kid": "some_kid", "x": "x", "y": "y"}]},
+        });
+
+        let client = json!({
+            "scope": "cedarling openid",
+            "jwks": {"keys": [{"kty": "EC", "kid": "some_kid", "x": "x", "y": "y", "exp": 0}]},
+        });
+        assert!(has_metadata(&client, &metadata));
+
+        let rotated_key = json!({
+            "scope": "cedarling",
+            "jwks": {"keys": [{"kty": "EC", "kid": "other_kid", "x": "x", "y": "y"}]},
+        });
+        assert!(!has_metadata(&rotated_key, &metadata));
+    }
+
     /// Mocks the `.well-known/openid-configuration` endpoint
     fn mock_oidc_endpoint(server: &mut ServerGuard) -> Mock {
         let oidc_path = "/.well-known/openid-configuration";
@@ -74,6 +74,8 @@
 //! before performing Dynamic Client Registration. The validated SSA JWT
 //! is then included in the DCR request to the Identity Provider.
 
+use crate::HttpClientConfig;
+use crate::http::HttpClientBuildError;
 use crate::jwt::{decode_jwt, DecodeJwtError, DecodedJwt};
 use base64::Engine;
 use jsonwebtoken::{self as jwt, Algorithm, DecodingKey, Validation};
@@ -113,9 +115,9 @@ pub struct SsaValidationConfig {
     /// Default: false
     pub validate_audience: bool,
     
-    /// Whether to accept invalid SSL certificates when fetching JWKS.
-    /// Default: false
-    pub accept_invalid_certs: bool,
+    /// Options of the HTTP client used to fetch the JWKS.
+    /// Default: no custom options
+    pub http_config: HttpClientConfig,
     
     /// Whether to validate that grant_types is an array.
     /// Default: true
@@ -144,7 +146,7 @@ impl Default for SsaValidationConfig {
             validate_issued_at: true,
             validate_not_before: false,
             validate_audience: false,
-            accept_invalid_certs: false,
+            http_config: HttpClientConfig::default(),
             validate_grant_types_array: true,
             validate_software_roles_array: true,
         }
@@ -184,10 +186,10 @@ pub struct SsaClaims {
 pub async fn validate_ssa_jwt(
     ssa_jwt: &str,
     jwks_uri: &str,
-    accept_invalid_certs: bool,
+    http_config: &HttpClientConfig,
 ) -> Result<SsaClaims, SsaValidationError> {
     let config = SsaValidationConfig {
-        accept_invalid_certs,
+        http_config: http_config.clone(),
         ..Default::default()
     };
     validate_ssa_jwt_with_config(ssa_jwt, jwks_uri, &config).await
@@ -215,7 +217,7 @@ pub async fn validate_ssa_jwt_with_config(
     }
     
     // Fetch JWKS from the issuer
-    let jwks = fetch_jwks(jwks_uri, config.accept_invalid_certs).await?;
+    let jwks = fetch_jwks(jwks_uri, &config.http_config).await?;
     
     // Find the appropriate key for validation
     let decoding_key = find_decoding_key(&decoded_jwt, &jwks)?;
@@ -279,10 +281,8 @@ pub fn validate_ssa_structure_with_config(
 }
 
 /// Fetches JWKS from the specified URI
-async fn fetch_jwks(jwks_uri: &str, accept_invalid_certs: bool) -> Result<Value, SsaValidationError> {
-    use super::init_http_client;
-    
-    let client = init_http_client(None, accept_invalid_certs)
+async fn fetch_jwks(jwks_uri: &str, http_config: &HttpClientConfig) -> Result<Value, SsaValidationError> {
+    let client = crate::http::build_client(http_config)
         .map_err(SsaValidationError::HttpClientError)?;
     
     let response = client
@@ -432,7 +432,7 @@ pub enum SsaValidationError {
     
     /// Failed to initialize HTTP client for JWKS fetching
     #[error("failed to initialize HTTP client: {0}")]
-    HttpClientError(reqwest::Error),
+    HttpClientError(HttpClientBuildError),
     
     /// Failed to fetch JWKS due to network or HTTP errors
     #[error("failed to fetch JWKS (network/HTTP error): {0}")]
@@ -853,7 +853,7 @@ mod tests {
         assert!(config.validate_issued_at);
         assert!(!config.validate_not_before);
         assert!(!config.validate_audience);
-        assert!(!config.accept_invalid_certs);
+        assert!(!config.http_config.accept_invalid_certs);
         assert!(config.validate_grant_types_array);
         assert!(config.validate_software_roles_array);
         assert!(config.allowed_algorithms.is_empty()); // Empty means all allowed
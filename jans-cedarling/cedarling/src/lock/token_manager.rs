@@ -0,0 +1,832 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! The [`TokenManager`] holds the access token used to authenticate with the lock server.
+//!
+//! The token is refreshed shortly before it expires and whenever the lock server rejects
+//! it. If the IDP no longer recognizes the client, the client is registered again.
+//!
+//! If a [`CredentialStore`] is configured, the credentials of the client are saved so the
+//! same client is used after a restart instead of registering a new one each time.
+
+use super::client_auth::ClientAuth;
+use super::credential_store::{CredentialStore, CredentialStoreError, StoredClient};
+use super::lock_config::Url;
+use super::log_entry::LockLogEntry;
+use super::register_client::{
+    AccessToken, ClientCredentials, ClientManagementError, ClientRegistration,
+    ClientRegistrationError, TokenRequestError, client_metadata, delete_client,
+    fetch_openid_config, has_metadata, read_client, register_client, request_access_token,
+    update_client,
+};
+use crate::LogWriter;
+use crate::app_types::PdpID;
+use crate::log::LoggerWeak;
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::lock::Mutex;
+use reqwest::Client;
+use thiserror::Error;
+
+/// How long before the expiry of the access token it gets refreshed.
+///
+/// Tokens with a short lifetime are refreshed after half of their lifetime instead.
+const REFRESH_MARGIN_SECS: u64 = 60;
+
+/// Keeps a valid access token for the lock server that is shared by all the lock workers.
+pub struct TokenManager {
+    registrar: ClientRegistrar,
+    state: Mutex<TokenState>,
+}
+
+struct TokenState {
+    client_id: String,
+    client_secret: String,
+    token_endpoint: String,
+    registration: Option<ClientRegistration>,
+    access_token: String,
+    /// When the access token should be refreshed, [`None`] if it does not expire.
+    refresh_at: Option<DateTime<Utc>>,
+    /// When the access token expires, [`None`] if it does not expire.
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl TokenState {
+    fn new(creds: ClientCredentials) -> Self {
+        let mut state = Self {
+            client_id: creds.client_id,
+            client_secret: creds.client_secret,
+            token_endpoint: creds.token_endpoint,
+            registration: creds.registration,
+            access_token: String::new(),
+            refresh_at: None,
+            expires_at: None,
+        };
+        state.set_access_token(creds.access_token);
+        state
+    }
+
+    fn set_access_token(&mut self, token: AccessToken) {
+        let now = Utc::now();
+        let lifetime = token.expires_in.map(|expires_in| {
+            let margin = REFRESH_MARGIN_SECS.min(expires_in / 2);
+            (expires_in, expires_in - margin)
+        });
+
+        self.access_token = token.access_token;
+        self.expires_at = lifetime.and_then(|(expires_in, _)| after(now, expires_in));
+        self.refresh_at = lifetime.and_then(|(_, refresh_in)| after(now, refresh_in));
+    }
+
+    fn should_refresh(&self, now: DateTime<Utc>) -> bool {
+        self.refresh_at.is_some_and(|refresh_at| now >= refresh_at)
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+}
+
+/// Returns the time `secs` seconds after `now`, [`None`] if it can't be represented.
+fn after(now: DateTime<Utc>, secs: u64) -> Option<DateTime<Utc>> {
+    i64::try_from(secs)
+        .ok()
+        .and_then(TimeDelta::try_seconds)
+        .and_then(|delta| now.checked_add_signed(delta))
+}
+
+impl TokenManager {
+    /// Gets the first access token, for the stored client if there is one that is still
+    /// registered with the IDP, otherwise for a newly registered client.
+    pub async fn new(
+        pdp_id: PdpID,
+        oidc_endpoint: Url,
+        ssa_jwt: Option<String>,
+        http_client: Client,
+        client_auth: ClientAuth,
+        store: Option<CredentialStore>,
+        logger: Option<LoggerWeak>,
+    ) -> Result<Self, ClientRegistrationError> {
+        let registrar = ClientRegistrar {
+            pdp_id,
+            oidc_endpoint,
+            ssa_jwt,
+            http_client,
+            client_auth,
+            store,
+            logger,
+        };
+
+        let client_creds = match registrar.restore().await? {
+            Some(client_creds) => client_creds,
+            None => registrar.register().await?,
+        };
+
+        Ok(Self {
+            registrar,
+            state: Mutex::new(TokenState::new(client_creds)),
+        })
+    }
+
+    /// Returns the current access token, refreshing it first if it is about to expire.
+    ///
+    /// If the refresh fails while the current token is still valid, the current token is
+    /// returned and the refresh is attempted again on the next call.
+    pub async fn access_token(&self) -> Result<String, TokenError> {
+        let mut state = self.state.lock().await;
+        let now = Utc::now();
+        if !state.should_refresh(now) {
+            return Ok(state.access_token.clone());
+        }
+
+        match self.refresh(&mut state).await {
+            Ok(()) => Ok(state.access_token.clone()),
+            Err(err) if !state.is_expired(now) => {
+                self.registrar.logger.log_any(LockLogEntry::warn(format!(
+                    "failed to refresh the lock server access token before it expires: {}",
+                    err
+                )));
+                Ok(state.access_token.clone())
+            },
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Gets a new access token after the lock server rejected `rejected_token`.
+    ///
+    /// If another worker already replaced the rejected token, the new token is returned
+    /// without requesting another one.
+    pub async fn refresh_rejected(&self, rejected_token: &str) -> Result<String, TokenError> {
+        let mut state = self.state.lock().await;
+        if state.access_token != rejected_token {
+            return Ok(state.access_token.clone());
+        }
+
+        self.refresh(&mut state).await?;
+        Ok(state.access_token.clone())
+    }
+
+    /// Deletes the client from the IDP and from the credential store.
+    pub async fn deregister(&self) -> Result<(), DeregisterClientError> {
+        let state = self.state.lock().await;
+        self.registrar.deregister(state.registration.as_ref()).await
+    }
+
+    async fn refresh(&self, state: &mut TokenState) -> Result<(), TokenError> {
+        let result = request_access_token(
+            &self.registrar.http_client,
+            &self.registrar.client_auth,
+            &state.token_endpoint,
+            &state.client_id,
+            &state.client_secret,
+        )
+        .await;
+
+        match result {
+            Ok(token) => {
+                state.set_access_token(token);
+                self.registrar
+                    .logger
+                    .log_any(LockLogEntry::info("refreshed the lock server access token"));
+            },
+            Err(TokenRequestError::InvalidClient(status)) => {
+                self.registrar.logger.log_any(LockLogEntry::warn(format!(
+                    "the IDP no longer recognizes client '{}' ({}), registering a new client",
+                    state.client_id, status
+                )));
+                *state = TokenState::new(self.registrar.register().await?);
+            },
+            Err(err) => return Err(err.into()),
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for TokenManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenManager")
+            .field("pdp_id", &self.registrar.pdp_id)
+            .field("oidc_endpoint", &self.registrar.oidc_endpoint)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Registers the client with the IDP and keeps the credential store up to date.
+struct ClientRegistrar {
+    pdp_id: PdpID,
+    oidc_endpoint: Url,
+    ssa_jwt: Option<String>,
+    http_client: Client,
+    client_auth: ClientAuth,
+    store: Option<CredentialStore>,
+    logger: Option<LoggerWeak>,
+}
+
+impl ClientRegistrar {
+    /// Registers a new client and saves its credentials.
+    async fn register(&self) -> Result<ClientCredentials, ClientRegistrationError> {
+        let client_creds = register_client(
+            self.pdp_id,
+            &self.oidc_endpoint,
+            self.ssa_jwt.as_ref(),
+            &self.http_client,
+            &self.client_auth,
+        )
+        .await?;
+
+        self.save(StoredClient {
+            pdp_id: self.pdp_id.to_string(),
+            oidc_endpoint: self.oidc_endpoint.0.to_string(),
+            client_id: client_creds.client_id.clone(),
+            client_secret: client_creds.client_secret.clone(),
+            registration: client_creds.registration.clone(),
+        });
+
+        Ok(client_creds)
+    }
+
+    /// Gets an access token for the stored client.
+    ///
+    /// Returns [`None`] if there is no stored client, if the credentials file can't be
+    /// decrypted, if it was registered for another PDP or IDP, or if it is no longer
+    /// registered with the IDP. If the metadata of the
+    /// client changed, the client is updated.
+    async fn restore(&self) -> Result<Option<ClientCredentials>, ClientRegistrationError> {
+        let Some(store) = &self.store else {
+            return Ok(None);
+        };
+        let mut stored = match store.load() {
+            Ok(Some(stored)) => stored,
+            Ok(None) => return Ok(None),
+            // the file is replaced once the new client is registered
+            Err(err @ (CredentialStoreError::Malformed(_) | CredentialStoreError::Decrypt)) => {
+                self.logger.log_any(LockLogEntry::warn(format!(
+                    "failed to load the lock client credentials from '{}', registering a new client: {}",
+                    store.path().display(),
+                    err
+                )));
+                return Ok(None);
+            },
+            Err(err) => return Err(err.into()),
+        };
+
+        if stored.pdp_id != self.pdp_id.to_string()
+            || stored.oidc_endpoint != self.oidc_endpoint.0.as_str()
+        {
+            self.logger.log_any(LockLogEntry::info(format!(
+                "the stored lock client '{}' was registered for another PDP ID or IDP, registering a new client",
+                stored.client_id
+            )));
+            // the stored client won't be used anymore
+            self.delete_stale_client(stored.registration.as_ref()).await;
+            return Ok(None);
+        }
+
+        let oidc = fetch_openid_config(&self.http_client, &self.oidc_endpoint).await?;
+
+        if let Some(registration) = stored.registration.clone() {
+            let metadata = client_metadata(self.pdp_id, self.ssa_jwt.as_ref(), &self.client_auth);
+            let current = read_client(&self.http_client, &registration)
+                .await
+                .map_err(ClientRegistrationError::ManageStoredClient)?;
+            let Some(current) = current else {
+                self.log_unknown_client(&stored.client_id);
+                return Ok(None);
+            };
+
+            if !has_metadata(&current, &metadata) {
+                let updated = update_client(&self.http_client, &registration, current, &metadata)
+                    .await
+                    .map_err(ClientRegistrationError::ManageStoredClient)?;
+                let Some(updated) = updated else {
+                    self.log_unknown_client(&stored.client_id);
+                    return Ok(None);
+                };
+
+                stored.client_id = updated.client_id;
+                stored.client_secret = updated.client_secret;
+                // the registration access token may be rotated
+                if let Some(registration) = updated.registration {
+                    stored.registration = Some(registration);
+                }
+                self.save(stored.clone());
+                self.logger.log_any(LockLogEntry::info(format!(
+                    "updated the metadata of the stored lock client '{}'",
+                    stored.client_id
+                )));
+            }
+        }
+
+        let access_token = request_access_token(
+            &self.http_client,
+            &self.client_auth,
+            &oidc.token_endpoint,
+            &stored.client_id,
+            &stored.client_secret,
+        )
+        .await;
+        let access_token = match access_token {
+            Ok(access_token) => access_token,
+            Err(TokenRequestError::InvalidClient(_)) => {
+                self.log_unknown_client(&stored.client_id);
+                return Ok(None);
+            },
+            Err(err) => return Err(ClientRegistrationError::GetStoredClientAccessToken(err)),
+        };
+
+        self.logger.log_any(LockLogEntry::info(format!(
+            "reusing the stored lock client '{}'",
+            stored.client_id
+        )));
+
+        Ok(Some(ClientCredentials {
+            client_id: stored.client_id,
+            client_secret: stored.client_secret,
+            token_endpoint: oidc.token_endpoint,
+            access_token,
+            registration: stored.registration,
+        }))
+    }
+
+    /// Deletes the client from the IDP then removes it from the credential store.
+    async fn deregister(
+        &self,
+        registration: Option<&ClientRegistration>,
+    ) -> Result<(), DeregisterClientError> {
+        match registration {
+            Some(registration) => delete_client(&self.http_client, registration).await?,
+            None => self.logger.log_any(LockLogEntry::warn(
+                "the IDP did not return a registration access token, the lock client can't be deleted",
+            )),
+        }
+
+        if let Some(store) = &self.store {
+            store.remove()?;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_stale_client(&self, registration: Option<&ClientRegistration>) {
+        let Some(registration) = registration else {
+            return;
+        };
+
+        if let Err(err) = delete_client(&self.http_client, registration).await {
+            self.logger.log_any(LockLogEntry::warn(format!(
+                "failed to delete the stored lock client: {}",
+                err
+            )));
+        }
+    }
+
+    /// Saves the client, the client is still used if it can't be saved.
+    fn save(&self, client: StoredClient) {
+        let Some(store) = &self.store else {
+            return;
+        };
+
+        if let Err(err) = store.save(&client) {
+            self.logger.log_any(LockLogEntry::warn(format!(
+                "failed to save the lock client credentials to '{}', a new client will be registered on the next startup: {}",
+                store.path().display(),
+                err
+            )));
+        }
+    }
+
+    fn log_unknown_client(&self, client_id: &str) {
+        self.logger.log_any(LockLogEntry::info(format!(
+            "the stored lock client '{}' is no longer registered with the IDP, registering a new client",
+            client_id
+        )));
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TokenError {
+    #[error("failed to refresh the access token: {0}")]
+    Refresh(#[from] TokenRequestError),
+    #[error("failed to register a new client: {0}")]
+    Reregister(#[from] ClientRegistrationError),
+}
+
+/// Error returned when the Lock client can't be deregistered.
+#[derive(Debug, Error)]
+pub enum DeregisterClientError {
+    /// The client could not be deleted from the IDP.
+    #[error("failed to delete the lock client from the IDP: {0}")]
+    Delete(#[from] ClientManagementError),
+    /// The credentials of the client could not be removed from the credential store.
+    #[error("failed to remove the stored lock client credentials: {0}")]
+    CredentialStore(#[from] CredentialStoreError),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use base64::prelude::*;
+    use mockito::{Matcher, Mock, Server, ServerGuard};
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    const TOKEN_PATH: &str = "/jans-auth/restv1/token";
+    const REGISTER_PATH: &str = "/jans-auth/restv1/register";
+
+    fn oidc_url(server: &ServerGuard) -> Url {
+        let url = format!("{}/.well-known/openid-configuration", server.url())
+            .parse()
+            .expect("valid URL");
+        Url(url)
+    }
+
+    fn mock_oidc_endpoint(server: &mut ServerGuard) -> Mock {
+        let registration_endpoint = format!("{}{}", server.url(), REGISTER_PATH);
+        let token_endpoint = format!("{}{}", server.url(), TOKEN_PATH);
+        server
+            .mock("GET", "/.well-known/openid-configuration")
+            .with_body(
+                json!({
+                    "registration_endpoint": registration_endpoint,
+                    "token_endpoint": token_endpoint,
+                })
+                .to_string(),
+            )
+            .create()
+    }
+
+    async fn token_manager(
+        server: &ServerGuard,
+        pdp_id: PdpID,
+        store: Option<CredentialStore>,
+    ) -> Result<TokenManager, ClientRegistrationError> {
+        TokenManager::new(
+            pdp_id,
+            oidc_url(server),
+            None,
+            Client::new(),
+            ClientAuth::ClientSecretBasic,
+            store,
+            None,
+        )
+        .await
+    }
+
+    fn mock_dcr_endpoint(server: &mut ServerGuard, client_id: &str) -> Mock {
+        server
+            .mock("POST", REGISTER_PATH)
+            .with_body(registered_client(server, client_id).to_string())
+            .expect(1)
+            .create()
+    }
+
+    /// The response of the registration and client configuration endpoints
+    fn registered_client(server: &ServerGuard, client_id: &str) -> serde_json::Value {
+        json!({
+            "client_id": client_id,
+            "client_secret": format!("{client_id}_secret"),
+            "grant_types": ["client_credentials"],
+            "scope": "cedarling https://jans.io/oauth/lock/log.write",
+            "registration_access_token": format!("{client_id}_registration_token"),
+            "registration_client_uri": format!(
+                "{}{}?client_id={}",
+                server.url(),
+                REGISTER_PATH,
+                client_id
+            ),
+        })
+    }
+
+    /// Mocks the client configuration endpoint for the given client, call `.create()`
+    /// on the result
+    fn mock_client_config_endpoint(
+        server: &mut ServerGuard,
+        method: &str,
+        client_id: &str,
+    ) -> Mock {
+        server
+            .mock(method, REGISTER_PATH)
+            .match_query(Matcher::UrlEncoded("client_id".into(), client_id.into()))
+            .match_header(
+                "authorization",
+                format!("Bearer {client_id}_registration_token").as_str(),
+            )
+    }
+
+    fn credential_store(path: &std::path::Path) -> CredentialStore {
+        CredentialStore::new(&crate::LockCredentialsStoreConfig {
+            path: path.to_path_buf(),
+            key: "some passphrase".to_string(),
+        })
+    }
+
+    fn credentials_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "cedarling-lock-credentials-{}.json",
+            crate::log::gen_uuid7()
+        ))
+    }
+
+    /// Mocks the `/token` endpoint for the given client, call `.create()` on the result
+    fn mock_token_endpoint(
+        server: &mut ServerGuard,
+        client_id: &str,
+        access_token: &str,
+        expires_in: u64,
+    ) -> Mock {
+        server
+            .mock("POST", TOKEN_PATH)
+            .match_header("authorization", basic_auth(client_id).as_str())
+            .with_body(
+                json!({
+                    "access_token": access_token,
+                    "expires_in": expires_in,
+                })
+                .to_string(),
+            )
+    }
+
+    fn basic_auth(client_id: &str) -> String {
+        let creds = BASE64_STANDARD.encode(format!("{client_id}:{client_id}_secret"));
+        format!("Basic {creds}")
+    }
+
+    #[tokio::test]
+    async fn refreshes_the_token_before_it_expires() {
+        let mut server = Server::new_async().await;
+        mock_oidc_endpoint(&mut server);
+        mock_dcr_endpoint(&mut server, "client_1");
+        let token_endpoint = mock_token_endpoint(&mut server, "client_1", "token_1", 2)
+            .expect(2)
+            .create();
+
+        let manager = token_manager(&server, PdpID::new(), None)
+            .await
+            .expect("should register the client");
+        assert_eq!(manager.access_token().await.unwrap(), "token_1");
+
+        // the token gets refreshed after half of its lifetime
+        sleep(Duration::from_millis(1100)).await;
+        assert_eq!(manager.access_token().await.unwrap(), "token_1");
+
+        token_endpoint.assert();
+    }
+
+    #[tokio::test]
+    async fn refetches_a_rejected_token_once() {
+        let mut server = Server::new_async().await;
+        mock_oidc_endpoint(&mut server);
+        mock_dcr_endpoint(&mut server, "client_1");
+        let token_endpoint = mock_token_endpoint(&mut server, "client_1", "token_1", 3600)
+            .expect(2)
+            .create();
+
+        let manager = token_manager(&server, PdpID::new(), None)
+            .await
+            .expect("should register the client");
+
+        let token = manager
+            .refresh_rejected("token_1")
+            .await
+            .expect("should get a new token");
+        assert_eq!(token, "token_1");
+
+        // a token that was already replaced does not trigger another request
+        let token = manager
+            .refresh_rejected("stale_token")
+            .await
+            .expect("should return the current token");
+        assert_eq!(token, "token_1");
+
+        token_endpoint.assert();
+    }
+
+    #[tokio::test]
+    async fn registers_a_new_client_when_the_client_is_gone() {
+        let mut server = Server::new_async().await;
+        mock_oidc_endpoint(&mut server);
+        let first_dcr = mock_dcr_endpoint(&mut server, "client_1");
+        let first_token = mock_token_endpoint(&mut server, "client_1", "token_1", 3600).create();
+
+        let manager = token_manager(&server, PdpID::new(), None)
+            .await
+            .expect("should register the client");
+        assert_eq!(manager.access_token().await.unwrap(), "token_1");
+        first_dcr.assert();
+
+        // the IDP deletes the first client
+        first_dcr.remove();
+        first_token.remove();
+        let rejected_client = server
+            .mock("POST", TOKEN_PATH)
+            .match_header("authorization", basic_auth("client_1").as_str())
+            .with_status(401)
+            .with_body(json!({"error": "invalid_client"}).to_string())
+            .expect(1)
+            .create();
+        let second_dcr = mock_dcr_endpoint(&mut server, "client_2");
+        let second_token = mock_token_endpoint(&mut server, "client_2", "token_2", 3600)
+            .expect(1)
+            .create();
+
+        let token = manager
+            .refresh_rejected("token_1")
+            .await
+            .expect("should register a new client");
+        assert_eq!(token, "token_2");
+
+        rejected_client.assert();
+        second_dcr.assert();
+        second_token.assert();
+    }
+
+    #[tokio::test]
+    async fn errors_when_the_token_expired_and_cannot_be_refreshed() {
+        let mut server = Server::new_async().await;
+        mock_oidc_endpoint(&mut server);
+        mock_dcr_endpoint(&mut server, "client_1");
+        let token_endpoint = mock_token_endpoint(&mut server, "client_1", "token_1", 0).create();
+
+        let manager = token_manager(&server, PdpID::new(), None)
+            .await
+            .expect("should register the client");
+
+        token_endpoint.remove();
+        server
+            .mock("POST", TOKEN_PATH)
+            .with_status(503)
+            .with_body(json!({"error": "temporarily_unavailable"}).to_string())
+            .create();
+
+        let err = manager
+            .access_token()
+            .await
+            .expect_err("should fail to refresh the expired token");
+        assert!(
+            matches!(
+                err,
+                TokenError::Refresh(TokenRequestError::ErrorResponse(status, _))
+                    if status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+            ),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn reuses_the_stored_client() {
+        let mut server = Server::new_async().await;
+        let pdp_id = PdpID::new();
+        let path = credentials_path();
+        mock_oidc_endpoint(&mut server);
+        let dcr_endpoint = mock_dcr_endpoint(&mut server, "client_1");
+        let read_endpoint = mock_client_config_endpoint(&mut server, "GET", "client_1")
+            .with_body(registered_client(&server, "client_1").to_string())
+            .expect(1)
+            .create();
+        let token_endpoint = mock_token_endpoint(&mut server, "client_1", "token_1", 3600)
+            .expect(2)
+            .create();
+
+        token_manager(&server, pdp_id, Some(credential_store(&path)))
+            .await
+            .expect("should register the client");
+
+        // the next startup uses the stored client
+        let manager = token_manager(&server, pdp_id, Some(credential_store(&path)))
+            .await
+            .expect("should reuse the stored client");
+        assert_eq!(manager.access_token().await.unwrap(), "token_1");
+
+        dcr_endpoint.assert();
+        read_endpoint.assert();
+        token_endpoint.assert();
+        credential_store(&path).remove().unwrap();
+    }
+
+    #[tokio::test]
+    async fn registers_a_new_client_when_the_credentials_cannot_be_loaded() {
+        let mut server = Server::new_async().await;
+        let path = credentials_path();
+        mock_oidc_endpoint(&mut server);
+        let dcr_endpoint = mock_dcr_endpoint(&mut server, "client_1");
+        mock_token_endpoint(&mut server, "client_1", "token_1", 3600).create();
+
+        std::fs::write(&path, "not a credentials file").unwrap();
+        let manager = token_manager(&server, PdpID::new(), Some(credential_store(&path)))
+            .await
+            .expect("should register a new client");
+        assert_eq!(manager.access_token().await.unwrap(), "token_1");
+        dcr_endpoint.assert();
+
+        // the new client replaces the corrupt file
+        assert!(
+            credential_store(&path).load().unwrap().is_some(),
+            "the new client should be saved"
+        );
+        credential_store(&path).remove().unwrap();
+    }
+
+    #[tokio::test]
+    async fn updates_the_metadata_of_the_stored_client() {
+        let mut server = Server::new_async().await;
+        let pdp_id = PdpID::new();
+        let path = credentials_path();
+        mock_oidc_endpoint(&mut server);
+        mock_dcr_endpoint(&mut server, "client_1");
+        mock_token_endpoint(&mut server, "client_1", "token_1", 3600).create();
+
+        token_manager(&server, pdp_id, Some(credential_store(&path)))
+            .await
+            .expect("should register the client");
+
+        // the client lost the `cedarling` scope
+        let mut client = registered_client(&server, "client_1");
+        client["scope"] = json!("https://jans.io/oauth/lock/log.write");
+        mock_client_config_endpoint(&mut server, "GET", "client_1")
+            .with_body(client.to_string())
+            .create();
+        let update_endpoint = mock_client_config_endpoint(&mut server, "PUT", "client_1")
+            .match_body(Matcher::PartialJson(json!({
+                "client_id": "client_1",
+                "client_name": format!("cedarling-{}", pdp_id),
+                "scope": "cedarling",
+            })))
+            .with_body(registered_client(&server, "client_1").to_string())
+            .expect(1)
+            .create();
+
+        let manager = token_manager(&server, pdp_id, Some(credential_store(&path)))
+            .await
+            .expect("should update the stored client");
+        assert_eq!(manager.access_token().await.unwrap(), "token_1");
+
+        update_endpoint.assert();
+        credential_store(&path).remove().unwrap();
+    }
+
+    #[tokio::test]
+    async fn registers_a_new_client_when_the_stored_client_is_gone() {
+        let mut server = Server::new_async().await;
+        let pdp_id = PdpID::new();
+        let path = credentials_path();
+        mock_oidc_endpoint(&mut server);
+        let first_dcr = mock_dcr_endpoint(&mut server, "client_1");
+        mock_token_endpoint(&mut server, "client_1", "token_1", 3600).create();
+
+        token_manager(&server, pdp_id, Some(credential_store(&path)))
+            .await
+            .expect("should register the client");
+        first_dcr.assert();
+
+        // the IDP deletes the stored client
+        first_dcr.remove();
+        let read_endpoint = mock_client_config_endpoint(&mut server, "GET", "client_1")
+            .with_status(401)
+            .expect(1)
+            .create();
+        let second_dcr = mock_dcr_endpoint(&mut server, "client_2");
+        mock_token_endpoint(&mut server, "client_2", "token_2", 3600).create();
+
+        let manager = token_manager(&server, pdp_id, Some(credential_store(&path)))
+            .await
+            .expect("should register a new client");
+        assert_eq!(manager.access_token().await.unwrap(), "token_2");
+        read_endpoint.assert();
+        second_dcr.assert();
+
+        let stored = credential_store(&path)
+            .load()
+            .unwrap()
+            .expect("the new client should be stored");
+        assert_eq!(stored.client_id, "client_2");
+        credential_store(&path).remove().unwrap();
+    }
+
+    #[tokio::test]
+    async fn deregister_deletes_the_client() {
+        let mut server = Server::new_async().await;
+        let path = credentials_path();
+        mock_oidc_endpoint(&mut server);
+        mock_dcr_endpoint(&mut server, "client_1");
+        mock_token_endpoint(&mut server, "client_1", "token_1", 3600).create();
+        let delete_endpoint = mock_client_config_endpoint(&mut server, "DELETE", "client_1")
+            .with_status(204)
+            .expect(1)
+            .create();
+
+        let manager = token_manager(&server, PdpID::new(), Some(credential_store(&path)))
+            .await
+            .expect("should register the client");
+        assert!(credential_store(&path).load().unwrap().is_some());
+
+        manager.deregister().await.expect("should deregister");
+        delete_endpoint.assert();
+        assert!(
+            credential_store(&path).load().unwrap().is_none(),
+            "the stored client should be removed"
+        );
+    }
+}
@@ -10,7 +10,7 @@ use std::sync::{Arc, Weak};
 
 use uuid7::Uuid;
 use crate::log::log_strategy::LogStrategyLogger;
-use super::{LogLevel, LogStrategy};
+use super::{LogLevel, LogQuery, LogQueryResult, LogStrategy};
 
 /// Log Writer
 /// interface for logging events
@@ -147,4 +147,9 @@ pub trait LogStorage {
     /// Return log entries that match the given request_id and tag.
     fn get_logs_by_request_id_and_tag(&self, request_id: &str, tag: &str)
     -> Vec<serde_json::Value>;
+
+    /// Get logs matching the filters of the query, without removing them from the storage.
+    /// Entries are paginated with the `cursor` and `limit` of the query,
+    /// use [`LogQueryResult::next_cursor`] to get the next page.
+    fn query_logs(&self, query: &LogQuery) -> LogQueryResult;
 }
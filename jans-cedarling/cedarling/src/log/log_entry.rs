@@ -6,13 +6,14 @@
 //! # Log entry
 //! The module contains structs for logging events.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::Display;
 use std::hash::Hash;
 use std::sync::Arc;
 
 use super::LogLevel;
 use super::interface::{Indexed, Loggable};
+use crate::authz::{PolicyTrace, PrincipalRule, ShadowStats};
 use crate::common::policy_store::PoliciesContainer;
 use crate::jwt::Token;
 use cedar_policy::EntityUid;
@@ -118,9 +119,14 @@ impl Loggable for LogEntry {
     derive_more::Display,
 )]
 pub enum LogType {
+    /// Result of an authorization request
     Decision,
+    /// Message about the operation of Cedarling
     System,
+    /// Metrics of Cedarling
     Metric,
+    /// Difference between the decisions of the policy store and of the shadow policy store
+    DecisionDiff,
 }
 
 /// Log information about authorization request
@@ -258,6 +264,9 @@ impl DiagnosticsRefs<'_> {
 pub struct PolicyInfo {
     pub id: String,
     pub description: Option<String>,
+    /// ID of the template, if the policy is a template link. The `id` is the ID of the link.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_id: Option<String>,
 }
 
 impl Hash for PolicyInfo {
@@ -281,6 +290,9 @@ impl Diagnostics {
                 description: policies
                     .get_policy_description(id.as_str())
                     .map(|v| v.to_string()),
+                template_id: policies
+                    .get_template_link(id.as_str())
+                    .map(|link| link.template_id.clone()),
                 id: policy_id.to_string(),
             }
         }));
@@ -326,6 +338,14 @@ pub struct DecisionLogEntry<'a> {
     pub tokens: LogTokensInfo<'a>,
     /// time in micro-seconds spent for decision
     pub decision_time_micro_sec: i64,
+    /// evaluation trace of the policies for each principal, when explain mode is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanations: Option<&'a HashMap<String, Vec<PolicyTrace>>>,
+    /// rule used to combine the decisions of the principals
+    pub principal_rule: PrincipalRule<'a>,
+    /// the result was taken from the decision cache
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cache_hit: bool,
 }
 
 impl DecisionLogEntry<'_> {
@@ -368,6 +388,72 @@ impl Loggable for &DecisionLogEntry<'_> {
     }
 }
 
+/// log entry for a request where the shadow policy store decides differently
+/// than the policy store
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DecisionDiffLogEntry {
+    /// base information of entry
+    /// it is unwrap to flatten structure
+    #[serde(flatten)]
+    pub base: BaseLogEntry,
+    /// id of policy store
+    pub policystore_id: String,
+    /// version of policy store
+    pub policystore_version: String,
+    /// id of the shadow policy store
+    pub shadow_policystore_id: String,
+    /// version of the shadow policy store
+    pub shadow_policystore_version: String,
+    /// action UID for request
+    pub action: String,
+    /// resource UID for request
+    pub resource: String,
+    /// decision of the policy store, the one returned for the request
+    pub decision: Decision,
+    /// decision of the shadow policy store
+    pub shadow_decision: Decision,
+    /// principals whose decision or determining policies differ, by principal UID
+    pub principals: BTreeMap<String, PrincipalDecisionDiff>,
+    /// counters of the shadow evaluation, this request included
+    pub counters: ShadowStats,
+}
+
+/// Decisions of a principal in a [`DecisionDiffLogEntry`]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct PrincipalDecisionDiff {
+    /// decision of the policy store
+    pub decision: Decision,
+    /// decision of the shadow policy store
+    pub shadow_decision: Decision,
+    /// IDs of the policies that determined the decision of the policy store
+    pub reason: BTreeSet<String>,
+    /// IDs of the policies that determined the decision of the shadow policy store
+    pub shadow_reason: BTreeSet<String>,
+    /// errors of the shadow evaluation
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub shadow_errors: Vec<String>,
+}
+
+impl Indexed for &DecisionDiffLogEntry {
+    fn get_id(&self) -> Uuid {
+        self.base.get_id()
+    }
+
+    fn get_additional_ids(&self) -> Vec<Uuid> {
+        self.base.get_additional_ids()
+    }
+
+    fn get_tags(&self) -> Vec<&str> {
+        self.base.get_tags()
+    }
+}
+
+impl Loggable for &DecisionDiffLogEntry {
+    fn get_log_level(&self) -> Option<LogLevel> {
+        self.base.get_log_level()
+    }
+}
+
 /// Custom uuid generation function to avoid using std::time because it makes panic in WASM
 //
 // TODO: maybe using wasm we can use `js_sys::Date::now()`
@@ -0,0 +1,270 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! # Log query
+//! Structured queries over the log entries held by the memory logger.
+
+use std::ops::Bound;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Decision, LogLevel, LogType};
+
+/// Largest unix timestamp (in milliseconds) that fits in the 48 bits of a uuid7.
+const MAX_UUID7_TIMESTAMP_MS: u64 = (1 << 48) - 1;
+
+/// Query for log entries in the memory log storage.
+///
+/// All filters are optional and are combined with `AND`.
+/// Entries are ordered by their ID, which is a uuid7, so the order is the order
+/// in which the entries were logged.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogQuery {
+    /// Only include entries logged at or after this unix timestamp, in milliseconds.
+    pub since: Option<u64>,
+    /// Only include entries logged before this unix timestamp, in milliseconds.
+    pub until: Option<u64>,
+    /// Filters on the content of the entries.
+    #[serde(flatten)]
+    pub filter: LogFilter,
+    /// ID of the last entry of the previous page.
+    ///
+    /// The page starts after this entry.
+    pub cursor: Option<String>,
+    /// Maximum number of entries in the page. All entries are returned if not set.
+    pub limit: Option<usize>,
+    /// Only count the matching entries without returning them.
+    pub count_only: bool,
+    /// Order of the returned entries.
+    pub order: LogQueryOrder,
+}
+
+/// Filters on the content of log entries, shared by [`LogQuery`] and log
+/// subscriptions.
+///
+/// All filters are optional and are combined with `AND`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFilter {
+    /// Only include entries of this kind, e.g. `Decision`.
+    pub log_kind: Option<LogType>,
+    /// Only include entries with this log level or a more important one.
+    ///
+    /// Entries without a level (e.g. decision logs) are not included when this is set.
+    pub level: Option<LogLevel>,
+    /// Only include entries with this decision.
+    ///
+    /// Matches the `decision` of decision logs and the `authorized` flag of
+    /// authorization debug logs.
+    pub decision: Option<Decision>,
+    /// Only include entries for this principal.
+    ///
+    /// Matches the principal entity types of decision logs, e.g. `Jans::User`,
+    /// and the principals of authorization debug logs.
+    pub principal: Option<String>,
+    /// Only include entries for this action, e.g. `Jans::Action::"Read"`.
+    pub action: Option<String>,
+    /// Only include entries for this resource, e.g. `Jans::Issue::"some_id"`.
+    pub resource: Option<String>,
+}
+
+/// Order of the entries returned by a [`LogQuery`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogQueryOrder {
+    /// Oldest entries first
+    #[default]
+    OldestFirst,
+    /// Newest entries first
+    NewestFirst,
+}
+
+/// Result of a [`LogQuery`].
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogQueryResult {
+    /// Entries of the requested page. Empty for count-only queries.
+    pub entries: Vec<Value>,
+    /// Number of entries matching the filters, regardless of the cursor and limit.
+    pub count: usize,
+    /// Cursor to get the next page, if there are more matching entries.
+    pub next_cursor: Option<String>,
+}
+
+impl LogQuery {
+    /// Bounds of the entry IDs within the time range of the query.
+    ///
+    /// The first 48 bits of a uuid7 are the unix timestamp in milliseconds, so the
+    /// time range can be mapped to a range of IDs in their string representation.
+    pub(crate) fn id_bounds(&self) -> (Bound<String>, Bound<String>) {
+        let lower = self
+            .since
+            .map_or(Bound::Unbounded, |ms| Bound::Included(uuid7_prefix(ms)));
+        let upper = self
+            .until
+            .map_or(Bound::Unbounded, |ms| Bound::Excluded(uuid7_prefix(ms)));
+        (lower, upper)
+    }
+
+    /// Checks if the log entry matches the filters of the query.
+    ///
+    /// The time range and the cursor are applied on the entry IDs instead, see
+    /// [`LogQuery::id_bounds`].
+    pub(crate) fn matches(&self, entry: &Value) -> bool {
+        self.filter.matches(entry)
+    }
+}
+
+impl LogFilter {
+    /// Checks if the log entry matches all filters.
+    pub(crate) fn matches(&self, entry: &Value) -> bool {
+        if let Some(log_kind) = self.log_kind {
+            let log_kind: &'static str = log_kind.into();
+            if entry.get("log_kind").and_then(Value::as_str) != Some(log_kind) {
+                return false;
+            }
+        }
+
+        if let Some(min_level) = self.level {
+            let level = entry
+                .get("level")
+                .and_then(Value::as_str)
+                .and_then(|level| level.parse::<LogLevel>().ok());
+            if level.is_none_or(|level| level < min_level) {
+                return false;
+            }
+        }
+
+        if let Some(decision) = self.decision {
+            let matches = match (entry.get("decision"), entry.get("authorized")) {
+                (Some(Value::String(value)), _) => *value == decision.to_string(),
+                (_, Some(Value::Bool(authorized))) => Decision::from(*authorized) == decision,
+                _ => false,
+            };
+            if !matches {
+                return false;
+            }
+        }
+
+        if let Some(principal) = self.principal.as_deref()
+            && !entry_has_principal(entry, principal)
+        {
+            return false;
+        }
+
+        if let Some(action) = self.action.as_deref()
+            && entry.get("action").and_then(Value::as_str) != Some(action)
+        {
+            return false;
+        }
+
+        if let Some(resource) = self.resource.as_deref()
+            && entry.get("resource").and_then(Value::as_str) != Some(resource)
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+fn entry_has_principal(entry: &Value, principal: &str) -> bool {
+    let in_decision_log = entry
+        .get("principal")
+        .and_then(Value::as_array)
+        .is_some_and(|principals| principals.iter().any(|p| p.as_str() == Some(principal)));
+
+    let in_authorize_info = entry
+        .get("authorize_info")
+        .and_then(Value::as_array)
+        .is_some_and(|infos| {
+            infos
+                .iter()
+                .any(|info| info.get("principal").and_then(Value::as_str) == Some(principal))
+        });
+
+    in_decision_log || in_authorize_info
+}
+
+/// Returns the prefix shared by the string representation of every uuid7
+/// generated at the given unix timestamp in milliseconds.
+///
+/// Every uuid7 with a later timestamp is greater than the prefix, and every uuid7
+/// with an earlier timestamp is smaller.
+fn uuid7_prefix(unix_ts_ms: u64) -> String {
+    let unix_ts_ms = unix_ts_ms.min(MAX_UUID7_TIMESTAMP_MS);
+    format!("{:08x}-{:04x}", unix_ts_ms >> 16, unix_ts_ms & 0xffff)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::log::gen_uuid7;
+    use serde_json::json;
+
+    #[test]
+    fn uuid7_prefix_orders_ids_by_time() {
+        let before = chrono::Utc::now().timestamp_millis() as u64;
+        let id = gen_uuid7().to_string();
+        let after = chrono::Utc::now().timestamp_millis() as u64;
+
+        assert!(uuid7_prefix(before) <= id, "{id} should be after {before}");
+        assert!(
+            uuid7_prefix(after + 1) > id,
+            "{id} should be before {after}"
+        );
+        assert_eq!(uuid7_prefix(0x0123_4567_89ab), "01234567-89ab");
+    }
+
+    #[test]
+    fn can_match_log_entries() {
+        let decision_log = json!({
+            "id": "01234567-89ab-7000-8000-000000000000",
+            "log_kind": "Decision",
+            "principal": ["Jans::User", "Jans::Workload"],
+            "action": "Jans::Action::\"Read\"",
+            "resource": "Jans::Issue::\"some_id\"",
+            "decision": "ALLOW",
+        });
+        let system_log = json!({
+            "id": "01234567-89ab-7000-8000-000000000001",
+            "log_kind": "System",
+            "level": "DEBUG",
+            "action": "Jans::Action::\"Read\"",
+            "authorize_info": [{"principal": "Jans::User::\"some_user\""}],
+            "authorized": false,
+        });
+
+        let query = serde_json::from_value::<LogQuery>(json!({
+            "decision": "ALLOW",
+            "principal": "Jans::User",
+            "action": "Jans::Action::\"Read\"",
+        }))
+        .expect("should parse log query");
+        assert!(query.matches(&decision_log));
+        assert!(!query.matches(&system_log));
+
+        let filter = LogFilter {
+            log_kind: Some(LogType::System),
+            level: Some(LogLevel::DEBUG),
+            decision: Some(Decision::Deny),
+            principal: Some("Jans::User::\"some_user\"".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&system_log));
+        assert!(!filter.matches(&decision_log));
+
+        let query = LogFilter {
+            level: Some(LogLevel::INFO),
+            ..Default::default()
+        };
+        assert!(
+            !query.matches(&system_log),
+            "DEBUG is less important than INFO"
+        );
+        assert!(!query.matches(&decision_log), "decision logs have no level");
+    }
+}
@@ -7,12 +7,14 @@ use std::sync::RwLock;
 
 use super::InitLockServiceError;
 use super::interface::{Indexed, LogStorage, LogWriter, Loggable};
+use super::log_subscription::LogSubscribers;
 use super::memory_logger::MemoryLogger;
 use super::nop_logger::NopLogger;
 use super::stdout_logger::StdOutLogger;
+use super::{LogLevel, LogQuery, LogQueryResult, LogSubscription, LogSubscriptionConfig};
 use crate::app_types::{ApplicationName, PdpID};
 use crate::bootstrap_config::log_config::{LogConfig, LogTypeConfig};
-use crate::lock::LockService;
+use crate::lock::{DeregisterClientError, LockService};
 use serde::Serialize;
 
 pub(crate) struct LogStrategy {
@@ -20,6 +22,7 @@ pub(crate) struct LogStrategy {
     pdp_id: PdpID,
     app_name: Option<ApplicationName>,
     lock_service: RwLock<Option<LockService>>,
+    subscribers: LogSubscribers,
 }
 
 /// LogStrategy implements strategy pattern for logging.
@@ -56,6 +59,7 @@ impl LogStrategy {
             pdp_id,
             app_name,
             lock_service: RwLock::new(None),
+            subscribers: LogSubscribers::new(config.log_level),
         })
     }
 
@@ -70,6 +74,7 @@ impl LogStrategy {
             pdp_id,
             app_name,
             lock_service: RwLock::new(lock_service),
+            subscribers: LogSubscribers::new(LogLevel::TRACE),
         }
     }
 
@@ -84,6 +89,11 @@ impl LogStrategy {
             .expect("obtain lock_service write lock") = Some(lock_service);
     }
 
+    /// Subscribes to the entries logged from now on.
+    pub fn subscribe(&self, config: LogSubscriptionConfig) -> LogSubscription {
+        self.subscribers.subscribe(config)
+    }
+
     pub async fn shut_down(&self) {
         let lock = self
             .lock_service
@@ -94,6 +104,24 @@ impl LogStrategy {
         if let Some(mut lock_service) = lock {
             lock_service.shut_down().await
         }
+
+        self.subscribers.close_all();
+    }
+
+    /// Stops sending logs to the lock server and deletes the lock client.
+    ///
+    /// Returns `false` if the lock service is not running.
+    pub async fn deregister_lock_client(&self) -> Result<bool, DeregisterClientError> {
+        let lock = self
+            .lock_service
+            .write()
+            .expect("obtain lock_service write lock")
+            .take();
+
+        match lock {
+            Some(mut lock_service) => lock_service.deregister().await.map(|_| true),
+            None => Ok(false),
+        }
     }
 }
 
@@ -139,6 +167,7 @@ impl LogWriter for LogStrategy {
         {
             lock_service.log_any(entry.clone());
         }
+        self.subscribers.publish(&entry);
         match &self.logger {
             LogStrategyLogger::Off(log) => log.log_any(entry),
             LogStrategyLogger::MemoryLogger(memory_logger) => memory_logger.log_any(entry),
@@ -206,4 +235,11 @@ impl LogStorage for LogStrategy {
             _ => Vec::new(),
         }
     }
+
+    fn query_logs(&self, query: &LogQuery) -> LogQueryResult {
+        match &self.logger {
+            LogStrategyLogger::MemoryLogger(memory_logger) => memory_logger.query_logs(query),
+            _ => LogQueryResult::default(),
+        }
+    }
 }
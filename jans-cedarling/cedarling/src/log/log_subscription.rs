@@ -0,0 +1,412 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! # Log subscription
+//! Streams the log entries to subscribers as they are logged, so they don't have
+//! to poll the log storage.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use futures::task::AtomicWaker;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::LogFilter;
+use super::LogLevel;
+use super::interface::Loggable;
+use super::memory_logger::to_json_value;
+
+const STATE_MUTEX_EXPECT_MESSAGE: &str = "log subscription state mutex should unlock";
+const SUBSCRIBERS_MUTEX_EXPECT_MESSAGE: &str = "log subscribers mutex should unlock";
+
+/// Default number of entries buffered for a subscriber.
+pub const DEFAULT_LOG_SUBSCRIPTION_BUFFER_SIZE: usize = 1000;
+
+/// What to do when an entry is logged while the buffer of a subscription is full.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogOverflowPolicy {
+    /// Remove the oldest buffered entry to make room for the new one
+    #[default]
+    DropOldest,
+    /// Discard the new entry
+    DropNewest,
+}
+
+/// Configuration of a log subscription.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSubscriptionConfig {
+    /// Only entries matching the filter are sent to the subscriber.
+    #[serde(flatten)]
+    pub filter: LogFilter,
+    /// Maximum number of entries waiting to be received. At least one entry is
+    /// always buffered.
+    pub buffer_size: usize,
+    /// What to do when the buffer is full.
+    pub overflow_policy: LogOverflowPolicy,
+}
+
+impl Default for LogSubscriptionConfig {
+    fn default() -> Self {
+        Self {
+            filter: LogFilter::default(),
+            buffer_size: DEFAULT_LOG_SUBSCRIPTION_BUFFER_SIZE,
+            overflow_policy: LogOverflowPolicy::default(),
+        }
+    }
+}
+
+impl From<LogFilter> for LogSubscriptionConfig {
+    fn from(filter: LogFilter) -> Self {
+        Self {
+            filter,
+            ..Default::default()
+        }
+    }
+}
+
+/// Receiving end of a log subscription.
+///
+/// The entries are received in the order they were logged, either with
+/// [`LogSubscription::recv`] or by using the subscription as a [`Stream`].
+/// The subscription ends once it is closed, by [`LogSubscription::close`] or when
+/// Cedarling is shut down or dropped, and every buffered entry has been received.
+///
+/// Dropping the subscription unsubscribes it.
+pub struct LogSubscription {
+    shared: Arc<SubscriptionShared>,
+}
+
+struct SubscriptionShared {
+    filter: LogFilter,
+    buffer_size: usize,
+    overflow_policy: LogOverflowPolicy,
+    state: Mutex<SubscriptionState>,
+    waker: AtomicWaker,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct SubscriptionState {
+    buffer: VecDeque<Value>,
+    dropped_count: u64,
+    closed: bool,
+}
+
+impl LogSubscription {
+    fn new(config: LogSubscriptionConfig) -> Self {
+        let buffer_size = config.buffer_size.max(1);
+        Self {
+            shared: Arc::new(SubscriptionShared {
+                filter: config.filter,
+                buffer_size,
+                overflow_policy: config.overflow_policy,
+                state: Mutex::new(SubscriptionState {
+                    buffer: VecDeque::with_capacity(buffer_size),
+                    ..Default::default()
+                }),
+                waker: AtomicWaker::new(),
+                condvar: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Waits for the next entry.
+    ///
+    /// Returns `None` when the subscription is closed and every buffered entry
+    /// has been received.
+    pub async fn recv(&self) -> Option<Value> {
+        std::future::poll_fn(|cx| self.shared.poll_recv(cx)).await
+    }
+
+    /// Returns the next entry if one is buffered, without waiting.
+    pub fn try_recv(&self) -> Option<Value> {
+        self.shared.state().buffer.pop_front()
+    }
+
+    /// Blocks the current thread until the next entry is logged.
+    ///
+    /// Returns `None` when the subscription is closed and every buffered entry
+    /// has been received. Use [`LogSubscription::recv`] in async code.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn blocking_recv(&self) -> Option<Value> {
+        let mut state = self.shared.state();
+        loop {
+            if let Some(entry) = state.buffer.pop_front() {
+                return Some(entry);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self
+                .shared
+                .condvar
+                .wait(state)
+                .expect(STATE_MUTEX_EXPECT_MESSAGE);
+        }
+    }
+
+    /// Number of entries discarded because the buffer was full.
+    pub fn dropped_count(&self) -> u64 {
+        self.shared.state().dropped_count
+    }
+
+    /// Stops receiving new entries. The buffered entries can still be received.
+    pub fn close(&self) {
+        self.shared.close();
+    }
+
+    /// Returns `true` if the subscription doesn't receive new entries anymore.
+    pub fn is_closed(&self) -> bool {
+        self.shared.state().closed
+    }
+}
+
+impl Stream for LogSubscription {
+    type Item = Value;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.shared.poll_recv(cx)
+    }
+}
+
+impl Drop for LogSubscription {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+impl SubscriptionShared {
+    fn state(&self) -> MutexGuard<'_, SubscriptionState> {
+        self.state.lock().expect(STATE_MUTEX_EXPECT_MESSAGE)
+    }
+
+    fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<Value>> {
+        // register the waker before checking the buffer so an entry pushed in
+        // between is not missed
+        self.waker.register(cx.waker());
+
+        let mut state = self.state();
+        if let Some(entry) = state.buffer.pop_front() {
+            Poll::Ready(Some(entry))
+        } else if state.closed {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn push(&self, entry: Value) {
+        {
+            let mut state = self.state();
+            if state.closed {
+                return;
+            }
+            if state.buffer.len() >= self.buffer_size {
+                state.dropped_count += 1;
+                match self.overflow_policy {
+                    LogOverflowPolicy::DropOldest => {
+                        state.buffer.pop_front();
+                    },
+                    LogOverflowPolicy::DropNewest => return,
+                }
+            }
+            state.buffer.push_back(entry);
+        }
+        self.wake();
+    }
+
+    fn close(&self) {
+        self.state().closed = true;
+        self.wake();
+    }
+
+    fn wake(&self) {
+        self.waker.wake();
+        self.condvar.notify_all();
+    }
+}
+
+/// Sends the logged entries to the active subscriptions.
+pub(crate) struct LogSubscribers {
+    log_level: LogLevel,
+    subscriptions: Mutex<Vec<Weak<SubscriptionShared>>>,
+}
+
+impl LogSubscribers {
+    /// Creates the subscribers registry. Entries below `log_level` are not sent.
+    pub fn new(log_level: LogLevel) -> Self {
+        Self {
+            log_level,
+            subscriptions: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn subscribe(&self, config: LogSubscriptionConfig) -> LogSubscription {
+        let subscription = LogSubscription::new(config);
+        self.subscriptions
+            .lock()
+            .expect(SUBSCRIBERS_MUTEX_EXPECT_MESSAGE)
+            .push(Arc::downgrade(&subscription.shared));
+        subscription
+    }
+
+    pub fn publish<T: Loggable>(&self, entry: &T) {
+        if !entry.can_log(self.log_level) {
+            return;
+        }
+
+        let subscriptions = {
+            let mut subscriptions = self
+                .subscriptions
+                .lock()
+                .expect(SUBSCRIBERS_MUTEX_EXPECT_MESSAGE);
+            // forget the subscriptions that were dropped or closed
+            subscriptions.retain(|subscription| {
+                subscription
+                    .upgrade()
+                    .is_some_and(|subscription| !subscription.state().closed)
+            });
+            subscriptions
+                .iter()
+                .filter_map(Weak::upgrade)
+                .collect::<Vec<_>>()
+        };
+        if subscriptions.is_empty() {
+            return;
+        }
+
+        let json = to_json_value(entry);
+        for subscription in subscriptions {
+            if subscription.filter.matches(&json) {
+                subscription.push(json.clone());
+            }
+        }
+    }
+
+    /// Closes every subscription, ending their streams.
+    pub fn close_all(&self) {
+        let subscriptions = std::mem::take(
+            &mut *self
+                .subscriptions
+                .lock()
+                .expect(SUBSCRIBERS_MUTEX_EXPECT_MESSAGE),
+        );
+        for subscription in subscriptions.iter().filter_map(Weak::upgrade) {
+            subscription.close();
+        }
+    }
+}
+
+impl Drop for LogSubscribers {
+    fn drop(&mut self) {
+        // end the streams when Cedarling is dropped without being shut down
+        self.close_all();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::log::{LogEntry, LogType};
+    use futures::StreamExt;
+
+    fn system_entry(level: LogLevel, msg: &str) -> LogEntry {
+        LogEntry::new_with_data(LogType::System, None)
+            .set_level(level)
+            .set_message(msg.to_string())
+    }
+
+    #[tokio::test]
+    async fn sends_matching_entries_to_subscribers() {
+        let subscribers = LogSubscribers::new(LogLevel::DEBUG);
+        let all = subscribers.subscribe(LogSubscriptionConfig::default());
+        let warnings = subscribers.subscribe(
+            LogFilter {
+                level: Some(LogLevel::WARN),
+                ..Default::default()
+            }
+            .into(),
+        );
+
+        subscribers.publish(&system_entry(LogLevel::TRACE, "below the log level"));
+        subscribers.publish(&system_entry(LogLevel::INFO, "info"));
+        subscribers.publish(&system_entry(LogLevel::ERROR, "error"));
+
+        assert_eq!(all.recv().await.unwrap()["msg"], "info");
+        assert_eq!(all.recv().await.unwrap()["msg"], "error");
+        assert_eq!(all.try_recv(), None);
+
+        assert_eq!(warnings.try_recv().unwrap()["msg"], "error");
+        assert_eq!(warnings.try_recv(), None);
+    }
+
+    #[test]
+    fn applies_overflow_policy() {
+        let subscribers = LogSubscribers::new(LogLevel::DEBUG);
+        let drop_oldest = subscribers.subscribe(LogSubscriptionConfig {
+            buffer_size: 2,
+            overflow_policy: LogOverflowPolicy::DropOldest,
+            ..Default::default()
+        });
+        let drop_newest = subscribers.subscribe(LogSubscriptionConfig {
+            buffer_size: 2,
+            overflow_policy: LogOverflowPolicy::DropNewest,
+            ..Default::default()
+        });
+
+        for msg in ["first", "second", "third"] {
+            subscribers.publish(&system_entry(LogLevel::INFO, msg));
+        }
+
+        assert_eq!(drop_oldest.dropped_count(), 1);
+        assert_eq!(drop_oldest.try_recv().unwrap()["msg"], "second");
+        assert_eq!(drop_oldest.try_recv().unwrap()["msg"], "third");
+
+        assert_eq!(drop_newest.dropped_count(), 1);
+        assert_eq!(drop_newest.try_recv().unwrap()["msg"], "first");
+        assert_eq!(drop_newest.try_recv().unwrap()["msg"], "second");
+    }
+
+    #[tokio::test]
+    async fn stream_ends_when_closed() {
+        let subscribers = LogSubscribers::new(LogLevel::DEBUG);
+        let mut subscription = subscribers.subscribe(LogSubscriptionConfig::default());
+
+        let waiting = tokio::spawn(async move {
+            let mut msgs = Vec::new();
+            while let Some(entry) = subscription.next().await {
+                msgs.push(entry["msg"].as_str().unwrap().to_string());
+            }
+            msgs
+        });
+
+        // wait until the task is waiting for entries
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        subscribers.publish(&system_entry(LogLevel::INFO, "logged"));
+        subscribers.close_all();
+        subscribers.publish(&system_entry(LogLevel::INFO, "after close"));
+
+        let msgs = waiting.await.expect("task should not panic");
+        assert_eq!(msgs, vec!["logged"]);
+    }
+
+    #[test]
+    fn forgets_dropped_subscriptions() {
+        let subscribers = LogSubscribers::new(LogLevel::DEBUG);
+        let subscription = subscribers.subscribe(LogSubscriptionConfig::default());
+        drop(subscribers.subscribe(LogSubscriptionConfig::default()));
+
+        subscribers.publish(&system_entry(LogLevel::INFO, "info"));
+
+        assert_eq!(subscribers.subscriptions.lock().unwrap().len(), 1);
+        assert!(subscription.try_recv().is_some());
+    }
+}
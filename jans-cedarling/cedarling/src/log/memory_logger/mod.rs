@@ -5,12 +5,13 @@
 
 use chrono::Duration;
 use serde_json::Value;
+use std::ops::Bound;
 use std::sync::Mutex;
 
 use sparkv::{Config as ConfigSparKV, Error, SparKV};
 
-use super::LogLevel;
 use super::err_log_entry::ErrorLogEntry;
+use super::{LogLevel, LogQuery, LogQueryOrder, LogQueryResult};
 use super::interface::{LogStorage, LogWriter, Loggable, composite_key};
 use crate::app_types::{ApplicationName, PdpID};
 use crate::bootstrap_config::log_config::MemoryLogConfig;
@@ -121,7 +122,7 @@ mod fallback {
     }
 }
 
-fn to_json_value<T: Loggable>(entry: &T) -> Value {
+pub(crate) fn to_json_value<T: Loggable>(entry: &T) -> Value {
     match serde_json::to_value(entry) {
         Ok(json) => json,
         Err(err) => {
@@ -242,12 +243,59 @@ impl LogStorage for MemoryLogger {
             .map(|v| v.to_owned())
             .collect()
     }
+
+    fn query_logs(&self, query: &LogQuery) -> LogQueryResult {
+        let storage = self.storage.lock().expect(STORAGE_MUTEX_EXPECT_MESSAGE);
+
+        let (lower, upper) = query.id_bounds();
+        let entries = storage.range(as_str_bound(&lower), as_str_bound(&upper));
+        let entries: Box<dyn Iterator<Item = (&String, &Value)>> = match query.order {
+            LogQueryOrder::OldestFirst => Box::new(entries),
+            LogQueryOrder::NewestFirst => Box::new(entries.rev()),
+        };
+
+        let is_after_cursor = |id: &str| match (query.cursor.as_deref(), query.order) {
+            (None, _) => true,
+            (Some(cursor), LogQueryOrder::OldestFirst) => id > cursor,
+            (Some(cursor), LogQueryOrder::NewestFirst) => id < cursor,
+        };
+        let limit = query.limit.unwrap_or(usize::MAX);
+
+        let mut result = LogQueryResult::default();
+        // the page continues after the last returned entry
+        let mut last_id = query.cursor.clone();
+        for (id, entry) in entries.filter(|(_, entry)| query.matches(entry)) {
+            result.count += 1;
+
+            if query.count_only || !is_after_cursor(id) {
+                continue;
+            }
+
+            if result.entries.len() < limit {
+                result.entries.push(entry.clone());
+                last_id = Some(id.clone());
+            } else if result.next_cursor.is_none() {
+                // there are more entries than fit in the page
+                result.next_cursor = last_id.take();
+            }
+        }
+
+        result
+    }
+}
+
+fn as_str_bound(bound: &Bound<String>) -> Bound<&str> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.as_str()),
+        Bound::Excluded(key) => Bound::Excluded(key.as_str()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::interface::Indexed;
-    use super::super::{AuthorizationLogInfo, LogEntry, LogType};
+    use super::super::{AuthorizationLogInfo, Decision, LogEntry, LogFilter, LogType};
     use super::*;
     use crate::log::gen_uuid7;
     use serde_json::json;
@@ -420,6 +468,89 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_query_logs() {
+        let logger = create_memory_logger(PdpID::new(), None);
+
+        let decision_entry = |authorized: bool| {
+            LogEntry::new_with_data(LogType::Decision, None).set_auth_info(AuthorizationLogInfo {
+                action: "Jans::Action::\"Read\"".to_string(),
+                resource: "Jans::Issue::\"some_id\"".to_string(),
+                context: json!({}),
+                authorize_info: Default::default(),
+                authorized,
+                entities: json!({}),
+            })
+        };
+
+        let mut allowed_ids = Vec::new();
+        for i in 0..5 {
+            let entry = decision_entry(i % 2 == 0);
+            if i % 2 == 0 {
+                allowed_ids.push(entry.base.id.to_string());
+            }
+            logger.log_any(entry);
+        }
+        logger.log_any(LogEntry::new_with_data(LogType::System, None).set_level(LogLevel::INFO));
+
+        let query = LogQuery {
+            filter: LogFilter {
+                decision: Some(Decision::Allow),
+                ..Default::default()
+            },
+            limit: Some(2),
+            ..Default::default()
+        };
+        let page = logger.query_logs(&query);
+        assert_eq!(page.count, 3);
+        assert_eq!(
+            page.entries
+                .iter()
+                .map(|entry| entry["id"].as_str().unwrap())
+                .collect::<Vec<_>>(),
+            allowed_ids[..2]
+        );
+        assert_eq!(page.next_cursor.as_ref(), Some(&allowed_ids[1]));
+
+        let page = logger.query_logs(&LogQuery {
+            cursor: page.next_cursor,
+            ..query.clone()
+        });
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0]["id"], allowed_ids[2].as_str());
+        assert_eq!(page.next_cursor, None);
+
+        let page = logger.query_logs(&LogQuery {
+            order: LogQueryOrder::NewestFirst,
+            limit: Some(1),
+            ..query.clone()
+        });
+        assert_eq!(page.entries[0]["id"], allowed_ids[2].as_str());
+        assert_eq!(page.next_cursor.as_ref(), Some(&allowed_ids[2]));
+
+        let page = logger.query_logs(&LogQuery {
+            filter: LogFilter {
+                log_kind: Some(LogType::System),
+                ..Default::default()
+            },
+            count_only: true,
+            ..Default::default()
+        });
+        assert_eq!(page.count, 1);
+        assert!(page.entries.is_empty());
+
+        // entries in the future are not in the time range
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        let page = logger.query_logs(&LogQuery {
+            since: Some(now + 60_000),
+            ..Default::default()
+        });
+        assert_eq!(page.count, 0);
+
+        // querying doesn't remove the entries
+        assert_eq!(logger.get_log_ids().len(), 6);
+    }
+
     #[test]
     fn test_max_items_config() {
         let default_config: ConfigSparKV = Default::default();
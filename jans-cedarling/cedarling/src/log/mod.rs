@@ -46,14 +46,22 @@
 //!  All log implementation should implement this.
 //!
 //!  * [`LogStorage`] is used to gettting logs from log storage.
+//!    Logs can be filtered and paginated with a [`LogQuery`].
 //!
 //!  Currently only [MemoryLogger](`memory_logger::MemoryLogger`) implement this.
+//!
+//!  ## Subscriptions
+//!
+//!  New entries can be streamed to subscribers with a [`LogSubscription`],
+//!  independently of the log type.
 
 mod err_log_entry;
 pub mod interface;
 
 mod log_entry;
 mod log_level;
+mod log_query;
+mod log_subscription;
 pub(crate) mod log_strategy;
 mod memory_logger;
 mod nop_logger;
@@ -61,6 +69,10 @@ mod stdout_logger;
 
 pub use log_entry::*;
 pub use log_level::*;
+pub use log_query::*;
+pub use log_subscription::{
+    DEFAULT_LOG_SUBSCRIPTION_BUFFER_SIZE, LogOverflowPolicy, LogSubscription, LogSubscriptionConfig,
+};
 
 #[cfg(test)]
 mod test;
@@ -72,10 +84,10 @@ use std::sync::{Arc, Weak};
 pub use interface::LogStorage;
 pub(crate) use log_strategy::LogStrategy;
 
-use crate::LockServiceConfig;
 use crate::app_types::{ApplicationName, PdpID};
 use crate::bootstrap_config::log_config::LogConfig;
 use crate::lock::{InitLockServiceError, LockService};
+use crate::{HttpClientConfig, LockServiceConfig};
 
 /// Type alias for logger that is used in application
 pub(crate) type Logger = Arc<LogStrategy>;
@@ -86,16 +98,20 @@ pub(crate) static TEST_LOGGER: LazyLock<Logger> = LazyLock::new(|| init_test_log
 
 /// Initialize logger.
 /// entry point for initialize logger
+///
+/// The lock service sends its requests with an HTTP client created from `lock_http_config`.
 pub(crate) async fn init_logger(
     config: &LogConfig,
     pdp_id: PdpID,
     app_name: Option<ApplicationName>,
     lock_config: Option<&LockServiceConfig>,
+    lock_http_config: &HttpClientConfig,
 ) -> Result<Logger, InitLockServiceError> {
     let logger = Arc::new(LogStrategy::new(config, pdp_id, app_name)?);
     let logger_weak = Arc::downgrade(&logger);
     if let Some(lock_config) = lock_config {
-        let lock_service = LockService::new(pdp_id, lock_config, Some(logger_weak)).await?;
+        let lock_service =
+            LockService::new(pdp_id, lock_config, lock_http_config, Some(logger_weak)).await?;
         logger.set_lock_service(lock_service);
     }
     Ok(logger)
@@ -0,0 +1,164 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Tests for listing the actions allowed for the principals and a resource
+
+use std::collections::BTreeSet;
+
+use tokio::test;
+
+use super::utils::*;
+use crate::authz::request::EntityData;
+use crate::log::interface::LogStorage;
+use crate::{Cedarling, JsonRule, RequestPrincipals};
+
+static POLICY_STORE_RAW_YAML: &str = r#"
+cedar_version: v4.0.0
+policy_stores:
+  a1bf93115de86de760ee0bea1d529b521489e5a11747:
+    name: "Jans"
+    policies:
+      read_issue:
+        description: users can read all the issues
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal is Jans::User,
+                action == Jans::Action::"Read",
+                resource is Jans::Issue
+            );
+      update_issue:
+        description: users can update the issues of their organization
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal is Jans::User,
+                action == Jans::Action::"Update",
+                resource is Jans::Issue
+            ) when {
+                principal.org_id == resource.org_id
+            };
+    schema:
+      encoding: none
+      content_type: cedar
+      body: |-
+        namespace Jans {
+          entity Issue = {"org_id": String};
+          entity Project;
+          entity User = {"org_id": String};
+          action "Read", "Update", "Delete" appliesTo {
+            principal: [User],
+            resource: [Issue],
+            context: {}
+          };
+          action "Create" appliesTo {
+            principal: [User],
+            resource: [Project],
+            context: {}
+          };
+        }
+"#;
+
+fn principals(user_org: &str) -> RequestPrincipals {
+    RequestPrincipals::Principals(vec![
+        EntityData::deserialize(json!({
+            "cedar_entity_mapping": {
+                "entity_type": "Jans::User",
+                "id": "some_user"
+            },
+            "org_id": user_org,
+        }))
+        .unwrap(),
+    ])
+}
+
+fn resource() -> EntityData {
+    EntityData::deserialize(json!({
+        "cedar_entity_mapping": {
+            "entity_type": "Jans::Issue",
+            "id": "issue_1"
+        },
+        "org_id": "some_org",
+    }))
+    .unwrap()
+}
+
+async fn get_cedarling() -> Cedarling {
+    cedarling_util::get_cedarling_with_callback(
+        PolicyStoreSource::Yaml(POLICY_STORE_RAW_YAML.to_string()),
+        |config| {
+            config.authorization_config.principal_bool_operator = JsonRule::new(json!({
+                "===": [{"var": "Jans::User"}, "ALLOW"]
+            }))
+            .unwrap();
+        },
+    )
+    .await
+}
+
+fn set(values: &[&str]) -> BTreeSet<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+/// Check that only the actions applying to the resource are evaluated
+/// and that the allowed ones are returned
+#[test]
+async fn test_allowed_actions() {
+    let cedarling = get_cedarling().await;
+
+    let result = cedarling
+        .allowed_actions(principals("other_org"), resource(), json!({}))
+        .await
+        .expect("request should be parsed without errors");
+
+    assert_eq!(result.allowed, set(&["Jans::Action::\"Read\""]));
+    assert_eq!(
+        result.actions.keys().cloned().collect::<BTreeSet<_>>(),
+        set(&[
+            "Jans::Action::\"Delete\"",
+            "Jans::Action::\"Read\"",
+            "Jans::Action::\"Update\"",
+        ]),
+        "actions that do not apply to the resource type should not be evaluated"
+    );
+
+    let update = &result.actions["Jans::Action::\"Update\""];
+    assert!(!update.decision);
+    let user = update
+        .principals
+        .get("Jans::User")
+        .expect("result should have the response of the user");
+    assert_eq!(user.diagnostics().reason().count(), 0);
+
+    let decision_logs = cedarling.get_logs_by_request_id_and_tag(&result.request_id, "Decision");
+    assert_eq!(
+        decision_logs.len(),
+        3,
+        "each evaluated action should have a decision log"
+    );
+}
+
+/// Check that the attributes of the entities are used to evaluate each action
+#[test]
+async fn test_allowed_actions_with_conditions() {
+    let cedarling = get_cedarling().await;
+
+    let result = cedarling
+        .allowed_actions(principals("some_org"), resource(), json!({}))
+        .await
+        .expect("request should be parsed without errors");
+
+    assert_eq!(
+        result.allowed,
+        set(&["Jans::Action::\"Read\"", "Jans::Action::\"Update\""])
+    );
+    assert!(!result.actions["Jans::Action::\"Delete\""].decision);
+}
@@ -0,0 +1,246 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Tests for the decision cache
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use tokio::test;
+
+use super::utils::*;
+use crate::common::policy_store::PolicyStoreWithID;
+use crate::log::interface::LogStorage;
+use crate::{Cedarling, DecisionCacheConfig, PolicyStoreConfig, TemplateLink};
+
+static POLICY_STORE_RAW_YAML: &str = include_str!("../../../test_files/policy-store_ok_2.yaml");
+
+static POLICY_TEMPLATES_YAML: &str = r#"    policy_templates:
+      share_issue:
+        description: lets a user update a shared issue
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal == ?principal,
+                action == Jans::Action::"Update",
+                resource == ?resource
+            );
+"#;
+
+fn request(resource_id: &str) -> Request {
+    Request::deserialize(json!(
+        {
+            "tokens": {
+                "access_token": generate_token_using_claims(json!({
+                    "org_id": "some_long_id",
+                    "jti": "some_jti",
+                    "client_id": "some_client_id",
+                    "iss": "https://account.gluu.org",
+                    "aud": "some_aud",
+                })),
+                "id_token": generate_token_using_claims(json!({
+                    "jti": "some_jti",
+                    "iss": "https://account.gluu.org",
+                    "aud": ["some_aud"],
+                    "sub": "some_sub",
+                })),
+                "userinfo_token":  generate_token_using_claims(json!({
+                    "jti": "some_jti",
+                    "country": "US",
+                    "sub": "some_sub",
+                    "iss": "https://account.gluu.org",
+                    "role": ["Guest"],
+                })),
+            },
+            "action": "Jans::Action::\"Update\"",
+            "resource": {
+                "cedar_entity_mapping": {
+                    "entity_type": "Jans::Issue",
+                    "id": resource_id
+                },
+                "org_id": "some_long_id",
+                "country": "US"
+            },
+            "context": {},
+        }
+    ))
+    .expect("Request should be deserialized from json")
+}
+
+async fn get_cedarling() -> Cedarling {
+    get_cedarling_with_policy_store(POLICY_STORE_RAW_YAML.to_string()).await
+}
+
+async fn get_cedarling_with_policy_store(policy_store_yaml: String) -> Cedarling {
+    cedarling_util::get_cedarling_with_callback(
+        PolicyStoreSource::Yaml(policy_store_yaml),
+        |config| {
+            config.authorization_config.decision_cache = Some(DecisionCacheConfig::default());
+        },
+    )
+    .await
+}
+
+/// Sends the same request twice and returns whether the second one was a cache hit
+async fn repeated_request_hits_cache(cedarling: &Cedarling) -> bool {
+    cedarling
+        .authorize(request("random_id"))
+        .await
+        .expect("request should be parsed without errors");
+    let result = cedarling
+        .authorize(request("random_id"))
+        .await
+        .expect("request should be parsed without errors");
+    cache_hit(cedarling, &result.request_id)
+}
+
+fn cache_hit(cedarling: &Cedarling, request_id: &str) -> bool {
+    let decision_logs = cedarling.get_logs_by_request_id_and_tag(request_id, "Decision");
+    assert_eq!(decision_logs.len(), 1);
+    decision_logs[0]["cache_hit"] == true
+}
+
+/// Check that the result of a request is reused for the same request
+/// and that the cache hit is flagged in the decision log
+#[test]
+async fn test_decision_cache_hit() {
+    let cedarling = get_cedarling().await;
+
+    let first = cedarling
+        .authorize(request("random_id"))
+        .await
+        .expect("request should be parsed without errors");
+    let second = cedarling
+        .authorize(request("random_id"))
+        .await
+        .expect("request should be parsed without errors");
+
+    assert_eq!(first.decision, second.decision);
+    assert_ne!(
+        first.request_id, second.request_id,
+        "the cached result should get the ID of the request"
+    );
+    assert!(!cache_hit(&cedarling, &first.request_id));
+    assert!(cache_hit(&cedarling, &second.request_id));
+
+    let decision_logs = cedarling.get_logs_by_request_id_and_tag(&second.request_id, "Decision");
+    assert_eq!(decision_logs[0]["resource"], "Jans::Issue::\"random_id\"");
+}
+
+/// Check that the result of a request is not reused for another resource
+#[test]
+async fn test_decision_cache_miss() {
+    let cedarling = get_cedarling().await;
+
+    cedarling
+        .authorize(request("random_id"))
+        .await
+        .expect("request should be parsed without errors");
+    let result = cedarling
+        .authorize(request("other_id"))
+        .await
+        .expect("request should be parsed without errors");
+
+    assert!(!cache_hit(&cedarling, &result.request_id));
+}
+
+/// Check that the cached results are dropped when a template link is added
+#[test]
+async fn test_decision_cache_invalidated_by_template_link() {
+    let cedarling = get_cedarling_with_policy_store(POLICY_STORE_RAW_YAML.replacen(
+        "    policies:\n",
+        &format!("{POLICY_TEMPLATES_YAML}    policies:\n"),
+        1,
+    ))
+    .await;
+    assert!(repeated_request_hits_cache(&cedarling).await);
+
+    cedarling
+        .add_template_link(
+            "some_sub_random_id",
+            TemplateLink {
+                template_id: "share_issue".to_string(),
+                values: HashMap::from([
+                    (
+                        "?principal".to_string(),
+                        "Jans::User::\"some_sub\"".to_string(),
+                    ),
+                    (
+                        "?resource".to_string(),
+                        "Jans::Issue::\"random_id\"".to_string(),
+                    ),
+                ]),
+            },
+        )
+        .expect("link should be added");
+
+    let result = cedarling
+        .authorize(request("random_id"))
+        .await
+        .expect("request should be parsed without errors");
+    assert!(
+        !cache_hit(&cedarling, &result.request_id),
+        "the request should be evaluated with the new link"
+    );
+}
+
+/// Check that the cached results are dropped when the policy store is replaced,
+/// e.g. by a polled policy store
+#[test]
+async fn test_decision_cache_invalidated_by_policy_store_replacement() {
+    let cedarling = get_cedarling().await;
+    assert!(repeated_request_hits_cache(&cedarling).await);
+
+    let policy_store = PolicyStoreWithID::clone(&cedarling.authz.policy_store());
+    cedarling
+        .authz
+        .replace_policy_store(policy_store, cedarling.authz.entity_builder(), None);
+
+    let result = cedarling
+        .authorize(request("random_id"))
+        .await
+        .expect("request should be parsed without errors");
+    assert!(
+        !cache_hit(&cedarling, &result.request_id),
+        "the request should be evaluated with the new policy store"
+    );
+}
+
+/// Check that the cached results are not evaluated against the shadow policy store
+#[test]
+async fn test_decision_cache_hit_skips_shadow_policy_store() {
+    let cedarling = cedarling_util::get_cedarling_with_callback(
+        PolicyStoreSource::Yaml(POLICY_STORE_RAW_YAML.to_string()),
+        |config| {
+            config.authorization_config.decision_cache = Some(DecisionCacheConfig::default());
+            config.shadow_policy_store_config = Some(PolicyStoreConfig {
+                source: PolicyStoreSource::Yaml(POLICY_STORE_RAW_YAML.to_string()),
+            });
+        },
+    )
+    .await;
+    assert!(repeated_request_hits_cache(&cedarling).await);
+
+    // the shadow evaluations run in the background
+    let mut stats = cedarling
+        .shadow_stats()
+        .expect("shadow policy store should be loaded");
+    for _ in 0..100 {
+        if stats.evaluations > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        stats = cedarling
+            .shadow_stats()
+            .expect("shadow policy store should be loaded");
+    }
+    assert_eq!(
+        stats.evaluations, 1,
+        "only the request that missed the cache should be evaluated"
+    );
+}
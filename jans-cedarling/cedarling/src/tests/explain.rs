@@ -0,0 +1,174 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Tests for the explain mode of the authorization
+
+use tokio::test;
+
+use super::utils::*;
+use crate::authz::request::EntityData;
+use crate::log::interface::LogStorage;
+use crate::{Cedarling, ConditionKind, JsonRule, PolicyTrace, RequestUnsigned};
+
+static POLICY_STORE_RAW_YAML: &str = r#"
+cedar_version: v4.0.0
+policy_stores:
+  a1bf93115de86de760ee0bea1d529b521489e5a11747:
+    name: "Jans"
+    policies:
+      same_org:
+        description: users can update the issues of their organization
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal is Jans::User,
+                action == Jans::Action::"Update",
+                resource is Jans::Issue
+            ) when {
+                principal.org_id == resource.org_id
+            };
+      other_action:
+        description: policy for another action
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal is Jans::User,
+                action == Jans::Action::"Read",
+                resource is Jans::Issue
+            );
+    schema:
+      encoding: none
+      content_type: cedar
+      body: |-
+        namespace Jans {
+          entity Issue = {"org_id": String};
+          entity User = {"org_id": String};
+          action "Update", "Read" appliesTo {
+            principal: [User],
+            resource: [Issue],
+            context: {}
+          };
+        }
+"#;
+
+fn request(user_org: &str) -> RequestUnsigned {
+    RequestUnsigned {
+        action: "Jans::Action::\"Update\"".to_string(),
+        context: json!({}),
+        principals: vec![
+            EntityData::deserialize(json!({
+                "cedar_entity_mapping": {
+                    "entity_type": "Jans::User",
+                    "id": "some_user"
+                },
+                "org_id": user_org,
+            }))
+            .unwrap(),
+        ],
+        resource: EntityData::deserialize(json!({
+            "cedar_entity_mapping": {
+                "entity_type": "Jans::Issue",
+                "id": "issue_1"
+            },
+            "org_id": "some_org",
+        }))
+        .unwrap(),
+    }
+}
+
+async fn get_cedarling(explain: bool) -> Cedarling {
+    cedarling_util::get_cedarling_with_callback(
+        PolicyStoreSource::Yaml(POLICY_STORE_RAW_YAML.to_string()),
+        |config| {
+            config.authorization_config.principal_bool_operator = JsonRule::new(json!({
+                "===": [{"var": "Jans::User"}, "ALLOW"]
+            }))
+            .unwrap();
+            config.authorization_config.explain = explain;
+        },
+    )
+    .await
+}
+
+fn user_traces(explanations: &serde_json::Value) -> Vec<PolicyTrace> {
+    serde_json::from_value(explanations["Jans::User::\"some_user\""].clone())
+        .expect("explanations should contain the traces of the user")
+}
+
+/// Check that the explanations are only returned when requested
+#[test]
+async fn test_explain_per_request() {
+    let cedarling = get_cedarling(false).await;
+
+    let result = cedarling
+        .authorize_unsigned(request("other_org"))
+        .await
+        .expect("request should be parsed without errors");
+    assert!(!result.decision);
+    assert_eq!(result.explanations, None);
+
+    let result = cedarling
+        .authorize_unsigned_explain(request("other_org"))
+        .await
+        .expect("request should be parsed without errors");
+    assert!(!result.decision);
+
+    let explanations = serde_json::to_value(&result)
+        .expect("result should be serialized")
+        .get("explanations")
+        .cloned()
+        .expect("result should have explanations");
+    let traces = user_traces(&explanations);
+    assert_eq!(
+        traces.len(),
+        1,
+        "only the policy for the requested action should be explained: {traces:?}"
+    );
+
+    let trace = &traces[0];
+    assert_eq!(trace.id, "same_org");
+    assert!(!trace.satisfied);
+    let failed = trace
+        .failed_condition
+        .as_ref()
+        .expect("the condition should fail");
+    assert_eq!(failed.kind, ConditionKind::When);
+    assert_eq!(failed.error, None);
+    assert!(trace.attributes_read.contains("principal.org_id"));
+    assert!(trace.attributes_read.contains("resource.org_id"));
+}
+
+/// Check that global explain mode adds the explanations to the result and the decision log
+#[test]
+async fn test_explain_global_in_decision_log() {
+    let cedarling = get_cedarling(true).await;
+
+    let result = cedarling
+        .authorize_unsigned(request("some_org"))
+        .await
+        .expect("request should be parsed without errors");
+    assert!(result.decision);
+    let traces = result
+        .explanations
+        .as_ref()
+        .and_then(|explanations| explanations.get("Jans::User::\"some_user\""))
+        .expect("result should have explanations");
+    assert!(
+        traces[0].satisfied,
+        "policy should be satisfied: {traces:?}"
+    );
+
+    let decision_logs = cedarling.get_logs_by_request_id_and_tag(&result.request_id, "Decision");
+    assert_eq!(decision_logs.len(), 1);
+    let traces = user_traces(&decision_logs[0]["explanations"]);
+    assert_eq!(traces[0].id, "same_org");
+    assert!(traces[0].satisfied);
+}
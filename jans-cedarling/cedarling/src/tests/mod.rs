@@ -7,12 +7,22 @@
 
 mod utils;
 
+mod allowed_actions;
 mod authorize_resource_entity;
 mod authorize_unsigned;
 mod cases_authorize_different_principals;
 mod cases_authorize_namespace_jans2;
 mod cases_authorize_without_check_jwt;
+mod decision_cache;
+mod explain;
 mod json_logic;
+mod policy_annotations;
+mod policy_store_builder;
+mod policy_store_polling;
+mod principal_rules;
+mod role_hierarchy;
 mod schema_type_mapping;
+mod shadow_policy_store;
 mod ssa_validation_integration;
 mod success_test_json;
+mod template_links;
@@ -0,0 +1,269 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Tests for the annotations of the determining policies in the authorization result
+
+use std::collections::BTreeSet;
+
+use tokio::test;
+
+use super::utils::*;
+use crate::authz::request::EntityData;
+use crate::{Cedarling, JsonRule, RequestUnsigned};
+
+static POLICY_STORE_RAW_YAML: &str = r#"
+cedar_version: v4.0.0
+policy_stores:
+  a1bf93115de86de760ee0bea1d529b521489e5a11747:
+    name: "Jans"
+    policies:
+      require_mfa:
+        description: users can update issues after a step-up authentication
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            @obligation("mfa")
+            @advice("show_banner")
+            permit(
+                principal is Jans::User,
+                action == Jans::Action::"Update",
+                resource is Jans::Issue
+            );
+      locked_issue:
+        description: locked issues can not be updated
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            @reason_code("E42")
+            @advice("issue_locked")
+            forbid(
+                principal,
+                action == Jans::Action::"Update",
+                resource is Jans::Issue
+            ) when {
+                resource.locked
+            };
+      workload_update:
+        description: workloads can update issues
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            @obligation("audit")
+            permit(
+                principal is Jans::Workload,
+                action == Jans::Action::"Update",
+                resource is Jans::Issue
+            );
+      workload_read:
+        description: only workloads can read issues
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            @obligation("audit_read")
+            permit(
+                principal is Jans::Workload,
+                action == Jans::Action::"Read",
+                resource is Jans::Issue
+            );
+    schema:
+      encoding: none
+      content_type: cedar
+      body: |-
+        namespace Jans {
+          entity Issue = {"locked": Bool};
+          entity User;
+          entity Workload;
+          action "Update", "Read" appliesTo {
+            principal: [User, Workload],
+            resource: [Issue],
+            context: {}
+          };
+        }
+"#;
+
+fn request(locked: bool) -> RequestUnsigned {
+    request_action("Update", locked)
+}
+
+fn request_action(action: &str, locked: bool) -> RequestUnsigned {
+    RequestUnsigned {
+        action: format!("Jans::Action::\"{action}\""),
+        context: json!({}),
+        principals: vec![
+            EntityData::deserialize(json!({
+                "cedar_entity_mapping": {
+                    "entity_type": "Jans::User",
+                    "id": "some_user"
+                },
+            }))
+            .unwrap(),
+            EntityData::deserialize(json!({
+                "cedar_entity_mapping": {
+                    "entity_type": "Jans::Workload",
+                    "id": "some_workload"
+                },
+            }))
+            .unwrap(),
+        ],
+        resource: EntityData::deserialize(json!({
+            "cedar_entity_mapping": {
+                "entity_type": "Jans::Issue",
+                "id": "issue_1"
+            },
+            "locked": locked,
+        }))
+        .unwrap(),
+    }
+}
+
+async fn get_cedarling() -> Cedarling {
+    get_cedarling_with_keys(None).await
+}
+
+async fn get_cedarling_with_keys(annotation_keys: Option<&[&str]>) -> Cedarling {
+    cedarling_util::get_cedarling_with_callback(
+        PolicyStoreSource::Yaml(POLICY_STORE_RAW_YAML.to_string()),
+        |config| {
+            config.authorization_config.principal_bool_operator = JsonRule::new(json!({
+                "and": [
+                    {"===": [{"var": "Jans::User"}, "ALLOW"]},
+                    {"===": [{"var": "Jans::Workload"}, "ALLOW"]}
+                ]
+            }))
+            .unwrap();
+            if let Some(keys) = annotation_keys {
+                config.authorization_config.annotation_keys =
+                    keys.iter().map(|key| key.to_string()).collect();
+            }
+        },
+    )
+    .await
+}
+
+fn set(values: &[&str]) -> Option<BTreeSet<String>> {
+    Some(values.iter().map(|v| v.to_string()).collect())
+}
+
+/// Check that the annotations of the permit policies are returned for an allowed request
+#[test]
+async fn test_annotations_of_allowed_request() {
+    let cedarling = get_cedarling().await;
+
+    let result = cedarling
+        .authorize_unsigned(request(false))
+        .await
+        .expect("request should be parsed without errors");
+    assert!(result.decision);
+
+    let user = result
+        .principal_annotations
+        .get("Jans::User")
+        .expect("user should have annotations");
+    assert_eq!(user.get("obligation").cloned(), set(&["mfa"]));
+    assert_eq!(user.get("advice").cloned(), set(&["show_banner"]));
+    assert!(user.get("reason_code").is_none());
+
+    let workload = result
+        .principal_annotations
+        .get("Jans::Workload::\"some_workload\"")
+        .expect("workload should have annotations");
+    assert_eq!(workload.get("obligation").cloned(), set(&["audit"]));
+
+    assert_eq!(
+        result.annotations.get("obligation").cloned(),
+        set(&["audit", "mfa"])
+    );
+    assert_eq!(
+        result.annotations.get("advice").cloned(),
+        set(&["show_banner"])
+    );
+}
+
+/// Check that the annotations of the forbid policies are returned for a denied request
+#[test]
+async fn test_annotations_of_denied_request() {
+    let cedarling = get_cedarling().await;
+
+    let result = cedarling
+        .authorize_unsigned(request(true))
+        .await
+        .expect("request should be parsed without errors");
+    assert!(!result.decision);
+
+    assert_eq!(
+        result.annotations.get("reason_code").cloned(),
+        set(&["E42"]),
+        "the reason code of the forbid policy should be returned"
+    );
+    assert_eq!(
+        result.annotations.get("advice").cloned(),
+        set(&["issue_locked"])
+    );
+    assert!(
+        result.annotations.get("obligation").is_none(),
+        "permit policies do not determine a denied decision"
+    );
+
+    let json = serde_json::to_value(&result).expect("result should be serialized");
+    assert_eq!(json["annotations"]["reason_code"], json!(["E42"]));
+    assert_eq!(
+        json["principal_annotations"]["Jans::User"]["advice"],
+        json!(["issue_locked"])
+    );
+}
+
+/// Check that the annotations of a principal whose decision did not decide the result
+/// are not returned
+#[test]
+async fn test_annotations_of_deciding_principals_only() {
+    let cedarling = get_cedarling().await;
+
+    let result = cedarling
+        .authorize_unsigned(request_action("Read", false))
+        .await
+        .expect("request should be parsed without errors");
+    assert!(!result.decision, "the user is not allowed to read issues");
+
+    assert!(
+        result.annotations.is_empty(),
+        "the workload is allowed, so it did not decide the denied result"
+    );
+    assert!(result.principal_annotations.is_empty());
+}
+
+/// Check that only the configured annotations are returned
+#[test]
+async fn test_configured_annotation_keys() {
+    let cedarling = get_cedarling_with_keys(Some(&["advice"])).await;
+
+    let result = cedarling
+        .authorize_unsigned(request(false))
+        .await
+        .expect("request should be parsed without errors");
+    assert!(result.decision);
+
+    assert_eq!(
+        result.annotations.get("advice").cloned(),
+        set(&["show_banner"])
+    );
+    assert!(
+        result.annotations.get("obligation").is_none(),
+        "obligations are not configured"
+    );
+    assert!(
+        !result
+            .principal_annotations
+            .contains_key("Jans::Workload::\"some_workload\""),
+        "the workload policy only has an obligation"
+    );
+}
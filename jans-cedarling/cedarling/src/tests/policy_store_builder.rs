@@ -0,0 +1,106 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Tests for the policy stores built with [`PolicyStoreBuilder`]
+
+use tokio::test;
+
+use super::utils::issue_fixtures::{request, share_issue_link, user_decision};
+use super::utils::*;
+use crate::{Cedarling, JsonRule, PolicyStoreBuilder, PolicyStoreWithID, cmp_decision, cmp_policy};
+
+static SCHEMA: &str = r#"
+namespace Jans {
+  entity Issue = {"org_id": String};
+  entity User;
+  action "Update" appliesTo {
+    principal: [User],
+    resource: [Issue],
+    context: {}
+  };
+}
+"#;
+
+fn policy_store() -> PolicyStoreWithID {
+    PolicyStoreBuilder::new("a1bf93115de86de760ee0bea1d529b521489e5a11747", "Jans")
+        .cedar_schema(SCHEMA)
+        .policy(
+            "admin_policy",
+            r#"permit(
+                principal == Jans::User::"admin",
+                action == Jans::Action::"Update",
+                resource is Jans::Issue
+            );"#,
+        )
+        .template(
+            "share_issue",
+            r#"permit(
+                principal == ?principal,
+                action == Jans::Action::"Update",
+                resource == ?resource
+            );"#,
+        )
+        .template_link("alice_issue_1", share_issue_link("alice", "issue_1"))
+        .build()
+        .expect("policy store should be built")
+}
+
+async fn get_cedarling(policy_store: PolicyStoreWithID) -> Cedarling {
+    cedarling_util::get_cedarling_with_callback(
+        PolicyStoreSource::InMemory(policy_store),
+        |config| {
+            config.authorization_config.principal_bool_operator = JsonRule::new(json!({
+                "===": [{"var": "Jans::User"}, "ALLOW"]
+            }))
+            .unwrap()
+        },
+    )
+    .await
+}
+
+/// Check that a policy store built in code is used for authorization
+#[test]
+async fn test_authorize_with_built_policy_store() {
+    let cedarling = get_cedarling(policy_store()).await;
+
+    let result = cedarling
+        .authorize_unsigned(request("admin", "issue_1"))
+        .await
+        .expect("request should be parsed without errors");
+    let user_result = result.principals.get("Jans::User").cloned();
+    cmp_decision!(
+        user_result,
+        Decision::Allow,
+        "admin should be allowed by the static policy"
+    );
+    cmp_policy!(
+        user_result,
+        ["admin_policy"],
+        "reason of permit should be the static policy"
+    );
+
+    assert!(
+        user_decision(&cedarling, "alice", "issue_1").await,
+        "alice should be allowed by the template link"
+    );
+    assert!(
+        !user_decision(&cedarling, "bob", "issue_1").await,
+        "bob should be denied"
+    );
+}
+
+/// Check that the template links of a policy store built in code can be changed at runtime
+#[test]
+async fn test_add_template_link_to_built_policy_store() {
+    let cedarling = get_cedarling(policy_store()).await;
+
+    cedarling
+        .add_template_link("bob_issue_1", share_issue_link("bob", "issue_1"))
+        .expect("link should be added");
+    assert!(
+        user_decision(&cedarling, "bob", "issue_1").await,
+        "bob should be allowed after the link is added"
+    );
+}
@@ -0,0 +1,302 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Tests for polling the policy store of `CEDARLING_POLICY_STORE_URI`
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use mockito::{Matcher, Mock, Server, ServerGuard};
+use test_utils::token_claims::{generate_jwks, generate_keypair_hs256};
+use tokio::test;
+use tokio::time::sleep;
+
+use super::utils::issue_fixtures::user_decision;
+use super::utils::*;
+use crate::{BootstrapConfig, Cedarling, DecisionCacheConfig, JsonRule};
+
+/// Returns a policy store that only allows `user` to update issues
+fn policy_store(version: &str, user: &str) -> String {
+    json!({
+        "cedar_version": "v4.0.0",
+        "policy_stores": {
+            "a1bf93115de86de760ee0bea1d529b521489e5a11747": {
+                "name": "Jans",
+                "version": version,
+                "policies": {
+                    "update_issue": {
+                        "description": "lets a single user update issues",
+                        "creation_date": "2024-09-20T17:22:39.996050",
+                        "policy_content": {
+                            "encoding": "none",
+                            "content_type": "cedar",
+                            "body": format!(
+                                "permit(principal == Jans::User::\"{user}\", \
+                                 action == Jans::Action::\"Update\", resource);"
+                            ),
+                        },
+                    },
+                },
+                "schema": {
+                    "encoding": "none",
+                    "content_type": "cedar",
+                    "body": "namespace Jans {\n\
+                             entity Issue = {\"org_id\": String};\n\
+                             entity TrustedIssuer;\n\
+                             entity User;\n\
+                             action \"Update\" appliesTo {\n\
+                               principal: [User],\n\
+                               resource: [Issue],\n\
+                               context: {}\n\
+                             };\n\
+                             }",
+                },
+            },
+        },
+    })
+    .to_string()
+}
+
+/// Returns the policy store of [`policy_store`] with a trusted issuer
+fn policy_store_with_issuer(version: &str, user: &str, oidc_endpoint: &str) -> String {
+    let mut policy_store: serde_json::Value =
+        serde_json::from_str(&policy_store(version, user)).unwrap();
+    let store = &mut policy_store["policy_stores"]["a1bf93115de86de760ee0bea1d529b521489e5a11747"];
+    store["trusted_issuers"] = json!({
+        "Idp": {
+            "name": "Idp",
+            "description": "the trusted issuer of the polled policy store",
+            "openid_configuration_endpoint": oidc_endpoint,
+            "token_metadata": {},
+        },
+    });
+    policy_store.to_string()
+}
+
+/// Serves the first version of the policy store on startup
+async fn policy_store_server() -> ServerGuard {
+    let mut server = Server::new_async().await;
+    server
+        .mock("GET", "/policy-store")
+        .match_header("if-none-match", Matcher::Missing)
+        .with_header("etag", "\"v1\"")
+        .with_body(policy_store("1.0.0", "alice"))
+        .create_async()
+        .await;
+    server
+}
+
+async fn get_cedarling(server: &ServerGuard) -> Cedarling {
+    get_cedarling_with_callback(server, |_| {}).await
+}
+
+async fn get_cedarling_with_callback<F>(server: &ServerGuard, cb: F) -> Cedarling
+where
+    F: FnOnce(&mut BootstrapConfig),
+{
+    let uri = format!("{}/policy-store", server.url());
+    cedarling_util::get_cedarling_with_callback(PolicyStoreSource::LockServer(uri), |config| {
+        config.policy_store_poll_interval = Some(Duration::from_secs(1));
+        config.authorization_config.decision_cache = Some(DecisionCacheConfig::default());
+        config.authorization_config.principal_bool_operator = JsonRule::new(json!({
+            "===": [{"var": "Jans::User"}, "ALLOW"]
+        }))
+        .unwrap();
+        cb(config);
+    })
+    .await
+}
+
+/// Validates the JWT signatures, the trusted issuers are only initialized when the
+/// JWTs are validated
+async fn get_cedarling_validating_jwts(server: &ServerGuard) -> Cedarling {
+    get_cedarling_with_callback(server, |config| {
+        config.jwt_config.jwt_sig_validation = true;
+    })
+    .await
+}
+
+fn oidc_endpoint(server: &ServerGuard, idp: &str) -> String {
+    format!("{}/{idp}/.well-known/openid-configuration", server.url())
+}
+
+/// Serves the OpenID configuration and the keys of a trusted issuer
+async fn idp_server(server: &mut ServerGuard, idp: &str) -> Mock {
+    let jwks = generate_jwks(&[generate_keypair_hs256(Some(idp))]);
+    server
+        .mock("GET", format!("/{idp}/jwks").as_str())
+        .with_body(serde_json::to_string(&jwks).unwrap())
+        .create_async()
+        .await;
+    server
+        .mock(
+            "GET",
+            format!("/{idp}/.well-known/openid-configuration").as_str(),
+        )
+        .with_body(
+            json!({
+                "issuer": format!("{}/{idp}", server.url()),
+                "jwks_uri": format!("{}/{idp}/jwks", server.url()),
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await
+}
+
+/// Serves the first version of the policy store on startup with a trusted issuer and
+/// a second one with another trusted issuer
+async fn policy_store_with_issuers_server(idp: &str) -> ServerGuard {
+    let mut server = Server::new_async().await;
+    idp_server(&mut server, "idp_1").await;
+    server
+        .mock("GET", "/policy-store")
+        .match_header("if-none-match", Matcher::Missing)
+        .with_header("etag", "\"v1\"")
+        .with_body(policy_store_with_issuer(
+            "1.0.0",
+            "alice",
+            &oidc_endpoint(&server, "idp_1"),
+        ))
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/policy-store")
+        .match_header("if-none-match", "\"v1\"")
+        .with_header("etag", "\"v2\"")
+        .with_body(policy_store_with_issuer(
+            "2.0.0",
+            "bob",
+            &oidc_endpoint(&server, idp),
+        ))
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/policy-store")
+        .match_header("if-none-match", "\"v2\"")
+        .with_status(304)
+        .create_async()
+        .await;
+    server
+}
+
+/// Check that a changed policy store replaces the current one
+#[test]
+async fn test_changed_policy_store_is_applied() {
+    let mut server = policy_store_server().await;
+    let changed = server
+        .mock("GET", "/policy-store")
+        .match_header("if-none-match", "\"v1\"")
+        .with_header("etag", "\"v2\"")
+        .with_body(policy_store("2.0.0", "bob"))
+        .expect_at_least(1)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/policy-store")
+        .match_header("if-none-match", "\"v2\"")
+        .with_status(304)
+        .create_async()
+        .await;
+
+    let cedarling = get_cedarling(&server).await;
+    assert!(user_decision(&cedarling, "alice", "issue_1").await);
+    assert!(!user_decision(&cedarling, "bob", "issue_1").await);
+
+    // the interval is randomized by up to 20%
+    sleep(Duration::from_millis(2500)).await;
+    changed.assert_async().await;
+
+    assert!(
+        !user_decision(&cedarling, "alice", "issue_1").await,
+        "the cached decision of the previous policy store should not be used"
+    );
+    assert!(
+        user_decision(&cedarling, "bob", "issue_1").await,
+        "bob should be allowed by the polled policy store"
+    );
+}
+
+/// Check that the current policy store is kept when it was not modified or when
+/// the server fails
+#[test]
+async fn test_policy_store_is_kept() {
+    let mut server = policy_store_server().await;
+    let not_modified = server
+        .mock("GET", "/policy-store")
+        .match_header("if-none-match", "\"v1\"")
+        .with_status(304)
+        .expect_at_least(1)
+        .create_async()
+        .await;
+
+    let cedarling = get_cedarling(&server).await;
+    sleep(Duration::from_millis(1500)).await;
+    not_modified.assert_async().await;
+    assert!(user_decision(&cedarling, "alice", "issue_1").await);
+
+    not_modified.remove_async().await;
+    let failing = server
+        .mock("GET", "/policy-store")
+        .match_header("if-none-match", "\"v1\"")
+        .with_status(500)
+        .expect_at_least(1)
+        .create_async()
+        .await;
+
+    sleep(Duration::from_millis(1500)).await;
+    failing.assert_async().await;
+    assert!(
+        user_decision(&cedarling, "alice", "issue_1").await,
+        "the current policy store should be kept when the server fails"
+    );
+    assert!(!user_decision(&cedarling, "bob", "issue_1").await);
+}
+
+/// Check that the trusted issuers of a polled policy store are initialized
+#[test]
+async fn test_changed_trusted_issuers_are_applied() {
+    let mut server = policy_store_with_issuers_server("idp_2").await;
+    let idp_2 = idp_server(&mut server, "idp_2").await.expect(1);
+
+    let cedarling = get_cedarling_validating_jwts(&server).await;
+    let jwt_service = cedarling.authz.jwt_service();
+    sleep(Duration::from_millis(2500)).await;
+    idp_2.assert_async().await;
+
+    assert!(
+        !Arc::ptr_eq(&jwt_service, &cedarling.authz.jwt_service()),
+        "the JWTs should be validated with the trusted issuers of the polled policy store"
+    );
+    assert!(
+        user_decision(&cedarling, "bob", "issue_1").await,
+        "bob should be allowed by the polled policy store"
+    );
+}
+
+/// Check that a polled policy store is rejected if its trusted issuers fail to
+/// initialize
+#[test]
+async fn test_policy_store_with_failing_trusted_issuers_is_rejected() {
+    let mut server = policy_store_with_issuers_server("idp_2").await;
+    let idp_2 = server
+        .mock("GET", "/idp_2/.well-known/openid-configuration")
+        .with_status(500)
+        .expect_at_least(1)
+        .create_async()
+        .await;
+
+    let cedarling = get_cedarling_validating_jwts(&server).await;
+    let jwt_service = cedarling.authz.jwt_service();
+    sleep(Duration::from_millis(2500)).await;
+    idp_2.assert_async().await;
+
+    assert!(Arc::ptr_eq(&jwt_service, &cedarling.authz.jwt_service()));
+    assert!(
+        user_decision(&cedarling, "alice", "issue_1").await,
+        "the current policy store should be kept"
+    );
+    assert!(!user_decision(&cedarling, "bob", "issue_1").await);
+}
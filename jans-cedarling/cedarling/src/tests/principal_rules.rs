@@ -0,0 +1,170 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Tests for the principal boolean operations defined per action and action group
+
+use std::collections::HashMap;
+
+use tokio::test;
+
+use super::utils::*;
+use crate::authz::request::EntityData;
+use crate::log::interface::LogStorage;
+use crate::{Cedarling, JsonRule, RequestUnsigned};
+
+static POLICY_STORE_RAW_YAML: &str = r#"
+cedar_version: v4.0.0
+policy_stores:
+  a1bf93115de86de760ee0bea1d529b521489e5a11747:
+    name: "Jans"
+    policies:
+      workload_all:
+        description: workloads can do anything with issues
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal is Jans::Workload,
+                action,
+                resource is Jans::Issue
+            );
+    principal_bool_operations:
+      'Jans::Action::"writes"':
+        "===": [{"var": "Jans::Workload"}, "ALLOW"]
+    schema:
+      encoding: none
+      content_type: cedar
+      body: |-
+        namespace Jans {
+          entity Issue;
+          entity User;
+          entity Workload;
+          action "writes";
+          action "Read" appliesTo {
+            principal: [User, Workload],
+            resource: [Issue],
+            context: {}
+          };
+          action "Update", "Delete" in ["writes"] appliesTo {
+            principal: [User, Workload],
+            resource: [Issue],
+            context: {}
+          };
+          action "Share" appliesTo {
+            principal: [User, Workload],
+            resource: [Issue],
+            context: {}
+          };
+        }
+"#;
+
+fn request(action: &str) -> RequestUnsigned {
+    RequestUnsigned {
+        action: format!("Jans::Action::\"{action}\""),
+        context: json!({}),
+        principals: vec![
+            EntityData::deserialize(json!({
+                "cedar_entity_mapping": {
+                    "entity_type": "Jans::User",
+                    "id": "some_user"
+                },
+            }))
+            .unwrap(),
+            EntityData::deserialize(json!({
+                "cedar_entity_mapping": {
+                    "entity_type": "Jans::Workload",
+                    "id": "some_workload"
+                },
+            }))
+            .unwrap(),
+        ],
+        resource: EntityData::deserialize(json!({
+            "cedar_entity_mapping": {
+                "entity_type": "Jans::Issue",
+                "id": "issue_1"
+            },
+        }))
+        .unwrap(),
+    }
+}
+
+async fn get_cedarling() -> Cedarling {
+    cedarling_util::get_cedarling_with_callback(
+        PolicyStoreSource::Yaml(POLICY_STORE_RAW_YAML.to_string()),
+        |config| {
+            let both = JsonRule::new(json!({
+                "and": [
+                    {"===": [{"var": "Jans::User"}, "ALLOW"]},
+                    {"===": [{"var": "Jans::Workload"}, "ALLOW"]}
+                ]
+            }))
+            .unwrap();
+            let workload_only = JsonRule::new(json!({
+                "===": [{"var": "Jans::Workload"}, "ALLOW"]
+            }))
+            .unwrap();
+
+            config.authorization_config.principal_bool_operator = both.clone();
+            config
+                .authorization_config
+                .principal_bool_operator_by_action = HashMap::from([
+                ("Jans::Action::\"Read\"".to_string(), workload_only),
+                ("Jans::Action::\"Delete\"".to_string(), both),
+            ]);
+        },
+    )
+    .await
+}
+
+/// Check that the rule of the action, then of the action group, then the global rule is used
+#[test]
+async fn test_principal_rule_per_action() {
+    let cedarling = get_cedarling().await;
+
+    let cases = [
+        ("Read", true, "action"),
+        ("Update", true, "action_group"),
+        ("Delete", false, "action"),
+        ("Share", false, "default"),
+    ];
+    for (action, expected, source) in cases {
+        let result = cedarling
+            .authorize_unsigned(request(action))
+            .await
+            .expect("request should be parsed without errors");
+        assert_eq!(result.decision, expected, "wrong decision for {action}");
+
+        let decision_logs =
+            cedarling.get_logs_by_request_id_and_tag(&result.request_id, "Decision");
+        assert_eq!(decision_logs.len(), 1);
+        assert_eq!(
+            decision_logs[0]["principal_rule"]["source"], source,
+            "wrong rule source for {action}"
+        );
+    }
+}
+
+/// Check that the decision log contains the rule and what it is defined for
+#[test]
+async fn test_principal_rule_in_decision_log() {
+    let cedarling = get_cedarling().await;
+
+    let result = cedarling
+        .authorize_unsigned(request("Update"))
+        .await
+        .expect("request should be parsed without errors");
+
+    let decision_logs = cedarling.get_logs_by_request_id_and_tag(&result.request_id, "Decision");
+    assert_eq!(
+        decision_logs[0]["principal_rule"],
+        json!({
+            "source": "action_group",
+            "uid": "Jans::Action::\"writes\"",
+            "rule": {"===": [{"var": "Jans::Workload"}, "ALLOW"]},
+        })
+    );
+}
@@ -0,0 +1,140 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Tests for role hierarchies declared in the policy store
+
+use tokio::test;
+
+use super::utils::*;
+use crate::authz::request::EntityData;
+use crate::{JsonRule, RequestUnsigned, cmp_decision, cmp_policy};
+
+static POLICY_STORE_RAW_YAML: &str = r#"
+cedar_version: v4.0.0
+policy_stores:
+  a1bf93115de86de760ee0bea1d529b521489e5a11747:
+    name: "Jans"
+    policies:
+      editor_policy:
+        description: editors can update issues
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal in Jans::Role::"editor",
+                action == Jans::Action::"Update",
+                resource is Jans::Issue
+            );
+    role_hierarchy:
+      admin: ["editor"]
+      editor: ["viewer"]
+    schema:
+      encoding: none
+      content_type: cedar
+      body: |-
+        namespace Jans {
+          entity Role in [Role];
+          entity Issue = {"org_id": String};
+          entity User in [Role] = {
+            role?: Set<String>,
+          };
+          action "Update" appliesTo {
+            principal: [User],
+            resource: [Issue],
+            context: {}
+          };
+        }
+"#;
+
+fn request_for_role(role: &str) -> RequestUnsigned {
+    RequestUnsigned {
+        action: "Jans::Action::\"Update\"".to_string(),
+        context: json!({}),
+        principals: vec![
+            EntityData::deserialize(json!({
+                "cedar_entity_mapping": {
+                    "entity_type": "Jans::User",
+                    "id": "some_user"
+                },
+                "role": [role],
+            }))
+            .unwrap(),
+        ],
+        resource: EntityData::deserialize(json!({
+            "cedar_entity_mapping": {
+                "entity_type": "Jans::Issue",
+                "id": "some_issue"
+            },
+            "org_id": "some_org",
+        }))
+        .unwrap(),
+    }
+}
+
+/// Check that a role inherits the permissions of the roles it is a member of
+#[test]
+async fn test_role_inherits_parent_role() {
+    let cedarling = cedarling_util::get_cedarling_with_callback(
+        PolicyStoreSource::Yaml(POLICY_STORE_RAW_YAML.to_string()),
+        |config| {
+            config.authorization_config.principal_bool_operator = JsonRule::new(json!({
+                "===": [{"var": "Jans::User"}, "ALLOW"]
+            }))
+            .unwrap()
+        },
+    )
+    .await;
+
+    let result = cedarling
+        .authorize_unsigned(request_for_role("admin"))
+        .await
+        .expect("request should be parsed without errors");
+    let user_result = result.principals.get("Jans::User").cloned();
+    cmp_decision!(
+        user_result,
+        Decision::Allow,
+        "admin should be allowed since it is a member of editor"
+    );
+    cmp_policy!(
+        user_result,
+        ["editor_policy"],
+        "reason of permit should be 'editor_policy'"
+    );
+
+    let result = cedarling
+        .authorize_unsigned(request_for_role("viewer"))
+        .await
+        .expect("request should be parsed without errors");
+    let user_result = result.principals.get("Jans::User").cloned();
+    cmp_decision!(
+        user_result,
+        Decision::Deny,
+        "viewer should be denied since it is a parent of editor"
+    );
+}
+
+/// Check that Cedarling fails to start with a cyclic role hierarchy
+#[test]
+async fn test_cyclic_role_hierarchy_fails_init() {
+    let policy_store = POLICY_STORE_RAW_YAML.replace(
+        "editor: [\"viewer\"]",
+        "editor: [\"viewer\"]\n      viewer: [\"admin\"]",
+    );
+
+    let err = crate::Cedarling::new(&cedarling_util::get_config(PolicyStoreSource::Yaml(
+        policy_store,
+    )))
+    .await
+    .err()
+    .expect("cedarling should fail to initialize");
+
+    assert!(
+        err.to_string()
+            .contains("the role hierarchy contains a cycle"),
+        "unexpected error: {err}"
+    );
+}
@@ -0,0 +1,218 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Tests for evaluating the requests against a shadow policy store
+
+use std::time::Duration;
+
+use tokio::test;
+
+use super::utils::*;
+use crate::authz::request::EntityData;
+use crate::log::interface::LogStorage;
+use crate::{Cedarling, JsonRule, PolicyStoreConfig, RequestUnsigned};
+
+static POLICY_STORE_RAW_YAML: &str = r#"
+cedar_version: v4.0.0
+policy_stores:
+  a1bf93115de86de760ee0bea1d529b521489e5a11747:
+    name: "Jans"
+    policies:
+      read_issue:
+        description: users can read all the issues
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal is Jans::User,
+                action == Jans::Action::"Read",
+                resource is Jans::Issue
+            );
+      update_issue:
+        description: users can update the issues
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal is Jans::User,
+                action == Jans::Action::"Update",
+                resource is Jans::Issue
+            );
+    schema:
+      encoding: none
+      content_type: cedar
+      body: |-
+        namespace Jans {
+          entity Issue;
+          entity User;
+          action "Read", "Update" appliesTo {
+            principal: [User],
+            resource: [Issue],
+            context: {}
+          };
+        }
+"#;
+
+// the shadow policy store only lets the users read the issues
+static SHADOW_POLICY_STORE_RAW_YAML: &str = r#"
+cedar_version: v4.0.0
+policy_stores:
+  b2cf93115de86de760ee0bea1d529b521489e5a11747:
+    name: "Jans shadow"
+    policies:
+      read_issue:
+        description: users can read all the issues
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal is Jans::User,
+                action == Jans::Action::"Read",
+                resource is Jans::Issue
+            );
+    schema:
+      encoding: none
+      content_type: cedar
+      body: |-
+        namespace Jans {
+          entity Issue;
+          entity User;
+          action "Read", "Update" appliesTo {
+            principal: [User],
+            resource: [Issue],
+            context: {}
+          };
+        }
+"#;
+
+fn request(action: &str) -> RequestUnsigned {
+    RequestUnsigned {
+        action: format!("Jans::Action::\"{action}\""),
+        context: json!({}),
+        principals: vec![
+            EntityData::deserialize(json!({
+                "cedar_entity_mapping": {
+                    "entity_type": "Jans::User",
+                    "id": "some_user"
+                },
+            }))
+            .unwrap(),
+        ],
+        resource: EntityData::deserialize(json!({
+            "cedar_entity_mapping": {
+                "entity_type": "Jans::Issue",
+                "id": "issue_1"
+            },
+        }))
+        .unwrap(),
+    }
+}
+
+async fn get_cedarling() -> Cedarling {
+    cedarling_util::get_cedarling_with_callback(
+        PolicyStoreSource::Yaml(POLICY_STORE_RAW_YAML.to_string()),
+        |config| {
+            config.authorization_config.principal_bool_operator = JsonRule::new(json!({
+                "===": [{"var": "Jans::User"}, "ALLOW"]
+            }))
+            .unwrap();
+            config.shadow_policy_store_config = Some(PolicyStoreConfig {
+                source: PolicyStoreSource::Yaml(SHADOW_POLICY_STORE_RAW_YAML.to_string()),
+            });
+        },
+    )
+    .await
+}
+
+/// Waits until the shadow policy store evaluated the given number of requests
+async fn wait_for_evaluations(cedarling: &Cedarling, evaluations: u64) {
+    for _ in 0..100 {
+        let stats = cedarling
+            .shadow_stats()
+            .expect("shadow policy store should be loaded");
+        if stats.evaluations >= evaluations {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the shadow policy store did not evaluate {evaluations} requests");
+}
+
+/// Check that a decision diff is logged when the shadow policy store decides differently
+/// and that the decision of the policy store is returned
+#[test]
+async fn test_shadow_decision_diff() {
+    let cedarling = get_cedarling().await;
+
+    let result = cedarling
+        .authorize_unsigned(request("Update"))
+        .await
+        .expect("request should be parsed without errors");
+    assert!(
+        result.decision,
+        "the decision of the policy store should be returned"
+    );
+
+    wait_for_evaluations(&cedarling, 1).await;
+
+    let diff_logs = cedarling.get_logs_by_request_id_and_tag(&result.request_id, "DecisionDiff");
+    assert_eq!(diff_logs.len(), 1);
+    let diff_log = &diff_logs[0];
+    assert_eq!(diff_log["decision"], "ALLOW");
+    assert_eq!(diff_log["shadow_decision"], "DENY");
+    assert_eq!(
+        diff_log["shadow_policystore_id"],
+        "b2cf93115de86de760ee0bea1d529b521489e5a11747"
+    );
+    assert_eq!(
+        diff_log["principals"]["Jans::User::\"some_user\""]["reason"],
+        json!(["update_issue"])
+    );
+    assert_eq!(
+        diff_log["principals"]["Jans::User::\"some_user\""]["shadow_reason"],
+        json!([])
+    );
+    assert_eq!(diff_log["counters"]["decision_diffs"], 1);
+}
+
+/// Check that no decision diff is logged when both policy stores decide the same
+/// and that the counters are kept
+#[test]
+async fn test_shadow_same_decision() {
+    let cedarling = get_cedarling().await;
+
+    let result = cedarling
+        .authorize_unsigned(request("Read"))
+        .await
+        .expect("request should be parsed without errors");
+    assert!(result.decision);
+
+    wait_for_evaluations(&cedarling, 1).await;
+
+    let diff_logs = cedarling.get_logs_by_request_id_and_tag(&result.request_id, "DecisionDiff");
+    assert!(diff_logs.is_empty(), "no decision diff should be logged");
+
+    cedarling
+        .authorize_unsigned(request("Update"))
+        .await
+        .expect("request should be parsed without errors");
+    wait_for_evaluations(&cedarling, 2).await;
+
+    let stats = cedarling.shadow_stats().unwrap();
+    assert_eq!(
+        stats.policy_store_id,
+        "a1bf93115de86de760ee0bea1d529b521489e5a11747"
+    );
+    assert_eq!(stats.evaluations, 2);
+    assert_eq!(stats.decision_diffs, 1);
+    assert_eq!(stats.policy_diffs, 1);
+    assert_eq!(stats.errors, 0);
+}
@@ -7,9 +7,9 @@
 
 use crate::common::json_rules::JsonRule;
 use crate::{
-    AuthorizationConfig, BootstrapConfig, Cedarling, EntityBuilderConfig, IdTokenTrustMode,
-    JwtConfig, LockServiceConfig, LogConfig, LogLevel, LogTypeConfig, PolicyStoreConfig,
-    PolicyStoreSource,
+    AuthorizationConfig, BootstrapConfig, Cedarling, EntityBuilderConfig, HttpConfig,
+    IdTokenTrustMode, JwtConfig, LockClientAuthConfig, LockServiceConfig, LogConfig, LogLevel,
+    LogTypeConfig, PolicyStoreConfig, PolicyStoreSource, PolicyValidationConfig,
 };
 use serde_json::json;
 use std::collections::HashSet;
@@ -35,10 +35,13 @@ async fn test_cedarling_with_valid_ssa() {
         telemetry_interval: None,
         listen_sse: false,
         accept_invalid_certs: true,
+        credentials_store: None,
+        client_auth: LockClientAuthConfig::ClientSecretBasic,
     };
 
     let result = Cedarling::new(&BootstrapConfig {
         application_name: "test_app".to_string(),
+        pdp_id: None,
         log_config: LogConfig {
             log_type: LogTypeConfig::StdOut,
             log_level: LogLevel::INFO,
@@ -46,11 +49,16 @@ async fn test_cedarling_with_valid_ssa() {
         policy_store_config: PolicyStoreConfig {
             source: PolicyStoreSource::Yaml(POLICY_STORE_RAW.to_string()),
         },
+        policy_validation_config: PolicyValidationConfig::default(),
+        policy_store_poll_interval: None,
+        policy_store_signature_config: None,
+        shadow_policy_store_config: None,
         jwt_config: JwtConfig {
             jwks: None,
             jwt_sig_validation: false,
             jwt_status_validation: false,
             signature_algorithms_supported: HashSet::new(),
+            ..Default::default()
         }
         .allow_all_algorithms(),
         authorization_config: AuthorizationConfig {
@@ -64,9 +72,11 @@ async fn test_cedarling_with_valid_ssa() {
                 {"===": [{"var": "Jans::User"}, "ALLOW"]}
             ))
             .unwrap(),
+            ..Default::default()
         },
         entity_builder_config: EntityBuilderConfig::default().with_user().with_workload(),
         lock_config: Some(lock_config),
+        http_config: HttpConfig::default(),
         max_default_entities: None,
         max_base64_size: None,
         token_cache_max_ttl_secs: 0,
@@ -94,10 +104,13 @@ async fn test_cedarling_without_ssa() {
         telemetry_interval: None,
         listen_sse: false,
         accept_invalid_certs: true,
+        credentials_store: None,
+        client_auth: LockClientAuthConfig::ClientSecretBasic,
     };
 
     let result = Cedarling::new(&BootstrapConfig {
         application_name: "test_app".to_string(),
+        pdp_id: None,
         log_config: LogConfig {
             log_type: LogTypeConfig::StdOut,
             log_level: LogLevel::INFO,
@@ -105,11 +118,16 @@ async fn test_cedarling_without_ssa() {
         policy_store_config: PolicyStoreConfig {
             source: PolicyStoreSource::Yaml(POLICY_STORE_RAW.to_string()),
         },
+        policy_validation_config: PolicyValidationConfig::default(),
+        policy_store_poll_interval: None,
+        policy_store_signature_config: None,
+        shadow_policy_store_config: None,
         jwt_config: JwtConfig {
             jwks: None,
             jwt_sig_validation: false,
             jwt_status_validation: false,
             signature_algorithms_supported: HashSet::new(),
+            ..Default::default()
         }
         .allow_all_algorithms(),
         authorization_config: AuthorizationConfig {
@@ -123,9 +141,11 @@ async fn test_cedarling_without_ssa() {
                 {"===": [{"var": "Jans::User"}, "ALLOW"]}
             ))
             .unwrap(),
+            ..Default::default()
         },
         entity_builder_config: EntityBuilderConfig::default().with_user().with_workload(),
         lock_config: Some(lock_config),
+        http_config: HttpConfig::default(),
         max_default_entities: None,
         max_base64_size: None,
         token_cache_max_ttl_secs: 60,
@@ -261,6 +281,8 @@ async fn test_ssa_configuration_validation() {
         telemetry_interval: None,
         listen_sse: false,
         accept_invalid_certs: true,
+        credentials_store: None,
+        client_auth: LockClientAuthConfig::ClientSecretBasic,
     };
 
     // Verify that the SSA JWT is properly set
@@ -280,6 +302,8 @@ async fn test_ssa_configuration_validation() {
         telemetry_interval: None,
         listen_sse: false,
         accept_invalid_certs: true,
+        credentials_store: None,
+        client_auth: LockClientAuthConfig::ClientSecretBasic,
     };
 
     // Verify that the SSA JWT is not set
@@ -0,0 +1,223 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Tests for policy templates and template links in the policy store
+
+use std::fs;
+
+use tokio::test;
+
+use super::utils::issue_fixtures::{request, share_issue_link, user_decision};
+use super::utils::*;
+use crate::init::policy_store_signature::detached_signature_location;
+use crate::init::policy_store_signature::test::{sign, signature_config};
+use crate::{Cedarling, JsonRule, TemplateLinkError, cmp_decision, cmp_policy};
+
+static POLICY_STORE_RAW_YAML: &str = r#"
+cedar_version: v4.0.0
+policy_stores:
+  a1bf93115de86de760ee0bea1d529b521489e5a11747:
+    name: "Jans"
+    policies: {}
+    policy_templates:
+      share_issue:
+        description: lets a user update a shared issue
+        creation_date: '2024-09-20T17:22:39.996050'
+        policy_content:
+          encoding: none
+          content_type: cedar
+          body: |-
+            permit(
+                principal == ?principal,
+                action == Jans::Action::"Update",
+                resource == ?resource
+            );
+    template_links:
+      alice_issue_1:
+        template_id: share_issue
+        values:
+          "?principal": 'Jans::User::"alice"'
+          "?resource": 'Jans::Issue::"issue_1"'
+    schema:
+      encoding: none
+      content_type: cedar
+      body: |-
+        namespace Jans {
+          entity Role;
+          entity Issue = {"org_id": String};
+          entity User in [Role] = {
+            role?: Set<String>,
+          };
+          action "Update" appliesTo {
+            principal: [User],
+            resource: [Issue],
+            context: {}
+          };
+        }
+"#;
+
+async fn get_cedarling(policy_source: PolicyStoreSource) -> Cedarling {
+    cedarling_util::get_cedarling_with_callback(policy_source, |config| {
+        config.authorization_config.principal_bool_operator = JsonRule::new(json!({
+            "===": [{"var": "Jans::User"}, "ALLOW"]
+        }))
+        .unwrap()
+    })
+    .await
+}
+
+/// Check that the template links of the policy store are used for authorization
+#[test]
+async fn test_template_link_from_policy_store() {
+    let cedarling = get_cedarling(PolicyStoreSource::Yaml(POLICY_STORE_RAW_YAML.to_string())).await;
+
+    let result = cedarling
+        .authorize_unsigned(request("alice", "issue_1"))
+        .await
+        .expect("request should be parsed without errors");
+    let user_result = result.principals.get("Jans::User").cloned();
+    cmp_decision!(
+        user_result,
+        Decision::Allow,
+        "alice should be allowed by the template link"
+    );
+    cmp_policy!(
+        user_result,
+        ["alice_issue_1"],
+        "reason of permit should be the ID of the link"
+    );
+
+    assert!(
+        !user_decision(&cedarling, "alice", "issue_2").await,
+        "alice should be denied for an issue that is not linked"
+    );
+    assert_eq!(
+        cedarling.template_links().get("alice_issue_1"),
+        Some(&share_issue_link("alice", "issue_1"))
+    );
+}
+
+/// Check that template links can be added and removed at runtime
+#[test]
+async fn test_add_and_remove_template_link() {
+    let cedarling = get_cedarling(PolicyStoreSource::Yaml(POLICY_STORE_RAW_YAML.to_string())).await;
+
+    assert!(!user_decision(&cedarling, "bob", "issue_2").await);
+
+    cedarling
+        .add_template_link("bob_issue_2", share_issue_link("bob", "issue_2"))
+        .expect("link should be added");
+    assert!(
+        user_decision(&cedarling, "bob", "issue_2").await,
+        "bob should be allowed after the link is added"
+    );
+
+    let err = cedarling
+        .add_template_link("bob_issue_2", share_issue_link("bob", "issue_3"))
+        .expect_err("link IDs should be unique");
+    assert!(matches!(err, TemplateLinkError::Link { .. }), "{err}");
+
+    let removed = cedarling
+        .remove_template_link("bob_issue_2")
+        .expect("link should be removed");
+    assert_eq!(removed, share_issue_link("bob", "issue_2"));
+    assert!(
+        !user_decision(&cedarling, "bob", "issue_2").await,
+        "bob should be denied after the link is removed"
+    );
+
+    let err = cedarling
+        .remove_template_link("bob_issue_2")
+        .expect_err("link was already removed");
+    assert!(matches!(err, TemplateLinkError::UnknownLink(_)), "{err}");
+}
+
+/// Check that the template links are saved next to the policy store file
+#[test]
+async fn test_template_links_saved_to_file() {
+    let path = std::env::temp_dir().join(format!(
+        "cedarling-template-links-{}.yaml",
+        crate::log::gen_uuid7()
+    ));
+    let links_path = path.with_extension("yaml.links.json");
+    fs::write(&path, POLICY_STORE_RAW_YAML).expect("should write policy store file");
+
+    let cedarling = get_cedarling(PolicyStoreSource::FileYaml(path.clone())).await;
+    cedarling
+        .add_template_link("bob_issue_2", share_issue_link("bob", "issue_2"))
+        .expect("link should be added");
+    assert_eq!(
+        fs::read_to_string(&path).expect("should read policy store file"),
+        POLICY_STORE_RAW_YAML,
+        "the policy store file should not change"
+    );
+    assert!(links_path.exists(), "the links should be saved next to it");
+
+    // a new instance loads the link from the file
+    let cedarling = get_cedarling(PolicyStoreSource::FileYaml(path.clone())).await;
+    assert!(user_decision(&cedarling, "bob", "issue_2").await);
+    assert!(user_decision(&cedarling, "alice", "issue_1").await);
+
+    cedarling
+        .remove_template_link("alice_issue_1")
+        .expect("link should be removed");
+    let cedarling = get_cedarling(PolicyStoreSource::FileYaml(path.clone())).await;
+    assert!(!user_decision(&cedarling, "alice", "issue_1").await);
+    assert!(user_decision(&cedarling, "bob", "issue_2").await);
+
+    fs::remove_file(&path).expect("should remove policy store file");
+    fs::remove_file(&links_path).expect("should remove links file");
+}
+
+/// Check that the template links are not saved to a signed policy store file, which
+/// would no longer verify
+#[test]
+async fn test_template_links_not_saved_to_signed_file() {
+    let policy_store = POLICY_STORE_RAW_YAML.replacen(
+        "    name: \"Jans\"\n",
+        "    name: \"Jans\"\n    version: \"1.0.0\"\n",
+        1,
+    );
+    let path = std::env::temp_dir().join(format!(
+        "cedarling-signed-template-links-{}.yaml",
+        crate::log::gen_uuid7()
+    ));
+    let signature_path = detached_signature_location(&path.to_string_lossy());
+    let (_, signature) = sign(&policy_store);
+    fs::write(&path, &policy_store).expect("should write policy store file");
+    fs::write(&signature_path, signature).expect("should write signature file");
+
+    let get_signed_cedarling = || {
+        cedarling_util::get_cedarling_with_callback(
+            PolicyStoreSource::FileYaml(path.clone()),
+            |config| {
+                config.authorization_config.principal_bool_operator = JsonRule::new(json!({
+                    "===": [{"var": "Jans::User"}, "ALLOW"]
+                }))
+                .unwrap();
+                config.policy_store_signature_config = Some(signature_config(None));
+            },
+        )
+    };
+
+    let cedarling = get_signed_cedarling().await;
+    cedarling
+        .add_template_link("bob_issue_2", share_issue_link("bob", "issue_2"))
+        .expect("link should be added in memory");
+    assert!(user_decision(&cedarling, "bob", "issue_2").await);
+    assert_eq!(
+        fs::read_to_string(&path).expect("should read policy store file"),
+        policy_store,
+        "the signed policy store file should not change"
+    );
+
+    // the policy store still verifies on the next startup, without the link
+    let cedarling = get_signed_cedarling().await;
+    assert!(!user_decision(&cedarling, "bob", "issue_2").await);
+    assert!(user_decision(&cedarling, "alice", "issue_1").await);
+
+    fs::remove_file(&path).expect("should remove policy store file");
+    fs::remove_file(&signature_path).expect("should remove signature file");
+}
@@ -4,7 +4,10 @@
 // Copyright (c) 2024, Gluu, Inc.
 
 use crate::authorization_config::IdTokenTrustMode;
-use crate::{AuthorizationConfig, EntityBuilderConfig, JsonRule, JwtConfig};
+use crate::{
+    AuthorizationConfig, EntityBuilderConfig, HttpConfig, JsonRule, JwtConfig,
+    PolicyValidationConfig,
+};
 pub use crate::{
     BootstrapConfig, Cedarling, LogConfig, LogTypeConfig, PolicyStoreConfig, PolicyStoreSource,
 };
@@ -13,6 +16,7 @@ pub use crate::{
 pub fn get_config(policy_source: PolicyStoreSource) -> BootstrapConfig {
     BootstrapConfig {
         application_name: "test_app".to_string(),
+        pdp_id: None,
         log_config: LogConfig {
             log_type: LogTypeConfig::Memory(crate::MemoryLogConfig {
                 log_ttl: 60,
@@ -24,6 +28,10 @@ pub fn get_config(policy_source: PolicyStoreSource) -> BootstrapConfig {
         policy_store_config: PolicyStoreConfig {
             source: policy_source,
         },
+        policy_validation_config: PolicyValidationConfig::default(),
+        policy_store_poll_interval: None,
+        policy_store_signature_config: None,
+        shadow_policy_store_config: None,
         jwt_config: JwtConfig::new_without_validation(),
         authorization_config: AuthorizationConfig {
             use_user_principal: true,
@@ -34,6 +42,7 @@ pub fn get_config(policy_source: PolicyStoreSource) -> BootstrapConfig {
         },
         entity_builder_config: EntityBuilderConfig::default().with_user().with_workload(),
         lock_config: None,
+        http_config: HttpConfig::default(),
         max_default_entities: None,
         max_base64_size: None,
         token_cache_max_ttl_secs: 60,
@@ -69,6 +78,7 @@ pub async fn get_cedarling_with_authorization_conf(
 ) -> Cedarling {
     Cedarling::new(&BootstrapConfig {
         application_name: "test_app".to_string(),
+        pdp_id: None,
         log_config: LogConfig {
             log_type: LogTypeConfig::Memory(crate::MemoryLogConfig {
                 log_ttl: 60,
@@ -80,10 +90,15 @@ pub async fn get_cedarling_with_authorization_conf(
         policy_store_config: PolicyStoreConfig {
             source: policy_source,
         },
+        policy_validation_config: PolicyValidationConfig::default(),
+        policy_store_poll_interval: None,
+        policy_store_signature_config: None,
+        shadow_policy_store_config: None,
         jwt_config: JwtConfig::new_without_validation(),
         authorization_config: auth_conf,
         entity_builder_config: entity_builder_conf,
         lock_config: None,
+        http_config: HttpConfig::default(),
         max_default_entities: None,
         max_base64_size: None,
         token_cache_max_ttl_secs: 60,
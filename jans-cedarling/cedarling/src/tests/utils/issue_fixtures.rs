@@ -0,0 +1,59 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+//! Fixtures for the tests where a `Jans::User` updates a `Jans::Issue`
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::authz::request::EntityData;
+use crate::{Cedarling, RequestUnsigned, TemplateLink};
+
+/// Unsigned request for `user` to update `issue`
+pub fn request(user: &str, issue: &str) -> RequestUnsigned {
+    RequestUnsigned {
+        action: "Jans::Action::\"Update\"".to_string(),
+        context: json!({}),
+        principals: vec![
+            EntityData::deserialize(json!({
+                "cedar_entity_mapping": {
+                    "entity_type": "Jans::User",
+                    "id": user
+                },
+            }))
+            .unwrap(),
+        ],
+        resource: EntityData::deserialize(json!({
+            "cedar_entity_mapping": {
+                "entity_type": "Jans::Issue",
+                "id": issue
+            },
+            "org_id": "some_org",
+        }))
+        .unwrap(),
+    }
+}
+
+/// Link of the `share_issue` template which lets `user` update `issue`
+pub fn share_issue_link(user: &str, issue: &str) -> TemplateLink {
+    TemplateLink {
+        template_id: "share_issue".to_string(),
+        values: HashMap::from([
+            ("?principal".to_string(), format!("Jans::User::\"{user}\"")),
+            ("?resource".to_string(), format!("Jans::Issue::\"{issue}\"")),
+        ]),
+    }
+}
+
+/// Returns if `user` is allowed to update `issue`
+pub async fn user_decision(cedarling: &Cedarling, user: &str, issue: &str) -> bool {
+    cedarling
+        .authorize_unsigned(request(user, issue))
+        .await
+        .expect("request should be parsed without errors")
+        .decision
+}
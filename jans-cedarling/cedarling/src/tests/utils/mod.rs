@@ -10,5 +10,6 @@ pub use serde_json::json;
 pub use crate::{PolicyStoreSource, Request};
 
 pub mod cedarling_util;
+pub mod issue_fixtures;
 pub use cedarling_util::{get_cedarling, get_cedarling_with_authorization_conf};
 pub use test_utils::token_claims::generate_token_using_claims;
@@ -3,14 +3,19 @@
 //
 // Copyright (c) 2024, Gluu, Inc.
 
+use rand::Rng;
 use std::time::Duration;
 use tokio::time::sleep;
 
+/// The jitter of the default backoff strategies
+const DEFAULT_JITTER: f64 = 0.2;
+
 /// Implements a backoff strategy for retrying operations.
 pub struct Backoff {
     attempts: u32,
     max_attempts: Option<u32>,
-    backoff_duration: Box<dyn Fn(u32) -> Duration>,
+    backoff_duration: Box<dyn Fn(u32) -> Duration + Send + Sync>,
+    jitter: f64,
 }
 
 impl Backoff {
@@ -23,6 +28,7 @@ impl Backoff {
             attempts: 0,
             max_attempts,
             backoff_duration: backoff_dur_calc,
+            jitter: 0.0,
         }
     }
 
@@ -35,6 +41,7 @@ impl Backoff {
             attempts: 0,
             max_attempts,
             backoff_duration: backoff_dur_calc,
+            jitter: 0.0,
         }
     }
 
@@ -42,19 +49,47 @@ impl Backoff {
     ///
     /// - `max_attempts`: `Some(3)` (retries up to three times before failing)
     /// - `base_delay`: `3` seconds (the delay before the first retry)
+    /// - `jitter`: `0.2` (each delay varies by up to 20%)
     pub fn default_exponential() -> Self {
-        Self::new_exponential(Duration::from_secs(3), Some(3))
+        Self::new_exponential(Duration::from_secs(3), Some(3)).with_jitter(DEFAULT_JITTER)
     }
 
     /// Creates an fixed backoff strategy with the following configuratoin:
     ///
     /// - `max_attempts`: `None` (retries indefinitely)
     /// - `base_delay`: `10` seconds (the delay before the first retry)
+    /// - `jitter`: `0.2` (each delay varies by up to 20%)
     pub fn default_fixed() -> Self {
-        Self::new_fixed(Duration::from_secs(10), None)
+        Self::new_fixed(Duration::from_secs(10), None).with_jitter(DEFAULT_JITTER)
+    }
+
+    /// Randomizes each delay by up to `jitter` times its duration so the clients that
+    /// failed at the same time do not retry at the same time.
+    ///
+    /// `jitter` is clamped between `0.0` and `1.0`.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
     }
 
+    /// Sleeps for the delay of the current attempt.
+    ///
+    /// Returns an error if the maximum number of attempts is exceeded.
     pub async fn snooze(&mut self) -> Result<(), ()> {
+        self.snooze_at_least(None).await
+    }
+
+    /// Sleeps for the delay of the current attempt or for `min_delay` if it's longer,
+    /// e.g. when the server sent a `Retry-After` header.
+    ///
+    /// Returns an error if the maximum number of attempts is exceeded.
+    pub async fn snooze_at_least(&mut self, min_delay: Option<Duration>) -> Result<(), ()> {
+        let backoff_duration = self.next_delay()?;
+        sleep(min_delay.map_or(backoff_duration, |min| min.max(backoff_duration))).await;
+        Ok(())
+    }
+
+    fn next_delay(&mut self) -> Result<Duration, ()> {
         let backoff_duration = (self.backoff_duration)(self.attempts);
         self.attempts += 1;
 
@@ -64,8 +99,11 @@ impl Backoff {
             }
         }
 
-        sleep(backoff_duration).await;
-        Ok(())
+        if self.jitter == 0.0 {
+            return Ok(backoff_duration);
+        }
+        let factor = rand::thread_rng().gen_range(1.0 - self.jitter..=1.0 + self.jitter);
+        Ok(backoff_duration.mul_f64(factor))
     }
 
     /// Resets `attempts` to `0`.
@@ -73,3 +111,34 @@ impl Backoff {
         self.attempts = 0;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn jitters_the_delays() {
+        let mut backoff =
+            Backoff::new_exponential(Duration::from_secs(1), Some(3)).with_jitter(0.5);
+
+        for expected in [1, 2, 4] {
+            let expected = Duration::from_secs(expected);
+            let delay = backoff
+                .next_delay()
+                .expect("should not exceed the max attempts");
+            assert!(
+                delay >= expected.mul_f64(0.5) && delay <= expected.mul_f64(1.5),
+                "{delay:?} should be within 50% of {expected:?}"
+            );
+        }
+        assert!(backoff.next_delay().is_err());
+    }
+
+    #[test]
+    fn does_not_jitter_by_default() {
+        let mut backoff = Backoff::new_fixed(Duration::from_secs(1), None);
+        for _ in 0..5 {
+            assert_eq!(backoff.next_delay(), Ok(Duration::from_secs(1)));
+        }
+    }
+}
@@ -0,0 +1,262 @@
+// This software is available under the Apache-2.0 license.
+// See https://www.apache.org/licenses/LICENSE-2.0.txt for full text.
+//
+// Copyright (c) 2024, Gluu, Inc.
+
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type OnStateChange = Arc<dyn Fn(&str, CircuitState) + Send + Sync>;
+
+/// State of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum CircuitState {
+    /// Requests are sent to the endpoint.
+    #[display("closed")]
+    Closed,
+    /// Requests fail right away without reaching the endpoint.
+    #[display("open")]
+    Open,
+    /// A single probe request is sent to check whether the endpoint recovered.
+    #[display("half-open")]
+    HalfOpen,
+}
+
+/// Stops sending requests to an endpoint after consecutive failures.
+///
+/// The circuit opens after `failure_threshold` consecutive failures. Once
+/// `open_duration` elapses, it becomes half-open and lets a single probe request
+/// through: the circuit closes if the probe succeeds and opens again otherwise.
+///
+/// Clones share the same state so a breaker can be used by every sender of an
+/// endpoint.
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    endpoint: Arc<str>,
+    failure_threshold: u32,
+    open_duration: Duration,
+    inner: Arc<Mutex<Inner>>,
+    on_state_change: Option<OnStateChange>,
+}
+
+struct Inner {
+    state: CircuitState,
+    failures: u32,
+    /// When the circuit was opened or when the half-open probe was sent
+    since: DateTime<Utc>,
+}
+
+/// Error returned when the [`CircuitBreaker`] rejects a request.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("the circuit breaker for '{endpoint}' is open")]
+pub struct CircuitOpenError {
+    pub endpoint: String,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed circuit breaker for the given endpoint.
+    pub fn new(
+        endpoint: impl Into<String>,
+        failure_threshold: u32,
+        open_duration: Duration,
+    ) -> Self {
+        Self {
+            endpoint: endpoint.into().into(),
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+            inner: Arc::new(Mutex::new(Inner {
+                state: CircuitState::Closed,
+                failures: 0,
+                since: Utc::now(),
+            })),
+            on_state_change: None,
+        }
+    }
+
+    /// Creates a circuit breaker with the following configuration:
+    ///
+    /// - `failure_threshold`: `5` (consecutive failures before opening)
+    /// - `open_duration`: `30` seconds (before sending a probe request)
+    pub fn default_for(endpoint: impl Into<String>) -> Self {
+        Self::new(endpoint, 5, Duration::from_secs(30))
+    }
+
+    /// Calls `callback` with the endpoint and the new state whenever the state changes.
+    pub fn on_state_change(
+        mut self,
+        callback: impl Fn(&str, CircuitState) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_state_change = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Checks whether a request may be sent to the endpoint.
+    ///
+    /// Once the open duration elapsed, only one caller gets to send the probe
+    /// request until its outcome is recorded.
+    pub fn try_acquire(&self) -> Result<(), CircuitOpenError> {
+        let transition = {
+            let mut inner = self.lock();
+            let state = inner.state;
+            match state {
+                CircuitState::Closed => None,
+                // a probe that was never recorded, e.g. if its future was dropped, gets
+                // replaced after the open duration
+                CircuitState::Open | CircuitState::HalfOpen
+                    if self.open_duration_elapsed(inner.since) =>
+                {
+                    inner.since = Utc::now();
+                    (state == CircuitState::Open).then(|| {
+                        inner.state = CircuitState::HalfOpen;
+                        CircuitState::HalfOpen
+                    })
+                },
+                CircuitState::Open | CircuitState::HalfOpen => {
+                    return Err(CircuitOpenError {
+                        endpoint: self.endpoint.to_string(),
+                    });
+                },
+            }
+        };
+
+        self.notify(transition);
+        Ok(())
+    }
+
+    /// Records a request that reached the endpoint, which closes the circuit.
+    pub fn record_success(&self) {
+        let transition = {
+            let mut inner = self.lock();
+            inner.failures = 0;
+            (inner.state != CircuitState::Closed).then(|| {
+                inner.state = CircuitState::Closed;
+                CircuitState::Closed
+            })
+        };
+        self.notify(transition);
+    }
+
+    /// Records a failed request, which opens the circuit if the probe failed or if
+    /// the failure threshold is reached.
+    pub fn record_failure(&self) {
+        let transition = {
+            let mut inner = self.lock();
+            inner.failures = inner.failures.saturating_add(1);
+            let should_open = match inner.state {
+                CircuitState::Closed => inner.failures >= self.failure_threshold,
+                CircuitState::HalfOpen => true,
+                CircuitState::Open => false,
+            };
+            should_open.then(|| {
+                inner.state = CircuitState::Open;
+                inner.since = Utc::now();
+                CircuitState::Open
+            })
+        };
+        self.notify(transition);
+    }
+
+    fn open_duration_elapsed(&self, since: DateTime<Utc>) -> bool {
+        (Utc::now() - since)
+            .to_std()
+            .is_ok_and(|elapsed| elapsed >= self.open_duration)
+    }
+
+    fn notify(&self, transition: Option<CircuitState>) {
+        if let (Some(state), Some(callback)) = (transition, &self.on_state_change) {
+            callback(&self.endpoint, state);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // the state stays consistent even if a callback panicked while holding the lock
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("endpoint", &self.endpoint)
+            .field("failure_threshold", &self.failure_threshold)
+            .field("open_duration", &self.open_duration)
+            .field("state", &self.state())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread::sleep;
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new("https://test.jans.io", 3, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.try_acquire().is_ok());
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(
+            breaker.try_acquire(),
+            Err(CircuitOpenError {
+                endpoint: "https://test.jans.io".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn probes_once_when_half_open() {
+        let transitions = Arc::new(Mutex::new(Vec::new()));
+        let breaker = CircuitBreaker::new("https://test.jans.io", 1, Duration::from_millis(10))
+            .on_state_change({
+                let transitions = transitions.clone();
+                move |_, state| transitions.lock().unwrap().push(state)
+            });
+
+        breaker.record_failure();
+        sleep(Duration::from_millis(20));
+        assert!(breaker.try_acquire().is_ok(), "the probe should be allowed");
+        assert!(
+            breaker.try_acquire().is_err(),
+            "only one probe should be sent"
+        );
+
+        // a failed probe opens the circuit again
+        breaker.record_failure();
+        assert!(breaker.try_acquire().is_err());
+
+        sleep(Duration::from_millis(20));
+        assert!(breaker.try_acquire().is_ok());
+        breaker.record_success();
+        assert!(breaker.try_acquire().is_ok());
+
+        assert_eq!(
+            *transitions.lock().unwrap(),
+            vec![
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+                CircuitState::Closed,
+            ]
+        );
+    }
+}
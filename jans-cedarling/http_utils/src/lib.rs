@@ -8,7 +8,12 @@
 //!
 //! # Usage
 //!
-//! Usage involves initializing a [`SenderWithBackoff`] with a [`Backoff`].
+//! Usage involves initializing a [`Sender`] with a [`Backoff`] and, optionally, a
+//! [`CircuitBreaker`] shared by the senders of an endpoint.
+//!
+//! Failed requests are retried unless the server responded with a client error other
+//! than `408 Request Timeout` or `429 Too Many Requests`. The `Retry-After` header of
+//! `429` and `503` responses is honored.
 //!
 //! ```no_run
 //! use std::error::Error;
@@ -34,57 +39,132 @@
 //! ```
 
 mod backoff;
+mod circuit_breaker;
 
 pub use backoff::Backoff;
+pub use circuit_breaker::{CircuitBreaker, CircuitOpenError, CircuitState};
 
-use reqwest::RequestBuilder;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
+type OnFailedAttempt = Arc<dyn Fn(&str, &reqwest::Error) + Send + Sync>;
+
+/// The longest `Retry-After` delay that is honored, so a misbehaving server cannot
+/// stall the requests indefinitely.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Error)]
 pub enum HttpRequestError {
     #[error("max retries exceeded")]
     MaxRetriesExceeded,
+    #[error("received a non-retryable HTTP error response: {0}")]
+    NonRetryableStatus(#[source] reqwest::Error),
+    #[error(transparent)]
+    CircuitOpen(#[from] CircuitOpenError),
     #[error("failed to deserialize response to JSON: {0}")]
     DeserializeToJson(#[source] reqwest::Error),
     #[error("failed to initialize HTTP client: {0}")]
     InitializeHttpClient(#[source] reqwest::Error),
 }
 
+impl HttpRequestError {
+    /// Returns the status of a non-retryable HTTP error response.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::NonRetryableStatus(err) => err.status(),
+            _ => None,
+        }
+    }
+}
+
 /// Sends an HTTP request with backoff retry logic.
 pub struct Sender {
     backoff: Backoff,
+    circuit_breaker: Option<CircuitBreaker>,
+    on_failed_attempt: Option<OnFailedAttempt>,
 }
 
 impl Sender {
     pub fn new(backoff: Backoff) -> Self {
-        Self { backoff }
+        Self {
+            backoff,
+            circuit_breaker: None,
+            on_failed_attempt: None,
+        }
+    }
+
+    /// Fails the requests right away while the [`CircuitBreaker`] is open.
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Calls `callback` with the URL and the error of each failed attempt that counts
+    /// towards the retries, like [`CircuitBreaker::on_state_change`] does for the state
+    /// changes. The failed attempts are not reported otherwise.
+    pub fn on_failed_attempt(
+        mut self,
+        callback: impl Fn(&str, &reqwest::Error) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_failed_attempt = Some(Arc::new(callback));
+        self
     }
 
     /// Sends an HTTP request with retry logic then deserializes the JSON response to a
     /// struct.
     ///
+    /// See [`Sender::send_request`] for the retry logic.
+    pub async fn send<T, F>(&mut self, request: F) -> Result<T, HttpRequestError>
+    where
+        F: FnMut() -> RequestBuilder,
+        T: serde::de::DeserializeOwned,
+    {
+        let response = self
+            .send_request(request)
+            .await?
+            .json::<T>()
+            .await
+            .map_err(HttpRequestError::DeserializeToJson)?;
+
+        Ok(response)
+    }
+
+    /// Sends an HTTP request with retry logic.
+    ///
     /// This function attempts to send a request using the provided [`RequestBuilder`]
-    /// generator. If the request fails (e.g., due to network errors or non-success HTTP
-    /// status codes), it will retry the request with an exponentially increasing delay
-    /// between attempts. The function returns the successfully parsed JSON response or
-    /// an error if all retries fail.
+    /// generator. If the request fails (e.g., due to network errors or server error
+    /// responses), it will retry the request with the delays of the [`Backoff`]. The
+    /// function returns the successful response or an error if all retries fail.
     ///
     /// # Notes
-    /// - The function retries on both network failures and HTTP error responses.
+    /// - Client error responses are returned right away, except for `408 Request Timeout`
+    ///   and `429 Too Many Requests`.
+    /// - The delay before the next attempt is at least the `Retry-After` of `429` and
+    ///   `503` responses.
+    /// - The failures are recorded in the [`CircuitBreaker`], if any, and no request is
+    ///   sent while it's open.
     /// - The `RequestBuilder` must be **re-created** for each attempt because it cannot be reused.
-    pub async fn send<T, F>(&mut self, mut request: F) -> Result<T, HttpRequestError>
+    pub async fn send_request<F>(&mut self, mut request: F) -> Result<Response, HttpRequestError>
     where
         F: FnMut() -> RequestBuilder,
-        T: serde::de::DeserializeOwned,
     {
         let backoff = &mut self.backoff;
         backoff.reset();
 
         loop {
+            if let Some(circuit_breaker) = &self.circuit_breaker {
+                circuit_breaker.try_acquire()?;
+            }
+
             let response = match request().send().await {
                 Ok(resp) => resp,
                 Err(err) => {
-                    eprintln!("failed to complete HTTP request: {err}");
+                    notify_failed_attempt(&self.on_failed_attempt, &err);
+                    record_failure(&self.circuit_breaker);
                     backoff
                         .snooze()
                         .await
@@ -93,24 +173,235 @@ impl Sender {
                 },
             };
 
-            let response = match response.error_for_status() {
-                Ok(resp) => resp,
-                Err(err) => {
-                    eprintln!("received an HTTP error response: {err}");
-                    backoff
-                        .snooze()
-                        .await
-                        .map_err(|_| HttpRequestError::MaxRetriesExceeded)?;
-                    continue;
-                },
-            };
+            let status = response.status();
+            if !status.is_client_error() && !status.is_server_error() {
+                record_success(&self.circuit_breaker);
+                return Ok(response);
+            }
 
-            let response = response
-                .json::<T>()
-                .await
-                .map_err(HttpRequestError::DeserializeToJson)?;
+            let retry_after = retry_after(status, response.headers());
+            let err = response
+                .error_for_status()
+                .expect_err("the response should have an error status");
+
+            // the endpoint is up, the request itself is rejected
+            if !is_retryable(status) {
+                record_success(&self.circuit_breaker);
+                return Err(HttpRequestError::NonRetryableStatus(err));
+            }
 
-            return Ok(response);
+            notify_failed_attempt(&self.on_failed_attempt, &err);
+            record_failure(&self.circuit_breaker);
+            backoff
+                .snooze_at_least(retry_after)
+                .await
+                .map_err(|_| HttpRequestError::MaxRetriesExceeded)?;
         }
     }
 }
+
+fn record_success(circuit_breaker: &Option<CircuitBreaker>) {
+    if let Some(circuit_breaker) = circuit_breaker {
+        circuit_breaker.record_success();
+    }
+}
+
+fn record_failure(circuit_breaker: &Option<CircuitBreaker>) {
+    if let Some(circuit_breaker) = circuit_breaker {
+        circuit_breaker.record_failure();
+    }
+}
+
+fn notify_failed_attempt(on_failed_attempt: &Option<OnFailedAttempt>, err: &reqwest::Error) {
+    if let Some(callback) = on_failed_attempt {
+        let url = err.url().map(|url| url.as_str()).unwrap_or_default();
+        callback(url, err);
+    }
+}
+
+/// Whether a request that got an error response with the given status may succeed
+/// if sent again.
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Reads the delay of the `Retry-After` header of `429` and `503` responses, which is
+/// either a number of seconds or an HTTP date.
+fn retry_after(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if !matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    ) {
+        return None;
+    }
+
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(secs) => Duration::from_secs(secs),
+        Err(_) => (DateTime::parse_from_rfc2822(value)
+            .ok()?
+            .with_timezone(&Utc)
+            - Utc::now())
+        .to_std()
+        .unwrap_or_default(),
+    };
+
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mockito::Server;
+    use serde_json::Value;
+    use std::time::Instant;
+
+    fn sender() -> Sender {
+        Sender::new(Backoff::new_fixed(Duration::from_millis(1), Some(3)))
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/config")
+            .with_status(502)
+            .expect(4)
+            .create();
+        let client = reqwest::Client::new();
+
+        let err = sender()
+            .send::<Value, _>(|| client.get(format!("{}/config", server.url())))
+            .await
+            .expect_err("the request should fail");
+
+        assert!(matches!(err, HttpRequestError::MaxRetriesExceeded), "{err}");
+        // the request is sent once then retried 3 times
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/config")
+            .with_status(403)
+            .expect(1)
+            .create();
+        let client = reqwest::Client::new();
+
+        let err = sender()
+            .send::<Value, _>(|| client.get(format!("{}/config", server.url())))
+            .await
+            .expect_err("the request should fail");
+
+        assert_eq!(err.status(), Some(StatusCode::FORBIDDEN), "{err}");
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn honors_retry_after() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/config")
+            .with_status(429)
+            .with_header("Retry-After", "1")
+            .expect(2)
+            .create();
+        let client = reqwest::Client::new();
+        let mut sender = Sender::new(Backoff::new_fixed(Duration::from_millis(1), Some(1)));
+
+        let start = Instant::now();
+        let err = sender
+            .send::<Value, _>(|| client.get(format!("{}/config", server.url())))
+            .await
+            .expect_err("the request should fail");
+
+        assert!(matches!(err, HttpRequestError::MaxRetriesExceeded), "{err}");
+        assert!(
+            start.elapsed() >= Duration::from_secs(1),
+            "the retry should wait for the Retry-After delay"
+        );
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn fails_fast_while_the_circuit_is_open() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/config")
+            .with_status(503)
+            .expect(2)
+            .create();
+        let client = reqwest::Client::new();
+        let circuit_breaker = CircuitBreaker::new(server.url(), 2, Duration::from_secs(60));
+        let mut sender = sender().with_circuit_breaker(circuit_breaker.clone());
+
+        let err = sender
+            .send::<Value, _>(|| client.get(format!("{}/config", server.url())))
+            .await
+            .expect_err("the request should fail");
+
+        assert!(matches!(err, HttpRequestError::CircuitOpen(_)), "{err}");
+        assert_eq!(circuit_breaker.state(), CircuitState::Open);
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn reports_failed_attempts() {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("GET", "/config")
+            .with_status(500)
+            .expect(2)
+            .create();
+        let client = reqwest::Client::new();
+        let failed_attempts = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut sender = Sender::new(Backoff::new_fixed(Duration::from_millis(1), Some(1)))
+            .on_failed_attempt({
+                let failed_attempts = failed_attempts.clone();
+                move |url, err| {
+                    failed_attempts
+                        .lock()
+                        .unwrap()
+                        .push((url.to_string(), err.status()))
+                }
+            });
+
+        sender
+            .send::<Value, _>(|| client.get(format!("{}/config", server.url())))
+            .await
+            .expect_err("the request should fail");
+
+        let url = format!("{}/config", server.url());
+        assert_eq!(
+            *failed_attempts.lock().unwrap(),
+            vec![
+                (url.clone(), Some(StatusCode::INTERNAL_SERVER_ERROR)),
+                (url, Some(StatusCode::INTERNAL_SERVER_ERROR)),
+            ]
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn parses_retry_after() {
+        let retry_after = |status: u16, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(RETRY_AFTER, value.parse().unwrap());
+            retry_after(StatusCode::from_u16(status).unwrap(), &headers)
+        };
+
+        assert_eq!(retry_after(503, "5"), Some(Duration::from_secs(5)));
+        assert_eq!(retry_after(429, "3600"), Some(MAX_RETRY_AFTER));
+        assert_eq!(
+            retry_after(429, "Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO),
+            "a date in the past should not delay the retry"
+        );
+        assert_eq!(retry_after(500, "5"), None);
+        assert_eq!(retry_after(503, "soon"), None);
+    }
+}
@@ -5,6 +5,7 @@
  * Copyright (c) 2024 U-Zyn Chua
  */
 use std::collections::{BTreeMap, BinaryHeap, btree_map};
+use std::ops::Bound;
 
 mod config;
 mod error;
@@ -157,6 +158,31 @@ impl<T> SparKV<T> {
         }
     }
 
+    /// Return an iterator of (key,value) : (&String,&T) for the keys within the given bounds.
+    /// Entries are yielded in ascending key order and expired entries are skipped.
+    /// If the bounds describe an empty range, the iterator is empty.
+    pub fn range<'a>(
+        &'a self,
+        lower: Bound<&str>,
+        upper: Bound<&str>,
+    ) -> impl DoubleEndedIterator<Item = (&'a String, &'a T)> + 'a {
+        let is_valid = match (lower, upper) {
+            (Bound::Included(l), Bound::Included(u)) => l <= u,
+            (Bound::Included(l) | Bound::Excluded(l), Bound::Included(u) | Bound::Excluded(u)) => {
+                l < u
+            },
+            _ => true,
+        };
+
+        let now = Utc::now();
+        is_valid
+            .then(|| self.data.range::<str, _>((lower, upper)))
+            .into_iter()
+            .flatten()
+            .filter(move |(_, entry)| entry.expired_at > now)
+            .map(|(_, entry)| (&entry.key, &entry.value))
+    }
+
     /// Return an iterator of (key,value) : (String,T) which empties the container.
     /// All entries will be owned by the iterator, and yielded entries will not be checked against expiry.
     /// All entries and expiries will be cleared.
@@ -290,3 +290,39 @@ fn drain() {
     assert_eq!(keys, vec!["ghost", "is", "like", "oh", "this", "woo"]);
     assert_eq!(values, vec!["town", "coming", "a", "yeah", "town", "oooo"]);
 }
+
+#[test]
+fn range() {
+    let mut sparkv = SparKV::<String>::new();
+    sparkv.set("this", "town".into(), &[]).unwrap();
+    sparkv.set("woo", "oooo".into(), &[]).unwrap();
+    sparkv.set("is", "coming".into(), &[]).unwrap();
+    sparkv.set("like", "a".into(), &[]).unwrap();
+    sparkv.set("ghost", "town".into(), &[]).unwrap();
+    sparkv.set("oh", "yeah".into(), &[]).unwrap();
+    sparkv
+        .set_with_ttl("jazz", "news".into(), Duration::microseconds(40), &[])
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_micros(80));
+
+    let keys = sparkv
+        .range(Bound::Included("is"), Bound::Excluded("this"))
+        .map(|(k, _)| k.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec!["is", "like", "oh"], "expired keys are skipped");
+
+    let keys = sparkv
+        .range(Bound::Excluded("oh"), Bound::Unbounded)
+        .rev()
+        .map(|(k, _)| k.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec!["woo", "this"]);
+
+    assert_eq!(
+        sparkv
+            .range(Bound::Included("woo"), Bound::Excluded("is"))
+            .count(),
+        0,
+        "an empty range should not panic"
+    );
+}